@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use criterion::{Criterion, criterion_group, criterion_main};
 use std::marker::PhantomData;
 use xecs::query::{Context, QueryPlan, SelectStmt, WithStmt};
@@ -33,10 +35,10 @@ struct Generic<T: DataComponent>(PhantomData<T>);
 fn bench_sparse_set(c: &mut Criterion) {
     let mut world = World::new();
     let test = world.register::<Test>(TagBuilder::new().storage(StorageType::Tables));
-    let likes = world.register::<Likes>(TagBuilder::new().storage(StorageType::Tables));
+    world.register::<Likes>(TagBuilder::new().storage(StorageType::Tables));
     let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
-    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
-    let my_enum = world.register::<MyEnum>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<MyEnum>(ComponentBuilder::new().storage(StorageType::Tables));
 
     let bob = world.new_id();
 
@@ -58,5 +60,21 @@ fn bench_sparse_set(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_sparse_set);
+fn bench_toggle_tag(c: &mut Criterion) {
+    let mut world = World::new();
+    world.register::<Likes>(TagBuilder::new().storage(StorageType::Tables));
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let bob = world.new_id();
+    world.set::<Position>(bob, Position(0));
+
+    c.bench_function("toggle tag", |b| {
+        b.iter(|| {
+            world.add::<Likes>(bob).unwrap();
+            world.remove::<Likes>(bob).unwrap();
+        });
+    });
+}
+
+criterion_group!(benches, bench_sparse_set, bench_toggle_tag);
 criterion_main!(benches);
@@ -0,0 +1,40 @@
+use std::alloc::Layout;
+
+/// Allocator used by a world for its column and arena memory.
+///
+/// # Safety
+/// Implementations must uphold the same contract as [GlobalAlloc](std::alloc::GlobalAlloc).
+pub unsafe trait WorldAlloc {
+    /// # Safety
+    /// See [GlobalAlloc::alloc](std::alloc::GlobalAlloc::alloc).
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8;
+
+    /// # Safety
+    /// See [GlobalAlloc::realloc](std::alloc::GlobalAlloc::realloc).
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8;
+
+    /// # Safety
+    /// See [GlobalAlloc::dealloc](std::alloc::GlobalAlloc::dealloc).
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout);
+}
+
+/// Passes allocations through to the global allocator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultAlloc;
+
+unsafe impl WorldAlloc for DefaultAlloc {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { std::alloc::realloc(ptr, layout, new_size) }
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::allocator::WorldAlloc;
+use std::{
+    alloc::Layout,
+    cell::RefCell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    rc::Rc,
+};
+
+/// Size of a newly allocated chunk, unless an allocation needs more.
+const CHUNK_SIZE: usize = 16 * 1024;
+/// Alignment of every chunk, allocations can't require more.
+const CHUNK_ALIGN: usize = 16;
+
+struct Chunk {
+    data: NonNull<u8>,
+    size: usize,
+}
+
+impl Chunk {
+    fn new(size: usize, alloc: &dyn WorldAlloc) -> Self {
+        let layout = Self::layout(size);
+
+        // SAFETY: size is never zero.
+        let ptr = unsafe { alloc.alloc(layout) };
+
+        Self {
+            data: match NonNull::new(ptr) {
+                Some(ptr) => ptr,
+                None => std::alloc::handle_alloc_error(layout),
+            },
+            size,
+        }
+    }
+
+    #[inline]
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, CHUNK_ALIGN).unwrap()
+    }
+}
+
+struct ArenaState {
+    chunks: Vec<Chunk>,
+    /// Index of the chunk allocations are made from.
+    current: usize,
+    /// Offset of the next free byte in the current chunk.
+    offset: usize,
+    /// Incremented every reset, used to detect allocations used across frames.
+    generation: u32,
+}
+
+/// Bump allocator for temporary allocations that live until the end of a frame.
+///
+/// Memory is never freed individually, [World::end_frame](crate::world::World::end_frame)
+/// resets the arena and keeps its chunks, so steady-state frames don't hit the global allocator.
+pub struct FrameArena {
+    state: RefCell<ArenaState>,
+    alloc: Rc<dyn WorldAlloc>,
+}
+
+impl FrameArena {
+    pub(crate) fn new(alloc: Rc<dyn WorldAlloc>) -> Self {
+        Self {
+            state: RefCell::new(ArenaState {
+                chunks: vec![],
+                current: 0,
+                offset: 0,
+                generation: 0,
+            }),
+            alloc,
+        }
+    }
+
+    /// Returns the current generation of the arena.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.state.borrow().generation
+    }
+
+    /// Allocates uninitialized memory for `layout`.
+    ///
+    /// # Panics
+    /// If `layout` requires an alignment greater than 16.
+    pub(crate) fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        assert!(
+            layout.align() <= CHUNK_ALIGN,
+            "FrameArena: alignment too large"
+        );
+
+        if layout.size() == 0 {
+            // SAFETY: alignment is never zero.
+            return unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(layout.align())) };
+        }
+
+        let mut state = self.state.borrow_mut();
+
+        loop {
+            let current = state.current;
+
+            if let Some(chunk) = state.chunks.get(current) {
+                let start = state.offset.next_multiple_of(layout.align());
+
+                if start + layout.size() <= chunk.size {
+                    // SAFETY: start is in bounds of the chunk.
+                    let ptr = unsafe { chunk.data.add(start) };
+                    state.offset = start + layout.size();
+                    return ptr;
+                }
+
+                state.current += 1;
+                state.offset = 0;
+            } else {
+                // `current` is always the length of chunks here.
+                state
+                    .chunks
+                    .push(Chunk::new(CHUNK_SIZE.max(layout.size()), &*self.alloc));
+            }
+        }
+    }
+
+    /// Resets the arena, invalidating all allocations from it.
+    pub(crate) fn reset(&mut self) {
+        let state = self.state.get_mut();
+        state.current = 0;
+        state.offset = 0;
+        state.generation = state.generation.wrapping_add(1);
+    }
+}
+
+impl Drop for FrameArena {
+    fn drop(&mut self) {
+        for chunk in &self.state.get_mut().chunks {
+            // SAFETY: chunks are allocated by self.alloc with the same layout.
+            unsafe {
+                self.alloc
+                    .dealloc(chunk.data.as_ptr(), Chunk::layout(chunk.size))
+            };
+        }
+    }
+}
+
+/// Growable vector of `Copy` values allocated from a [FrameArena].
+///
+/// Values are never dropped and growing leaves the old block in the arena until it's reset.
+pub struct ArenaVec<'a, T: Copy> {
+    arena: &'a FrameArena,
+    data: NonNull<T>,
+    len: usize,
+    cap: usize,
+    generation: u32,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: Copy> ArenaVec<'a, T> {
+    pub fn new_in(arena: &'a FrameArena) -> Self {
+        Self {
+            arena,
+            data: NonNull::dangling(),
+            len: 0,
+            cap: if size_of::<T>() == 0 { usize::MAX } else { 0 },
+            generation: arena.generation(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with_capacity_in(capacity: usize, arena: &'a FrameArena) -> Self {
+        let mut vec = Self::new_in(arena);
+        vec.reserve(capacity);
+        vec
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.check_generation();
+
+        let new_cap = self.len + additional;
+
+        if new_cap <= self.cap {
+            return;
+        }
+
+        let new_cap = new_cap.next_power_of_two().max(4);
+        let layout = Layout::array::<T>(new_cap).unwrap();
+        let data = self.arena.alloc(layout).cast::<T>();
+
+        // SAFETY: both blocks are valid for len items and don't overlap.
+        unsafe { ptr::copy_nonoverlapping(self.data.as_ptr(), data.as_ptr(), self.len) };
+
+        self.data = data;
+        self.cap = new_cap;
+    }
+
+    pub fn push(&mut self, val: T) {
+        self.reserve(1);
+
+        // SAFETY: we just reserved space for one more item.
+        unsafe { self.data.add(self.len).write(val) };
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.check_generation();
+
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+
+        // SAFETY: len was in bounds and items are Copy.
+        Some(unsafe { self.data.add(self.len).read() })
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Removes consecutive repeated items.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        let mut write = 0;
+
+        for read in 0..self.len {
+            if write == 0 || self[read] != self[write - 1] {
+                self[write] = self[read];
+                write += 1;
+            }
+        }
+
+        self.len = write;
+    }
+
+    #[inline]
+    fn check_generation(&self) {
+        debug_assert_eq!(
+            self.generation,
+            self.arena.generation(),
+            "ArenaVec used after the end of its frame"
+        );
+    }
+}
+
+impl<T: Copy> Deref for ArenaVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.check_generation();
+
+        // SAFETY: the first len items are initialized.
+        unsafe { std::slice::from_raw_parts(self.data.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> DerefMut for ArenaVec<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.check_generation();
+
+        // SAFETY: the first len items are initialized.
+        unsafe { std::slice::from_raw_parts_mut(self.data.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy> Extend<T> for ArenaVec<'_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|val| self.push(val));
+    }
+}
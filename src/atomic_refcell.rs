@@ -69,7 +69,7 @@ impl<T> AtomicRefCell<T> {
 impl<T: ?Sized> AtomicRefCell<T> {
     /// Immutably borrows the wrapped value.
     #[inline]
-    pub fn borrow(&self) -> AtomicRef<T> {
+    pub fn borrow(&self) -> AtomicRef<'_, T> {
         match AtomicBorrowRef::try_new(&self.borrow) {
             Ok(borrow) => AtomicRef {
                 value: unsafe { NonNull::new_unchecked(self.value.get()) },
@@ -82,7 +82,7 @@ impl<T: ?Sized> AtomicRefCell<T> {
     /// Attempts to immutably borrow the wrapped value, but instead of panicking
     /// on a failed borrow, returns `Err`.
     #[inline]
-    pub fn try_borrow(&self) -> Result<AtomicRef<T>, BorrowError> {
+    pub fn try_borrow(&self) -> Result<AtomicRef<'_, T>, BorrowError> {
         match AtomicBorrowRef::try_new(&self.borrow) {
             Ok(borrow) => Ok(AtomicRef {
                 value: unsafe { NonNull::new_unchecked(self.value.get()) },
@@ -94,7 +94,7 @@ impl<T: ?Sized> AtomicRefCell<T> {
 
     /// Mutably borrows the wrapped value.
     #[inline]
-    pub fn borrow_mut(&self) -> AtomicRefMut<T> {
+    pub fn borrow_mut(&self) -> AtomicRefMut<'_, T> {
         match AtomicBorrowRefMut::try_new(&self.borrow) {
             Ok(borrow) => AtomicRefMut {
                 value: unsafe { NonNull::new_unchecked(self.value.get()) },
@@ -108,7 +108,7 @@ impl<T: ?Sized> AtomicRefCell<T> {
     /// Attempts to mutably borrow the wrapped value, but instead of panicking
     /// on a failed borrow, returns `Err`.
     #[inline]
-    pub fn try_borrow_mut(&self) -> Result<AtomicRefMut<T>, BorrowMutError> {
+    pub fn try_borrow_mut(&self) -> Result<AtomicRefMut<'_, T>, BorrowMutError> {
         match AtomicBorrowRefMut::try_new(&self.borrow) {
             Ok(borrow) => Ok(AtomicRefMut {
                 value: unsafe { NonNull::new_unchecked(self.value.get()) },
@@ -348,6 +348,7 @@ impl<'b, T: ?Sized> AtomicRef<'b, T> {
     /// Like its [std-counterpart](core::cell::Ref::clone), this type does not implement `Clone`
     /// to not interfere with cloning the contained type.
     #[inline]
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(orig: &AtomicRef<'b, T>) -> AtomicRef<'b, T> {
         AtomicRef {
             value: orig.value,
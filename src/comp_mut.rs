@@ -0,0 +1,84 @@
+use crate::{
+    id::Id, type_info::TypeInfo, type_traits::TypedId, world::World, world_utils::mark_changed,
+};
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    rc::Rc,
+};
+
+/// Mutable access to a component value, created by [World::component_mut] and
+/// [World::get_or_insert_with].
+///
+/// If the value was inserted or accessed mutably, dropping the guard records the change and
+/// calls the on_set hook once.
+pub struct CompMut<'w, T: TypedId> {
+    world: &'w mut World,
+    id: Id,
+    comp: Id,
+    ptr: NonNull<T::Data>,
+    type_info: Rc<TypeInfo>,
+    written: bool,
+    marker: PhantomData<&'w mut T::Data>,
+}
+
+impl<'w, T: TypedId> CompMut<'w, T> {
+    /// # Safety
+    /// `ptr` must point to the value of `comp` for `id`, and `T::Data` must be its type.
+    pub(crate) unsafe fn new(
+        world: &'w mut World,
+        id: Id,
+        comp: Id,
+        ptr: NonNull<u8>,
+        type_info: Rc<TypeInfo>,
+    ) -> Self {
+        Self {
+            world,
+            id,
+            comp,
+            ptr: ptr.cast(),
+            type_info,
+            written: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reports the value as changed on drop even if it's not accessed mutably,
+    /// e.g. because it was just inserted.
+    pub(crate) fn written(mut self, written: bool) -> Self {
+        self.written |= written;
+        self
+    }
+}
+
+impl<T: TypedId> Deref for CompMut<'_, T> {
+    type Target = T::Data;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The guard holds the exclusive borrow of the world, so the value can't move.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: TypedId> DerefMut for CompMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.written = true;
+
+        // SAFETY: The guard holds the exclusive borrow of the world, so the value can't move.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: TypedId> Drop for CompMut<'_, T> {
+    fn drop(&mut self) {
+        if !self.written {
+            return;
+        }
+
+        mark_changed(self.world, self.id, self.comp);
+
+        // SAFETY: The pointer points to a value of the component type.
+        unsafe { self.type_info.call_on_set(self.id, self.ptr.cast()) };
+    }
+}
@@ -1,20 +1,21 @@
 use crate::{
-    flags::ComponentFlags,
+    error::{EcsError, EcsResult},
+    flags::{ComponentFlags, IdFlags, Policy},
     id::Id,
     storage::{
         Storage, StorageType,
+        relation::{RelationStore, stored_relation},
         sparse::{SparseData, SparseTag},
     },
     type_info::{TypeHooksBuilder, TypeInfo, TypeName},
     type_traits::{Component, DataComponent},
     world::World,
+    world_utils::set_id_flag,
 };
 use std::{collections::HashMap, rc::Rc};
 
 /// Component location in a [Table](crate::storage::table::Table).
 pub(crate) struct ComponentLocation {
-    /// Index of id in the table's [IdList](crate::types::IdList).
-    pub(crate) id_idx: usize,
     /// [Column](crate::storage::Column) index where the id appears.
     /// Defaults to `None` if the id is a tag.
     pub(crate) col_idx: Option<usize>,
@@ -22,15 +23,92 @@ pub(crate) struct ComponentLocation {
 
 pub(crate) struct ComponentInfo {
     pub(crate) id: Id,
+    /// Name given at registration, or the name of the registered type.
+    pub(crate) name: Option<TypeName>,
     pub(crate) flags: ComponentFlags,
     pub(crate) type_info: Option<Rc<TypeInfo>>,
     pub(crate) storage: Storage,
+    /// Tags added along with the component, see [ComponentFlags::WITH].
+    pub(crate) with: Vec<Id>,
+    /// Processing order among components, higher first. See [ComponentBuilder::priority].
+    pub(crate) priority: i32,
+}
+
+impl ComponentInfo {
+    /// Returns the name of the component, or its id if it has none.
+    pub(crate) fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.id.to_string(),
+        }
+    }
+}
+
+/// Settings shared by [TagBuilder] and [ComponentBuilder].
+pub trait BuilderCommon: Sized {
+    fn name(self, name: impl Into<TypeName>) -> Self;
+
+    fn storage(self, storage: StorageType) -> Self;
+
+    /// Returns the flags the component is built with.
+    fn flags_mut(&mut self) -> &mut ComponentFlags;
+
+    #[inline]
+    fn add_flags(mut self, flags: ComponentFlags) -> Self {
+        self.flags_mut().insert(flags);
+        self
+    }
+
+    #[inline]
+    fn set_flags(mut self, flags: ComponentFlags) -> Self {
+        *self.flags_mut() = flags;
+        self
+    }
+
+    #[inline]
+    fn clear_flags(mut self, flags: ComponentFlags) -> Self {
+        self.flags_mut().remove(flags);
+        self
+    }
+
+    /// Marks the component as an exclusive relationship, see [ComponentFlags::EXCLUSIVE].
+    #[inline]
+    fn exclusive(self) -> Self {
+        self.add_flags(ComponentFlags::EXCLUSIVE)
+    }
+
+    /// Marks the component as a transitive relationship.
+    ///
+    /// Components can be inherited through the relationship with
+    /// [World::get_transitive](crate::world::World::get_transitive).
+    #[inline]
+    fn transitive(self) -> Self {
+        self.add_flags(ComponentFlags::TRANSITIVE)
+    }
+
+    /// Sets what happens to ids with the component when it's deleted,
+    /// replacing the previous policy.
+    #[inline]
+    fn on_delete(self, policy: Policy) -> Self {
+        self.clear_flags(ComponentFlags::ON_DELETE_MASK)
+            .add_flags(policy.on_delete_flag())
+    }
+
+    /// Sets what happens to ids with a pair of the relationship when the target
+    /// is deleted, replacing the previous policy.
+    #[inline]
+    fn on_delete_object(self, policy: Policy) -> Self {
+        self.clear_flags(ComponentFlags::ON_DELETE_TARGET_MASK)
+            .add_flags(policy.on_delete_target_flag())
+    }
 }
 
 pub struct TagBuilder {
     name: Option<TypeName>,
     flags: ComponentFlags,
     storage_type: StorageType,
+    with: Vec<Id>,
+    priority: i32,
 }
 
 impl TagBuilder {
@@ -39,6 +117,8 @@ impl TagBuilder {
             name: None,
             flags: ComponentFlags::empty(),
             storage_type: StorageType::default(),
+            with: vec![],
+            priority: 0,
         }
     }
 
@@ -52,18 +132,46 @@ impl TagBuilder {
         self
     }
 
-    pub fn with_flags(mut self, flag: ComponentFlags) -> Self {
-        self.flags.insert(flag);
+    #[deprecated(note = "use `BuilderCommon::add_flags`")]
+    #[inline]
+    pub fn with_flags(self, flags: ComponentFlags) -> Self {
+        self.add_flags(flags)
+    }
+
+    #[deprecated(note = "use `BuilderCommon::clear_flags`")]
+    #[inline]
+    pub fn clear_flag(self, flags: ComponentFlags) -> Self {
+        self.clear_flags(flags)
+    }
+
+    /// Marks the tag as a symmetric relationship.
+    ///
+    /// Adding `(R, B)` to `A` also adds `(R, A)` to `B`, removing either side removes both,
+    /// and despawning either id removes the mirrored pair from the other.
+    pub fn symmetric(mut self) -> Self {
+        self.flags.insert(ComponentFlags::SYMMETRIC);
+        self
+    }
+
+    /// Adds `other` whenever this tag is added.
+    ///
+    /// Tags stored in tables are added in the same table move, data components are
+    /// initialized with their default hook. Registration fails if `other` has no default hook.
+    pub fn auto_add(mut self, other: Id) -> Self {
+        self.flags.insert(ComponentFlags::WITH);
+        self.with.push(other);
         self
     }
 
-    pub fn set_flags(mut self, flags: ComponentFlags) -> Self {
-        self.flags = flags;
+    /// Marks the tag as a relationship, see [ComponentFlags::RELATIONSHIP].
+    pub fn relationship(mut self) -> Self {
+        self.flags.insert(ComponentFlags::RELATIONSHIP);
         self
     }
 
-    pub fn clear_flag(mut self, flag: ComponentFlags) -> Self {
-        self.flags.remove(flag);
+    /// Sets the processing order of the tag among components, see [ComponentBuilder::priority].
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
         self
     }
 
@@ -72,37 +180,69 @@ impl TagBuilder {
 
         self.flags.insert(ComponentFlags::IS_TAG);
 
-        let storage = match self.storage_type {
-            StorageType::Tables => Storage::Tables(HashMap::new()),
-            StorageType::Sparse => Storage::SparseTag(SparseTag::new()),
-        };
+        let storage = new_storage(world, id, self.storage_type, self.flags, None);
 
         world.components.insert(
             id,
             ComponentInfo {
                 id,
+                name: self.name,
                 flags: self.flags,
                 type_info: None,
                 storage,
+                with: self.with,
+                priority: self.priority,
             },
         );
     }
 }
 
+impl BuilderCommon for TagBuilder {
+    #[inline]
+    fn name(self, name: impl Into<TypeName>) -> Self {
+        TagBuilder::name(self, name)
+    }
+
+    #[inline]
+    fn storage(self, storage: StorageType) -> Self {
+        TagBuilder::storage(self, storage)
+    }
+
+    #[inline]
+    fn flags_mut(&mut self) -> &mut ComponentFlags {
+        &mut self.flags
+    }
+}
+
+impl Default for TagBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs once a component is registered.
+type OnRegister = Box<dyn FnOnce(&mut World, Id)>;
+
 pub struct ComponentBuilder<T: DataComponent> {
     name: Option<TypeName>,
     hooks: TypeHooksBuilder<T>,
     flags: ComponentFlags,
     storage_type: StorageType,
+    with: Vec<Id>,
+    priority: i32,
+    on_register: Option<OnRegister>,
 }
 
 impl<T: Component + DataComponent> ComponentBuilder<T> {
     pub fn new() -> Self {
         Self {
             name: None,
-            hooks: TypeHooksBuilder::new(),
+            hooks: T::default_hooks(),
             flags: ComponentFlags::empty(),
             storage_type: T::STORAGE,
+            with: vec![],
+            priority: 0,
+            on_register: None,
         }
     }
 
@@ -118,76 +258,184 @@ impl<T: Component + DataComponent> ComponentBuilder<T> {
     }
 
     #[inline]
-    pub fn add_flags(mut self, flags: ComponentFlags) -> Self {
-        self.flags.insert(flags);
+    pub fn default(mut self, f: fn() -> T) -> Self {
+        self.hooks = self.hooks.with_default(f);
+        self
+    }
+
+    #[inline]
+    pub fn clone(mut self, f: fn(&T) -> T) -> Self {
+        self.hooks = self.hooks.with_clone(f);
         self
     }
 
     #[inline]
-    pub fn set_flags(mut self, flags: ComponentFlags) -> Self {
-        self.flags = flags;
+    pub fn on_set(mut self, f: impl FnMut(Id, &mut T) + 'static) -> Self {
+        self.hooks = self.hooks.on_set(f);
         self
     }
 
     #[inline]
-    pub fn clear_flags(mut self, flags: ComponentFlags) -> Self {
-        self.flags.remove(flags);
+    pub fn on_remove(mut self, f: impl FnMut(Id, &mut T) + 'static) -> Self {
+        self.hooks = self.hooks.on_remove(f);
         self
     }
 
     #[inline]
-    pub fn default(mut self, f: fn() -> T) -> Self {
-        self.hooks = self.hooks.with_default(f);
+    pub fn fmt(mut self, f: fn(&T, &mut std::fmt::Formatter) -> std::fmt::Result) -> Self {
+        self.hooks = self.hooks.with_fmt(f);
         self
     }
 
     #[inline]
-    pub fn clone(mut self, f: fn(&T) -> T) -> Self {
-        self.hooks = self.hooks.with_clone(f);
+    pub fn serialize(mut self, ser: fn(&T, &mut Vec<u8>), de: fn(&[u8]) -> T) -> Self {
+        self.hooks = self.hooks.with_serialize(ser, de);
         self
     }
 
+    /// Marks the type as plain old data, see [TypeHooksBuilder::with_pod].
+    ///
+    /// # Safety
+    /// `T` must have no padding, and every bit pattern of its size must be a valid `T`.
     #[inline]
-    pub fn on_set(mut self, f: impl FnMut(Id, &mut T) + 'static) -> Self {
-        self.hooks = self.hooks.on_set(f);
+    pub unsafe fn pod(mut self) -> Self {
+        // SAFETY: Caller upholds the contract of with_pod.
+        self.hooks = unsafe { self.hooks.with_pod() };
         self
     }
 
+    /// Sets the version of the serialized layout, see [TypeHooksBuilder::with_version].
     #[inline]
-    pub fn on_remove(mut self, f: impl FnMut(Id, &mut T) + 'static) -> Self {
-        self.hooks = self.hooks.on_remove(f);
+    pub fn version(mut self, version: u32) -> Self {
+        self.hooks = self.hooks.with_version(version);
+        self
+    }
+
+    /// Converts values serialized with an older `version`, see [TypeHooksBuilder::with_migration].
+    #[inline]
+    pub fn migrate_from(mut self, version: u32, f: fn(&[u8]) -> T) -> Self {
+        self.hooks = self.hooks.with_migration(version, f);
         self
     }
 
-    pub(crate) fn build(mut self, world: &mut World, id: Id) {
+    /// Sets the processing order of the component among components, higher first.
+    ///
+    /// Batched observers see the components of an id in this order, see [World::dispatch_events].
+    /// Defaults to 0. Pairs use the priority of their relationship.
+    #[inline]
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[inline]
+    pub fn eq(mut self, f: fn(&T, &T) -> bool) -> Self {
+        self.hooks = self.hooks.with_eq(f);
+        self
+    }
+
+    #[inline]
+    pub fn hash(mut self, f: fn(&T) -> u64) -> Self {
+        self.hooks = self.hooks.with_hash(f);
+        self
+    }
+
+    /// Adds `other` whenever this component is added, see [TagBuilder::auto_add].
+    pub fn auto_add(mut self, other: Id) -> Self {
+        self.flags.insert(ComponentFlags::WITH);
+        self.with.push(other);
+        self
+    }
+
+    /// Marks the component as a relationship, see [ComponentFlags::RELATIONSHIP].
+    #[inline]
+    pub fn relationship(mut self) -> Self {
+        self.flags.insert(ComponentFlags::RELATIONSHIP);
+        self
+    }
+
+    /// Calls `f` with the component id once it's registered, e.g. to register
+    /// components it depends on.
+    pub fn on_register(mut self, f: impl FnOnce(&mut World, Id) + 'static) -> Self {
+        self.on_register = Some(Box::new(f));
+        self
+    }
+
+    pub(crate) fn build(mut self, world: &mut World, id: Id) -> EcsResult<()> {
         debug_assert!(id.is_id(), "attempted to build pair as entity");
 
-        let type_info = Rc::new(TypeInfo::of::<T>(self.hooks));
+        // Both sides of a symmetric pair can't share one value, so only tags can be symmetric.
+        if self.flags.contains(ComponentFlags::SYMMETRIC) {
+            return Err(EcsError::SymmetricData(id));
+        }
 
-        let storage = match self.storage_type {
-            StorageType::Tables => Storage::Tables(HashMap::new()),
-            StorageType::Sparse => Storage::SparseData(SparseData::new(id, Rc::clone(&type_info))),
-        };
+        let type_info = Rc::new(TypeInfo::of::<T>(self.hooks));
 
         self.flags.remove(ComponentFlags::IS_TAG);
 
+        let storage = new_storage(world, id, self.storage_type, self.flags, Some(&type_info));
+
         world.components.insert(
             id,
             ComponentInfo {
                 id,
+                name: self.name,
                 flags: self.flags,
                 type_info: Some(type_info),
                 storage,
+                with: self.with,
+                priority: self.priority,
             },
         );
+
+        if let Some(on_register) = self.on_register {
+            on_register(world, id);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Component + DataComponent> BuilderCommon for ComponentBuilder<T> {
+    #[inline]
+    fn name(self, name: impl Into<TypeName>) -> Self {
+        ComponentBuilder::name(self, name)
+    }
+
+    #[inline]
+    fn storage(self, storage: StorageType) -> Self {
+        ComponentBuilder::storage(self, storage)
+    }
+
+    #[inline]
+    fn flags_mut(&mut self) -> &mut ComponentFlags {
+        &mut self.flags
+    }
+}
+
+impl<T: Component + DataComponent> Default for ComponentBuilder<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// Ensures that a component exists for this id.
 ///
 /// This function creates the component as a tag if it didn't exist.
+/// Pairs stored in a relation store have no component of their own.
+/// The target of a pair is flagged with [IdFlags::IS_TARGET].
 pub(crate) fn ensure_component(world: &mut World, comp: Id) {
-    if !world.components.contains(comp) {
+    // The pair is about to be added, so its target becomes a relationship target.
+    if comp.is_pair()
+        && let Some(tgt) = world.id_manager.get_current(comp.pair_tgt())
+    {
+        set_id_flag(world, tgt, IdFlags::IS_TARGET);
+        world
+            .target_generations
+            .insert(tgt.index(), tgt.generation());
+    }
+
+    if !world.components.contains(comp) && stored_relation(world, comp).is_none() {
         if comp.is_pair() {
             build_pair(world, comp);
         } else {
@@ -197,6 +445,59 @@ pub(crate) fn ensure_component(world: &mut World, comp: Id) {
     }
 }
 
+/// Checks that the auto-added components can be added without a value.
+///
+/// Ids that are not registered yet are created as tags when first added.
+fn check_auto_added(world: &World, with: &[Id]) -> EcsResult<()> {
+    for &auto in with {
+        let Some(ci) = world.components.get(auto) else {
+            continue;
+        };
+
+        if matches!(ci.storage, Storage::SparseRelation(_)) {
+            return Err(EcsError::IsRelationship(auto));
+        }
+
+        if let Some(ti) = &ci.type_info
+            && ti.hooks.default.is_none()
+        {
+            return Err(EcsError::NoDefaultHook(auto));
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates empty storage for a component.
+///
+/// Sparse relationships store all their pairs in a single [RelationStore].
+pub(crate) fn new_storage(
+    world: &World,
+    id: Id,
+    storage_type: StorageType,
+    flags: ComponentFlags,
+    type_info: Option<&Rc<TypeInfo>>,
+) -> Storage {
+    match storage_type {
+        StorageType::Tables => Storage::Tables(HashMap::new()),
+        StorageType::Sparse if flags.contains(ComponentFlags::RELATIONSHIP) => {
+            Storage::SparseRelation(RelationStore::new(
+                id,
+                type_info.map(Rc::clone),
+                Rc::clone(&world.allocator),
+            ))
+        }
+        StorageType::Sparse => match type_info {
+            Some(ti) => Storage::SparseData(SparseData::new(
+                id,
+                Rc::clone(ti),
+                Rc::clone(&world.allocator),
+            )),
+            None => Storage::SparseTag(SparseTag::new()),
+        },
+    }
+}
+
 pub(crate) fn build_pair(world: &mut World, id: Id) {
     debug_assert!(id.is_pair(), "attemped to build entity as pair");
 
@@ -207,7 +508,10 @@ pub(crate) fn build_pair(world: &mut World, id: Id) {
 
     let ci_r = world.components.get(rel).unwrap();
     let flags = ci_r.flags;
+    // Pairs of the relationship auto-add the same tags.
+    let with = ci_r.with.clone();
     let storage_type = ci_r.storage.get_type();
+    let priority = ci_r.priority;
 
     // TODO: pair storages.
 
@@ -222,21 +526,18 @@ pub(crate) fn build_pair(world: &mut World, id: Id) {
         }
     };
 
-    let storage = match storage_type {
-        StorageType::Tables => Storage::Tables(HashMap::new()),
-        StorageType::Sparse => match &type_info {
-            Some(ti) => Storage::SparseData(SparseData::new(id, Rc::clone(ti))),
-            None => Storage::SparseTag(SparseTag::new()),
-        },
-    };
+    let storage = new_storage(world, id, storage_type, flags, type_info.as_ref());
 
     world.components.insert(
         id,
         ComponentInfo {
             id,
+            name: None,
             flags,
             type_info,
             storage,
+            with,
+            priority,
         },
     );
 }
@@ -247,18 +548,35 @@ pub(crate) mod private {
 
 #[doc(hidden)]
 pub trait ComponentDescriptor {
-    fn build(self, world: &mut World, id: Id, _: private::Passkey);
+    /// Returns an error if the settings are invalid, nothing is registered then.
+    fn build(self, world: &mut World, id: Id, _: private::Passkey) -> EcsResult<()>;
+
+    /// Storage type the component is built with.
+    fn storage_type(&self) -> StorageType;
 }
 
 impl ComponentDescriptor for TagBuilder {
     #[inline(always)]
-    fn build(self, world: &mut World, id: Id, _: private::Passkey) {
+    fn build(self, world: &mut World, id: Id, _: private::Passkey) -> EcsResult<()> {
+        check_auto_added(world, &self.with)?;
         self.build(world, id);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn storage_type(&self) -> StorageType {
+        self.storage_type
     }
 }
 
 impl<T: Component + DataComponent> ComponentDescriptor for ComponentBuilder<T> {
-    fn build(self, world: &mut World, id: Id, _: private::Passkey) {
-        self.build(world, id);
+    fn build(self, world: &mut World, id: Id, _: private::Passkey) -> EcsResult<()> {
+        check_auto_added(world, &self.with)?;
+        self.build(world, id)
+    }
+
+    #[inline(always)]
+    fn storage_type(&self) -> StorageType {
+        self.storage_type
     }
 }
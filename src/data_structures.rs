@@ -9,11 +9,13 @@ impl SparseIndex for usize {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct Entry<K: SparseIndex, V = K> {
     pub(crate) key: K,
     pub(crate) value: V,
 }
 
+#[derive(Clone)]
 pub struct SparseSet<K: SparseIndex + PartialEq, V> {
     dense: Vec<Entry<K, V>>,
     sparse: Vec<usize>,
@@ -51,6 +53,22 @@ impl<K: SparseIndex + PartialEq, V> SparseSet<K, V> {
         }
     }
 
+    /// Returns the entry of the key, to read or insert its value with a single lookup.
+    pub fn entry(&mut self, key: K) -> SparseEntry<'_, K, V> {
+        let sparse_idx = key.to_sparse_index();
+
+        if sparse_idx >= self.sparse.len() {
+            self.sparse.resize(sparse_idx + 1, Self::INVALID_DENSE_IDX);
+        }
+
+        let dense_idx = self.sparse[sparse_idx];
+
+        match dense_idx < self.dense.len() {
+            true => SparseEntry::Occupied(&mut self.dense[dense_idx].value),
+            false => SparseEntry::Vacant(VacantEntry { set: self, key, sparse_idx }),
+        }
+    }
+
     /// Removes an entry from the set.
     /// Returns the value associated with the key if it was present.
     pub fn remove(&mut self, key: &K) -> Option<V> {
@@ -79,6 +97,52 @@ impl<K: SparseIndex + PartialEq, V> SparseSet<K, V> {
         }
     }
 
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// Returns the number of bytes used by the sparse array.
+    #[inline]
+    pub fn sparse_bytes(&self) -> usize {
+        self.sparse.len() * size_of::<usize>()
+    }
+
+    /// Returns the number of bytes allocated for the dense and sparse arrays.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.dense.capacity() * size_of::<Entry<K, V>>()
+            + self.sparse.capacity() * size_of::<usize>()
+    }
+
+    /// Reserves capacity for at least `additional` more entries.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.dense.reserve(additional);
+    }
+
+    /// Removes all entries, dropping their values.
+    ///
+    /// The sparse array keeps its allocation.
+    pub fn clear(&mut self) {
+        self.dense.clear();
+        self.sparse.fill(Self::INVALID_DENSE_IDX);
+    }
+
+    /// Returns an iterator over all entries in insertion order (unless removals occured).
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.dense.iter().map(|e| (&e.key, &e.value))
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.dense.iter_mut().map(|e| &mut e.value)
+    }
+
     #[inline]
     pub fn get(&self, key: &K) -> Option<&V> {
         self.sparse
@@ -95,3 +159,43 @@ impl<K: SparseIndex + PartialEq, V> SparseSet<K, V> {
             .map(|e| &mut e.value)
     }
 }
+
+/// Entry of a key in a [SparseSet], see [SparseSet::entry].
+pub enum SparseEntry<'a, K: SparseIndex, V> {
+    Occupied(&'a mut V),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<'a, K: SparseIndex, V> SparseEntry<'a, K, V> {
+    /// Returns the value, inserting the result of `f` if the key is vacant.
+    #[inline]
+    pub fn or_insert_with(self, f: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            Self::Occupied(value) => value,
+            Self::Vacant(entry) => entry.insert(f()),
+        }
+    }
+}
+
+/// Key of a [SparseSet] without a value, see [SparseSet::entry].
+pub struct VacantEntry<'a, K: SparseIndex, V> {
+    set: &'a mut SparseSet<K, V>,
+    key: K,
+    sparse_idx: usize,
+}
+
+impl<'a, K: SparseIndex, V> VacantEntry<'a, K, V> {
+    /// Inserts the value of the key.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let set = self.set;
+        set.sparse[self.sparse_idx] = set.dense.len();
+        set.dense.push(Entry { key: self.key, value });
+        &mut set.dense.last_mut().unwrap().value
+    }
+}
+
+impl<K: SparseIndex + PartialEq, V> Default for SparseSet<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
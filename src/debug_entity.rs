@@ -0,0 +1,68 @@
+use crate::{
+    id::Id,
+    world::World,
+    world_utils::{components_of, get_component_ptr, id_name},
+};
+use std::fmt;
+
+/// Debug view of an id and its components, created by [World::debug_entity].
+///
+/// Values are only shown for components registered with a fmt hook.
+pub struct DebugEntity<'w> {
+    world: &'w World,
+    id: Id,
+}
+
+impl<'w> DebugEntity<'w> {
+    pub(crate) fn new(world: &'w World, id: Id) -> Self {
+        Self { world, id }
+    }
+}
+
+/// A single component of a [DebugEntity].
+struct DebugComponent<'w> {
+    world: &'w World,
+    id: Id,
+    comp: Id,
+}
+
+impl fmt::Debug for DebugComponent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&id_name(self.world, self.comp))?;
+
+        let hook = self
+            .world
+            .components
+            .get(self.comp)
+            .and_then(|ci| ci.type_info.as_ref())
+            .and_then(|ti| ti.hooks.fmt.as_ref());
+
+        match (hook, get_component_ptr(self.world, self.id, self.comp)) {
+            (Some(hook), Some(ptr)) => {
+                f.write_str(": ")?;
+                hook(ptr.as_ptr(), f)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for DebugEntity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comps = components_of(self.world, self.id).unwrap_or_default();
+        let comps: Vec<_> = comps
+            .into_iter()
+            .map(|comp| DebugComponent {
+                world: self.world,
+                id: self.id,
+                comp,
+            })
+            .collect();
+
+        f.debug_struct("Entity")
+            .field("id", &self.id)
+            .field("alive", &self.world.is_alive(self.id))
+            .field("components", &comps)
+            .finish()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    error::GetResult,
+    get_params::component_ptr,
+    id::{Id, Signature, manager::IdLocation},
+    type_traits::{DataComponent, TypedId},
+    world::World,
+    world_utils::{has_component_at, missing_component},
+};
+
+/// Read-only handle to an id, created by [World::entity].
+///
+/// The id is located once, so reading several components of the same id
+/// doesn't look it up again.
+#[derive(Clone, Copy)]
+pub struct EntityRef<'w> {
+    world: &'w World,
+    id: Id,
+    loc: IdLocation,
+}
+
+impl<'w> EntityRef<'w> {
+    #[inline]
+    pub(crate) fn new(world: &'w World, id: Id, loc: IdLocation) -> Self {
+        Self { world, id, loc }
+    }
+
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Gets a reference to the component `T` of the id.
+    pub fn get<T>(&self) -> GetResult<&'w T::Data>
+    where
+        T: TypedId,
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self.world)?;
+
+        match component_ptr(self.world, self.id, self.loc, comp)? {
+            // SAFETY: The id of T holds values of T::Data.
+            Some(ptr) => Ok(unsafe { ptr.deref::<T::Data>() }),
+            None => Err(missing_component(self.world, comp)),
+        }
+    }
+
+    /// Checks if the id has the component.
+    #[inline]
+    pub fn has<T: TypedId>(&self) -> bool {
+        T::id(self.world).is_ok_and(|comp| self.has_id(comp))
+    }
+
+    /// Checks if the id has the component.
+    #[inline]
+    pub fn has_id(&self, comp: Id) -> bool {
+        has_component_at(self.world, self.id, self.loc, comp)
+    }
+
+    /// Returns the signature of the table of the id.
+    ///
+    /// Sparse components are not part of it.
+    #[inline]
+    pub fn signature(&self) -> &'w Signature {
+        &self.world.table_index[self.loc.table].signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{component::ComponentBuilder, id::manager::LOCATION_LOOKUPS, world::World};
+    use xecs_macros::Component;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Age(u32);
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Height(f32);
+
+    #[test]
+    fn reads_locate_the_id_once() {
+        let mut world = World::new();
+        world.register::<Name>(ComponentBuilder::new());
+        world.register::<Age>(ComponentBuilder::new());
+        world.register::<Height>(ComponentBuilder::new());
+
+        let id = world.new_id();
+        world.set::<Name>(id, Name("bob"));
+        world.set::<Age>(id, Age(30));
+        world.set::<Height>(id, Height(1.8));
+
+        let before = LOCATION_LOOKUPS.with(|count| count.get());
+        let entity = world.entity(id).unwrap();
+
+        assert_eq!(entity.get::<Name>().ok(), Some(&Name("bob")));
+        assert_eq!(entity.get::<Age>().ok(), Some(&Age(30)));
+        assert_eq!(entity.get::<Height>().ok(), Some(&Height(1.8)));
+        assert_eq!(LOCATION_LOOKUPS.with(|count| count.get()) - before, 1);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::id::Id;
+use crate::{id::Id, storage::StorageType};
 use std::fmt::{Debug, Display};
 use thiserror::Error;
 
@@ -14,10 +14,52 @@ pub enum EcsError {
     IsNotTag(Id),
     #[error("{0}")]
     MissingComponent(#[from] MissingComponent),
-    #[error("Type {0} is not registered for this world, must register before use")]
+    #[error("{0}")]
     UnregisteredType(#[from] UnregisteredTypeErr),
     #[error("Entity {0} is not registered as a component")]
     IdNotComponent(Id),
+    #[error("Component {0} has no default hook")]
+    NoDefaultHook(Id),
+    #[error("Component {0} has no clone hook and is not plain old data")]
+    NoCloneHook(Id),
+    #[error("Component {0} data is not of the given type")]
+    TypeMismatch(Id),
+    #[error("Component {0} is written more than once")]
+    DuplicateComponent(Id),
+    #[error("Component {0} is already registered with a different storage type")]
+    StorageConflict(Id),
+    #[error("Component {0} has data, only tags can be symmetric relationships")]
+    SymmetricData(Id),
+    #[error("Relationship {0} stores its pairs together, it can only be added as a pair")]
+    IsRelationship(Id),
+    #[error("Relationship {0} is exclusive, an id can have only one of its pairs")]
+    ExclusiveRelationship(Id),
+    #[error("Index {0} is already used by an alive id")]
+    IndexInUse(u32),
+    #[error("Index {0} was retired and can't be issued again")]
+    IndexRetired(u32),
+    #[error("Guid {0:#x} is already used by another id")]
+    GuidCollision(u128),
+    #[error("Id {0} is not a union")]
+    NotUnion(Id),
+    #[error("Component {1} is not a variant of union {0}")]
+    NotUnionVariant(Id, Id),
+    #[error("Union {0} is exclusive, an id can have only one of its variants")]
+    ExclusiveUnion(Id),
+    #[error("A union needs at least one variant")]
+    EmptyUnion,
+    #[error("Component {0} is listed more than once as a union variant")]
+    DuplicateUnionVariant(Id),
+    #[error("Component {0} is already a variant of union {1}")]
+    VariantOfOtherUnion(Id, Id),
+    #[error("No component is named {0}")]
+    UnknownComponent(String),
+    #[error("Component {0} has no migration from version {1} to version {2}")]
+    MissingMigration(Id, u32, u32),
+    #[error("Malformed entity data: {0}")]
+    MalformedData(&'static str),
+    #[error("Entity data is too large to serialize: {0}")]
+    DataTooLarge(&'static str),
     #[error("User error: {0}")]
     Other(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
@@ -41,12 +83,25 @@ pub enum InvalidPair {
 #[error("Id {0} is does not have component {1}")]
 pub struct MissingComponent(pub Id, pub Id);
 
+/// Error returned when a type is used with a world it's not registered with.
 #[derive(Error, Debug)]
 pub struct UnregisteredTypeErr(fn() -> &'static str);
 
+impl UnregisteredTypeErr {
+    /// Returns the name of the unregistered type.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        (self.0)()
+    }
+}
+
 impl Display for UnregisteredTypeErr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Type: {} is not registered", (self.0)())
+        write!(
+            f,
+            "Type {} is not registered for this world, must register before use",
+            self.type_name()
+        )
     }
 }
 
@@ -56,14 +111,41 @@ pub enum GetError {
     InvalidId(#[from] InvalidId),
     #[error("Id: {0} is not a component")]
     IdNotComponent(Id),
-    #[error("Id does not have component {0}")]
-    MissingComponent(Id),
-    #[error("Type {0} is not registered for this world, must register before use")]
+    /// The id lacks the component, with the storage that was checked for it.
+    #[error("Id does not have component {0} (checked {1:?} storage)")]
+    MissingComponent(Id, StorageType),
+    #[error("{0}")]
     UnregisteredType(#[from] UnregisteredTypeErr),
+    #[error("Component {0} is accessed mutably more than once")]
+    AliasedComponent(Id),
+    #[error("Id does not have any of the components {0:?}")]
+    MissingAnyComponent(Vec<Id>),
 }
 
 pub type GetResult<T> = Result<T, GetError>;
 
+/// Inconsistency found by [World::check_invariants](crate::world::World::check_invariants).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The location of the id doesn't point to its row.
+    #[error("Id {0} location does not point to its row {1}")]
+    InvalidLocation(Id, usize),
+    /// The id in a table row is recorded at a different location.
+    #[error("Id {0} in table row {1} is recorded at a different location")]
+    UnrecordedRow(Id, usize),
+    /// The column of a component doesn't have the same number of rows as its table.
+    ///
+    /// Carries the debug name of the table.
+    #[error("Column for component {0} has {1} rows, table {3} has {2}")]
+    ColumnLength(Id, usize, usize, String),
+    /// A sparse component has an entry for a dead id.
+    #[error("Sparse component {0} has an entry for dead id {1}")]
+    DeadSparseEntry(Id, Id),
+    /// An id has a pair whose target is not alive.
+    #[error("Id {0} has pair {1} with a dead target")]
+    DeadPairTarget(Id, Id),
+}
+
 /// Unregistered type error.
 pub(crate) const fn unreg_type_err<T>() -> UnregisteredTypeErr {
     UnregisteredTypeErr(std::any::type_name::<T>)
@@ -7,6 +7,18 @@ macro_rules! impl_bitflags {
                 Self(0)
             }
 
+            /// Returns the raw bits of the flags.
+            #[inline]
+            pub const fn bits(self) -> u64 {
+                self.0
+            }
+
+            /// Creates flags from raw bits.
+            #[inline]
+            pub const fn from_bits(bits: u64) -> Self {
+                Self(bits)
+            }
+
             #[inline]
             pub const fn contains(self, other: Self) -> bool {
                 (self.0 & other.0) == other.0
@@ -157,6 +169,8 @@ impl IdFlags {
     pub const IS_TARGET: Self = Self(1 << 1);
     pub const IS_TRAVERSABLE: Self = Self(1 << 2);
     pub const HAS_SPARSE: Self = Self(1 << 3);
+    /// The id is marked for deletion at the next flush.
+    pub const PENDING_DELETE: Self = Self(1 << 4);
 }
 
 impl_bitflags!(IdFlags);
@@ -169,6 +183,75 @@ impl ComponentFlags {
     pub const IS_TAG: Self = Self(1 << 0);
     /// Marks the component as exclusive when used as a relationship.
     pub const EXCLUSIVE: Self = Self(1 << 1);
+    /// Marks the tag as symmetric when used as a relationship.
+    /// Adding `(R, B)` to `A` also adds `(R, A)` to `B`.
+    pub const SYMMETRIC: Self = Self(1 << 2);
+    /// Marks the tag as transitive when used as a relationship.
+    /// If `A` has `(R, B)` and `B` has `(R, C)`, `A` is also related to `C`.
+    pub const TRANSITIVE: Self = Self(1 << 3);
+    /// Adding the component also adds the tags it was configured to auto-add.
+    pub const WITH: Self = Self(1 << 4);
+    /// Marks the component as a relationship.
+    /// Pairs of a sparse relationship share a single storage instead of one per pair.
+    pub const RELATIONSHIP: Self = Self(1 << 5);
+
+    /// Ids with the component lose it when the component is deleted.
+    pub const ON_DELETE_REMOVE: Self = Self(1 << 7);
+    /// Ids with the component are deleted along with the component.
+    pub const ON_DELETE_DELETE: Self = Self(1 << 8);
+    /// Deleting the component while ids have it panics.
+    pub const ON_DELETE_PANIC: Self = Self(1 << 9);
+    /// All policies for deleting the component, only one is set at a time.
+    pub const ON_DELETE_MASK: Self =
+        Self(Self::ON_DELETE_REMOVE.0 | Self::ON_DELETE_DELETE.0 | Self::ON_DELETE_PANIC.0);
+
+    /// Ids with a pair of the relationship lose it when the target is deleted.
+    pub const ON_DELETE_TARGET_REMOVE: Self = Self(1 << 10);
+    /// Ids with a pair of the relationship are deleted along with the target.
+    pub const ON_DELETE_TARGET_DELETE: Self = Self(1 << 11);
+    /// Deleting the target of a pair of the relationship panics.
+    pub const ON_DELETE_TARGET_PANIC: Self = Self(1 << 12);
+    /// All policies for deleting the target of a pair, only one is set at a time.
+    pub const ON_DELETE_TARGET_MASK: Self = Self(
+        Self::ON_DELETE_TARGET_REMOVE.0
+            | Self::ON_DELETE_TARGET_DELETE.0
+            | Self::ON_DELETE_TARGET_PANIC.0,
+    );
+
+    /// Tag added with the active variant of a union, see
+    /// [World::register_union](crate::world::World::register_union).
+    pub const UNION: Self = Self(1 << 13);
 }
 
 impl_bitflags!(ComponentFlags);
+
+/// What happens to the ids using a component or relationship target when it's deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// The component or pair is removed from the ids.
+    Remove,
+    /// The ids are deleted too.
+    Delete,
+    /// Deleting panics.
+    Panic,
+}
+
+impl Policy {
+    /// Returns the flag of the policy in [ComponentFlags::ON_DELETE_MASK].
+    pub const fn on_delete_flag(self) -> ComponentFlags {
+        match self {
+            Policy::Remove => ComponentFlags::ON_DELETE_REMOVE,
+            Policy::Delete => ComponentFlags::ON_DELETE_DELETE,
+            Policy::Panic => ComponentFlags::ON_DELETE_PANIC,
+        }
+    }
+
+    /// Returns the flag of the policy in [ComponentFlags::ON_DELETE_TARGET_MASK].
+    pub const fn on_delete_target_flag(self) -> ComponentFlags {
+        match self {
+            Policy::Remove => ComponentFlags::ON_DELETE_TARGET_REMOVE,
+            Policy::Delete => ComponentFlags::ON_DELETE_TARGET_DELETE,
+            Policy::Panic => ComponentFlags::ON_DELETE_TARGET_PANIC,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Random operation sequences against the public API, checking that malformed input
+//! (dead ids, unregistered components, tags where data is expected) comes back as an
+//! error instead of a panic.
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::{
+    component::{ComponentBuilder, TagBuilder},
+    error::InvariantViolation,
+    id::{Id, IntoId, Signature},
+    query::{SelectStmt, WithStmt},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+const OPS: usize = 100_000;
+
+#[derive(Component, Clone, Debug)]
+struct Pos(#[allow(dead_code)] u32);
+
+#[derive(Component, Clone, Debug)]
+struct Name(#[allow(dead_code)] String);
+
+#[derive(Component, Clone, Debug)]
+struct Owes(#[allow(dead_code)] u32);
+
+#[derive(Component)]
+struct Marker;
+
+#[derive(Component)]
+struct Likes;
+
+/// xorshift64*, so runs are reproducible without a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+struct Fuzzer {
+    world: World,
+    rng: Rng,
+    /// Every id ever spawned, dead ones included, so stale and recycled ids are used too.
+    ids: Vec<Id>,
+    comps: Vec<Id>,
+    likes: Id,
+}
+
+impl Fuzzer {
+    fn new(seed: u64) -> Self {
+        let mut world = World::new();
+        let comps = vec![
+            world.register::<Pos>(
+                ComponentBuilder::new()
+                    .storage(StorageType::Tables)
+                    .clone(Pos::clone),
+            ),
+            world.register::<Name>(ComponentBuilder::new().clone(Name::clone)),
+            world.register::<Owes>(ComponentBuilder::new().clone(Owes::clone)),
+            world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables)),
+            world.register::<Likes>(TagBuilder::new()),
+            world.child_of(),
+            world.prefab_tag(),
+            Id::NULL,
+        ];
+        let likes = comps[4];
+
+        Self {
+            world,
+            rng: Rng(seed),
+            ids: vec![],
+            comps,
+            likes,
+        }
+    }
+
+    fn id(&mut self) -> Id {
+        match self.ids.is_empty() || self.rng.below(16) == 0 {
+            true => self.rng.pick(&self.comps),
+            false => self.rng.pick(&self.ids),
+        }
+    }
+
+    /// A component, a plain id or a pair, valid or not.
+    fn comp(&mut self) -> Id {
+        match self.rng.below(4) {
+            0 => self.id(),
+            1 => (self.rng.pick(&self.comps), self.id()).into_id(),
+            _ => self.rng.pick(&self.comps),
+        }
+    }
+
+    fn step(&mut self) {
+        let id = self.id();
+        let comp = self.comp();
+        let val = self.rng.next() as u32;
+        let world = &mut self.world;
+
+        match self.rng.below(20) {
+            0..=2 => self.ids.push(world.new_id()),
+            3 => _ = world.despawn(id),
+            4 => _ = world.add_id(id, comp),
+            5 => _ = world.set::<Pos>(id, Pos(val)),
+            6 => _ = world.set::<Name>(id, Name(val.to_string())),
+            7 => _ = world.set_id(id, comp, Owes(val)),
+            8 => _ = world.remove_id(id, comp),
+            9 => _ = world.add_id(id, (self.likes, comp)),
+            10 => _ = world.set_pair_target(id, comp, Owes(val)),
+            11 => _ = world.remove::<Pos>(id),
+            12 => _ = world.move_to(id, &Signature::from(vec![comp, self.comps[0]])),
+            13 => _ = world.replace_component::<Pos, Name>(id, Name(val.to_string())),
+            14 => _ = world.batch_remove::<Name>(&[id, comp]),
+            15 => {
+                _ = world.mark_for_delete(id);
+
+                if val.is_multiple_of(4) {
+                    world.flush_deletions();
+                }
+            }
+            16 => {
+                let select = SelectStmt::default().read(comp);
+                _ = world.collect(select, WithStmt::new().without(id));
+            }
+            17 => {
+                _ = world.collect(SelectStmt::default(), WithStmt::new().with_pair(comp, id));
+            }
+            18 => {
+                _ = world.get::<&Pos>(id);
+                _ = world.has_id(id, comp);
+                _ = world.pairs_of(id, comp);
+                _ = world
+                    .entity(id)
+                    .map(|entity| entity.signature().ids().len());
+            }
+            _ => match val % 4 {
+                0 => world.compact_tables(),
+                1 => _ = world.defragment(16),
+                2 => _ = world.get_or_default::<Pos>(id),
+                _ => _ = world.reparent_children(id, comp),
+            },
+        }
+    }
+}
+
+#[test]
+fn random_ops_dont_panic() {
+    let mut fuzzer = Fuzzer::new(0x9e37_79b9_7f4a_7c15);
+
+    for op in 0..OPS {
+        let result = catch_unwind(AssertUnwindSafe(|| fuzzer.step()));
+        assert!(result.is_ok(), "operation {op} panicked");
+    }
+
+    // Pairs targeting despawned ids are kept unless their relationship has a target policy.
+    let violations: Vec<_> = fuzzer
+        .world
+        .check_invariants()
+        .into_iter()
+        .filter(|violation| !matches!(violation, InvariantViolation::DeadPairTarget(..)))
+        .collect();
+    assert_eq!(violations, vec![]);
+}
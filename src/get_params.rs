@@ -1,10 +1,19 @@
 use crate::{
     error::{GetError, GetResult},
     id::{Id, manager::IdLocation},
+    query::SelectStmt,
+    storage::{
+        Storage,
+        column::Ptr,
+        relation::{relation_store, stored_relation},
+    },
     type_traits::{DataComponent, TypedId},
+    unsafe_world_ptr::UnsafeWorldPtr,
     world::World,
+    world_utils::missing_component,
 };
 use private::Sealed;
+use std::marker::PhantomData;
 use xecs_macros::all_tuples;
 
 mod private {
@@ -12,38 +21,124 @@ mod private {
 }
 
 pub trait GetParam: Sealed {
-    type Data: DataComponent;
     type Output<'a>;
     const IS_IMMUTABLE: bool;
-    fn make(world: &World, id: Id, loc: IdLocation) -> GetResult<Self::Output<'_>>;
+
+    /// Returns the component accessed by the param, used to detect aliasing mutable access.
+    fn component(world: &World) -> Option<Id>;
+
+    /// Returns true if the param accesses `comp`.
+    ///
+    /// Params that may access more than one component override this.
+    #[inline]
+    fn accesses(world: &World, comp: Id) -> bool {
+        Self::component(world) == Some(comp)
+    }
+
+    /// Adds the table components required by the param to a query.
+    ///
+    /// Sparse components can't be matched by tables, they're checked per id instead.
+    #[inline]
+    fn select_terms(_world: &World, stmt: SelectStmt) -> SelectStmt {
+        stmt
+    }
+
+    /// # Safety
+    /// - Caller ensures that no other live reference aliases the component of `id`
+    ///   if the param is mutable.
+    unsafe fn make(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::Output<'_>>;
 }
 
 impl<T: GetParam> private::Sealed for T {}
 
+/// Returns a pointer to the data of `comp` for `id`, or `None` if `id` doesn't have it.
+///
+/// Only shared access to the world is needed, the pointer is derived from the storage buffers.
+/// `loc` must be the current location of `id`.
+pub(crate) fn component_ptr(
+    world: &World,
+    id: Id,
+    loc: IdLocation,
+    comp: Id,
+) -> GetResult<Option<Ptr<'_>>> {
+    if let Some(rel) = stored_relation(world, comp) {
+        let store = relation_store(world, rel);
+
+        // SAFETY: The store is borrowed with the world, so the value can't move.
+        return match store.is_tag() {
+            true => Err(GetError::IdNotComponent(comp)),
+            false => Ok(store
+                .get_ptr(id, comp.pair_tgt())
+                .map(|ptr| unsafe { Ptr::new(ptr) })),
+        };
+    }
+
+    let comp_info = match world.components.get(comp) {
+        Some(ci) => ci,
+        None => return Err(GetError::IdNotComponent(comp)),
+    };
+
+    Ok(match &comp_info.storage {
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => {
+            return Err(GetError::IdNotComponent(comp));
+        }
+        // SAFETY: The set is borrowed with the world, so the value can't move.
+        Storage::SparseData(set) => set.get_ptr(id).map(|ptr| unsafe { Ptr::new(ptr) }),
+        Storage::Tables(_) => {
+            let table = &world.table_index[loc.table];
+
+            // SAFETY: The location of an alive id has a valid row.
+            table
+                .column_map
+                .get(comp)
+                .map(|&col| unsafe { table.id_data.column_ref(col).ptr_unchecked(loc.row) })
+        }
+    })
+}
+
+/// Returns the id of `T` if it's stored in tables.
+fn table_component<T: TypedId>(world: &World) -> Option<Id> {
+    let comp = T::id(world).ok()?;
+    let ci = world.components.get(comp)?;
+    matches!(ci.storage, Storage::Tables(_)).then_some(comp)
+}
+
 impl<T> GetParam for &T
 where
     T: TypedId + DataComponent,
     <T as TypedId>::Data: DataComponent,
 {
-    type Data = <T as TypedId>::Data;
-    type Output<'a> = &'a Self::Data;
+    type Output<'a> = &'a T::Data;
     const IS_IMMUTABLE: bool = true;
 
-    fn make(world: &World, id: Id, loc: IdLocation) -> GetResult<Self::Output<'_>> {
-        let comp = T::id(world)?;
-        let comp_info = match world.components.get(comp) {
-            Some(ci) => ci,
-            None => return Err(GetError::IdNotComponent(comp)),
-        };
+    fn component(world: &World) -> Option<Id> {
+        T::id(world).ok()
+    }
 
-        match &comp_info.storage {
-            crate::storage::Storage::SparseTag(_) => return Err(GetError::IdNotComponent(comp)),
-            crate::storage::Storage::SparseData(set) => unsafe { set.get::<Self::Data>(id) },
-            crate::storage::Storage::Tables(_) => unsafe {
-                world.table_index[loc.table].get::<Self::Data>(comp, loc.row)
-            },
+    fn select_terms(world: &World, stmt: SelectStmt) -> SelectStmt {
+        match table_component::<T>(world) {
+            Some(comp) => stmt.read(comp),
+            None => stmt,
         }
-        .ok_or(GetError::MissingComponent(comp))
+    }
+
+    unsafe fn make(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::Output<'_>> {
+        // SAFETY: Caller ensures there's no mutable borrow of the component.
+        let world = unsafe { world.world() };
+        let comp = T::id(world)?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        component_ptr(world, id, loc, comp)?
+            .map(|ptr| unsafe { ptr.deref::<T::Data>() })
+            .ok_or_else(|| missing_component(world, comp))
     }
 }
 
@@ -52,27 +147,35 @@ where
     T: TypedId + DataComponent,
     <T as TypedId>::Data: DataComponent,
 {
-    type Data = <T as TypedId>::Data;
-    type Output<'a> = &'a mut Self::Data;
+    type Output<'a> = &'a mut T::Data;
     const IS_IMMUTABLE: bool = false;
 
-    fn make(world: &World, id: Id, loc: IdLocation) -> GetResult<Self::Output<'_>> {
-        // SAFETY: We have checked component ids to prevent aliasing.
-        let world = todo!();
-        let comp = T::id(world)?;
-        let comp_info = match world.components.get_mut(comp) {
-            Some(ci) => ci,
-            None => return Err(GetError::IdNotComponent(comp)),
-        };
+    fn component(world: &World) -> Option<Id> {
+        T::id(world).ok()
+    }
 
-        match &mut comp_info.storage {
-            crate::storage::Storage::SparseTag(_) => return Err(GetError::IdNotComponent(comp)),
-            crate::storage::Storage::SparseData(set) => unsafe { set.get_mut::<Self::Data>(id) },
-            crate::storage::Storage::Tables(_) => unsafe {
-                world.table_index[loc.table].get_mut::<Self::Data>(loc.row, comp)
-            },
+    fn select_terms(world: &World, stmt: SelectStmt) -> SelectStmt {
+        match table_component::<T>(world) {
+            Some(comp) => stmt.write(comp),
+            None => stmt,
         }
-        .ok_or(GetError::MissingComponent(comp))
+    }
+
+    unsafe fn make(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::Output<'_>> {
+        // SAFETY: Only the storage maps are read, the data is accessed through the pointer.
+        let world = unsafe { world.world() };
+        let comp = T::id(world)?;
+
+        // SAFETY:
+        // - The component id is obtained from the type, so the data type matches.
+        // - Caller ensures that no other reference aliases the component data.
+        component_ptr(world, id, loc, comp)?
+            .map(|ptr| unsafe { ptr.assert_unique().deref_mut::<T::Data>() })
+            .ok_or_else(|| missing_component(world, comp))
     }
 }
 
@@ -81,27 +184,29 @@ where
     T: TypedId + DataComponent,
     <T as TypedId>::Data: DataComponent,
 {
-    type Data = <T as TypedId>::Data;
-    type Output<'a> = Option<&'a Self::Data>;
+    type Output<'a> = Option<&'a T::Data>;
     const IS_IMMUTABLE: bool = true;
 
-    fn make(world: &World, id: Id, loc: IdLocation) -> GetResult<Self::Output<'_>> {
-        // SAFETY: We have checked component ids to prevent aliasing.
-        let world = todo!();
+    fn component(world: &World) -> Option<Id> {
+        T::id(world).ok()
+    }
+
+    unsafe fn make(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::Output<'_>> {
+        // SAFETY: Caller ensures there's no mutable borrow of the component.
+        let world = unsafe { world.world() };
         let Ok(comp) = T::id(world) else {
             return Ok(None);
         };
-        let Some(comp_info) = world.components.get(comp) else {
-            return Ok(None);
-        };
 
-        Ok(match &comp_info.storage {
-            crate::storage::Storage::SparseTag(_) => return Ok(None),
-            crate::storage::Storage::SparseData(set) => unsafe { set.get::<Self::Data>(id) },
-            crate::storage::Storage::Tables(_) => unsafe {
-                world.table_index[loc.table].get::<Self::Data>(comp, loc.row)
-            },
-        })
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        Ok(component_ptr(world, id, loc, comp)
+            .ok()
+            .flatten()
+            .map(|ptr| unsafe { ptr.deref::<T::Data>() }))
     }
 }
 
@@ -110,44 +215,186 @@ where
     T: TypedId + DataComponent,
     <T as TypedId>::Data: DataComponent,
 {
-    type Data = <T as TypedId>::Data;
-    type Output<'a> = Option<&'a mut Self::Data>;
+    type Output<'a> = Option<&'a mut T::Data>;
     const IS_IMMUTABLE: bool = false;
 
-    fn make(world: &World, id: Id, loc: IdLocation) -> GetResult<Self::Output<'_>> {
-        // SAFETY: We have checked component ids to prevent aliasing.
-        let world = todo!();
+    fn component(world: &World) -> Option<Id> {
+        T::id(world).ok()
+    }
+
+    unsafe fn make(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::Output<'_>> {
+        // SAFETY: Only the storage maps are read, the data is accessed through the pointer.
+        let world = unsafe { world.world() };
         let Ok(comp) = T::id(world) else {
             return Ok(None);
         };
-        let Some(comp_info) = world.components.get_mut(comp) else {
-            return Ok(None);
-        };
 
-        Ok(match &mut comp_info.storage {
-            crate::storage::Storage::SparseTag(_) => return Ok(None),
-            crate::storage::Storage::SparseData(set) => unsafe { set.get_mut::<Self::Data>(id) },
-            crate::storage::Storage::Tables(_) => unsafe {
-                world.table_index[loc.table].get_mut::<Self::Data>(loc.row, comp)
-            },
-        })
+        // SAFETY:
+        // - The component id is obtained from the type, so the data type matches.
+        // - Caller ensures that no other reference aliases the component data.
+        Ok(component_ptr(world, id, loc, comp)
+            .ok()
+            .flatten()
+            .map(|ptr| unsafe { ptr.assert_unique().deref_mut::<T::Data>() }))
+    }
+}
+
+/// Gets the id itself, `world.get::<(Entity, &Position)>(id)` returns `(Id, &Position)`.
+pub struct Entity;
+
+impl GetParam for Entity {
+    type Output<'a> = Id;
+    const IS_IMMUTABLE: bool = true;
+
+    #[inline(always)]
+    fn component(_: &World) -> Option<Id> {
+        None
+    }
+
+    #[inline(always)]
+    unsafe fn make(_: UnsafeWorldPtr<'_>, id: Id, _: IdLocation) -> GetResult<Self::Output<'_>> {
+        Ok(id)
     }
 }
 
+/// Gets the first component of the group that the id has, in order.
+///
+/// `world.get::<Or<(Position, Velocity)>>(id)` returns [OrOutput2::A] with the position,
+/// or [OrOutput2::B] with the velocity if the id has no position.
+pub struct Or<T>(PhantomData<T>);
+
+macro_rules! impl_or_param {
+    ($output:ident, $($t:ident),*) => {
+        /// Output of [Or], holds the first present member of the group.
+        pub enum $output<$($t),*> {
+            $($t($t),)*
+        }
+
+        impl<$($t),*> GetParam for Or<($($t,)*)>
+        where
+            $($t: TypedId + DataComponent, <$t as TypedId>::Data: DataComponent,)*
+        {
+            type Output<'a> = $output<$(&'a $t::Data),*>;
+            const IS_IMMUTABLE: bool = true;
+
+            /// The group reads more than one component, see [GetParam::accesses].
+            #[inline]
+            fn component(_: &World) -> Option<Id> {
+                None
+            }
+
+            fn accesses(world: &World, comp: Id) -> bool {
+                $($t::id(world).is_ok_and(|id| id == comp))||*
+            }
+
+            unsafe fn make(
+                world: UnsafeWorldPtr<'_>,
+                id: Id,
+                loc: IdLocation,
+            ) -> GetResult<Self::Output<'_>> {
+                // SAFETY: Caller ensures there's no mutable borrow of the components.
+                let world = unsafe { world.world() };
+
+                $(
+                    if let Ok(comp) = $t::id(world) {
+                        let ptr = component_ptr(world, id, loc, comp).ok().flatten();
+
+                        // SAFETY: The component id is obtained from the type, so the data type matches.
+                        if let Some(ptr) = ptr {
+                            return Ok($output::$t(unsafe { ptr.deref::<$t::Data>() }));
+                        }
+                    }
+                )*
+
+                Err(GetError::MissingAnyComponent(
+                    [$($t::id(world).ok()),*].into_iter().flatten().collect(),
+                ))
+            }
+        }
+    };
+}
+
+impl_or_param!(OrOutput2, A, B);
+impl_or_param!(OrOutput3, A, B, C);
+impl_or_param!(OrOutput4, A, B, C, D);
+
 pub trait Params: Sized + private::Sealed {
     type ParamsType<'a>;
     const ALL_IMMUTABLE: bool;
-    fn create(world: &World, id: Id) -> GetResult<Self::ParamsType<'_>>;
+
+    /// Adds the table components required by the params to a query.
+    fn select_terms(world: &World, stmt: SelectStmt) -> SelectStmt;
+
+    /// Checks that no component accessed mutably is accessed by another param.
+    fn check_access(_world: &World) -> GetResult<()> {
+        Ok(())
+    }
+
+    /// # Safety
+    /// - If the params are not all immutable, `world` must allow mutable access.
+    unsafe fn create(world: UnsafeWorldPtr<'_>, id: Id) -> GetResult<Self::ParamsType<'_>> {
+        let id_loc = world.get_id_location(id)?;
+
+        // SAFETY: The location was just looked up, caller ensures the rest.
+        unsafe { Self::create_at(world, id, id_loc) }
+    }
+
+    /// Creates the params like [Params::create], for an id that was already located.
+    ///
+    /// # Safety
+    /// - Same as [Params::create].
+    /// - `loc` must be the current location of `id`.
+    unsafe fn create_at(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::ParamsType<'_>>;
 }
 
 impl<T: GetParam> Params for T {
     type ParamsType<'a> = T::Output<'a>;
     const ALL_IMMUTABLE: bool = T::IS_IMMUTABLE;
 
-    fn create(world: &World, id: Id) -> GetResult<Self::ParamsType<'_>> {
-        let id_loc = world.id_manager.get_location(id)?;
-        T::make(world, id, id_loc)
+    #[inline]
+    fn select_terms(world: &World, stmt: SelectStmt) -> SelectStmt {
+        T::select_terms(world, stmt)
+    }
+
+    unsafe fn create_at(
+        world: UnsafeWorldPtr<'_>,
+        id: Id,
+        loc: IdLocation,
+    ) -> GetResult<Self::ParamsType<'_>> {
+        // SAFETY: A single param can't alias itself.
+        unsafe { T::make(world, id, loc) }
+    }
+}
+
+/// Access of a param: its component, whether it is immutable, and [GetParam::accesses].
+type ParamAccess = (Option<Id>, bool, fn(&World, Id) -> bool);
+
+/// Checks that no component accessed mutably is accessed by another param.
+fn check_aliasing(world: &World, access: &[ParamAccess]) -> GetResult<()> {
+    for (i, &(comp, immutable, _)) in access.iter().enumerate() {
+        let Some(comp) = comp else { continue };
+
+        let aliased = access
+            .iter()
+            .enumerate()
+            .any(|(j, &(_, other_immutable, accesses))| {
+                i != j && accesses(world, comp) && !(immutable && other_immutable)
+            });
+
+        if aliased {
+            return Err(GetError::AliasedComponent(comp));
+        }
     }
+
+    Ok(())
 }
 
 macro_rules! impl_tuple_params {
@@ -157,17 +404,80 @@ macro_rules! impl_tuple_params {
             type ParamsType<'a> = ($($t::Output<'a>,)*);
             const ALL_IMMUTABLE: bool = { $($t::IS_IMMUTABLE &&)* true };
 
-            fn create(world: &World, id: Id) -> GetResult<Self::ParamsType<'_>> {
-                let id_loc = world.id_manager.get_location(id)?;
+            fn select_terms(world: &World, stmt: SelectStmt) -> SelectStmt {
+                $(let stmt = $t::select_terms(world, stmt);)*
+                stmt
+            }
 
-                if !Self::ALL_IMMUTABLE {
-                    panic!("mutable access not yet supported")
+            fn check_access(world: &World) -> GetResult<()> {
+                if Self::ALL_IMMUTABLE {
+                    return Ok(());
                 }
 
-                Ok(($($t::make(world, id, id_loc)?,)*))
+                check_aliasing(world, &[$(($t::component(world), $t::IS_IMMUTABLE, $t::accesses as fn(&World, Id) -> bool)),*])
+            }
+
+            unsafe fn create_at(world: UnsafeWorldPtr<'_>, id: Id, id_loc: IdLocation) -> GetResult<Self::ParamsType<'_>> {
+                // SAFETY: Only component ids are read.
+                Self::check_access(unsafe { world.world() })?;
+
+                // SAFETY: We have checked component ids to prevent aliasing.
+                Ok(($(unsafe { $t::make(world, id, id_loc)? },)*))
             }
         }
     }
 }
 
 all_tuples!(impl_tuple_params, 1, 13);
+
+/// Struct view over the params of an id, implemented with `#[derive(QueryData)]`.
+///
+/// Each field is a param: `&T` reads, `&mut T` writes and `Option<&T>` is optional.
+/// A field marked `#[entity]` holds the id, like [Entity]. Iterated with [World::query_data].
+///
+/// ```
+/// # use xecs::{component::ComponentBuilder, id::Id, world::{World, WorldGet}};
+/// # use xecs_macros::{Component, QueryData};
+/// #[derive(Component)]
+/// struct Position { x: f32 }
+///
+/// #[derive(Component)]
+/// struct Velocity { x: f32 }
+///
+/// #[derive(Component)]
+/// struct Name(&'static str);
+///
+/// #[derive(QueryData)]
+/// struct Mover<'a> {
+///     #[entity]
+///     id: Id,
+///     pos: &'a mut Position,
+///     vel: &'a Velocity,
+///     name: Option<&'a Name>,
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Position>(ComponentBuilder::new());
+/// world.register::<Velocity>(ComponentBuilder::new());
+/// world.register::<Name>(ComponentBuilder::new());
+///
+/// let id = world.new_id();
+/// world.set::<Position>(id, Position { x: 1.0 });
+/// world.set::<Velocity>(id, Velocity { x: 2.0 });
+///
+/// for mover in world.query_data::<Mover>().unwrap() {
+///     mover.pos.x += mover.vel.x;
+///     assert_eq!((mover.id, mover.name.map(|name| name.0)), (id, None));
+/// }
+///
+/// assert_eq!(world.get::<&Position>(id).unwrap().x, 3.0);
+/// ```
+pub trait QueryData {
+    /// The view borrowing the world for `'a`.
+    type Item<'a>;
+    /// Params of the fields, in declaration order.
+    type Fields: Params;
+
+    /// Builds the view from the params of its fields.
+    fn from_fields(fields: <Self::Fields as Params>::ParamsType<'_>) -> Self::Item<'_>;
+}
@@ -1,22 +1,29 @@
 use crate::{
-    component::ComponentLocation,
-    flags::TableFlags,
-    id::{Id, Signature},
-    storage::{Storage, column::ColumnVec, table::Table},
+    component::{ComponentLocation, ensure_component},
+    flags::{ComponentFlags, TableFlags},
+    id::{Id, IdMap, Signature},
+    storage::{
+        Storage,
+        column::ColumnVec,
+        table::{Table, TableData},
+    },
     table_index::TableId,
     world::World,
 };
-use std::rc::Rc;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 #[derive(Default)]
 pub(crate) struct GraphEdge {
-    from: TableId,
     to: TableId,
 }
 
 pub(crate) struct GraphNode {
     add: IdMap<GraphEdge>,
     remove: IdMap<GraphEdge>,
+    /// Last traversed add edge, checked before the map since ids often toggle the same component.
+    last_add: Option<(Id, TableId)>,
+    /// Last traversed remove edge, see `last_add`.
+    last_remove: Option<(Id, TableId)>,
 }
 
 impl GraphNode {
@@ -24,71 +31,175 @@ impl GraphNode {
         Self {
             add: IdMap::new(),
             remove: IdMap::new(),
+            last_add: None,
+            last_remove: None,
         }
     }
+
+    /// Returns the number of bytes allocated for the edges.
+    #[inline]
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.add.allocated_bytes() + self.remove.allocated_bytes()
+    }
 }
 
-fn new_table(world: &mut World, ids: Signature) -> TableId {
-    world.table_index.add_with_id(|table_id| {
+pub(crate) fn new_table(world: &mut World, ids: Signature) -> TableId {
+    let alloc = Rc::clone(&world.allocator);
+
+    let table = world.table_index.add_with_id(|table_id| {
         let mut columns = Vec::new();
         let mut component_map = IdMap::new();
 
-        for (index, &id) in ids.iter().enumerate() {
+        for &id in ids.iter() {
             let cr = world.components.get_mut(id).unwrap();
-            let mut cl = ComponentLocation {
-                id_idx: index,
-                col_idx: None,
-            };
+            let mut cl = ComponentLocation { col_idx: None };
 
             if let Some(ti) = &cr.type_info {
                 let col_idx = columns.len();
                 cl.col_idx = Some(col_idx);
                 component_map.insert(id, col_idx);
-                columns.push(ColumnVec::new(id, Rc::clone(ti)));
+                columns.push(ColumnVec::new(id, Rc::clone(ti), Rc::clone(&alloc)));
             }
 
-            match &mut cr.storage {
-                Storage::Tables(tables) => tables.insert(table_id, cl),
-                _ => panic!("INTERNAL ERROR: unexpected storage type"),
-            };
+            // Tables emptied by World::migrate_storage keep the migrated component
+            // in their signature, and are created again when the world is cloned.
+            if let Storage::Tables(tables) = &mut cr.storage {
+                tables.insert(table_id, cl);
+            }
         }
 
         Table {
             id: table_id,
             _flags: TableFlags::empty(),
             signature: ids,
-            id_data: ComponentData::new(columns.into()),
+            id_data: TableData::new(columns.into()),
             column_map: component_map,
             node: GraphNode::new(),
+            debug_name: RefCell::new(None),
+            moves: HashMap::new(),
+            deletes: 0,
         }
-    })
+    });
+
+    let signature = &world.table_index[table].signature;
+
+    for on_create in &mut world.observers.tables {
+        on_create(table, signature);
+    }
+
+    table
+}
+
+/// Returns the tags stored in tables that are auto-added with `comp`.
+///
+/// Auto-added ids that aren't components yet are created as tags.
+pub(crate) fn auto_added_tags(world: &mut World, comp: Id) -> Vec<Id> {
+    let with = match world.components.get(comp) {
+        Some(ci) if ci.flags.contains(ComponentFlags::WITH) => ci.with.clone(),
+        _ => return vec![],
+    };
+
+    with.into_iter()
+        .filter(|&auto| {
+            ensure_component(world, auto);
+            let ci = world.components.get(auto).unwrap();
+            ci.type_info.is_none() && matches!(ci.storage, Storage::Tables(_))
+        })
+        .collect()
 }
 
 /// Traverse the table graph to find the destination table for an added component.
 ///
 /// Returns `None` if the component is already present.
 pub fn table_traverse_add(world: &mut World, from_id: TableId, with: Id) -> Option<TableId> {
-    let from = &world.table_index[from_id];
+    let from = &mut world.table_index[from_id];
+
+    match from.node.last_add {
+        Some((comp, to)) if comp == with => return Some(to),
+        _ => {}
+    }
 
     if let Some(edge) = from.node.add.get(with) {
-        return Some(edge.to);
+        let to = edge.to;
+        from.node.last_add = Some((with, to));
+        return Some(to);
+    }
+
+    let mut ids = from.signature.try_extend(with)?;
+
+    // Tags auto-added with the component are part of the same move.
+    for auto in auto_added_tags(world, with) {
+        if let Some(extended) = ids.try_extend(auto) {
+            ids = extended;
+        }
     }
 
-    let ids = from.signature.try_extend(with)?;
     let to_id = match world.table_index.get_id(&ids) {
         Some(id) => id,
         None => new_table(world, ids),
     };
 
     let from = &mut world.table_index[from_id];
+    from.node.add.insert(with, GraphEdge { to: to_id });
+    from.node.last_add = Some((with, to_id));
+
+    Some(to_id)
+}
+
+/// Traverse the table graph to find the destination table for a removed component.
+///
+/// Returns `None` if the component is not present.
+pub fn table_traverse_remove(world: &mut World, from_id: TableId, without: Id) -> Option<TableId> {
+    let from = &mut world.table_index[from_id];
+
+    match from.node.last_remove {
+        Some((comp, to)) if comp == without => return Some(to),
+        _ => {}
+    }
+
+    if let Some(edge) = from.node.remove.get(without) {
+        let to = edge.to;
+        from.node.last_remove = Some((without, to));
+        return Some(to);
+    }
+
+    let ids = from.signature.try_shrink(without)?;
+    let to_id = match world.table_index.get_id(&ids) {
+        Some(id) => id,
+        None => new_table(world, ids),
+    };
 
-    from.node.add.insert(
-        with,
-        GraphEdge {
-            from: from_id,
-            to: to_id,
-        },
-    );
+    let from = &mut world.table_index[from_id];
+    from.node.remove.insert(without, GraphEdge { to: to_id });
+    from.node.last_remove = Some((without, to_id));
 
     Some(to_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_edge_follows_the_most_recent_traversal() {
+        let mut world = World::new();
+        let root = world.root_table;
+        let a = world.new_id();
+        let b = world.new_id();
+        ensure_component(&mut world, a);
+        ensure_component(&mut world, b);
+
+        let to_a = table_traverse_add(&mut world, root, a).unwrap();
+        assert_eq!(world.table_index[root].node.last_add, Some((a, to_a)));
+
+        let to_b = table_traverse_add(&mut world, root, b).unwrap();
+        assert_eq!(world.table_index[root].node.last_add, Some((b, to_b)));
+
+        // A miss falls back to the map and refreshes the cached edge.
+        assert_eq!(table_traverse_add(&mut world, root, a), Some(to_a));
+        assert_eq!(world.table_index[root].node.last_add, Some((a, to_a)));
+
+        assert_eq!(table_traverse_remove(&mut world, to_a, a), Some(root));
+        assert_eq!(world.table_index[to_a].node.last_remove, Some((a, root)));
+    }
+}
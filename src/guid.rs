@@ -0,0 +1,39 @@
+use crate::{
+    component::ComponentBuilder,
+    registration::{ComponentId, TypeIndex, allocate_type_index},
+    type_traits::{Component, Data},
+};
+use std::sync::LazyLock;
+
+/// Globally unique id of an id, stable across worlds and sessions.
+///
+/// Set with [World::set_guid](crate::world::World::set_guid) so the guid is indexed,
+/// and found again with [World::by_guid](crate::world::World::by_guid).
+///
+/// Values can't be created or copied outside the world, so a guid can't be written
+/// to an id without going through the index.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Guid(pub(crate) u128);
+
+impl Guid {
+    /// Returns the raw guid.
+    #[inline]
+    pub fn get(&self) -> u128 {
+        self.0
+    }
+}
+
+// SAFETY: Implemented the same way as #[derive(Component)].
+unsafe impl Component for Guid {
+    type DataType = Data;
+    type DescType = ComponentBuilder<Self>;
+    const IS_GENERIC: bool = false;
+}
+
+// SAFETY: The type index is allocated once for the type.
+unsafe impl ComponentId for Guid {
+    fn type_index() -> TypeIndex {
+        static INDEX: LazyLock<TypeIndex> = LazyLock::new(allocate_type_index);
+        *INDEX
+    }
+}
@@ -1,23 +1,36 @@
 use crate::{
-    data_structures::SparseIndex, error::InvalidId, flags::IdFlags, id::Id, table_index::TableId,
+    data_structures::SparseIndex,
+    error::{EcsError, EcsResult, InvalidId},
+    flags::IdFlags,
+    id::Id,
+    table_index::TableId,
 };
 
+#[cfg(test)]
+thread_local! {
+    /// Number of [IdManager::get_location] calls on this thread, for tests.
+    pub(crate) static LOCATION_LOOKUPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 #[derive(Clone, Copy)]
 pub struct IdLocation {
     pub(crate) table: TableId,
     pub(crate) row: usize,
 }
 
+#[derive(Clone)]
 pub(crate) struct IdRecord {
     pub(crate) location: IdLocation,
     pub(crate) flags: IdFlags,
 }
 
+#[derive(Clone)]
 struct Entry {
     id: Id,
     record: IdRecord,
 }
 
+#[derive(Clone)]
 pub struct IdManager {
     dense: Vec<Entry>,
     sparse: Vec<usize>,
@@ -39,6 +52,9 @@ impl IdManager {
     ///
     /// [Id] must exist and must be alive to have a record.
     pub(crate) fn get_location(&self, id: Id) -> Result<IdLocation, InvalidId> {
+        #[cfg(test)]
+        LOCATION_LOOKUPS.with(|count| count.set(count.get() + 1));
+
         match self.sparse.get(id.to_sparse_index()) {
             Some(&dense) if dense < self.alive_count => {
                 // SAFETY: we just checked that dense is in bounds,
@@ -110,6 +126,13 @@ impl IdManager {
         }
     }
 
+    /// Returns an iterator over all alive ids and their records.
+    pub(crate) fn iter_alive(&self) -> impl Iterator<Item = (Id, &IdRecord)> {
+        self.dense[..self.alive_count]
+            .iter()
+            .map(|entry| (entry.id, &entry.record))
+    }
+
     /// Checks if the [Entity] is alive
     pub fn is_alive(&self, id: Id) -> bool {
         match self.sparse.get(id.to_sparse_index()) {
@@ -165,6 +188,24 @@ impl IdManager {
         debug_assert!(!self.is_alive(id), "INTERNAL ERROR: IdIndex corrupted");
     }
 
+    /// Removes the id and retires its index, so it's never issued again.
+    pub(crate) fn retire_id(&mut self, id: Id) {
+        if !self.is_alive(id) {
+            return;
+        }
+
+        self.remove_id(id);
+
+        // The removed id is the first dead entry.
+        let dense = self.alive_count;
+        self.sparse[id.to_sparse_index()] = usize::MAX;
+        self.dense.swap_remove(dense);
+
+        if let Some(moved) = self.dense.get(dense) {
+            self.sparse[moved.id.to_sparse_index()] = dense;
+        }
+    }
+
     pub(crate) fn new_id(&mut self, f: impl FnOnce(Id) -> IdRecord) -> Id {
         if self.alive_count < self.dense.len() {
             // Recycle id.
@@ -211,13 +252,113 @@ impl IdManager {
         new_id
     }
 
+    /// Creates the id at `index`.
+    ///
+    /// Unused indices below `index` are created dead, so they're recycled by [IdManager::new_id].
+    /// A dead index is revived with its next generation. Indices retired by
+    /// [IdManager::compact] and alive indices are refused.
+    pub(crate) fn new_id_at(
+        &mut self,
+        index: u32,
+        f: impl FnOnce(Id) -> IdRecord,
+    ) -> EcsResult<Id> {
+        assert!(index < u32::MAX, "index {index} is reserved");
+
+        while self.max_id <= index as u64 {
+            self.push_dead(Id::from_raw(self.max_id));
+            self.max_id += 1;
+        }
+
+        let dense = match self.sparse.get(index as usize) {
+            Some(&dense) if dense < self.alive_count => return Err(EcsError::IndexInUse(index)),
+            Some(&dense) if dense < self.dense.len() => dense,
+            // The index was retired by compact, its generation is lost.
+            _ => return Err(EcsError::IndexRetired(index)),
+        };
+
+        // Move the entry to the end of the alive ids.
+        if dense != self.alive_count {
+            self.dense.swap(dense, self.alive_count);
+            self.sparse[self.dense[dense].id.to_sparse_index()] = dense;
+            self.sparse[index as usize] = self.alive_count;
+        }
+
+        let entry = &mut self.dense[self.alive_count];
+        entry.record = f(entry.id);
+        self.alive_count += 1;
+
+        Ok(entry.id)
+    }
+
+    /// Adds a dead entry for `id`, to be recycled.
+    fn push_dead(&mut self, id: Id) {
+        let sparse = id.to_sparse_index();
+
+        if sparse >= self.sparse.len() {
+            self.sparse.resize(sparse + 1, usize::MAX);
+        }
+
+        self.sparse[sparse] = self.dense.len();
+        self.dense.push(Entry {
+            id,
+            record: IdRecord {
+                location: IdLocation {
+                    table: TableId::NULL,
+                    row: 0,
+                },
+                flags: IdFlags::default(),
+            },
+        });
+    }
+
+    /// Releases the memory held for indices above the highest alive id.
+    ///
+    /// Dead ids above the highest alive index are retired: their indices are never issued
+    /// again, and [IdManager::exists] returns false for them. Dead ids below it are kept
+    /// for recycling.
+    pub fn compact(&mut self) {
+        let keep_len = self.dense[..self.alive_count]
+            .iter()
+            .map(|entry| entry.id.to_sparse_index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut dense = self.alive_count;
+
+        while dense < self.dense.len() {
+            let sparse = self.dense[dense].id.to_sparse_index();
+
+            if sparse < keep_len {
+                dense += 1;
+                continue;
+            }
+
+            self.sparse[sparse] = usize::MAX;
+            self.dense.swap_remove(dense);
+
+            // Order of dead ids doesn't matter, fix the index of the moved one.
+            if let Some(moved) = self.dense.get(dense) {
+                self.sparse[moved.id.to_sparse_index()] = dense;
+            }
+        }
+
+        // All entries at or above keep_len were retired.
+        self.sparse.truncate(keep_len);
+        self.sparse.shrink_to_fit();
+
+        if self.dense.capacity() > self.dense.len() * 2 {
+            self.dense.shrink_to_fit();
+        }
+    }
+
     #[inline]
-    pub fn alive_count(&self) -> usize {
+    pub(crate) fn alive_count(&self) -> usize {
         self.alive_count - 1
     }
 
+    /// Returns the number of bytes allocated for the records of all ids.
     #[inline]
-    pub fn dead_count(&self) -> usize {
-        self.dense.len() - self.alive_count
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.dense.capacity() * size_of::<Entry>() + self.sparse.capacity() * size_of::<usize>()
     }
 }
@@ -62,6 +62,36 @@ impl Id {
     pub const fn from_parts(index: u32, generation: u32) -> Self {
         Self(((generation as u64) << 32) | index as u64)
     }
+
+    /// Checks if this id is a pair.
+    #[inline]
+    pub const fn is_pair(&self) -> bool {
+        (self.0 & Self::PAIR_FLAG) != 0
+    }
+
+    /// Checks if this id is a regular id (not a pair).
+    #[inline]
+    pub const fn is_id(&self) -> bool {
+        !self.is_pair()
+    }
+
+    /// Returns the relationship of a pair.
+    ///
+    /// Only the index is stored in the pair, use [IdManager::get_current](manager::IdManager::get_current)
+    /// to get the current id.
+    #[inline]
+    pub const fn pair_rel(&self) -> Id {
+        Id((self.0 & !Self::PAIR_FLAG) >> 32)
+    }
+
+    /// Returns the target of a pair.
+    ///
+    /// Only the index is stored in the pair, use [IdManager::get_current](manager::IdManager::get_current)
+    /// to get the current id.
+    #[inline]
+    pub const fn pair_tgt(&self) -> Id {
+        Id(self.index() as u64)
+    }
 }
 
 #[inline(always)]
@@ -76,6 +106,8 @@ impl SparseIndex for Id {
 }
 
 /// This trait should never be implemented by users.
+///
+/// # Safety
 /// There is no safe way to implement this trait.
 pub unsafe trait IntoId {
     fn validate(&self, world: &World) -> bool;
@@ -166,7 +198,7 @@ impl Signature {
         match self.binary_search(&with) {
             Ok(_) => None,
             Err(pos) => Some({
-                let mut new_list = Vec::with_capacity(pos);
+                let mut new_list = Vec::with_capacity(self.len() + 1);
                 new_list.extend_from_slice(&self[..pos]);
                 new_list.push(with);
                 new_list.extend_from_slice(&self[pos..]);
@@ -181,8 +213,9 @@ impl Signature {
     pub fn try_shrink(&self, from: Id) -> Option<Self> {
         match self.binary_search(&from) {
             Ok(pos) => Some({
-                let mut new_list = Vec::from(self.as_ref());
-                new_list.remove(pos);
+                let mut new_list = Vec::with_capacity(self.len() - 1);
+                new_list.extend_from_slice(&self[..pos]);
+                new_list.extend_from_slice(&self[pos + 1..]);
                 new_list.into()
             }),
             Err(_) => None,
@@ -190,6 +223,135 @@ impl Signature {
     }
 }
 
+/// Map keyed by [Id], the map to use for anything keyed by components.
+///
+/// Regular ids are stored in a [SparseSet] indexed by the id index.
+/// Pairs are hashed since the index of a pair is the index of its target,
+/// so pairs sharing a target would collide in a sparse set.
+///
+/// A plain [SparseSet] is only used for keys that can't be pairs, e.g. entities
+/// holding sparse components. [KeyMap] takes pairs split into a [Relation].
+#[derive(Clone)]
+pub struct IdMap<V> {
+    ids: SparseSet<Id, V>,
+    pairs: HashMap<Id, V>,
+}
+
+impl<V> IdMap<V> {
+    pub fn new() -> Self {
+        Self {
+            ids: SparseSet::new(),
+            pairs: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, id: Id) -> Option<&V> {
+        if id.is_pair() {
+            self.pairs.get(&id)
+        } else {
+            self.ids.get(&id)
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, id: Id) -> Option<&mut V> {
+        if id.is_pair() {
+            self.pairs.get_mut(&id)
+        } else {
+            self.ids.get_mut(&id)
+        }
+    }
+
+    /// Inserts a value for the id.
+    /// Returns the old value if the id was already in the map.
+    #[inline]
+    pub fn insert(&mut self, id: Id, value: V) -> Option<V> {
+        if id.is_pair() {
+            self.pairs.insert(id, value)
+        } else {
+            self.ids.insert(id, value)
+        }
+    }
+
+    /// Removes the id from the map.
+    /// Returns the value associated with the id if it was present.
+    #[inline]
+    pub fn remove(&mut self, id: Id) -> Option<V> {
+        if id.is_pair() {
+            self.pairs.remove(&id)
+        } else {
+            self.ids.remove(&id)
+        }
+    }
+
+    /// Returns the value of the id, inserting the result of `f` if it's not in the map.
+    #[inline]
+    pub fn get_or_insert_with(&mut self, id: Id, f: impl FnOnce() -> V) -> &mut V {
+        if id.is_pair() {
+            return self.pairs.entry(id).or_insert_with(f);
+        }
+
+        self.ids.entry(id).or_insert_with(f)
+    }
+
+    #[inline]
+    pub fn contains(&self, id: Id) -> bool {
+        if id.is_pair() {
+            self.pairs.contains_key(&id)
+        } else {
+            self.ids.contains_key(&id)
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ids.len() + self.pairs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reserves capacity for at least `additional` more regular ids.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.ids.reserve(additional);
+    }
+
+    /// Reserves capacity for at least `additional` more pairs.
+    #[inline]
+    pub fn reserve_pairs(&mut self, additional: usize) {
+        self.pairs.reserve(additional);
+    }
+
+    /// Returns the number of bytes allocated for the entries, not counting
+    /// memory owned by the values.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.ids.allocated_bytes() + self.pairs.capacity() * size_of::<(Id, V)>()
+    }
+
+    /// Returns an iterator over all entries, regular ids first, then pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &V)> {
+        self.ids
+            .iter()
+            .map(|(&id, value)| (id, value))
+            .chain(self.pairs.iter().map(|(&id, value)| (id, value)))
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.ids.values_mut().chain(self.pairs.values_mut())
+    }
+}
+
+impl<V> Default for IdMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub struct Relation {
@@ -197,27 +359,61 @@ pub struct Relation {
     tgt: Id,
 }
 
-pub trait Key {
+mod private {
+    pub trait Sealed {}
+}
+
+/// Key of a [KeyMap], implemented for [Id] and [Relation].
+pub trait Key: private::Sealed {
     fn map_get<'a, V>(&self, map: &'a KeyMap<V>) -> Option<&'a V>;
     fn map_get_mut<'a, V>(&self, map: &'a mut KeyMap<V>) -> Option<&'a mut V>;
+    fn map_get_or_insert_with<'a, V>(
+        &self,
+        map: &'a mut KeyMap<V>,
+        f: impl FnOnce() -> V,
+    ) -> &'a mut V;
 }
 
+impl private::Sealed for Id {}
+impl private::Sealed for Relation {}
+
+// Pair ids would collide by target in the sparse set, they're keyed as a [Relation].
 impl Key for Id {
     fn map_get<'a, V>(&self, map: &'a KeyMap<V>) -> Option<&'a V> {
-        map.ids.get(&self)
+        debug_assert!(!self.is_pair(), "pair keyed as an id");
+        map.ids.get(self)
     }
 
     fn map_get_mut<'a, V>(&self, map: &'a mut KeyMap<V>) -> Option<&'a mut V> {
-        map.ids.get_mut(&self)
+        debug_assert!(!self.is_pair(), "pair keyed as an id");
+        map.ids.get_mut(self)
+    }
+
+    fn map_get_or_insert_with<'a, V>(
+        &self,
+        map: &'a mut KeyMap<V>,
+        f: impl FnOnce() -> V,
+    ) -> &'a mut V {
+        debug_assert!(!self.is_pair(), "pair keyed as an id");
+        map.ids.entry(*self).or_insert_with(f)
     }
 }
+
 impl Key for Relation {
     fn map_get<'a, V>(&self, map: &'a KeyMap<V>) -> Option<&'a V> {
         map.rels.get(self)
     }
 
     fn map_get_mut<'a, V>(&self, map: &'a mut KeyMap<V>) -> Option<&'a mut V> {
-        map.rels.get_mut(&self)
+        map.rels.get_mut(self)
+    }
+
+    fn map_get_or_insert_with<'a, V>(
+        &self,
+        map: &'a mut KeyMap<V>,
+        f: impl FnOnce() -> V,
+    ) -> &'a mut V {
+        map.rels.entry(*self).or_insert_with(f)
     }
 }
 
@@ -243,4 +439,30 @@ impl<V> KeyMap<V> {
     pub fn get_mut<'a, K: Key>(&'a mut self, key: &K) -> Option<&'a mut V> {
         key.map_get_mut(self)
     }
+
+    /// Returns the value of the key, inserting the result of `f` if it's not in the map.
+    #[inline(always)]
+    pub fn get_or_insert_with<'a, K: Key>(
+        &'a mut self,
+        key: &K,
+        f: impl FnOnce() -> V,
+    ) -> &'a mut V {
+        key.map_get_or_insert_with(self, f)
+    }
+
+    /// Returns an iterator over the entries keyed by regular ids.
+    pub fn iter_ids(&self) -> impl Iterator<Item = (Id, &V)> {
+        self.ids.iter().map(|(&id, value)| (id, value))
+    }
+
+    /// Returns an iterator over the entries keyed by relations, in arbitrary order.
+    pub fn iter_rels(&self) -> impl Iterator<Item = (Relation, &V)> {
+        self.rels.iter().map(|(&rel, value)| (rel, value))
+    }
+}
+
+impl<V> Default for KeyMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
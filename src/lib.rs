@@ -1,24 +1,45 @@
+// Lets #[derive(Component)] resolve `xecs::` paths in unit tests.
+#[cfg(test)]
+extern crate self as xecs;
+
 // Public modules
+pub mod allocator;
+pub mod arena;
 pub mod atomic_refcell;
+pub mod comp_mut;
 pub mod component;
 pub mod data_structures;
+pub mod debug_entity;
+pub mod entity_ref;
 pub mod error;
 pub mod flags;
 pub mod get_params;
+pub mod guid;
 pub mod id;
 pub mod macros;
+pub mod prefab;
 pub mod query;
+pub mod recording;
 pub mod registration;
+pub mod schema;
+pub mod stats;
 pub mod storage;
+pub mod transaction;
 pub mod type_info;
 pub mod type_traits;
 pub mod unsafe_world_ptr;
+pub mod watch;
 pub mod world;
+pub mod world_ref;
+pub mod world_split;
+pub mod write_list;
 
 // Internal modules
 mod dynamic_struct;
+#[cfg(test)]
+mod fuzz_world;
 mod graph;
-mod pointer;
+mod observer;
 mod table_index;
 mod utils;
 mod world_utils;
@@ -0,0 +1,84 @@
+use crate::{
+    data_structures::SparseSet,
+    id::{Id, Signature},
+    table_index::TableId,
+    watch::Watch,
+    world::World,
+};
+use std::{collections::HashMap, ptr::NonNull};
+
+/// Type-erased event listener.
+///
+/// Called with the world, the id holding the event and a pointer to the event data.
+pub(crate) type EventListener = Box<dyn Fn(&World, Id, NonNull<u8>)>;
+
+/// Callback of a batched observer, called with the world, the id and the component.
+pub(crate) type BatchedListener = Box<dyn Fn(&World, Id, Id)>;
+
+/// Callback for a table with its signature, see [World::observe_tables].
+pub(crate) type TableListener = Box<dyn FnMut(TableId, &Signature)>;
+
+pub(crate) struct Observers {
+    /// Event listeners keyed by the event component.
+    listeners: HashMap<Id, Vec<EventListener>>,
+    /// Listeners for created tables, in registration order.
+    pub(crate) tables: Vec<TableListener>,
+    /// Batched OnSet observers with the components they watch, see [World::observe_batched].
+    pub(crate) batched: Vec<(Vec<Id>, BatchedListener)>,
+    /// Components set on each id since the last [World::dispatch_events].
+    touched: SparseSet<Id, Vec<Id>>,
+    /// Query watches, see [World::watch].
+    pub(crate) watches: Vec<Watch>,
+}
+
+impl Observers {
+    pub(crate) fn new() -> Self {
+        Self {
+            listeners: HashMap::new(),
+            tables: vec![],
+            batched: vec![],
+            touched: SparseSet::new(),
+            watches: vec![],
+        }
+    }
+
+    /// Registers a listener for the event component.
+    ///
+    /// Listeners are invoked in registration order.
+    pub(crate) fn register(&mut self, event: Id, listener: EventListener) {
+        self.listeners.entry(event).or_default().push(listener);
+    }
+
+    /// Returns the listeners registered for the event component.
+    #[inline]
+    pub(crate) fn listeners(&self, event: Id) -> &[EventListener] {
+        self.listeners.get(&event).map_or(&[], Vec::as_slice)
+    }
+
+    /// Records that `comp` of `id` was set, if a batched observer watches it.
+    pub(crate) fn touch(&mut self, id: Id, comp: Id) {
+        if !self.batched.iter().any(|(comps, _)| comps.contains(&comp)) {
+            return;
+        }
+
+        match self.touched.get_mut(&id) {
+            Some(comps) if comps.contains(&comp) => {}
+            Some(comps) => comps.push(comp),
+            None => {
+                self.touched.insert(id, vec![comp]);
+            }
+        }
+    }
+
+    /// Returns the `(id, comp)` pairs set since the last call, in no particular order.
+    pub(crate) fn take_touched(&mut self) -> Vec<(Id, Id)> {
+        let touched: Vec<_> = self
+            .touched
+            .iter()
+            .flat_map(|(&id, comps)| comps.iter().map(move |&comp| (id, comp)))
+            .collect();
+
+        self.touched.clear();
+        touched
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    error::{EcsError, EcsResult},
+    id::Id,
+    type_traits::{DataComponent, TagComponent, TypedId},
+    world::World,
+    world_utils::try_set_component,
+};
+
+/// Builder for a tree of prefabs, created by [World::prefab_tree].
+///
+/// Each node is a prefab id with the `Prefab` tag, children get a `(ChildOf, parent)` pair.
+/// Components are set on the current node, [PrefabTreeBuilder::child] descends into
+/// a new child and [PrefabTreeBuilder::parent] returns to the parent.
+pub struct PrefabTreeBuilder<'w> {
+    world: &'w mut World,
+    root: Option<Id>,
+    /// Path from the root to the current node.
+    path: Vec<Id>,
+    /// First error of a node setter, returned by [PrefabTreeBuilder::build].
+    error: Option<EcsError>,
+}
+
+impl<'w> PrefabTreeBuilder<'w> {
+    pub(crate) fn new(world: &'w mut World) -> Self {
+        Self {
+            world,
+            root: None,
+            path: vec![],
+            error: None,
+        }
+    }
+
+    /// Creates the root of the tree and makes it the current node.
+    ///
+    /// # Panics
+    /// If the root was already created.
+    pub fn root(mut self) -> Self {
+        assert!(self.root.is_none(), "prefab tree already has a root");
+
+        let root = self.new_prefab();
+        self.root = Some(root);
+        self.path.push(root);
+        self
+    }
+
+    /// Creates a child of the current node and makes it the current node.
+    ///
+    /// # Panics
+    /// If the root was not created.
+    pub fn child(mut self) -> Self {
+        let parent = self.current();
+        let child = self.new_prefab();
+        let child_of = self.world.child_of();
+
+        // Both ids are alive, and ChildOf is a tag.
+        self.world.add_id(child, (child_of, parent)).unwrap();
+        self.path.push(child);
+        self
+    }
+
+    /// Returns to the parent of the current node.
+    ///
+    /// # Panics
+    /// If the current node is the root.
+    pub fn parent(mut self) -> Self {
+        assert!(
+            self.path.len() > 1,
+            "the root of a prefab tree has no parent"
+        );
+        self.path.pop();
+        self
+    }
+
+    /// Sets the component `T` on the current node.
+    pub fn with<T: TypedId>(mut self, val: T::Data) -> Self
+    where
+        T::Data: DataComponent,
+    {
+        let id = self.current();
+        let result = T::id(self.world)
+            .map_err(EcsError::from)
+            .and_then(|comp| try_set_component(self.world, id, comp, val));
+
+        self.keep_error(result.map(|_| ()));
+        self
+    }
+
+    /// Adds the tag `T` to the current node.
+    pub fn tag<T: TypedId + TagComponent>(mut self) -> Self {
+        let id = self.current();
+        let result = self.world.add::<T>(id);
+
+        self.keep_error(result);
+        self
+    }
+
+    /// Returns the root of the tree, see [World::instantiate_tree].
+    ///
+    /// Returns the first error of [PrefabTreeBuilder::with] or [PrefabTreeBuilder::tag],
+    /// the tree is despawned then.
+    ///
+    /// # Panics
+    /// If the root was not created.
+    pub fn build(self) -> EcsResult<Id> {
+        let root = self.root.expect("prefab tree has no root");
+
+        match self.error {
+            Some(err) => {
+                // Children are despawned with the root, see World::child_of.
+                self.world.despawn(root)?;
+                Err(err)
+            }
+            None => Ok(root),
+        }
+    }
+
+    fn keep_error(&mut self, result: EcsResult<()>) {
+        if let Err(err) = result
+            && self.error.is_none()
+        {
+            self.error = Some(err);
+        }
+    }
+
+    fn current(&self) -> Id {
+        *self.path.last().expect("prefab tree has no root")
+    }
+
+    fn new_prefab(&mut self) -> Id {
+        let id = self.world.new_id();
+        let prefab = self.world.prefab_tag();
+
+        // The id was just created.
+        self.world.add_id(id, prefab).unwrap();
+        id
+    }
+}
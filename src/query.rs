@@ -1,8 +1,19 @@
-use crate::storage::Storage;
+use crate::component::ComponentLocation;
+use crate::error::{GetResult, UnregisteredTypeErr};
+use crate::get_params::Params;
+use crate::registration::ComponentId;
+use crate::storage::{Storage, relation::stored_relation, sparse::SparseData};
 use crate::table_index::TableId;
-use crate::{id::Id, storage::table::Table, world::World};
-use std::collections::HashSet;
-use std::vec;
+use crate::type_info::TypeInfo;
+use crate::type_traits::{DataComponent, TypedId};
+use crate::unsafe_world_ptr::UnsafeWorldPtr;
+use crate::world_utils::{component_type_info, get_component_ptr, has_component, id_name};
+use crate::{
+    id::{Id, pair},
+    storage::table::Table,
+    world::World,
+};
+use std::{collections::HashMap, fmt::Write, marker::PhantomData, ptr::NonNull, rc::Rc, vec};
 
 //  Grammar
 //
@@ -51,72 +62,235 @@ enum ColumnAccess {
     Write(usize),
 }
 
+#[derive(Clone, Copy)]
 struct Field {
-    id: Id,
     access: ColumnAccess,
-    is_optional: bool,
 }
 
 impl Field {
     #[inline(always)]
-    fn new(select: &Select, column_index: usize, is_optional: bool) -> Self {
+    fn new(select: &Select, column_index: usize) -> Self {
         Self {
-            id: select.id,
             access: match select.access {
                 SelectAccess::Read => ColumnAccess::Read(column_index),
                 SelectAccess::Write => ColumnAccess::Write(column_index),
             },
-            is_optional,
         }
     }
 }
 
 pub struct Context<'w> {
     world: &'w World,
-    fields: Vec<Field>,
 }
 
 impl<'w> Context<'w> {
     pub fn new(world: &'w World) -> Self {
-        Self {
-            world,
-            fields: vec![],
+        Self { world }
+    }
+}
+
+/// Check evaluated for each row of a matched table, for terms tables can't resolve.
+pub(crate) type RowFilter = Box<dyn Fn(&World, &Table, usize) -> bool>;
+
+/// Predicate on the id and components of a table, see [QueryPlan::add_table_filter].
+type TableFilter = Box<dyn Fn(TableId, &[Id]) -> bool>;
+
+/// Resolves the component of a singleton term, see [WithStmt::singleton].
+type SingletonTerm = fn(&World) -> Result<Id, UnregisteredTypeErr>;
+
+/// Predicate on the value of a component, see [SelectStmt::filter].
+struct ValueFilter {
+    comp: FilterComp,
+    f: Box<dyn Fn(NonNull<u8>) -> bool>,
+}
+
+/// Component of a [ValueFilter], resolved when the tables are computed.
+enum FilterComp {
+    Id(Id),
+    Typed(fn(&World) -> Result<Id, UnregisteredTypeErr>),
+}
+
+impl FilterComp {
+    /// Returns the component id, `None` if the type is not registered.
+    fn resolve(&self, world: &World) -> Option<Id> {
+        match *self {
+            FilterComp::Id(comp) => Some(comp),
+            FilterComp::Typed(id) => id(world).ok(),
         }
     }
 }
 
+/// Value of a singleton term, bound once when the iteration starts.
+struct Singleton {
+    ptr: NonNull<u8>,
+    type_info: Rc<TypeInfo>,
+}
+
 pub struct TableView<'a> {
+    world: &'a World,
     table: &'a Table,
+    row_filters: &'a [RowFilter],
+    /// Sparse components required or excluded by the terms, checked per row.
+    sparse_with: &'a [Id],
+    sparse_without: &'a [Id],
+    /// Value filters with their resolved components.
+    filters: &'a [ValueFilter],
+    filter_ids: &'a [Id],
 }
 
-pub struct SelectStmt {
+impl TableView<'_> {
+    /// Returns the ids stored in the table, in row order, including the rows
+    /// filtered out by [TableView::rows].
+    #[inline]
+    pub(crate) fn ids(&self) -> &[Id] {
+        self.table.id_data.ids()
+    }
+
+    /// Returns the id of the table.
+    #[inline]
+    pub fn table_id(&self) -> TableId {
+        self.table.id
+    }
+
+    /// Returns `true` if rows are checked one by one, so [TableView::rows] may skip some.
+    #[inline]
+    pub(crate) fn has_row_terms(&self) -> bool {
+        !(self.row_filters.is_empty()
+            && self.sparse_with.is_empty()
+            && self.sparse_without.is_empty()
+            && self.filters.is_empty())
+    }
+
+    /// Iterates the rows that pass all per-row terms, with their ids.
+    pub fn rows(&self) -> impl Iterator<Item = (usize, Id)> + '_ {
+        self.ids().iter().copied().enumerate().filter(|&(row, id)| {
+            self.sparse_with
+                .iter()
+                .all(|&comp| has_component(self.world, id, comp))
+                && !self
+                    .sparse_without
+                    .iter()
+                    .any(|&comp| has_component(self.world, id, comp))
+                && self
+                    .row_filters
+                    .iter()
+                    .all(|filter| filter(self.world, self.table, row))
+                && self
+                    .filters
+                    .iter()
+                    .zip(self.filter_ids)
+                    .all(|(filter, &comp)| {
+                        get_component_ptr(self.world, id, comp)
+                            .is_some_and(|ptr| (filter.f)(ptr.as_ptr()))
+                    })
+        })
+    }
+}
+
+/// Term of a query, either a fixed id or a variable resolved while iterating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Id(Id),
+    /// Placeholder for the iterated id, e.g. `(Owns, This)` matches ids that own themselves.
+    This,
+}
+
+impl From<Id> for Term {
+    #[inline]
+    fn from(id: Id) -> Self {
+        Self::Id(id)
+    }
+}
+
+/// Access mode of a [SelectStmt] that allows both read and write selects.
+pub struct ReadWrite;
+
+/// Access mode of a [SelectStmt] that only allows read selects,
+/// used by queries built from a [WorldRef](crate::world_ref::WorldRef).
+pub struct ReadOnly;
+
+pub struct SelectStmt<A = ReadWrite> {
     /// SELECT (A, mut B)
     select: Vec<Select>,
     /// SELECT (A?, mut B?)
     optionals: Vec<Select>,
     /// SELECT ((A | mut B | C))
     anyofs: Vec<Vec<Select>>,
+    /// Predicates on the values of read components.
+    filters: Vec<ValueFilter>,
+    access: PhantomData<A>,
 }
 
-impl SelectStmt {
+impl<A> SelectStmt<A> {
     pub fn new() -> Self {
         Self {
             select: vec![],
             optionals: vec![],
             anyofs: vec![],
+            filters: vec![],
+            access: PhantomData,
         }
     }
 
-    pub fn select(mut self, select: Select) -> Self {
-        self.select.push(select);
+    pub fn read(mut self, id: Id) -> Self {
+        self.select.push(Select {
+            id,
+            access: SelectAccess::Read,
+        });
         self
     }
 
-    pub fn read(self, id: Id) -> Self {
-        self.select(Select {
+    pub fn read_optional(mut self, id: Id) -> Self {
+        self.optionals.push(Select {
             id,
             access: SelectAccess::Read,
-        })
+        });
+        self
+    }
+
+    /// Only matches rows where `f` returns true for the value of `T`.
+    ///
+    /// `T` is required and only read. Filters run in order after all other terms,
+    /// a row is skipped at the first one returning false.
+    pub fn filter<T: TypedId>(mut self, f: fn(&T::Data) -> bool) -> Self
+    where
+        T::Data: DataComponent,
+    {
+        self.filters.push(ValueFilter {
+            comp: FilterComp::Typed(T::id),
+            // SAFETY: The component is resolved from `T`, so its values are `T::Data`.
+            f: Box::new(move |ptr| f(unsafe { ptr.cast::<T::Data>().as_ref() })),
+        });
+        self
+    }
+
+    /// Like [SelectStmt::filter], `f` receives a pointer to the value of `comp`.
+    ///
+    /// Nothing matches if `comp` is a tag.
+    pub fn filter_id(mut self, comp: Id, f: fn(NonNull<u8>) -> bool) -> Self {
+        self.filters.push(ValueFilter {
+            comp: FilterComp::Id(comp),
+            f: Box::new(f),
+        });
+        self
+    }
+
+    /// Drops the access mode once the statement is built.
+    fn erase(self) -> SelectStmt {
+        SelectStmt {
+            select: self.select,
+            optionals: self.optionals,
+            anyofs: self.anyofs,
+            filters: self.filters,
+            access: PhantomData,
+        }
+    }
+}
+
+impl SelectStmt<ReadWrite> {
+    pub fn select(mut self, select: Select) -> Self {
+        self.select.push(select);
+        self
     }
 
     pub fn write(self, id: Id) -> Self {
@@ -138,6 +312,12 @@ impl SelectStmt {
     }
 }
 
+impl Default for SelectStmt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct WithStmt {
     /// WITH (A)
     with: Vec<Id>,
@@ -145,6 +325,12 @@ pub struct WithStmt {
     without: Vec<Id>,
     /// WITH ((A | B))
     anyofs: Vec<Vec<Id>>,
+    /// WITH ((R, this))
+    this_pairs: Vec<Id>,
+    /// WITH (!(R, this))
+    not_this_pairs: Vec<Id>,
+    /// Singletons, bound once per iteration.
+    singletons: Vec<SingletonTerm>,
 }
 
 impl WithStmt {
@@ -153,7 +339,28 @@ impl WithStmt {
             with: vec![],
             anyofs: vec![],
             without: vec![],
+            this_pairs: vec![],
+            not_this_pairs: vec![],
+            singletons: vec![],
+        }
+    }
+
+    /// Requires the pair `(rel, tgt)`, where `tgt` can be [Term::This].
+    pub fn with_pair(mut self, rel: Id, tgt: impl Into<Term>) -> Self {
+        match tgt.into() {
+            Term::Id(tgt) => self.with.push(pair(rel, tgt)),
+            Term::This => self.this_pairs.push(rel),
+        }
+        self
+    }
+
+    /// Excludes the pair `(rel, tgt)`, where `tgt` can be [Term::This].
+    pub fn without_pair(mut self, rel: Id, tgt: impl Into<Term>) -> Self {
+        match tgt.into() {
+            Term::Id(tgt) => self.without.push(pair(rel, tgt)),
+            Term::This => self.not_this_pairs.push(rel),
         }
+        self
     }
 
     pub fn with(mut self, id: Id) -> Self {
@@ -171,38 +378,136 @@ impl WithStmt {
         self.anyofs.push(any);
         self
     }
+
+    /// Requires the singleton `T`, see [World::set_singleton](crate::world::World::set_singleton).
+    ///
+    /// The value is bound once per iteration and read with [MatchedRow::singleton],
+    /// in the order the singleton terms were added. Nothing matches if it's not set,
+    /// and the id of the component itself is never matched.
+    pub fn singleton<T: ComponentId + DataComponent>(mut self) -> Self {
+        self.singletons.push(<T as ComponentId>::id);
+        self
+    }
 }
 
+impl Default for WithStmt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the tables and rows matching a query.
+///
+/// Ids marked for deletion are skipped, unless a term uses the
+/// [pending delete tag](crate::world::World::pending_delete_tag).
 pub struct QueryPlan {
     select_stmt: SelectStmt,
     with_stmt: WithStmt,
     table_ids: Vec<TableId>,
+    /// Tables given with [QueryPlan::for_tables], used instead of the computed ones.
+    fixed_tables: Option<Vec<TableId>>,
+    table_filters: Vec<TableFilter>,
+    /// Terms checked per row of matched tables.
+    row_filters: Vec<RowFilter>,
+    /// Components of the with and without terms that are not stored in tables.
+    sparse_with: Vec<Id>,
+    sparse_without: Vec<Id>,
+    /// Components of the value filters, resolved by [QueryPlan::init_tables].
+    filter_ids: Vec<Id>,
+    /// Fields of the table last returned by [QueryPlan::next_table].
+    fields: Vec<Field>,
+    /// Scratch memory of [QueryPlan::init_tables], kept to reuse its capacity.
+    candidates: Vec<TableId>,
 }
 
 impl QueryPlan {
-    pub fn new(select_stmt: SelectStmt, with_stmt: WithStmt) -> Self {
+    pub fn new<A>(select_stmt: SelectStmt<A>, with_stmt: WithStmt) -> Self {
+        let mut row_filters: Vec<RowFilter> = vec![];
+
+        for &rel in &with_stmt.this_pairs {
+            row_filters.push(Box::new(move |world, table, row| {
+                let id = table.id_data.ids()[row];
+                has_component(world, id, pair(rel, id))
+            }));
+        }
+
+        for &rel in &with_stmt.not_this_pairs {
+            row_filters.push(Box::new(move |world, table, row| {
+                let id = table.id_data.ids()[row];
+                !has_component(world, id, pair(rel, id))
+            }));
+        }
+
+        for &term in &with_stmt.singletons {
+            row_filters.push(Box::new(move |world, table, row| {
+                term(world).map_or(true, |comp| table.id_data.ids()[row] != comp)
+            }));
+        }
+
         Self {
-            select_stmt,
+            select_stmt: select_stmt.erase(),
             with_stmt,
             table_ids: vec![],
+            fixed_tables: None,
+            table_filters: vec![],
+            row_filters,
+            sparse_with: vec![],
+            sparse_without: vec![],
+            filter_ids: vec![],
+            fields: vec![],
+            candidates: vec![],
         }
     }
 
+    /// Only iterates `table_ids` instead of the tables computed from the terms.
+    ///
+    /// The terms are still checked for each table, tables that don't exist are skipped.
+    pub fn for_tables(mut self, table_ids: impl IntoIterator<Item = TableId>) -> Self {
+        self.fixed_tables = Some(table_ids.into_iter().collect());
+        self
+    }
+
+    /// Skips tables for which `f` returns false.
+    ///
+    /// `f` receives the id of the table and its components.
+    pub fn add_table_filter(mut self, f: impl Fn(TableId, &[Id]) -> bool + 'static) -> Self {
+        self.table_filters.push(Box::new(f));
+        self
+    }
+
     pub fn init_tables(&mut self, world: &World) {
-        let mut candidates = vec![];
+        if !self.has_singletons(world)
+            || !self.init_sparse_terms(world)
+            || !self.resolve_filters(world)
+        {
+            self.table_ids.clear();
+            return;
+        }
+
+        if let Some(fixed) = &self.fixed_tables {
+            self.table_ids.clear();
+            self.table_ids.extend(
+                fixed
+                    .iter()
+                    .copied()
+                    .filter(|&table| world.table_index.get(table).is_some()),
+            );
+            return;
+        }
+
+        let candidates = &mut self.candidates;
+        candidates.clear();
         let mut has_mandatory = false;
 
-        // Mandatory WITH: pick smallest
-        for &cid in &self.with_stmt.with {
-            let ci = world.components.get(cid).unwrap();
+        // Mandatory WITH: pick smallest, filtered components are required too
+        for &cid in self.with_stmt.with.iter().chain(&self.filter_ids) {
+            let Some(tables) = table_records(world, cid) else {
+                continue;
+            };
 
-            match &ci.storage {
-                Storage::Tables(tables) => {
-                    if !has_mandatory || tables.len() < candidates.len() {
-                        candidates = tables.keys().cloned().collect()
-                    }
-                }
-                _ => panic!("invalid storage"),
+            if !has_mandatory || tables.len() < candidates.len() {
+                candidates.clear();
+                candidates.extend(tables.keys().copied());
             }
 
             has_mandatory = true;
@@ -210,34 +515,33 @@ impl QueryPlan {
 
         // Mandatory SELECT: pick smallest
         for select in &self.select_stmt.select {
-            let ci = world.components.get(select.id).unwrap();
-            match &ci.storage {
-                Storage::Tables(tables) => {
-                    if !has_mandatory || tables.len() < candidates.len() {
-                        candidates = tables.keys().cloned().collect()
-                    }
-                }
-                _ => panic!("invalid storage"),
+            let Some(tables) = table_records(world, select.id) else {
+                continue;
+            };
+
+            if !has_mandatory || tables.len() < candidates.len() {
+                candidates.clear();
+                candidates.extend(tables.keys().copied());
             }
+
+            has_mandatory = true;
         }
 
+        self.table_ids.clear();
+
         if has_mandatory {
-            self.table_ids = candidates;
+            self.table_ids.extend_from_slice(candidates);
             return;
         }
 
         // No mandatory components → build from anyof groups
-        let mut anyof_candidates = HashSet::new();
+        let anyof_candidates = candidates;
 
         // WITH anyof: union group, intersect across groups
         for group in &self.with_stmt.anyofs {
             for &cid in group {
-                let ci = world.components.get(cid).unwrap();
-                match &ci.storage {
-                    Storage::Tables(tables) => {
-                        anyof_candidates.extend(tables.keys());
-                    }
-                    _ => panic!("invalid storage"),
+                if let Some(tables) = table_records(world, cid) {
+                    anyof_candidates.extend(tables.keys().copied());
                 }
             }
         }
@@ -245,29 +549,275 @@ impl QueryPlan {
         // SELECT anyof: union group, intersect across groups
         for group in &self.select_stmt.anyofs {
             for select in group {
-                let ci = world.components.get(select.id).unwrap();
-                match &ci.storage {
-                    Storage::Tables(tables) => {
-                        anyof_candidates.extend(tables.keys());
-                    }
-                    _ => panic!("invalid storage"),
+                if let Some(tables) = table_records(world, select.id) {
+                    anyof_candidates.extend(tables.keys().copied());
                 }
             }
         }
 
+        anyof_candidates.sort_unstable();
+        anyof_candidates.dedup();
+
         // Final candidate list
-        self.table_ids = if anyof_candidates.is_empty() {
-            world.table_index.all_table_ids().copied().collect()
+        if anyof_candidates.is_empty() {
+            self.table_ids
+                .extend(world.table_index.all_table_ids().copied());
         } else {
-            anyof_candidates.into_iter().collect()
+            self.table_ids.extend_from_slice(anyof_candidates);
+        }
+    }
+
+    /// Sorts out the with and without terms that are not stored in tables.
+    ///
+    /// Returns `false` if nothing can match: a required component doesn't exist,
+    /// or a selected one is not stored in tables.
+    fn init_sparse_terms(&mut self, world: &World) -> bool {
+        self.sparse_with.clear();
+        self.sparse_without.clear();
+
+        for &cid in &self.with_stmt.with {
+            match term_in_tables(world, cid) {
+                Some(true) => {}
+                Some(false) => self.sparse_with.push(cid),
+                None => return false,
+            }
+        }
+
+        for &cid in &self.with_stmt.without {
+            if term_in_tables(world, cid) == Some(false) {
+                self.sparse_without.push(cid);
+            }
+        }
+
+        if self.skips_pending(world) {
+            self.sparse_without.push(world.pending_delete_tag());
+        }
+
+        self.select_stmt
+            .select
+            .iter()
+            .all(|select| term_in_tables(world, select.id) == Some(true))
+    }
+
+    /// Returns `true` if ids marked for deletion must be skipped, which is the case
+    /// while there are any, unless the query has a term on the pending delete tag.
+    fn skips_pending(&self, world: &World) -> bool {
+        let pending = world.pending_delete_tag();
+
+        world.pending_delete_count() > 0
+            && !self.with_stmt.with.contains(&pending)
+            && !self.with_stmt.without.contains(&pending)
+    }
+
+    /// Resolves the components of the value filters, sparse ones are checked per row.
+    ///
+    /// Returns `false` if a component is not registered or has no value.
+    fn resolve_filters(&mut self, world: &World) -> bool {
+        self.filter_ids.clear();
+
+        for filter in &self.select_stmt.filters {
+            let Some(comp) = filter.comp.resolve(world) else {
+                return false;
+            };
+
+            if component_type_info(world, comp).is_none() {
+                return false;
+            }
+
+            if term_in_tables(world, comp) == Some(false) {
+                self.sparse_with.push(comp);
+            }
+
+            self.filter_ids.push(comp);
+        }
+
+        true
+    }
+
+    /// Describes how the matched tables are found, to debug queries matching nothing.
+    ///
+    /// Lists where the candidate tables come from, then each term with the number of
+    /// tables left after it. Terms checked per row don't remove tables.
+    pub fn explain(&self, world: &World) -> String {
+        let name = |comp: Id| id_name(world, comp);
+        let mut out = String::new();
+
+        let filter_ids: Vec<_> = self
+            .select_stmt
+            .filters
+            .iter()
+            .filter_map(|filter| filter.comp.resolve(world))
+            .collect();
+
+        let mut tables: Vec<TableId> = match &self.fixed_tables {
+            Some(fixed) => {
+                let tables: Vec<_> = fixed
+                    .iter()
+                    .copied()
+                    .filter(|&table| world.table_index.get(table).is_some())
+                    .collect();
+
+                writeln!(out, "candidates: {} fixed tables", tables.len()).unwrap();
+                tables
+            }
+            None => {
+                let source = self
+                    .with_stmt
+                    .with
+                    .iter()
+                    .chain(&filter_ids)
+                    .chain(self.select_stmt.select.iter().map(|select| &select.id))
+                    .filter_map(|&comp| Some((comp, table_records(world, comp)?)))
+                    .min_by_key(|(_, records)| records.len());
+
+                match source {
+                    Some((comp, records)) => {
+                        let tables: Vec<_> = records.keys().copied().collect();
+                        writeln!(
+                            out,
+                            "candidates: {} tables with {}",
+                            tables.len(),
+                            name(comp)
+                        )
+                        .unwrap();
+                        tables
+                    }
+                    None => {
+                        let tables: Vec<_> = world.table_index.all_table_ids().copied().collect();
+                        writeln!(out, "candidates: all {} tables", tables.len()).unwrap();
+                        tables
+                    }
+                }
+            }
+        };
+
+        let mut stage = |label: String, keep: &dyn Fn(&Table) -> bool| {
+            tables.retain(|&table| keep(&world.table_index[table]));
+            writeln!(out, "{label}: {} tables", tables.len()).unwrap();
         };
+
+        if !self.table_filters.is_empty() {
+            stage("table filters".to_string(), &|table| {
+                self.table_filters
+                    .iter()
+                    .all(|filter| filter(table.id, table.signature.ids()))
+            });
+        }
+
+        for &comp in self.with_stmt.with.iter().chain(&filter_ids) {
+            match term_in_tables(world, comp) {
+                Some(true) => stage(format!("with {}", name(comp)), &|table| {
+                    table.signature.has_id(comp)
+                }),
+                Some(false) => stage(format!("with {} (sparse, per row)", name(comp)), &|_| true),
+                None => stage(format!("with {} (not a component)", name(comp)), &|_| false),
+            }
+        }
+
+        for &comp in &self.with_stmt.without {
+            match term_in_tables(world, comp) {
+                Some(false) => stage(format!("without {} (sparse, per row)", name(comp)), &|_| {
+                    true
+                }),
+                _ => stage(format!("without {}", name(comp)), &|table| {
+                    !table.signature.has_id(comp)
+                }),
+            }
+        }
+
+        for group in &self.with_stmt.anyofs {
+            let names: Vec<_> = group.iter().map(|&comp| name(comp)).collect();
+            stage(format!("with any of ({})", names.join(" | ")), &|table| {
+                group.iter().any(|&comp| table.signature.has_id(comp))
+            });
+        }
+
+        for select in &self.select_stmt.select {
+            stage(format!("select {}", name(select.id)), &|table| {
+                table.column_map.get(select.id).is_some()
+            });
+        }
+
+        for group in &self.select_stmt.anyofs {
+            let names: Vec<_> = group.iter().map(|select| name(select.id)).collect();
+            stage(format!("select any of ({})", names.join(" | ")), &|table| {
+                group
+                    .iter()
+                    .any(|select| table.column_map.get(select.id).is_some())
+            });
+        }
+
+        if self.skips_pending(world) {
+            writeln!(out, "without ids pending deletion: per row").unwrap();
+        }
+
+        for &rel in &self.with_stmt.this_pairs {
+            writeln!(out, "with ({}, this): per row", name(rel)).unwrap();
+        }
+
+        for &rel in &self.with_stmt.not_this_pairs {
+            writeln!(out, "without ({}, this): per row", name(rel)).unwrap();
+        }
+
+        if filter_ids.len() != self.select_stmt.filters.len() {
+            writeln!(
+                out,
+                "value filters: unregistered component, nothing matches"
+            )
+            .unwrap();
+        } else {
+            for &comp in &filter_ids {
+                writeln!(out, "filter {}: per row", name(comp)).unwrap();
+            }
+        }
+
+        if !self.with_stmt.singletons.is_empty() {
+            let set = if self.has_singletons(world) {
+                "set"
+            } else {
+                "missing, nothing matches"
+            };
+            writeln!(out, "singletons: {set}").unwrap();
+        }
+
+        out
+    }
+
+    /// Returns `true` if all singletons of the singleton terms are set.
+    fn has_singletons(&self, world: &World) -> bool {
+        self.with_stmt
+            .singletons
+            .iter()
+            .all(|term| term(world).is_ok_and(|comp| has_component(world, comp, comp)))
     }
 
-    pub fn next_table<'w>(&mut self, ctx: &'w mut Context) -> Option<TableView<'w>> {
+    /// Binds the values of the singleton terms, nothing matches if one is missing.
+    fn bind_singletons(&mut self, world: &World) -> Rc<[Singleton]> {
+        let singletons: Rc<[Singleton]> = self
+            .with_stmt
+            .singletons
+            .iter()
+            .filter_map(|term| {
+                let comp = term(world).ok()?;
+                Some(Singleton {
+                    ptr: get_component_ptr(world, comp, comp)?.as_ptr(),
+                    type_info: Rc::clone(component_type_info(world, comp)?),
+                })
+            })
+            .collect();
+
+        if singletons.len() != self.with_stmt.singletons.len() {
+            self.table_ids.clear();
+        }
+
+        singletons
+    }
+
+    pub fn next_table<'a>(&'a mut self, ctx: &'a mut Context) -> Option<TableView<'a>> {
         #[inline]
         fn try_select(select: &Select, table: &Table, fields: &mut Vec<Field>) -> bool {
             if let Some(&col) = table.column_map.get(select.id) {
-                fields.push(Field::new(select, col, false));
+                fields.push(Field::new(select, col));
                 return true;
             }
             false
@@ -276,7 +826,7 @@ impl QueryPlan {
         #[inline]
         fn try_anyof(select: &Select, table: &Table, fields: &mut Vec<Field>) -> bool {
             if let Some(&col) = table.column_map.get(select.id) {
-                fields.push(Field::new(select, col, true));
+                fields.push(Field::new(select, col));
                 return true;
             }
             false
@@ -290,19 +840,29 @@ impl QueryPlan {
                 .copied()
                 .unwrap_or(usize::MAX);
 
-            fields.push(Field::new(select, col, true));
+            fields.push(Field::new(select, col));
         }
 
         while let Some(arch_id) = self.table_ids.pop() {
             let table = &ctx.world.table_index[arch_id];
-            ctx.fields.clear();
+            self.fields.clear();
+
+            // Check table filters
+            if !self
+                .table_filters
+                .iter()
+                .all(|filter| filter(arch_id, table.signature.ids()))
+            {
+                continue;
+            }
 
             // Check with
             if !self
                 .with_stmt
                 .with
                 .iter()
-                .all(|&cid| table.signature.has_id(cid))
+                .chain(&self.filter_ids)
+                .all(|&cid| table.signature.has_id(cid) || self.sparse_with.contains(&cid))
             {
                 continue;
             }
@@ -317,6 +877,24 @@ impl QueryPlan {
                 continue;
             }
 
+            // Check (R, this): the table must have some (R, *) pair, targets are checked per row.
+            // Pairs of sparse relationships are not in tables, so they're only checked per row.
+            if !self.with_stmt.this_pairs.iter().all(|&rel| {
+                let in_tables = ctx
+                    .world
+                    .components
+                    .get(rel)
+                    .is_none_or(|ci| matches!(ci.storage, Storage::Tables(_)));
+
+                !in_tables
+                    || table
+                        .signature
+                        .iter()
+                        .any(|id| id.is_pair() && id.pair_rel().index() == rel.index())
+            }) {
+                continue;
+            }
+
             // Check with anyof
             if !self
                 .with_stmt
@@ -332,7 +910,7 @@ impl QueryPlan {
                 .select_stmt
                 .select
                 .iter()
-                .all(|comp| try_select(comp, table, &mut ctx.fields))
+                .all(|comp| try_select(comp, table, &mut self.fields))
             {
                 continue;
             }
@@ -341,7 +919,7 @@ impl QueryPlan {
             if !self.select_stmt.anyofs.iter().all(|anyof| {
                 anyof
                     .iter()
-                    .any(|comp| try_anyof(comp, table, &mut ctx.fields))
+                    .any(|comp| try_anyof(comp, table, &mut self.fields))
             }) {
                 continue;
             }
@@ -350,11 +928,507 @@ impl QueryPlan {
             self.select_stmt
                 .optionals
                 .iter()
-                .for_each(|comp| select_optional(comp, table, &mut ctx.fields));
+                .for_each(|comp| select_optional(comp, table, &mut self.fields));
 
-            return Some(TableView { table });
+            if ctx.world.query_validation {
+                table.validate_data();
+            }
+
+            return Some(TableView {
+                world: ctx.world,
+                table,
+                row_filters: &self.row_filters,
+                sparse_with: &self.sparse_with,
+                sparse_without: &self.sparse_without,
+                filters: &self.select_stmt.filters,
+                filter_ids: &self.filter_ids,
+            });
         }
 
         None
     }
 }
+
+/// Returns whether the term `comp` is stored in tables, `None` if it's not a component.
+fn term_in_tables(world: &World, comp: Id) -> Option<bool> {
+    if stored_relation(world, comp).is_some() {
+        return Some(false);
+    }
+
+    let ci = world.components.get(comp)?;
+    Some(matches!(ci.storage, Storage::Tables(_)))
+}
+
+/// Returns the tables holding `comp`, `None` if it's not stored in tables.
+fn table_records(world: &World, comp: Id) -> Option<&HashMap<TableId, ComponentLocation>> {
+    match &world.components.get(comp)?.storage {
+        Storage::Tables(tables) => Some(tables),
+        _ => None,
+    }
+}
+
+impl QueryPlan {
+    /// Initializes the tables and calls `f` with each matching table.
+    pub(crate) fn for_each_table(&mut self, world: &World, mut f: impl FnMut(&TableView)) {
+        self.init_tables(world);
+        let mut ctx = Context::new(world);
+
+        while let Some(view) = self.next_table(&mut ctx) {
+            f(&view);
+        }
+    }
+
+    /// Returns the ids of all matching rows, table by table.
+    pub(crate) fn matching_ids(&mut self, world: &World) -> Vec<Id> {
+        let mut ids = vec![];
+        self.for_each_table(world, |view| ids.extend(view.rows().map(|(_, id)| id)));
+        ids
+    }
+
+    /// Iterates the matching rows of all tables, with their ids.
+    ///
+    /// The tables are initialized first, see [QueryPlan::init_tables].
+    pub fn iter<'w>(&'w mut self, world: &'w mut World) -> QueryIter<'w> {
+        let world: &'w World = world;
+        self.init_tables(world);
+        let singletons = self.bind_singletons(world);
+
+        QueryIter {
+            plan: self,
+            ctx: Context::new(world),
+            table: None,
+            fields: Rc::new([]),
+            singletons,
+            rows: vec![].into_iter(),
+            resume: None,
+        }
+    }
+
+    /// Continues an iteration stopped by a [BudgetedIter], see [QueryCursor].
+    ///
+    /// Tables are not computed again, tables created since the iteration started
+    /// are only visited by the next [QueryPlan::iter].
+    pub fn resume<'w>(&'w mut self, cursor: QueryCursor, world: &'w mut World) -> QueryIter<'w> {
+        let world: &'w World = world;
+
+        self.table_ids = cursor.tables;
+        self.table_ids
+            .retain(|&table| world.table_index.get(table).is_some());
+
+        // The current table restarts from its first row if rows were removed or reordered.
+        let resume =
+            cursor
+                .resume
+                .map(|(table, row, version)| match world.table_index.get(table) {
+                    Some(t) if t.id_data.structure_version() == version => (table, row),
+                    _ => (table, 0),
+                });
+
+        let singletons = self.bind_singletons(world);
+
+        QueryIter {
+            plan: self,
+            ctx: Context::new(world),
+            table: None,
+            fields: Rc::new([]),
+            singletons,
+            rows: vec![].into_iter(),
+            resume,
+        }
+    }
+
+    /// Iterates the matching ids with the params `Q`.
+    ///
+    /// Ids for which `Q` can't be created are skipped.
+    pub fn iter_as<'w, Q: Params>(
+        &'w mut self,
+        world: &'w mut World,
+    ) -> impl Iterator<Item = (Id, Q::ParamsType<'w>)> + 'w {
+        let ids = self.matching_ids(world);
+        let world: UnsafeWorldPtr<'w> = world.into();
+
+        ids.into_iter().filter_map(move |id| {
+            // SAFETY:
+            // - We hold the exclusive borrow of the world.
+            // - Each id is yielded once, so params of different items don't alias.
+            unsafe { Q::create(world, id) }
+                .ok()
+                .map(|params| (id, params))
+        })
+    }
+}
+
+/// Iterator over the matching rows of a [QueryPlan], created by [QueryPlan::iter].
+pub struct QueryIter<'w> {
+    plan: &'w mut QueryPlan,
+    ctx: Context<'w>,
+    table: Option<&'w Table>,
+    /// Fields of the current table.
+    fields: Rc<[Field]>,
+    /// Values of the singleton terms.
+    singletons: Rc<[Singleton]>,
+    /// Remaining rows of the current table.
+    rows: vec::IntoIter<(usize, Id)>,
+    /// Table resumed by [QueryPlan::resume] and the row to start from.
+    resume: Option<(TableId, usize)>,
+}
+
+impl<'w> QueryIter<'w> {
+    /// Stops iterating after `max_rows` rows, the rest can be visited later
+    /// with the cursor of the returned iterator.
+    #[inline]
+    pub fn by_budget(self, max_rows: usize) -> BudgetedIter<'w> {
+        BudgetedIter {
+            iter: self,
+            remaining: max_rows,
+        }
+    }
+
+    /// Returns the position of the next row, `None` if all tables were visited.
+    fn cursor(&self) -> Option<QueryCursor> {
+        let mut tables = self.plan.table_ids.clone();
+
+        let resume = match (self.table, self.rows.as_slice().first()) {
+            (Some(table), Some(&(row, _))) => {
+                tables.push(table.id);
+                Some((table.id, row))
+            }
+            // The resumed table is still on top of the remaining tables.
+            _ => self.resume,
+        };
+
+        if tables.is_empty() {
+            return None;
+        }
+
+        let resume = resume.map(|(table, row)| {
+            let version = self.ctx.world.table_index[table]
+                .id_data
+                .structure_version();
+            (table, row, version)
+        });
+
+        Some(QueryCursor { tables, resume })
+    }
+}
+
+impl<'w> Iterator for QueryIter<'w> {
+    type Item = (Id, MatchedRow<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some((row, id)), Some(table)) = (self.rows.next(), self.table) {
+                let matched = MatchedRow {
+                    table,
+                    row,
+                    fields: Rc::clone(&self.fields),
+                    singletons: Rc::clone(&self.singletons),
+                };
+
+                return Some((id, matched));
+            }
+
+            let view = self.plan.next_table(&mut self.ctx)?;
+            let table = view.table.id;
+
+            let start = match self.resume.take() {
+                Some((resumed, row)) if resumed == table => row,
+                _ => 0,
+            };
+
+            let rows: Vec<_> = view.rows().filter(|&(row, _)| row >= start).collect();
+
+            let world = self.ctx.world;
+            self.table = Some(&world.table_index[table]);
+            self.fields = self.plan.fields.iter().copied().collect();
+            self.rows = rows.into_iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let table_index = &self.ctx.world.table_index;
+        let remaining = self.rows.len();
+        let candidates = self
+            .plan
+            .table_ids
+            .iter()
+            .map(|&table| table_index[table].id_data.row_count())
+            .sum::<usize>();
+
+        (remaining, Some(remaining + candidates))
+    }
+}
+
+/// Position of a stopped iteration, continued with [QueryPlan::resume].
+///
+/// Rows of the current table are continued from the stored row if no rows were removed
+/// or reordered since. Otherwise the table restarts, so its rows may be visited again,
+/// but rows are never skipped. Rows added to a table are visited if the table isn't
+/// finished yet.
+#[derive(Debug, Clone)]
+pub struct QueryCursor {
+    /// Tables left to visit, the current table is last.
+    tables: Vec<TableId>,
+    /// Current table, the row to continue from and the structure version of the table.
+    resume: Option<(TableId, usize, u32)>,
+}
+
+/// Iterator over at most a fixed number of rows of a [QueryIter],
+/// created by [QueryIter::by_budget].
+pub struct BudgetedIter<'w> {
+    iter: QueryIter<'w>,
+    remaining: usize,
+}
+
+impl BudgetedIter<'_> {
+    /// Returns the cursor to continue from, `None` if the iteration is complete.
+    #[inline]
+    pub fn into_cursor(self) -> Option<QueryCursor> {
+        self.iter.cursor()
+    }
+}
+
+impl<'w> Iterator for BudgetedIter<'w> {
+    type Item = (Id, MatchedRow<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        self.iter.next()
+    }
+}
+
+/// Row matched by a [QueryIter], giving access to the selected fields.
+///
+/// Fields are indexed in selection order: selects, any-of groups, then optionals.
+pub struct MatchedRow<'w> {
+    table: &'w Table,
+    row: usize,
+    fields: Rc<[Field]>,
+    singletons: Rc<[Singleton]>,
+}
+
+impl MatchedRow<'_> {
+    /// Returns the column of `field` if it's present and holds `T`.
+    fn column<T: 'static>(&self, field: usize, write: bool) -> Option<usize> {
+        let col = match (self.fields.get(field)?.access, write) {
+            (ColumnAccess::Write(col), _) | (ColumnAccess::Read(col), false) => col,
+            (ColumnAccess::Read(_), true) => return None,
+        };
+
+        // Missing optionals have no column.
+        let columns = self.table.id_data.columns();
+        let column = columns.get(col)?;
+        column.type_info().is::<T>().then_some(col)
+    }
+
+    /// Gets the value of `field`.
+    ///
+    /// Returns `None` if the field is a missing optional or doesn't hold `T`.
+    pub fn get<T: DataComponent>(&self, field: usize) -> Option<&T> {
+        let col = self.column::<T>(field, false)?;
+
+        // SAFETY:
+        // - The row was matched in this table, so it's in bounds.
+        // - We checked that the column holds T.
+        Some(unsafe { self.table.id_data.get(col, self.row) })
+    }
+
+    /// Gets the value of the singleton term at `index`, in the order of [WithStmt::singleton].
+    ///
+    /// Returns `None` if there's no such term or it doesn't hold `T`.
+    pub fn singleton<T: DataComponent>(&self, index: usize) -> Option<&T> {
+        let singleton = self.singletons.get(index)?;
+
+        if !singleton.type_info.is::<T>() {
+            return None;
+        }
+
+        // SAFETY:
+        // - We checked that the singleton holds T.
+        // - The id of the component is never matched, so no row aliases the value.
+        Some(unsafe { singleton.ptr.cast::<T>().as_ref() })
+    }
+
+    /// Gets the value of `field` mutably.
+    ///
+    /// Returns `None` if the field was not selected for writing, is a missing optional
+    /// or doesn't hold `T`.
+    pub fn get_mut<T: DataComponent>(&mut self, field: usize) -> Option<&mut T> {
+        let col = self.column::<T>(field, true)?;
+
+        // SAFETY:
+        // - The row was matched in this table, so it's in bounds.
+        // - We checked that the column holds T.
+        // - The iterator holds the exclusive borrow of the world, and each row is yielded once,
+        //   so the value is only reachable through this row.
+        Some(unsafe {
+            self.table
+                .id_data
+                .column_ref(col)
+                .ptr_unchecked(self.row)
+                .assert_unique()
+                .deref_mut::<T>()
+        })
+    }
+}
+
+/// Iterator over the ids and values of a sparse component, created by
+/// [World::query_sparse_only](crate::world::World::query_sparse_only).
+///
+/// Walks the sparse set directly, entities without the component are never visited.
+/// Ids marked for deletion are skipped, unless required with [SparseQueryIter::with].
+pub struct SparseQueryIter<'w, T: TypedId> {
+    world: &'w World,
+    set: Option<&'w SparseData>,
+    dense: usize,
+    with: Vec<Id>,
+    without: Vec<Id>,
+    marker: PhantomData<T>,
+}
+
+impl<'w, T: TypedId> SparseQueryIter<'w, T> {
+    pub(crate) fn new(world: &'w World, set: Option<&'w SparseData>) -> Self {
+        Self {
+            world,
+            set,
+            dense: 0,
+            with: vec![],
+            without: vec![],
+            marker: PhantomData,
+        }
+    }
+
+    /// Only yields entities that have `comp`.
+    pub fn with(mut self, comp: Id) -> Self {
+        self.with.push(comp);
+        self
+    }
+
+    /// Only yields entities that don't have `comp`.
+    pub fn without(mut self, comp: Id) -> Self {
+        self.without.push(comp);
+        self
+    }
+
+    fn matches(&self, id: Id) -> bool {
+        // Ids marked for deletion are skipped unless asked for.
+        let pending = self.world.pending_delete_tag();
+        let skip_pending = !self.with.contains(&pending) && self.world.is_pending_delete(id);
+
+        !skip_pending
+            && self.with.iter().all(|&comp| self.world.has_id(id, comp))
+            && !self.without.iter().any(|&comp| self.world.has_id(id, comp))
+    }
+}
+
+impl<'w, T: TypedId> Iterator for SparseQueryIter<'w, T>
+where
+    T::Data: DataComponent,
+{
+    type Item = (Id, &'w T::Data);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set?;
+
+        loop {
+            // SAFETY: the set was looked up with the id of T, so the data type matches.
+            let (id, val) = unsafe { set.get_dense::<T::Data>(self.dense)? };
+            self.dense += 1;
+
+            if self.matches(id) {
+                return Some((id, val));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.map_or(0, |set| set.ids().len() - self.dense);
+
+        match self.with.is_empty() && self.without.is_empty() {
+            true => (remaining, Some(remaining)),
+            false => (0, Some(remaining)),
+        }
+    }
+}
+
+/// Query over the ids matching `Q`, passed to [World::run_once](crate::world::World::run_once).
+pub struct WorldQuery<'w, Q: Params> {
+    world: UnsafeWorldPtr<'w>,
+    ids: Vec<Id>,
+    marker: PhantomData<Q>,
+}
+
+impl<'w, Q: Params> WorldQuery<'w, Q> {
+    pub(crate) fn new(world: &'w mut World) -> Self {
+        let select = Q::select_terms(world, SelectStmt::new());
+        let ids = QueryPlan::new(select, WithStmt::new()).matching_ids(world);
+
+        Self {
+            world: world.into(),
+            ids,
+            marker: PhantomData,
+        }
+    }
+
+    /// Iterates the ids matching the query with their params.
+    ///
+    /// Ids are matched by table first, components that are not stored in tables
+    /// are checked per id.
+    ///
+    /// # Panics
+    /// Panics if the params of a matched id can't be created, e.g. if they access
+    /// a component mutably more than once. See [WorldQuery::try_iter].
+    pub fn iter(&mut self) -> impl Iterator<Item = (Id, Q::ParamsType<'_>)> + '_ {
+        self.try_iter().map(expect_params)
+    }
+
+    /// Iterates the ids matching the query with the result of creating their params.
+    pub fn try_iter(&mut self) -> impl Iterator<Item = (Id, GetResult<Q::ParamsType<'_>>)> + '_ {
+        let world: UnsafeWorldPtr<'_> = self.world;
+
+        self.ids.iter().map(move |&id| {
+            // SAFETY:
+            // - The query holds the exclusive borrow of the world.
+            // - Each id is yielded once, so params of different items don't alias.
+            (id, unsafe { Q::create(world, id) })
+        })
+    }
+
+    /// Iterates the ids matching the query with their params, borrowing the world
+    /// for as long as the query did.
+    ///
+    /// # Panics
+    /// Same as [WorldQuery::iter].
+    pub fn into_items(self) -> impl Iterator<Item = (Id, Q::ParamsType<'w>)> + 'w {
+        let world = self.world;
+
+        self.ids.into_iter().map(move |id| {
+            // SAFETY: Same as WorldQuery::into_try_items.
+            expect_params((id, unsafe { Q::create(world, id) }))
+        })
+    }
+
+    /// Same as [WorldQuery::into_items], with the result of creating the params.
+    pub fn into_try_items(self) -> impl Iterator<Item = (Id, GetResult<Q::ParamsType<'w>>)> + 'w {
+        let world = self.world;
+
+        self.ids.into_iter().map(move |id| {
+            // SAFETY:
+            // - The query held the exclusive borrow of the world, which moves to the iterator.
+            // - Each id is yielded once, so params of different items don't alias.
+            (id, unsafe { Q::create(world, id) })
+        })
+    }
+}
+
+/// Unwraps the params of an id matched by a [WorldQuery].
+fn expect_params<P>((id, params): (Id, GetResult<P>)) -> (Id, P) {
+    match params {
+        Ok(params) => (id, params),
+        Err(err) => panic!("params of matched id {id} can't be created: {err}"),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    id::Id,
+    world::World,
+    world_utils::{component_type_info, get_component_ptr},
+};
+
+/// Structural change recorded by [World::enable_recording].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Spawn {
+        id: Id,
+    },
+    Despawn {
+        id: Id,
+    },
+    /// The id gained a component. Values of data components follow in a [Event::Set].
+    AddId {
+        id: Id,
+        comp: Id,
+    },
+    Remove {
+        id: Id,
+        comp: Id,
+    },
+    Set {
+        id: Id,
+        comp: Id,
+        data: SetData,
+    },
+}
+
+/// Value written by a [Event::Set].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetData {
+    /// Value written by the serialize hook, or the bytes of a pod value.
+    Bytes(Vec<u8>),
+    /// The value can't be recorded, replay writes the default value instead.
+    Marker,
+}
+
+/// Receives the events recorded by a [World].
+pub trait EventSink {
+    fn record(&mut self, event: Event);
+}
+
+impl<F: FnMut(Event)> EventSink for F {
+    #[inline]
+    fn record(&mut self, event: Event) {
+        self(event)
+    }
+}
+
+/// Sends the event to the sink of the world, if it's recording.
+///
+/// The event is only created while recording.
+#[inline]
+pub(crate) fn record(world: &mut World, event: impl FnOnce(&World) -> Event) {
+    if world.recorder.is_none() {
+        return;
+    }
+
+    let event = event(world);
+
+    if let Some(recorder) = &mut world.recorder {
+        recorder.record(event);
+    }
+}
+
+/// Creates the [Event::Set] for the current value of `comp` for `id`.
+pub(crate) fn set_event(world: &World, id: Id, comp: Id) -> Event {
+    let ti = component_type_info(world, comp);
+
+    let data = match (ti, get_component_ptr(world, id, comp)) {
+        (Some(ti), Some(ptr)) => match &ti.hooks.serialize {
+            Some(ser) => {
+                let mut buf = vec![];
+                ser(ptr.as_ptr(), &mut buf);
+                SetData::Bytes(buf)
+            }
+            // SAFETY: The pointer is valid for a value of the component size,
+            // and pod types have no padding.
+            None if ti.hooks.pod => SetData::Bytes(unsafe {
+                std::slice::from_raw_parts(ptr.as_ptr().as_ptr(), ti.size).to_vec()
+            }),
+            None => SetData::Marker,
+        },
+        _ => SetData::Marker,
+    };
+
+    Event::Set { id, comp, data }
+}
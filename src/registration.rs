@@ -1,10 +1,16 @@
 use crate::{
-    error::{UnregisteredTypeErr, unreg_type_err},
+    error::{EcsResult, UnregisteredTypeErr, unreg_type_err},
     id::Id,
     type_traits::Component,
     world::World,
 };
+use private::Sealed;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use xecs_macros::all_tuples;
+
+mod private {
+    pub trait Sealed {}
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TypeIndex(usize);
@@ -67,3 +73,32 @@ pub unsafe trait ComponentId: Component {
         }
     }
 }
+
+/// A list of component types registered at once by [World::register_bulk].
+///
+/// Implemented for tuples of components, e.g. `(Position, Velocity, Player)`.
+pub trait ComponentList: Sealed {
+    /// Number of components in the list.
+    #[doc(hidden)]
+    const LEN: usize;
+
+    /// Registers the components of the list with their default descriptors, in order.
+    #[doc(hidden)]
+    fn register_all(world: &mut World, ids: &mut Vec<Id>) -> EcsResult<()>;
+}
+
+macro_rules! impl_tuple_component_list {
+    ($($t:ident),*) => {
+        impl<$($t: ComponentId),*> Sealed for ($($t,)*) where $($t::DescType: Default),* {}
+        impl<$($t: ComponentId),*> ComponentList for ($($t,)*) where $($t::DescType: Default),* {
+            const LEN: usize = [$(stringify!($t)),*].len();
+
+            fn register_all(world: &mut World, ids: &mut Vec<Id>) -> EcsResult<()> {
+                $(ids.push(world.try_register::<$t>(Default::default())?);)*
+                Ok(())
+            }
+        }
+    }
+}
+
+all_tuples!(impl_tuple_component_list, 1, 13);
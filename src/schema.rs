@@ -0,0 +1,138 @@
+use crate::{flags::ComponentFlags, storage::StorageType, world::World};
+use thiserror::Error;
+
+/// Registration details of a component, without any entity data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentSchema {
+    /// Raw id of the component in the exporting world.
+    pub id: u64,
+    /// Name of the component, used to match components across worlds.
+    pub name: String,
+    /// Size of the component data, `None` for tags.
+    pub size: Option<usize>,
+    /// Alignment of the component data, `None` for tags.
+    pub align: Option<usize>,
+    pub storage: StorageType,
+    /// Raw [ComponentFlags] bits, includes relationship properties.
+    pub flags: u64,
+    pub has_default: bool,
+    pub has_clone: bool,
+    pub has_on_set: bool,
+    pub has_on_remove: bool,
+}
+
+/// Component registration schema of a world.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schema {
+    pub components: Vec<ComponentSchema>,
+}
+
+/// Difference between a saved [Schema] and the components registered in a world.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SchemaMismatch {
+    #[error("Component {0} is not registered")]
+    MissingComponent(String),
+    #[error("Component {name} size changed from {saved:?} to {current:?}")]
+    SizeChanged {
+        name: String,
+        saved: Option<usize>,
+        current: Option<usize>,
+    },
+    #[error("Component {name} storage changed from {saved:?} to {current:?}")]
+    StorageChanged {
+        name: String,
+        saved: StorageType,
+        current: StorageType,
+    },
+    #[error("Component {name} flags changed from {saved:?} to {current:?}")]
+    FlagsChanged {
+        name: String,
+        saved: ComponentFlags,
+        current: ComponentFlags,
+    },
+}
+
+impl Schema {
+    /// Exports the schema of all components registered in the world.
+    ///
+    /// Pairs are left out, their properties are part of their relationship's flags.
+    pub(crate) fn of(world: &World) -> Self {
+        let mut components: Vec<_> = world
+            .components
+            .iter()
+            .filter(|(id, _)| !id.is_pair())
+            .map(|(id, ci)| {
+                let ti = ci.type_info.as_deref();
+
+                ComponentSchema {
+                    id: id.to_raw(),
+                    name: ci.display_name(),
+                    size: ti.map(|ti| ti.size),
+                    align: ti.map(|ti| ti.align),
+                    storage: ci.storage.get_type(),
+                    flags: ci.flags.bits(),
+                    has_default: ti.is_some_and(|ti| ti.hooks.default.is_some()),
+                    has_clone: ti.is_some_and(|ti| ti.hooks.clone.is_some()),
+                    has_on_set: ti.is_some_and(|ti| ti.hooks.on_set.is_some()),
+                    has_on_remove: ti.is_some_and(|ti| ti.hooks.on_remove.is_some()),
+                }
+            })
+            .collect();
+
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { components }
+    }
+
+    /// Lists how the components of the world differ from the ones in `self`.
+    ///
+    /// Components are matched by name, components missing from `self` are not reported.
+    pub(crate) fn mismatches(&self, world: &World) -> Vec<SchemaMismatch> {
+        let current = Self::of(world);
+        let mut mismatches = vec![];
+
+        for saved in &self.components {
+            let Some(comp) = current.components.iter().find(|c| c.name == saved.name) else {
+                mismatches.push(SchemaMismatch::MissingComponent(saved.name.clone()));
+                continue;
+            };
+
+            if comp.size != saved.size || comp.align != saved.align {
+                mismatches.push(SchemaMismatch::SizeChanged {
+                    name: saved.name.clone(),
+                    saved: saved.size,
+                    current: comp.size,
+                });
+            }
+
+            if comp.storage != saved.storage {
+                mismatches.push(SchemaMismatch::StorageChanged {
+                    name: saved.name.clone(),
+                    saved: saved.storage,
+                    current: comp.storage,
+                });
+            }
+
+            if comp.flags != saved.flags {
+                mismatches.push(SchemaMismatch::FlagsChanged {
+                    name: saved.name.clone(),
+                    saved: ComponentFlags::from_bits(saved.flags),
+                    current: ComponentFlags::from_bits(comp.flags),
+                });
+            }
+        }
+
+        mismatches
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    id::Id,
+    storage::{Storage, StorageType},
+    world::World,
+};
+
+/// Storage usage of a single component, see [World::component_stats].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStats {
+    pub storage: StorageType,
+    /// Number of ids that have the component.
+    pub count: usize,
+    /// Number of tables with the component, 0 for sparse components.
+    pub tables: usize,
+    /// Bytes used by the values of the component.
+    pub bytes: usize,
+    /// Bytes allocated for the values of the component.
+    pub capacity_bytes: usize,
+    /// Average number of rows of the tables with the component.
+    pub avg_table_rows: f32,
+    /// Number of times the component was added to an id.
+    ///
+    /// Adds and removes of table components are only counted while
+    /// [World::enable_move_stats] is on.
+    pub adds: u64,
+    /// Number of times the component was removed from an id, including despawns.
+    pub removes: u64,
+}
+
+impl ComponentStats {
+    /// Returns `None` if `comp` is not a component.
+    pub(crate) fn of(world: &World, comp: Id) -> Option<Self> {
+        let ci = world.components.get(comp)?;
+
+        let mut stats = Self {
+            storage: ci.storage.get_type(),
+            count: 0,
+            tables: 0,
+            bytes: 0,
+            capacity_bytes: 0,
+            avg_table_rows: 0.0,
+            adds: 0,
+            removes: 0,
+        };
+
+        for src in world.table_index.all_tables() {
+            let had = src.signature.has_id(comp);
+
+            if had {
+                stats.removes += src.deletes;
+            }
+
+            for (&dst, &count) in &src.moves {
+                match (had, world.table_index[dst].signature.has_id(comp)) {
+                    (false, true) => stats.adds += count,
+                    (true, false) => stats.removes += count,
+                    _ => {}
+                }
+            }
+        }
+
+        match &ci.storage {
+            Storage::SparseTag(set) => {
+                stats.count = set.ids().len();
+                stats.adds += set.changes().adds;
+                stats.removes += set.changes().removes;
+            }
+            Storage::SparseRelation(store) => {
+                stats.count = store.pair_count();
+                stats.adds += store.changes().adds;
+                stats.removes += store.changes().removes;
+
+                if let Some(column) = store.column() {
+                    stats.bytes = column.byte_size();
+                    stats.capacity_bytes = column.capacity_bytes();
+                }
+            }
+            Storage::SparseData(set) => {
+                stats.count = set.ids().len();
+                stats.bytes = set.column().byte_size();
+                stats.capacity_bytes = set.column().capacity_bytes();
+                stats.adds += set.changes().adds;
+                stats.removes += set.changes().removes;
+            }
+            Storage::Tables(tables) => {
+                for &table in tables.keys() {
+                    let table = &world.table_index[table];
+                    stats.count += table.id_data.row_count();
+
+                    if let Some(&col) = table.column_map.get(comp) {
+                        let column = table.id_data.column(col);
+                        stats.bytes += column.byte_size();
+                        stats.capacity_bytes += column.capacity_bytes();
+                    }
+                }
+
+                stats.tables = tables.len();
+
+                if stats.tables > 0 {
+                    stats.avg_table_rows = stats.count as f32 / stats.tables as f32;
+                }
+            }
+        }
+
+        Some(stats)
+    }
+}
+
+/// Memory used by a single component, see [World::component_storage_stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentStorageStats {
+    /// Number of ids that have the component.
+    pub entity_count: usize,
+    /// Bytes used by the values of the component.
+    pub dense_bytes: usize,
+    /// Bytes used to find the value of an id, 0 for components stored in tables.
+    pub sparse_bytes: usize,
+}
+
+impl ComponentStorageStats {
+    /// Returns `None` if `comp` is not a component.
+    pub(crate) fn of(world: &World, comp: Id) -> Option<Self> {
+        let ci = world.components.get(comp)?;
+        let size = ci.type_info.as_ref().map_or(0, |ti| ti.size);
+
+        let stats = match &ci.storage {
+            Storage::SparseTag(set) => Self {
+                entity_count: set.ids().len(),
+                dense_bytes: 0,
+                sparse_bytes: set.sparse_bytes(),
+            },
+            Storage::SparseRelation(store) => Self {
+                entity_count: store.ids().count(),
+                dense_bytes: store.pair_count() * size,
+                sparse_bytes: store.sparse_bytes(),
+            },
+            Storage::SparseData(set) => Self {
+                entity_count: set.ids().len(),
+                dense_bytes: set.ids().len() * size,
+                sparse_bytes: set.sparse_bytes(),
+            },
+            Storage::Tables(tables) => {
+                let entity_count = tables
+                    .keys()
+                    .map(|&table| world.table_index[table].id_data.row_count())
+                    .sum();
+
+                Self {
+                    entity_count,
+                    dense_bytes: entity_count * size,
+                    sparse_bytes: 0,
+                }
+            }
+        };
+
+        Some(stats)
+    }
+}
+
+/// Storage type suggested by [World::suggest_storage], with the reason for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageSuggestion {
+    pub storage: StorageType,
+    pub reason: &'static str,
+}
+
+/// Tables with the component above which small tables suggest sparse storage.
+const MANY_TABLES: usize = 16;
+/// Average rows per table under which tables are considered small.
+const FEW_ROWS: f32 = 4.0;
+/// Adds and removes per id above which the component is considered churning.
+const CHURN_PER_ID: u64 = 4;
+
+impl StorageSuggestion {
+    pub(crate) fn of(stats: &ComponentStats) -> Self {
+        if stats.tables > MANY_TABLES && stats.avg_table_rows < FEW_ROWS {
+            return Self {
+                storage: StorageType::Sparse,
+                reason: "the component is spread across many small tables",
+            };
+        }
+
+        let changes = stats.adds + stats.removes;
+
+        if changes > CHURN_PER_ID * stats.count.max(1) as u64 {
+            return Self {
+                storage: StorageType::Sparse,
+                reason: "the component is added and removed frequently",
+            };
+        }
+
+        Self {
+            storage: StorageType::Tables,
+            reason: "the component is stable and iterated with others",
+        }
+    }
+}
+
+/// Bytes allocated by a world, see [World::total_memory_usage].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// Records of all ids.
+    pub entity_index_bytes: usize,
+    /// Ids and columns of all tables.
+    pub table_column_bytes: usize,
+    /// Ids, values and sparse arrays of components not stored in tables.
+    pub sparse_set_bytes: usize,
+    /// Add and remove edges between tables.
+    pub graph_bytes: usize,
+    /// Component records and the ids of registered types.
+    pub component_registry_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub(crate) fn of(world: &World) -> Self {
+        let mut usage = Self {
+            entity_index_bytes: world.id_manager.allocated_bytes(),
+            component_registry_bytes: world.components.allocated_bytes()
+                + world.type_arr.capacity() * size_of::<Option<Id>>(),
+            ..Self::default()
+        };
+
+        for &table in world.table_index.all_table_ids() {
+            let table = &world.table_index[table];
+            usage.table_column_bytes += table.id_data.allocated_bytes();
+            usage.graph_bytes += table.node.allocated_bytes();
+        }
+
+        for (_, ci) in world.components.iter() {
+            usage.sparse_set_bytes += match &ci.storage {
+                Storage::SparseTag(set) => set.allocated_bytes(),
+                Storage::SparseData(set) => set.allocated_bytes(),
+                Storage::SparseRelation(store) => store.allocated_bytes(),
+                Storage::Tables(_) => 0,
+            };
+        }
+
+        usage
+    }
+}
@@ -1,5 +1,7 @@
-use crate::{id::Key, type_info::TypeInfo};
+use crate::{allocator::WorldAlloc, id::Key, type_info::TypeInfo};
 use std::{
+    marker::PhantomData,
+    mem::MaybeUninit,
     ptr::{self, NonNull},
     rc::Rc,
 };
@@ -13,16 +15,24 @@ pub(crate) struct ColumnVec<K: Key> {
     len: usize,
     cap: usize,
     type_info: Rc<TypeInfo>,
+    /// Change tick of each row, see [World::change_tick](crate::world::World::change_tick).
+    ticks: Vec<u64>,
+    /// Highest change tick of all rows.
+    last_written_tick: u64,
+    alloc: Rc<dyn WorldAlloc>,
 }
 
 impl<K: Key> ColumnVec<K> {
-    pub fn new(id: K, type_info: Rc<TypeInfo>) -> Self {
+    pub fn new(id: K, type_info: Rc<TypeInfo>, alloc: Rc<dyn WorldAlloc>) -> Self {
         Self {
             id,
             data: (type_info.dangling)(),
             len: 0,
             cap: if type_info.size == 0 { usize::MAX } else { 0 },
             type_info,
+            ticks: vec![],
+            last_written_tick: 0,
+            alloc,
         }
     }
 
@@ -36,6 +46,78 @@ impl<K: Key> ColumnVec<K> {
         self.len
     }
 
+    #[inline]
+    pub(crate) fn type_info(&self) -> &TypeInfo {
+        &self.type_info
+    }
+
+    /// Returns the number of bytes used by the values in the column.
+    #[inline]
+    pub(crate) fn byte_size(&self) -> usize {
+        self.len * self.type_info.size
+    }
+
+    /// Returns the number of values the column can hold without reallocating.
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the number of bytes allocated for the column.
+    #[inline]
+    pub(crate) fn capacity_bytes(&self) -> usize {
+        match self.type_info.size {
+            0 => 0,
+            size => self.cap * size,
+        }
+    }
+
+    /// Returns the values of the column, in row order.
+    ///
+    /// # Safety
+    /// `T` must be the value type of the column.
+    #[inline]
+    pub(crate) unsafe fn as_slice<T>(&self) -> &[T] {
+        // SAFETY:
+        // - data is non-null and aligned for T, even for empty columns.
+        // - Caller ensures that T matches, and all rows below len are initialized.
+        unsafe { std::slice::from_raw_parts(self.data.cast::<T>().as_ptr(), self.len) }
+    }
+
+    /// Returns the bytes of the values of the column, in row order.
+    ///
+    /// Padding bytes of the values may be uninitialized.
+    #[inline]
+    pub(crate) fn as_bytes(&self) -> &[MaybeUninit<u8>] {
+        // SAFETY: The bytes of the first len values are allocated, and any byte is a valid
+        // MaybeUninit<u8>.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.data.cast::<MaybeUninit<u8>>().as_ptr(),
+                self.byte_size(),
+            )
+        }
+    }
+
+    /// Returns the change tick of `row`.
+    #[inline]
+    pub(crate) fn tick(&self, row: usize) -> u64 {
+        self.ticks[row]
+    }
+
+    /// Returns the highest change tick of all rows.
+    #[inline]
+    pub(crate) fn last_written_tick(&self) -> u64 {
+        self.last_written_tick
+    }
+
+    /// Records that `row` was written at `tick`.
+    #[inline]
+    pub(crate) fn set_tick(&mut self, row: usize, tick: u64) {
+        self.ticks[row] = tick;
+        self.last_written_tick = self.last_written_tick.max(tick);
+    }
+
     pub(crate) fn reserve(&mut self, additional: usize) {
         let new_cap = self.len + additional;
 
@@ -55,10 +137,11 @@ impl<K: Key> ColumnVec<K> {
 
         let ptr = unsafe {
             if self.cap == 0 {
-                std::alloc::alloc(new_layout)
+                self.alloc.alloc(new_layout)
             } else {
                 let old_layout = (self.type_info.arr_layout)(self.cap).unwrap();
-                std::alloc::realloc(self.data.as_ptr(), old_layout, new_layout.size())
+                self.alloc
+                    .realloc(self.data.as_ptr(), old_layout, new_layout.size())
             }
         };
 
@@ -70,12 +153,118 @@ impl<K: Key> ColumnVec<K> {
         self.cap = new_cap;
     }
 
+    /// Reallocates the column to hold exactly `new_cap` values.
+    ///
+    /// # Safety
+    /// `new_cap` must not be less than the length of the column.
+    pub(super) unsafe fn shrink_to(&mut self, new_cap: usize) {
+        debug_assert!(new_cap >= self.len, "Column: capacity below length");
+        self.ticks.shrink_to(new_cap);
+
+        // Zero sized values never allocate.
+        if self.type_info.size == 0 || new_cap >= self.cap {
+            return;
+        }
+
+        let old_layout = (self.type_info.arr_layout)(self.cap).unwrap();
+
+        if new_cap == 0 {
+            // SAFETY: The column is allocated with old_layout since its capacity is not 0.
+            unsafe { self.alloc.dealloc(self.data.as_ptr(), old_layout) };
+            self.data = (self.type_info.dangling)();
+            self.cap = 0;
+            return;
+        }
+
+        let new_layout = (self.type_info.arr_layout)(new_cap).unwrap();
+
+        // SAFETY: The column is allocated with old_layout and the values fit in new_cap.
+        let ptr = unsafe {
+            self.alloc
+                .realloc(self.data.as_ptr(), old_layout, new_layout.size())
+        };
+
+        self.data = match NonNull::new(ptr) {
+            Some(ptr) => ptr,
+            None => std::alloc::handle_alloc_error(new_layout),
+        };
+
+        self.cap = new_cap;
+    }
+
+    /// Creates a column with clones of all values, with their change ticks.
+    ///
+    /// Values are cloned with the clone hook, or copied if the type is plain old data.
+    /// Returns `None` if it has neither.
+    pub(crate) fn try_clone(&self) -> Option<Self>
+    where
+        K: Copy,
+    {
+        let ti = &self.type_info;
+
+        if ti.hooks.clone.is_none() && !ti.hooks.pod {
+            return None;
+        }
+
+        let mut dst = Self::new(self.id, Rc::clone(ti), Rc::clone(&self.alloc));
+        dst.reserve(self.len);
+
+        for row in 0..self.len {
+            // SAFETY:
+            // - row is in bounds of self, and space was reserved for it in dst.
+            // - Values without a clone hook are plain old data, so their bytes can be copied.
+            unsafe {
+                let src = self.get_ptr(row);
+                let ptr = dst.data.add(row * ti.size);
+
+                match &ti.hooks.clone {
+                    Some(clone) => clone(src, ptr),
+                    None => ptr::copy_nonoverlapping(src.as_ptr(), ptr.as_ptr(), ti.size),
+                }
+            }
+
+            // The length is only increased once the value is written, in case the hook panics.
+            dst.ticks.push(self.ticks[row]);
+            dst.len += 1;
+        }
+
+        dst.last_written_tick = self.last_written_tick;
+        Some(dst)
+    }
+
     pub(super) unsafe fn push<T>(&mut self, val: T) {
         self.reserve(1);
         unsafe { self.data.as_ptr().cast::<T>().add(self.len).write(val) };
+        self.ticks.push(0);
+        self.len += 1;
+    }
+
+    /// Appends a value by copying its bytes from `src`.
+    ///
+    /// # Safety
+    /// `src` must point to a value of the column item type, which must not be used again.
+    pub(super) unsafe fn push_raw(&mut self, src: NonNull<u8>) {
+        self.reserve(1);
+
+        let size = self.type_info.size;
+
+        // SAFETY: we just reserved space for one more item.
+        unsafe {
+            ptr::copy_nonoverlapping(src.as_ptr(), self.data.as_ptr().add(self.len * size), size)
+        };
+
+        self.ticks.push(0);
         self.len += 1;
     }
 
+    /// Removes all values WITHOUT dropping them.
+    ///
+    /// Used once the values were moved out by copying their bytes.
+    pub(super) fn forget_all(&mut self) {
+        self.len = 0;
+        self.ticks.clear();
+    }
+
     /// # Safety
     /// - Caller must ensure that `row` is valid for this column.
     /// - Caller must ensure that `T` is the value type of this column.
@@ -124,6 +313,14 @@ impl<K: Key> ColumnVec<K> {
         unsafe { self.data.add(row * self.type_info.size) }
     }
 
+    /// Returns pointers to the values of all rows, in row order.
+    pub(crate) fn iter_ptr(&self) -> impl Iterator<Item = NonNull<u8>> + '_ {
+        let size = self.type_info.size;
+
+        // SAFETY: all rows below len are in bounds of the allocation.
+        (0..self.len).map(move |row| unsafe { self.data.add(row * size) })
+    }
+
     /// Removes this row by swapping with the last row and dropping its value.
     ///
     /// # Panics
@@ -143,6 +340,7 @@ impl<K: Key> ColumnVec<K> {
             }
 
             self.len = last_row;
+            self.ticks.swap_remove(row);
 
             if let Some(drop_fn) = self.type_info.drop_fn {
                 drop_fn(last_ptr)
@@ -172,6 +370,64 @@ impl<K: Key> ColumnVec<K> {
         }
 
         self.len = last_row;
+        self.ticks.swap_remove(row);
+    }
+
+    /// Moves all values of `src` to the end of the column, leaving `src` empty.
+    ///
+    /// # Safety
+    /// `self` and `src` must hold the same item type.
+    pub(super) unsafe fn append(&mut self, src: &mut Self) {
+        self.reserve(src.len);
+
+        let size = self.type_info.size;
+
+        // SAFETY: we just reserved space for the values of src, and columns don't overlap.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                src.data.as_ptr(),
+                self.data.as_ptr().add(self.len * size),
+                src.len * size,
+            )
+        };
+
+        self.len += src.len;
+        self.ticks.append(&mut src.ticks);
+        self.last_written_tick = self.last_written_tick.max(src.last_written_tick);
+        src.len = 0;
+    }
+
+    /// Removes all values, dropping them.
+    pub(super) fn clear(&mut self) {
+        if let Some(drop_fn) = self.type_info.drop_fn {
+            // SAFETY: all rows below len hold a value.
+            self.iter_ptr()
+                .for_each(|ptr| unsafe { drop_fn(ptr.as_ptr()) });
+        }
+
+        self.forget_all();
+    }
+
+    /// Swaps the values of two rows, along with their change ticks.
+    ///
+    /// # Safety
+    /// `a` and `b` must be in bounds for this column.
+    pub(super) unsafe fn swap_rows(&mut self, a: usize, b: usize) {
+        debug_assert!(a < self.len && b < self.len, "Column: row out of bounds");
+
+        if a == b {
+            return;
+        }
+
+        let size = self.type_info.size;
+
+        // SAFETY: Caller ensures both rows are in bounds, and they don't overlap.
+        unsafe {
+            let base = self.data.as_ptr();
+            ptr::swap_nonoverlapping(base.add(a * size), base.add(b * size), size);
+        }
+
+        self.ticks.swap(a, b);
     }
 
     /// Moves the data from `src_row` and appends to dest [Column].
@@ -196,6 +452,188 @@ impl<K: Key> ColumnVec<K> {
             ptr::copy_nonoverlapping(src_data, dst_data, size);
 
             dest.len += 1;
+            dest.ticks.push(self.ticks[src_row]);
+            dest.last_written_tick = dest.last_written_tick.max(self.ticks[src_row]);
+        }
+    }
+}
+
+/// Pointer to a component value, valid for the borrow `'a` of its storage.
+///
+/// Storage can't be reallocated while the borrow is held, so the pointer can't dangle.
+#[derive(Clone, Copy)]
+pub(crate) struct Ptr<'a> {
+    ptr: NonNull<u8>,
+    marker: PhantomData<&'a u8>,
+}
+
+impl<'a> Ptr<'a> {
+    /// # Safety
+    /// `ptr` must point to a value that is neither moved nor dropped for `'a`.
+    #[inline]
+    pub(crate) unsafe fn new(ptr: NonNull<u8>) -> Self {
+        Self {
+            ptr,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the raw pointer, e.g. to pass it to a type-erased hook.
+    #[inline]
+    pub(crate) fn as_ptr(self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// # Safety
+    /// `T` must be the type of the value.
+    #[inline]
+    pub(crate) unsafe fn deref<T>(self) -> &'a T {
+        // SAFETY: The value is valid for 'a, caller ensures that T matches.
+        unsafe { self.ptr.cast::<T>().as_ref() }
+    }
+
+    /// Returns a pointer valid for writes.
+    ///
+    /// # Safety
+    /// The caller must have exclusive access to the value for `'a`, e.g. through
+    /// an exclusive borrow of the world, and no other reference to it may be alive.
+    #[inline]
+    pub(crate) unsafe fn assert_unique(self) -> PtrMut<'a> {
+        PtrMut {
+            ptr: self.ptr,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Pointer to a component value valid for writes, see [Ptr].
+pub(crate) struct PtrMut<'a> {
+    ptr: NonNull<u8>,
+    marker: PhantomData<&'a mut u8>,
+}
+
+impl<'a> PtrMut<'a> {
+    /// Returns the raw pointer, e.g. to pass it to a type-erased hook.
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// # Safety
+    /// `T` must be the type of the value.
+    #[inline]
+    pub(crate) unsafe fn deref_mut<T>(self) -> &'a mut T {
+        // SAFETY: The value is valid and unaliased for 'a, caller ensures that T matches.
+        unsafe { self.ptr.cast::<T>().as_mut() }
+    }
+}
+
+/// Shared borrow of a column.
+///
+/// Pointers from the guard borrow it, so the column can't be grown or shrunk
+/// while one of them is alive.
+pub(crate) struct ColumnRef<'a, K: Key> {
+    column: &'a ColumnVec<K>,
+}
+
+impl<'a, K: Key> ColumnRef<'a, K> {
+    #[inline]
+    pub(super) fn new(column: &'a ColumnVec<K>) -> Self {
+        Self { column }
+    }
+
+    /// Returns a pointer to the value at `row`.
+    ///
+    /// # Panics
+    /// if `row` is out of bounds.
+    #[inline]
+    pub(crate) fn ptr(&self, row: usize) -> Ptr<'a> {
+        assert!(row < self.column.len, "Column: row out of bounds");
+
+        // SAFETY: we just checked that row is in bounds.
+        unsafe { self.ptr_unchecked(row) }
+    }
+
+    /// Returns a pointer to the value at `row` without bounds checking.
+    ///
+    /// # Safety
+    /// `row` must be in bounds, e.g. the row of an alive id in its table.
+    #[inline]
+    pub(crate) unsafe fn ptr_unchecked(&self, row: usize) -> Ptr<'a> {
+        // SAFETY:
+        // - Caller ensures that row is in bounds.
+        // - The column is borrowed for 'a, so the value can't move.
+        unsafe { Ptr::new(self.column.get_ptr(row)) }
+    }
+
+    /// Reads the value at `row` out of the column.
+    ///
+    /// # Safety
+    /// - `T` must be the value type of the column.
+    /// - The value must not be used or dropped again.
+    #[inline]
+    pub(crate) unsafe fn read<T>(&self, row: usize) -> T {
+        // SAFETY: Caller ensures that T matches and the value is not used again.
+        unsafe { self.ptr(row).as_ptr().cast::<T>().read() }
+    }
+}
+
+/// Exclusive borrow of a column, see [ColumnRef].
+pub(crate) struct ColumnRefMut<'a, K: Key> {
+    column: &'a mut ColumnVec<K>,
+}
+
+impl<'a, K: Key> ColumnRefMut<'a, K> {
+    #[inline]
+    pub(super) fn new(column: &'a mut ColumnVec<K>) -> Self {
+        Self { column }
+    }
+
+    /// Returns a pointer to the value at `row`, valid for writes.
+    ///
+    /// # Panics
+    /// if `row` is out of bounds.
+    #[inline]
+    pub(crate) fn ptr_mut(&mut self, row: usize) -> PtrMut<'_> {
+        assert!(row < self.column.len, "Column: row out of bounds");
+
+        // SAFETY: we just checked that row is in bounds.
+        let ptr = unsafe { self.column.get_ptr_mut(row) };
+
+        PtrMut {
+            ptr,
+            marker: PhantomData,
+        }
+    }
+
+    /// Replaces the value at `row` and returns the old one.
+    ///
+    /// # Safety
+    /// `T` must be the value type of the column.
+    #[inline]
+    pub(crate) unsafe fn replace<T>(&mut self, row: usize, val: T) -> T {
+        // SAFETY: Caller ensures that T matches.
+        unsafe { std::mem::replace(self.ptr_mut(row).deref_mut::<T>(), val) }
+    }
+
+    /// Records that every row was written at `tick`.
+    #[inline]
+    pub(crate) fn set_all_ticks(&mut self, tick: u64) {
+        self.column.ticks.fill(tick);
+        self.column.last_written_tick = self.column.last_written_tick.max(tick);
+    }
+
+    /// Returns the values of all rows as a slice, for the rest of the borrow.
+    ///
+    /// # Safety
+    /// `T` must be the value type of the column.
+    #[inline]
+    pub(crate) unsafe fn into_slice<T>(self) -> &'a mut [T] {
+        // SAFETY:
+        // - data is non-null and aligned for T, even for empty columns.
+        // - Caller ensures that T matches, and all rows below len are initialized.
+        unsafe {
+            std::slice::from_raw_parts_mut(self.column.data.cast::<T>().as_ptr(), self.column.len)
         }
     }
 }
@@ -207,18 +645,42 @@ impl<K: Key> Drop for ColumnVec<K> {
         }
 
         unsafe {
-            let size = self.type_info.size;
             let layout = (self.type_info.arr_layout)(self.cap).unwrap();
 
             if let Some(drop_fn) = self.type_info.drop_fn {
-                let mut ptr = self.data.as_ptr();
-                for _ in 0..self.len {
-                    drop_fn(ptr);
-                    ptr = ptr.add(size)
-                }
+                self.iter_ptr().for_each(|ptr| drop_fn(ptr.as_ptr()));
             }
 
-            std::alloc::dealloc(self.data.as_ptr(), layout);
+            self.alloc.dealloc(self.data.as_ptr(), layout);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{allocator::DefaultAlloc, id::Id, type_info::TypeHooksBuilder};
+    use xecs_macros::Component;
+
+    #[derive(Component)]
+    #[repr(transparent)]
+    struct Count(u32);
+
+    #[test]
+    fn row_pointers_read_back_the_values() {
+        let type_info = Rc::new(TypeInfo::of(TypeHooksBuilder::<Count>::new()));
+        let mut column = ColumnVec::new(Id::NULL, type_info, Rc::new(DefaultAlloc));
+
+        for val in [3, 1, 4, 1, 5] {
+            // SAFETY: the column holds Count values.
+            unsafe { column.push(Count(val)) };
+        }
+
+        // SAFETY: Count is a transparent u32.
+        let values: Vec<u32> = column
+            .iter_ptr()
+            .map(|ptr| unsafe { ptr.cast::<u32>().read() })
+            .collect();
+        assert_eq!(values, [3, 1, 4, 1, 5]);
+    }
+}
@@ -1,13 +1,18 @@
-use crate::{component::ComponentLocation, table_index::TableId};
+use crate::component::ComponentLocation;
+use relation::RelationStore;
 use sparse::{SparseData, SparseTag};
 use std::collections::HashMap;
 
 pub(crate) mod column;
+pub(crate) mod relation;
 pub(crate) mod sparse;
 pub(crate) mod table;
 
+pub use crate::table_index::TableId;
+
 /// The type of storage used for components
-#[derive(Default, Clone, Copy, PartialEq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StorageType {
     /// Component data or Tag is stored in tables.
     ///
@@ -31,6 +36,8 @@ pub enum StorageType {
 pub(crate) enum Storage {
     SparseTag(SparseTag),
     SparseData(SparseData),
+    /// All pairs of a sparse relationship, stored together.
+    SparseRelation(RelationStore),
     Tables(HashMap<TableId, ComponentLocation>),
 }
 
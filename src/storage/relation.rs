@@ -0,0 +1,326 @@
+use super::{Storage, column::ColumnVec, sparse::SparseChanges};
+use crate::{
+    allocator::WorldAlloc, data_structures::SparseSet, id::Id, type_info::TypeInfo, world::World,
+};
+use std::{ptr::NonNull, rc::Rc};
+
+/// Target of an id in a [RelationStore], with the row of the pair value.
+#[derive(Clone, Copy)]
+struct Target {
+    /// Index of the target, as stored in pairs.
+    tgt: Id,
+    /// Row in the value column, unused if the relationship is a tag.
+    row: usize,
+}
+
+/// Storage for all pairs of a sparse relationship.
+///
+/// Pairs don't get their own component record, each id holds the list of its targets
+/// and the values of all pairs share a single column.
+pub(crate) struct RelationStore {
+    targets: SparseSet<Id, Vec<Target>>,
+    /// Values of all pairs, `None` if the relationship is a tag.
+    values: Option<ColumnVec<Id>>,
+    /// Id and target owning each row of `values`.
+    owners: Vec<(Id, Id)>,
+    /// Number of pairs in the store.
+    pairs: usize,
+    /// Number of pairs inserted and removed.
+    changes: SparseChanges,
+}
+
+impl RelationStore {
+    pub(crate) fn new(rel: Id, type_info: Option<Rc<TypeInfo>>, alloc: Rc<dyn WorldAlloc>) -> Self {
+        Self {
+            targets: SparseSet::new(),
+            values: type_info.map(|ti| ColumnVec::new(rel, ti, alloc)),
+            owners: vec![],
+            pairs: 0,
+            changes: SparseChanges::default(),
+        }
+    }
+
+    /// Creates a store with the same pairs and clones of all values,
+    /// see [ColumnVec::try_clone].
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        let values = match &self.values {
+            Some(values) => Some(values.try_clone()?),
+            None => None,
+        };
+
+        Some(Self {
+            targets: self.targets.clone(),
+            values,
+            owners: self.owners.clone(),
+            pairs: self.pairs,
+            changes: self.changes,
+        })
+    }
+
+    /// Returns `true` if the pairs have no value.
+    #[inline]
+    pub(crate) fn is_tag(&self) -> bool {
+        self.values.is_none()
+    }
+
+    #[inline]
+    pub(crate) fn changes(&self) -> SparseChanges {
+        self.changes
+    }
+
+    /// Returns the column holding the values of all pairs, `None` for tags.
+    #[inline]
+    pub(crate) fn column(&self) -> Option<&ColumnVec<Id>> {
+        self.values.as_ref()
+    }
+
+    /// Returns the number of pairs in the store.
+    #[inline]
+    pub(crate) fn pair_count(&self) -> usize {
+        self.pairs
+    }
+
+    /// Returns the number of bytes used by the sparse array of the ids.
+    #[inline]
+    pub(crate) fn sparse_bytes(&self) -> usize {
+        self.targets.sparse_bytes()
+    }
+
+    /// Returns the number of bytes allocated for the targets, values and owners of all pairs.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        let target_lists = self
+            .targets
+            .iter()
+            .map(|(_, targets)| targets.capacity() * size_of::<Target>())
+            .sum::<usize>();
+
+        self.targets.allocated_bytes()
+            + target_lists
+            + self.values.as_ref().map_or(0, ColumnVec::capacity_bytes)
+            + self.owners.capacity() * size_of::<(Id, Id)>()
+    }
+
+    /// Returns the ids that have at least one pair.
+    pub(crate) fn ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.targets.iter().map(|(&id, _)| id)
+    }
+
+    /// Returns the targets of `id`, in insertion order unless removals occured.
+    pub(crate) fn targets(&self, id: Id) -> impl Iterator<Item = Id> + '_ {
+        self.targets
+            .get(&id)
+            .into_iter()
+            .flat_map(|targets| targets.iter().map(|t| t.tgt))
+    }
+
+    fn find(&self, id: Id, tgt: Id) -> Option<Target> {
+        let targets = self.targets.get(&id)?;
+        targets
+            .iter()
+            .find(|t| t.tgt.index() == tgt.index())
+            .copied()
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, id: Id, tgt: Id) -> bool {
+        self.find(id, tgt).is_some()
+    }
+
+    /// Returns a pointer to the value of `(id, tgt)`, `None` for tags.
+    pub(crate) fn get_ptr(&self, id: Id, tgt: Id) -> Option<NonNull<u8>> {
+        let values = self.values.as_ref()?;
+        let target = self.find(id, tgt)?;
+
+        // SAFETY: Rows of targets are always in bounds of the column.
+        Some(unsafe { values.get_ptr(target.row) })
+    }
+
+    /// Adds the pair `(id, tgt)` and returns its row, the value must be written by the caller.
+    fn push_target(&mut self, id: Id, tgt: Id) -> usize {
+        let row = self.owners.len();
+        let target = Target {
+            tgt: Id::from_raw(tgt.index() as u64),
+            row,
+        };
+
+        match self.targets.get_mut(&id) {
+            Some(targets) => targets.push(target),
+            None => {
+                self.targets.insert(id, vec![target]);
+            }
+        }
+
+        if self.values.is_some() {
+            self.owners.push((id, target.tgt));
+        }
+
+        self.pairs += 1;
+        self.changes.adds += 1;
+        row
+    }
+
+    /// Adds the pair `(id, tgt)` to a tag relationship.
+    /// Returns `false` if `id` already has it.
+    pub(crate) fn insert_tag(&mut self, id: Id, tgt: Id) -> bool {
+        debug_assert!(self.values.is_none(), "relationship has values");
+
+        if self.contains(id, tgt) {
+            return false;
+        }
+
+        self.push_target(id, tgt);
+        true
+    }
+
+    /// Sets the value of `(id, tgt)` and returns the old one.
+    ///
+    /// # Safety
+    /// `T` must be the value type of the relationship.
+    pub(crate) unsafe fn insert<T>(&mut self, id: Id, tgt: Id, val: T) -> Option<T> {
+        if let Some(ptr) = self.get_ptr(id, tgt) {
+            // SAFETY: Caller ensures that T matches.
+            return Some(unsafe { ptr.cast::<T>().replace(val) });
+        }
+
+        self.push_target(id, tgt);
+
+        // SAFETY: Caller ensures that T matches.
+        unsafe { self.values.as_mut().unwrap().push(val) };
+        None
+    }
+
+    /// Adds `(id, tgt)` with a value copied from `src`.
+    ///
+    /// # Safety
+    /// - `id` must not have the pair.
+    /// - `src` must point to a value of the relationship type, which must not be used again.
+    pub(crate) unsafe fn insert_raw(&mut self, id: Id, tgt: Id, src: NonNull<u8>) {
+        self.push_target(id, tgt);
+
+        // SAFETY: Caller ensures that src matches.
+        unsafe { self.values.as_mut().unwrap().push_raw(src) };
+    }
+
+    /// Removes the target from the list of `id`, returns the removed entry.
+    fn remove_target(&mut self, id: Id, tgt: Id) -> Option<Target> {
+        let targets = self.targets.get_mut(&id)?;
+        let index = targets.iter().position(|t| t.tgt.index() == tgt.index())?;
+        let target = targets.swap_remove(index);
+
+        if targets.is_empty() {
+            self.targets.remove(&id);
+        }
+
+        self.pairs -= 1;
+        self.changes.removes += 1;
+        Some(target)
+    }
+
+    /// Updates the row of the value swapped into `row` after a swap remove.
+    fn fix_swapped(&mut self, row: usize) {
+        self.owners.swap_remove(row);
+
+        if let Some(&(id, tgt)) = self.owners.get(row) {
+            let targets = self.targets.get_mut(&id).unwrap();
+            let target = targets.iter_mut().find(|t| t.tgt == tgt).unwrap();
+            target.row = row;
+        }
+    }
+
+    /// Removes the pair `(id, tgt)`, dropping its value.
+    /// Returns `false` if `id` doesn't have it.
+    pub(crate) fn remove(&mut self, id: Id, tgt: Id) -> bool {
+        let Some(target) = self.remove_target(id, tgt) else {
+            return false;
+        };
+
+        if self.values.is_some() {
+            self.fix_swapped(target.row);
+
+            // The value is dropped last, so the store stays consistent if its drop panics.
+            self.values.as_mut().unwrap().swap_remove_drop(target.row);
+        }
+
+        true
+    }
+
+    /// Removes the pair `(id, tgt)` and returns its value.
+    ///
+    /// # Safety
+    /// `T` must be the value type of the relationship.
+    pub(crate) unsafe fn take<T>(&mut self, id: Id, tgt: Id) -> Option<T> {
+        self.values.as_ref()?;
+        let target = self.remove_target(id, tgt)?;
+        let values = self.values.as_mut().unwrap();
+
+        // SAFETY:
+        // - The row of a target is in bounds.
+        // - Caller ensures that T matches.
+        // - The value is read out, so it's removed without dropping.
+        let val = unsafe {
+            let val = values.get_ptr(target.row).cast::<T>().read();
+            values.swap_remove(target.row);
+            val
+        };
+
+        self.fix_swapped(target.row);
+        Some(val)
+    }
+
+    /// Removes all pairs of `id`, dropping their values.
+    pub(crate) fn remove_all(&mut self, id: Id) {
+        while let Some(target) = self.targets.get(&id).and_then(|t| t.last().copied()) {
+            self.remove(id, target.tgt);
+        }
+    }
+
+    /// Removes all pairs WITHOUT dropping their values.
+    ///
+    /// Used once the values were moved out by copying their bytes.
+    pub(crate) fn forget_all(&mut self) {
+        if let Some(values) = &mut self.values {
+            values.forget_all();
+        }
+
+        self.targets.clear();
+        self.owners.clear();
+        self.pairs = 0;
+    }
+
+    /// Records that the value of `(id, tgt)` was written at `tick`.
+    pub(crate) fn set_tick(&mut self, id: Id, tgt: Id, tick: u64) {
+        if let (Some(target), Some(values)) = (self.find(id, tgt), &mut self.values) {
+            values.set_tick(target.row, tick);
+        }
+    }
+}
+
+/// Returns the relationship of `comp` if it's a pair stored in a [RelationStore].
+pub(crate) fn stored_relation(world: &World, comp: Id) -> Option<Id> {
+    if !comp.is_pair() {
+        return None;
+    }
+
+    let rel = world.id_manager.get_current(comp.pair_rel())?;
+
+    match world.components.get(rel)?.storage {
+        Storage::SparseRelation(_) => Some(rel),
+        _ => None,
+    }
+}
+
+/// Returns the store of a relationship found by [stored_relation].
+pub(crate) fn relation_store(world: &World, rel: Id) -> &RelationStore {
+    match &world.components.get(rel).unwrap().storage {
+        Storage::SparseRelation(store) => store,
+        _ => unreachable!("relationship is not stored in a relation store"),
+    }
+}
+
+/// Returns the store of a relationship found by [stored_relation] mutably.
+pub(crate) fn relation_store_mut(world: &mut World, rel: Id) -> &mut RelationStore {
+    match &mut world.components.get_mut(rel).unwrap().storage {
+        Storage::SparseRelation(store) => store,
+        _ => unreachable!("relationship is not stored in a relation store"),
+    }
+}
@@ -1,6 +1,7 @@
-use super::column::ColumnVec;
+use super::column::{ColumnRefMut, ColumnVec};
 use crate::{
-    data_structures::SparseIndex, id::Id, type_info::TypeInfo, type_traits::DataComponent,
+    allocator::WorldAlloc, data_structures::SparseIndex, id::Id, type_info::TypeInfo,
+    type_traits::DataComponent,
 };
 use std::{ptr::NonNull, rc::Rc};
 
@@ -8,22 +9,68 @@ pub(crate) struct SparseData {
     ids: Vec<Id>,
     dense: ColumnVec<Id>,
     sparse: Vec<usize>,
+    /// Number of ids inserted and removed.
+    changes: SparseChanges,
+}
+
+/// Insert and remove counts of a sparse storage.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct SparseChanges {
+    pub(crate) adds: u64,
+    pub(crate) removes: u64,
 }
 
 impl SparseData {
-    pub(crate) fn new(id: Id, type_info: Rc<TypeInfo>) -> Self {
+    pub(crate) fn new(id: Id, type_info: Rc<TypeInfo>, alloc: Rc<dyn WorldAlloc>) -> Self {
         Self {
             ids: vec![],
-            dense: ColumnVec::new(id, type_info),
+            dense: ColumnVec::new(id, type_info, alloc),
             sparse: vec![],
+            changes: SparseChanges::default(),
         }
     }
 
+    #[inline]
+    pub(crate) fn ids(&self) -> &[Id] {
+        &self.ids
+    }
+
+    #[inline]
+    pub(crate) fn changes(&self) -> SparseChanges {
+        self.changes
+    }
+
+    #[inline]
+    pub(crate) fn column(&self) -> &ColumnVec<Id> {
+        &self.dense
+    }
+
+    /// Returns the number of bytes used by the sparse array.
+    #[inline]
+    pub(crate) fn sparse_bytes(&self) -> usize {
+        self.sparse.len() * size_of::<usize>()
+    }
+
+    /// Returns the number of bytes allocated for the ids, values and sparse array.
+    #[inline]
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.dense.capacity_bytes()
+            + self.ids.capacity() * size_of::<Id>()
+            + self.sparse.capacity() * size_of::<usize>()
+    }
+
+    /// Borrows the values of the set mutably, in dense order.
+    #[inline]
+    pub(crate) fn column_mut(&mut self) -> ColumnRefMut<'_, Id> {
+        ColumnRefMut::new(&mut self.dense)
+    }
+
     /// Inserts a value into the set for the given entity.
-    /// Replaces the data if the entity is already in the set.
+    /// Replaces the data if the entity is already in the set, and returns the old value
+    /// undropped, so the set is consistent before its drop runs.
     ///
     /// # Safety
-    /// `val` must point to data that is the same type as the set items.
+    /// `T` must be the item type of the set.
     pub(crate) unsafe fn insert<T: DataComponent>(&mut self, id: Id, val: T) -> Option<T> {
         let sparse = id.to_sparse_index();
 
@@ -43,16 +90,65 @@ impl SparseData {
                 self.sparse[sparse] = self.dense.len();
                 self.dense.push(val);
                 self.ids.push(id);
+                self.changes.adds += 1;
                 None
             }
         }
     }
 
-    /// Removes an entity from the set.
-    /// Returns the value associated with the id if it was present.
+    /// Inserts a value for an id that is not in the set, by copying its bytes from `src`.
     ///
     /// # Safety
-    /// Caller ensures that `C` matches the item type of the column.
+    /// - `id` must not be in the set.
+    /// - `src` must point to a value of the set item type, which must not be used again.
+    pub(crate) unsafe fn insert_raw(&mut self, id: Id, src: NonNull<u8>) {
+        let sparse = id.to_sparse_index();
+
+        if sparse >= self.sparse.len() {
+            self.sparse.resize(sparse + 1, usize::MAX);
+        }
+
+        self.sparse[sparse] = self.dense.len();
+
+        // SAFETY: Caller ensures that src matches the type of column items.
+        unsafe { self.dense.push_raw(src) };
+        self.ids.push(id);
+        self.changes.adds += 1;
+    }
+
+    /// Removes all values WITHOUT dropping them.
+    ///
+    /// Used once the values were moved out by copying their bytes.
+    pub(crate) fn forget_all(&mut self) {
+        self.dense.forget_all();
+        self.ids.clear();
+        self.sparse.clear();
+    }
+
+    /// Removes an id from the set WITHOUT dropping its value.
+    ///
+    /// Used once the value was moved out by copying its bytes.
+    pub(crate) fn forget(&mut self, id: Id) {
+        let dense = match self.sparse.get_mut(id.to_sparse_index()) {
+            Some(dense) if *dense < self.dense.len() => dense,
+            _ => return, // id not in set.
+        };
+
+        let dense = std::mem::replace(dense, usize::MAX);
+
+        // SAFETY: dense is in bounds, and the value is forgotten, not dropped.
+        unsafe { self.dense.swap_remove(dense) };
+        self.ids.swap_remove(dense);
+        self.changes.removes += 1;
+
+        if let Some(moved) = self.ids.get(dense) {
+            self.sparse[moved.to_sparse_index()] = dense;
+        }
+    }
+
+    /// Removes an entity from the set and drops its value, no-op if it's not in the set.
+    ///
+    /// The value is dropped after the set is updated, so a panicking drop leaves it consistent.
     pub(crate) fn remove(&mut self, id: Id) {
         let dense = match self.sparse.get_mut(id.to_sparse_index()) {
             Some(dense) if *dense < self.dense.len() => dense,
@@ -60,14 +156,49 @@ impl SparseData {
         };
 
         let dense = std::mem::replace(dense, usize::MAX);
-        self.dense.swap_remove_drop(dense);
         self.ids.swap_remove(dense);
+        self.changes.removes += 1;
 
-        if dense != self.dense.len() {
-            self.sparse[self.ids[dense].to_sparse_index()] = dense;
+        if let Some(moved) = self.ids.get(dense) {
+            self.sparse[moved.to_sparse_index()] = dense;
+        }
+
+        self.dense.swap_remove_drop(dense);
+    }
+
+    /// Removes an id from the set and returns its value.
+    ///
+    /// # Safety
+    /// `T` must be the item type of the set.
+    pub(crate) unsafe fn take<T>(&mut self, id: Id) -> Option<T> {
+        let ptr = self.get_ptr(id)?;
+
+        // SAFETY:
+        // - Caller ensures T is the item type.
+        // - The value is read out, so it's removed without dropping.
+        let val = unsafe { ptr.cast::<T>().read() };
+        self.forget(id);
+        Some(val)
+    }
+
+    /// Records that the value of `id` was written at `tick`.
+    #[inline]
+    pub(crate) fn set_tick(&mut self, id: Id, tick: u64) {
+        match self.sparse.get(id.to_sparse_index()) {
+            Some(&dense) if dense < self.dense.len() => self.dense.set_tick(dense, tick),
+            _ => {}
         }
     }
 
+    /// Returns the ids whose value was written after `tick`.
+    pub(crate) fn modified_since(&self, tick: u64) -> impl Iterator<Item = Id> + '_ {
+        self.ids
+            .iter()
+            .enumerate()
+            .filter(move |&(dense, _)| self.dense.tick(dense) > tick)
+            .map(|(_, &id)| id)
+    }
+
     #[inline]
     pub(crate) fn contains(&self, id: Id) -> bool {
         match self.sparse.get(id.to_sparse_index()) {
@@ -76,30 +207,42 @@ impl SparseData {
         }
     }
 
-    #[inline]
-    pub(crate) unsafe fn get<T: DataComponent>(&self, id: Id) -> Option<&T> {
-        match self.sparse.get(id.to_sparse_index()) {
-            Some(&dense) if dense < self.dense.len() => {
-                // SAFETY:
-                // - We just checked dense is in bounds.
-                // - Caller ensures T is dense item type
-                Some(unsafe { self.dense.get(dense) })
-            }
-            _ => None,
-        }
+    /// Creates a set with the same ids and clones of all values, see [ColumnVec::try_clone].
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        Some(Self {
+            ids: self.ids.clone(),
+            dense: self.dense.try_clone()?,
+            sparse: self.sparse.clone(),
+            changes: self.changes,
+        })
     }
 
+    /// Iterates the ids and values of the set, in dense order.
+    ///
+    /// # Safety
+    /// Caller ensures `T` is the dense item type.
+    pub(crate) unsafe fn iter<T: DataComponent>(&self) -> impl Iterator<Item = (Id, &T)> + '_ {
+        // SAFETY:
+        // - ids and dense have the same length, so each index is in bounds.
+        // - Caller ensures T is dense item type
+        self.ids
+            .iter()
+            .enumerate()
+            .map(|(dense, &id)| (id, unsafe { self.dense.get(dense) }))
+    }
+
+    /// Gets the id and value stored at `dense` in the packed arrays.
+    ///
+    /// # Safety
+    /// Caller ensures `T` is the dense item type.
     #[inline]
-    pub(crate) unsafe fn get_mut<T: DataComponent>(&mut self, id: Id) -> Option<&mut T> {
-        match self.sparse.get(id.to_sparse_index()) {
-            Some(&dense_idx) if dense_idx < self.dense.len() => {
-                // SAFETY:
-                // - We just checked dense is in bounds.
-                // - Caller ensures T is dense item type
-                Some(unsafe { self.dense.get_mut(dense_idx) })
-            }
-            _ => None,
-        }
+    pub(crate) unsafe fn get_dense<T: DataComponent>(&self, dense: usize) -> Option<(Id, &T)> {
+        let id = *self.ids.get(dense)?;
+
+        // SAFETY:
+        // - ids and dense have the same length, so dense is in bounds.
+        // - Caller ensures T is dense item type
+        Some((id, unsafe { self.dense.get(dense) }))
     }
 
     #[inline]
@@ -125,9 +268,11 @@ impl SparseData {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct SparseTag {
     ids: Vec<Id>,
     sparse: Vec<usize>,
+    changes: SparseChanges,
 }
 
 impl SparseTag {
@@ -135,9 +280,32 @@ impl SparseTag {
         Self {
             ids: vec![],
             sparse: vec![],
+            changes: SparseChanges::default(),
         }
     }
 
+    #[inline]
+    pub(crate) fn ids(&self) -> &[Id] {
+        &self.ids
+    }
+
+    #[inline]
+    pub(crate) fn changes(&self) -> SparseChanges {
+        self.changes
+    }
+
+    /// Returns the number of bytes used by the sparse array.
+    #[inline]
+    pub(crate) fn sparse_bytes(&self) -> usize {
+        self.sparse.len() * size_of::<usize>()
+    }
+
+    /// Returns the number of bytes allocated for the ids and sparse array.
+    #[inline]
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.ids.capacity() * size_of::<Id>() + self.sparse.capacity() * size_of::<usize>()
+    }
+
     /// Resizes the sparse array such that
     /// it can hold at least (`index` + 1) entries.
     #[inline(always)]
@@ -156,14 +324,11 @@ impl SparseTag {
         if dense > self.ids.len() {
             self.sparse[sparse] = self.ids.len();
             self.ids.push(id);
+            self.changes.adds += 1;
         }
     }
 
-    /// Removes an entity from the set.
-    /// Returns the value associated with the entity if it was present.
-    ///
-    /// # Safety
-    /// Caller ensures that `C` matches the item type of the column.
+    /// Removes an entity from the set, no-op if it's not in the set.
     pub(crate) fn remove(&mut self, id: Id) {
         let dense = match self.sparse.get_mut(id.to_sparse_index()) {
             Some(dense) if *dense < self.ids.len() => dense,
@@ -172,12 +337,19 @@ impl SparseTag {
 
         let dense = std::mem::replace(dense, usize::MAX);
         let _ = self.ids.swap_remove(dense);
+        self.changes.removes += 1;
 
         if dense != self.ids.len() {
             self.sparse[self.ids[dense].to_sparse_index()] = dense;
         }
     }
 
+    /// Iterates the ids of the set, in dense order.
+    #[inline]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Id> + '_ {
+        self.ids.iter().copied()
+    }
+
     #[inline]
     pub fn contains(&self, id: Id) -> bool {
         match self.sparse.get(id.to_sparse_index()) {
@@ -186,3 +358,41 @@ impl SparseTag {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{allocator::DefaultAlloc, type_info::TypeHooksBuilder};
+    use xecs_macros::Component;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn iteration_yields_the_surviving_entries() {
+        let type_info = Rc::new(TypeInfo::of(TypeHooksBuilder::<Health>::new()));
+        let mut data = SparseData::new(Id::NULL, type_info, Rc::new(DefaultAlloc));
+        let mut tags = SparseTag::new();
+        let ids = [10, 20, 30, 40].map(Id::from_raw);
+
+        for (i, &id) in ids.iter().enumerate() {
+            // SAFETY: the set holds Health values.
+            unsafe { data.insert(id, Health(i as u32)) };
+            tags.insert(id);
+        }
+
+        data.remove(ids[1]);
+        tags.remove(ids[1]);
+
+        // SAFETY: the set holds Health values.
+        let mut values: Vec<_> = unsafe { data.iter::<Health>() }
+            .map(|(id, health)| (id, health.0))
+            .collect();
+        values.sort();
+        assert_eq!(values, [(ids[0], 0), (ids[2], 2), (ids[3], 3)]);
+
+        let mut tagged: Vec<_> = tags.iter().collect();
+        tagged.sort();
+        assert_eq!(tagged, [ids[0], ids[2], ids[3]]);
+    }
+}
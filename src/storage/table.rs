@@ -1,18 +1,23 @@
-use super::column::ColumnVec;
+use super::column::{ColumnRef, ColumnRefMut, ColumnVec};
 use crate::{
-    data_structures::SparseSet,
     flags::TableFlags,
     graph::GraphNode,
-    id::{Id, Key, KeyMap, Relation, Signature, manager::IdLocation},
+    id::{Id, IdMap, Key, Signature, manager::IdLocation},
+    recording::Event,
     table_index::TableId,
     type_traits::DataComponent,
     world::World,
+    world_utils::id_name,
 };
-use std::{collections::HashMap, ptr::NonNull};
+use std::{cell::RefCell, collections::HashMap, ptr::NonNull};
 
 pub(crate) struct TableData<K: Key> {
     ids: Vec<Id>,
     columns: Box<[ColumnVec<K>]>,
+    /// Number of rows filled by swapping in the last row, since the last defragmentation.
+    swap_removes: usize,
+    /// Incremented when rows are removed or reordered, appending rows doesn't change it.
+    structure_version: u32,
 }
 
 impl<K: Key> TableData<K> {
@@ -20,6 +25,8 @@ impl<K: Key> TableData<K> {
         Self {
             ids: vec![],
             columns,
+            swap_removes: 0,
+            structure_version: 0,
         }
     }
 
@@ -33,6 +40,11 @@ impl<K: Key> TableData<K> {
         &self.columns[index]
     }
 
+    #[inline]
+    pub(crate) fn columns(&self) -> &[ColumnVec<K>] {
+        &self.columns
+    }
+
     /// Returns number of rows in this table.
     #[inline]
     pub(crate) fn row_count(&self) -> usize {
@@ -81,30 +93,151 @@ impl<K: Key> TableData<K> {
         unsafe { self.columns.get_unchecked_mut(col).get_mut(row) }
     }
 
-    /// Returns a pointer to the element at `row`, in `column`.
+    /// Borrows the column at `col`.
     ///
-    /// This function does not perform bounds checking.
+    /// # Panics
+    /// if `col` is out of bounds.
+    #[inline]
+    pub(crate) fn column_ref(&self, col: usize) -> ColumnRef<'_, K> {
+        ColumnRef::new(&self.columns[col])
+    }
+
+    /// Borrows the column at `col` mutably.
+    ///
+    /// # Panics
+    /// if `col` is out of bounds.
+    #[inline]
+    pub(crate) fn column_mut(&mut self, col: usize) -> ColumnRefMut<'_, K> {
+        ColumnRefMut::new(&mut self.columns[col])
+    }
+
+    /// Appends a value to `col` by copying its bytes from `src`.
     ///
     /// # Safety
-    /// - Caller ensures that `row` and `column` are valid.
-    pub(crate) unsafe fn get_ptr(&self, col: usize, row: usize) -> NonNull<u8> {
-        debug_assert!(col < self.columns.len(), "column out of bounds");
-        // SAFETY: The caller ensures that `row` and `column` in bounds.
-        unsafe { self.columns.get_unchecked(col).get_ptr(row) }
+    /// - `col` must be in bounds.
+    /// - `src` must point to a value of the column item type, which must not be used again.
+    pub(crate) unsafe fn push_raw(&mut self, col: usize, src: NonNull<u8>) {
+        unsafe { self.columns[col].push_raw(src) };
+    }
+
+    /// Returns the number of rows the table can hold without reallocating.
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.columns
+            .iter()
+            .map(ColumnVec::capacity)
+            .fold(self.ids.capacity(), usize::min)
+    }
+
+    /// Returns the number of bytes allocated for the ids and all columns.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.columns
+            .iter()
+            .map(ColumnVec::capacity_bytes)
+            .sum::<usize>()
+            + self.ids.capacity() * size_of::<Id>()
     }
 
-    /// Returns a mutable reference to the element at `row`, in `column`.
+    /// Returns the number of rows that are allocated but unused.
+    #[inline]
+    pub(crate) fn excess_capacity(&self) -> usize {
+        self.capacity() - self.row_count()
+    }
+
+    /// Reallocates the ids and all columns to hold `new_cap` rows.
     ///
-    /// This function does not perform bounds checking.
+    /// Capacities above `new_cap` are reduced, smaller ones are kept.
+    ///
+    /// # Safety
+    /// `new_cap` must not be less than the number of rows.
+    pub(crate) unsafe fn shrink_to(&mut self, new_cap: usize) {
+        debug_assert!(
+            new_cap >= self.ids.len(),
+            "TableData: capacity below row count"
+        );
+        self.ids.shrink_to(new_cap);
+
+        for col in self.columns.iter_mut() {
+            // SAFETY: Columns have as many values as there are rows.
+            unsafe { col.shrink_to(new_cap) };
+        }
+    }
+
+    /// Frees the memory of unused rows, see [TableData::shrink_to].
+    pub(crate) fn compact(&mut self) {
+        // SAFETY: The capacity is the number of rows.
+        unsafe { self.shrink_to(self.ids.len()) };
+    }
+
+    /// Creates a table data with the same ids and clones of all values, see [ColumnVec::try_clone].
+    ///
+    /// Returns the index of the first column that can't be cloned.
+    pub(crate) fn try_clone(&self) -> Result<Self, usize>
+    where
+        K: Copy,
+    {
+        let columns = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(col, column)| column.try_clone().ok_or(col))
+            .collect::<Result<Box<[_]>, _>>()?;
+
+        Ok(Self {
+            ids: self.ids.clone(),
+            columns,
+            swap_removes: self.swap_removes,
+            structure_version: self.structure_version,
+        })
+    }
+
+    /// Removes all values of all columns WITHOUT dropping them.
+    ///
+    /// Rows are kept, the table must not be accessed again.
+    pub(crate) fn forget_values(&mut self) {
+        self.columns.iter_mut().for_each(ColumnVec::forget_all);
+    }
+
+    /// Returns the number of swap removes since the last call to [TableData::reset_swap_removes].
+    #[inline]
+    pub(crate) fn swap_removes(&self) -> usize {
+        self.swap_removes
+    }
+
+    #[inline]
+    pub(crate) fn reset_swap_removes(&mut self) {
+        self.swap_removes = 0;
+    }
+
+    /// Returns the version of the row order, see [QueryCursor](crate::query::QueryCursor).
+    #[inline]
+    pub(crate) fn structure_version(&self) -> u32 {
+        self.structure_version
+    }
+
+    /// Swaps two rows in all columns.
     ///
     /// # Safety
-    /// - The caller ensures that `row` and `column` are valid.
-    pub(crate) unsafe fn get_ptr_mut(&mut self, col: usize, row: usize) -> NonNull<u8> {
-        debug_assert!(col < self.columns.len(), "TableData: column out of bounds");
-        // SAFETY: The caller ensures that `row` and `column` in bounds.
-        unsafe { self.columns.get_unchecked_mut(col).get_ptr_mut(row) }
+    /// `a` and `b` must be in bounds.
+    pub(crate) unsafe fn swap_rows(&mut self, a: usize, b: usize) {
+        for col in self.columns.iter_mut() {
+            // SAFETY: Caller ensures both rows are in bounds.
+            unsafe { col.swap_rows(a, b) };
+        }
+
+        self.ids.swap(a, b);
+        self.structure_version = self.structure_version.wrapping_add(1);
+    }
+
+    /// Records that the value at `row` in `col` was written at `tick`.
+    #[inline]
+    pub(crate) fn set_tick(&mut self, col: usize, row: usize, tick: u64) {
+        self.columns[col].set_tick(row, tick);
     }
 
+    /// Removes `row` by swapping it with the last row.
+    /// Returns the id that was swapped into `row`, if any.
+    ///
     /// # Safety
     /// - `row` must be in bounds
     /// - `drop_check` must have the same length as `self.columns`
@@ -122,13 +255,14 @@ impl<K: Key> TableData<K> {
             }
         }
 
-        let removed = self.ids.swap_remove(row);
+        self.ids.swap_remove(row);
+        self.structure_version = self.structure_version.wrapping_add(1);
 
-        if row == self.ids.len() {
-            None
-        } else {
-            Some(removed)
+        let swapped = self.ids.get(row).copied();
+        if swapped.is_some() {
+            self.swap_removes += 1;
         }
+        swapped
     }
 }
 
@@ -141,15 +275,59 @@ pub(crate) struct Table {
     pub(crate) signature: Signature,
     /// Storage for id component data.
     pub(crate) id_data: TableData<Id>,
-    /// Storage for pair component data.
-    pub(crate) pair_data: TableData<Relation>,
-    /// Maps keys to columns indices.
-    pub(crate) column_map: KeyMap<usize>,
+    /// Maps components, including pairs, to column indices.
+    pub(crate) column_map: IdMap<usize>,
     /// Node representation for traversals.
     pub(crate) node: GraphNode,
+    /// Cached debug name and the world name version it was built with.
+    pub(crate) debug_name: RefCell<Option<(u32, String)>>,
+    /// Number of ids moved to each other table, see [World::enable_move_stats].
+    pub(crate) moves: HashMap<TableId, u64>,
+    /// Number of ids deleted from this table.
+    pub(crate) deletes: u64,
 }
 
 impl Table {
+    /// Returns a readable name built from the names of the components in the signature,
+    /// e.g. `[Position, Velocity, (ChildOf, Entity(4, v0))]`.
+    ///
+    /// The name is cached until components are renamed.
+    pub(crate) fn debug_name(&self, world: &World) -> String {
+        let mut cache = self.debug_name.borrow_mut();
+
+        if let Some((version, name)) = &*cache
+            && *version == world.names_version
+        {
+            return name.clone();
+        }
+
+        let names: Vec<_> = self
+            .signature
+            .iter()
+            .map(|&id| id_name(world, id))
+            .collect();
+        let name = format!("[{}]", names.join(", "));
+        *cache = Some((world.names_version, name.clone()));
+        name
+    }
+
+    /// Returns the ids whose value of `comp` was written after `tick`.
+    ///
+    /// Yields nothing without checking rows if the column wasn't written after `tick`.
+    pub(crate) fn modified_since(&self, comp: Id, tick: u64) -> impl Iterator<Item = Id> + '_ {
+        let column = self
+            .column_map
+            .get(comp)
+            .map(|&col| self.id_data.column(col))
+            .filter(|column| column.last_written_tick() > tick);
+
+        column.into_iter().flat_map(move |column| {
+            let ids = self.id_data.ids().iter().enumerate();
+            ids.filter(move |&(row, _)| column.tick(row) > tick)
+                .map(|(_, &id)| id)
+        })
+    }
+
     pub(crate) fn validate_data(&self) {
         #[cfg(debug_assertions)]
         {
@@ -161,34 +339,6 @@ impl Table {
                 .for_each(|col| assert_eq!(len, col.len()));
         }
     }
-
-    /// Gets a reference to the component of an entity.
-    ///
-    /// # Safety
-    /// - `row` must be valid in this table.
-    /// - `T` must be the value type of the column.
-    #[inline]
-    pub(crate) unsafe fn get<T: DataComponent>(&self, column_id: Id, row: usize) -> Option<&T> {
-        self.column_map
-            .get(&column_id)
-            .map(|&column| unsafe { self.id_data.get(column, row) })
-    }
-
-    /// Gets a reference to the component of an entity.
-    ///
-    /// # Safety
-    /// - `row` must be valid in this table.
-    /// - `T` must be the value type of the column.
-    #[inline]
-    pub(crate) unsafe fn get_mut<T: DataComponent>(
-        &mut self,
-        row: usize,
-        column_id: Id,
-    ) -> Option<&mut T> {
-        self.column_map
-            .get(&column_id)
-            .map(|&col| unsafe { self.id_data.get_mut(col, row) })
-    }
 }
 
 /// Moves `id` from src table to dst.
@@ -203,6 +353,24 @@ pub(crate) unsafe fn move_id(
     src: TableId,
     src_row: usize,
     dst: TableId,
+) {
+    unsafe { move_id_forget(world, id, src, src_row, dst, &[]) };
+}
+
+/// Moves `id` from src table to dst like [move_id], without dropping the values
+/// of `forget` that are not in dst.
+///
+/// # Safety
+/// - `src_row` must be a valid row in `src`.
+/// - `src` and `dst` must not be the same table.
+/// - The values of `forget` must have been moved out by the caller.
+pub(crate) unsafe fn move_id_forget(
+    world: &mut World,
+    id: Id,
+    src: TableId,
+    src_row: usize,
+    dst: TableId,
+    forget: &[Id],
 ) {
     let (src, dst) = world.table_index.get_2_mut(src, dst).unwrap();
 
@@ -215,12 +383,14 @@ pub(crate) unsafe fn move_id(
     let mut drop_check = vec![true; src_columns.len()];
 
     for (i_src, src_col) in src_columns.iter_mut().enumerate() {
-        if let Some(&i_dst) = dst.column_map.get(src_col.id()) {
+        if let Some(&i_dst) = dst.column_map.get(*src_col.id()) {
             // SAFETY:
             // - We guarantee that src_row and dst_row are valid.
             // - We ensure that src_col and dst_col contain the same item type.
             unsafe { src_col.move_row_to(src_row, &mut dst_columns[i_dst]) };
             drop_check[i_src] = false;
+        } else if forget.contains(src_col.id()) {
+            drop_check[i_src] = false;
         } else {
             // Component not in destination table.
             // TODO: Emit remove hooks
@@ -238,6 +408,24 @@ pub(crate) unsafe fn move_id(
         );
     }
 
+    if world.move_stats {
+        *src.moves.entry(dst.id).or_default() += 1;
+    }
+
+    if let Some(recorder) = &mut world.recorder {
+        for &comp in dst.signature.ids() {
+            if !src.signature.has_id(comp) {
+                recorder.record(Event::AddId { id, comp });
+            }
+        }
+
+        for &comp in src.signature.ids() {
+            if !dst.signature.has_id(comp) {
+                recorder.record(Event::Remove { id, comp });
+            }
+        }
+    }
+
     // update record of moved entity.
     world.id_manager.set_location(
         id,
@@ -247,3 +435,80 @@ pub(crate) unsafe fn move_id(
         },
     );
 }
+
+/// Moves all ids from src table to dst, in row order.
+///
+/// Columns are copied at once instead of row by row.
+/// Values of components that are not in dst are dropped.
+///
+/// # Safety
+/// - `src` and `dst` must not be the same table.
+/// - Every column of `dst` must be in `src`.
+pub(crate) unsafe fn move_all(world: &mut World, src: TableId, dst: TableId) {
+    let (src, dst) = world.table_index.get_2_mut(src, dst).unwrap();
+
+    let start = dst.id_data.row_count();
+    let moved = src.id_data.row_count();
+
+    for src_col in src.id_data.columns.iter_mut() {
+        match dst.column_map.get(*src_col.id()) {
+            // SAFETY: Both columns are for the same component, so they hold the same item type.
+            Some(&i_dst) => unsafe { dst.id_data.columns[i_dst].append(src_col) },
+            None => src_col.clear(),
+        }
+    }
+
+    dst.id_data.ids.append(&mut src.id_data.ids);
+    src.id_data.reset_swap_removes();
+    src.id_data.structure_version = src.id_data.structure_version.wrapping_add(1);
+
+    for (row, &id) in dst.id_data.ids.iter().enumerate().skip(start) {
+        world
+            .id_manager
+            .set_location(id, IdLocation { table: dst.id, row });
+    }
+
+    if world.move_stats {
+        *src.moves.entry(dst.id).or_default() += moved as u64;
+    }
+
+    if let Some(recorder) = &mut world.recorder {
+        for &id in &dst.id_data.ids[start..] {
+            for &comp in dst.signature.ids() {
+                if !src.signature.has_id(comp) {
+                    recorder.record(Event::AddId { id, comp });
+                }
+            }
+
+            for &comp in src.signature.ids() {
+                if !dst.signature.has_id(comp) {
+                    recorder.record(Event::Remove { id, comp });
+                }
+            }
+        }
+    }
+
+    dst.validate_data();
+}
+
+/// Deletes the row of an id in `table`, dropping all its components.
+///
+/// # Safety
+/// - `row` must be a valid row in `table`.
+pub(crate) unsafe fn delete_id(world: &mut World, table: TableId, row: usize) {
+    let table = &mut world.table_index[table];
+    let drop_check = vec![true; table.id_data.columns.len()];
+
+    table.deletes += 1;
+
+    // update the record of the id swapped into row.
+    if let Some(i) = unsafe { table.id_data.delete_row(row, &drop_check) } {
+        world.id_manager.set_location(
+            i,
+            IdLocation {
+                table: table.id,
+                row,
+            },
+        );
+    }
+}
@@ -8,9 +8,9 @@ use std::{
 };
 
 /// Stable, non-recycled handle into [TableIndex].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
-pub(crate) struct TableId(u32);
+pub struct TableId(u32);
 
 impl Display for TableId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -54,6 +54,11 @@ impl TableIndex {
         id
     }
 
+    #[inline]
+    pub(crate) fn get(&self, id: TableId) -> Option<&Table> {
+        self.tables.get(id.0 as usize)
+    }
+
     #[inline]
     pub(crate) fn get_id(&self, ids: &Signature) -> Option<TableId> {
         self.table_ids.get(ids).copied()
@@ -78,7 +83,11 @@ impl TableIndex {
         self.tables.as_slice()
     }
 
-    pub(crate) fn all_table_ids(&self) -> Values<Signature, TableId> {
+    pub(crate) fn all_tables_mut(&mut self) -> &mut [Table] {
+        self.tables.as_mut_slice()
+    }
+
+    pub(crate) fn all_table_ids(&self) -> Values<'_, Signature, TableId> {
         self.table_ids.values()
     }
 }
@@ -0,0 +1,256 @@
+use crate::{
+    allocator::WorldAlloc,
+    error::{EcsError, EcsResult},
+    id::Id,
+    type_info::TypeInfo,
+    type_traits::{DataComponent, TagComponent, TypedId},
+    world::World,
+    world_utils::{
+        add_tag, component_type_info, get_component_ptr, get_component_ptr_mut, has_component,
+        move_for_writes, remove_component, set_component, write_component_raw,
+    },
+};
+use std::{alloc::Layout, ptr::NonNull, rc::Rc};
+
+/// Undoes a change made in a transaction.
+type Undo = Box<dyn FnOnce(&mut World)>;
+
+/// Undoing a change can't fail, since the ids it touched can't be despawned before the
+/// transaction commits.
+const UNDO_FAILED: &str = "transaction change can't be undone";
+
+/// Handle to a [World] in [World::transaction], which journals every change to undo it.
+///
+/// Changes are undone in reverse order if the transaction returns an error or panics.
+/// Despawns are only applied once the transaction commits.
+///
+/// Undoing a change runs no hooks: old values are written back in place, and values
+/// added by the transaction are dropped with the components and ids that hold them.
+pub struct TxWorld<'w> {
+    world: &'w mut World,
+    journal: Vec<Undo>,
+    despawns: Vec<Id>,
+}
+
+impl<'w> TxWorld<'w> {
+    pub(crate) fn new(world: &'w mut World) -> Self {
+        Self {
+            world,
+            journal: vec![],
+            despawns: vec![],
+        }
+    }
+
+    /// Gives read access to the world, e.g. to get component values.
+    #[inline]
+    pub fn world(&self) -> &World {
+        self.world
+    }
+
+    /// Creates a new id, despawned on rollback.
+    pub fn spawn(&mut self) -> Id {
+        let id = self.world.new_id();
+        self.journal.push(Box::new(move |world| {
+            world.despawn(id).expect(UNDO_FAILED);
+        }));
+        id
+    }
+
+    /// Sets the component `T` of `id`, the previous value is restored on rollback.
+    pub fn set<T: TypedId>(&mut self, id: Id, val: T::Data) -> EcsResult<()>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self.world)?;
+        self.world.id_manager.get_location(id)?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        let old = unsafe { set_component(self.world, id, comp, val) }?;
+
+        self.journal.push(Box::new(move |world| match old {
+            Some(old) => {
+                let ptr = get_component_ptr_mut(world, id, comp).expect(UNDO_FAILED);
+
+                // SAFETY: The old value was read from the same component, so the types match.
+                unsafe { *ptr.deref_mut::<T::Data>() = old };
+            }
+            None => remove_component(world, id, comp).expect(UNDO_FAILED),
+        }));
+
+        Ok(())
+    }
+
+    /// Adds the tag `T` to `id`, removed on rollback.
+    #[inline]
+    pub fn add<T: TypedId + TagComponent>(&mut self, id: Id) -> EcsResult<()> {
+        let comp = T::id(self.world)?;
+        self.add_id(id, comp)
+    }
+
+    /// Adds the tag `comp` to `id`, removed on rollback.
+    pub fn add_id(&mut self, id: Id, comp: Id) -> EcsResult<()> {
+        if has_component(self.world, id, comp) {
+            return Ok(());
+        }
+
+        add_tag(self.world, id, comp)?;
+        self.journal.push(Box::new(move |world| {
+            remove_component(world, id, comp).expect(UNDO_FAILED);
+        }));
+
+        Ok(())
+    }
+
+    /// Removes the component `T` from `id`, restored on rollback.
+    ///
+    /// See [TxWorld::remove_id].
+    #[inline]
+    pub fn remove<T: TypedId>(&mut self, id: Id) -> EcsResult<()> {
+        let comp = T::id(self.world)?;
+        self.remove_id(id, comp)
+    }
+
+    /// Removes the component `comp` from `id`, restored on rollback.
+    ///
+    /// The value is cloned before it's removed, so data components need a clone hook
+    /// or to be plain old data. Returns an error without removing anything if they're not.
+    pub fn remove_id(&mut self, id: Id, comp: Id) -> EcsResult<()> {
+        self.world.id_manager.get_location(id)?;
+
+        if !has_component(self.world, id, comp) {
+            return Ok(());
+        }
+
+        let saved = match get_component_ptr(self.world, id, comp) {
+            Some(ptr) => {
+                let ti = Rc::clone(component_type_info(self.world, comp).unwrap());
+
+                let alloc = Rc::clone(&self.world.allocator);
+
+                // SAFETY: The pointer is to a value of the component type.
+                match unsafe { SavedValue::clone_from(ti, alloc, ptr.as_ptr()) } {
+                    Some(saved) => Some(saved),
+                    None => return Err(EcsError::NoCloneHook(comp)),
+                }
+            }
+            None => None,
+        };
+
+        remove_component(self.world, id, comp)?;
+
+        self.journal.push(Box::new(move |world| match saved {
+            Some(saved) => saved.restore(world, id, comp),
+            None => add_tag(world, id, comp).expect(UNDO_FAILED),
+        }));
+
+        Ok(())
+    }
+
+    /// Despawns `id` once the transaction commits, nothing happens on rollback.
+    pub fn despawn(&mut self, id: Id) -> EcsResult<()> {
+        self.world.id_manager.get_location(id)?;
+        self.despawns.push(id);
+        Ok(())
+    }
+
+    /// Drops the journal and applies the despawns.
+    pub(crate) fn commit(mut self) {
+        self.journal.clear();
+
+        for id in std::mem::take(&mut self.despawns) {
+            // The same id may be despawned twice.
+            self.world.despawn(id).ok();
+        }
+    }
+}
+
+impl Drop for TxWorld<'_> {
+    fn drop(&mut self) {
+        while let Some(undo) = self.journal.pop() {
+            undo(self.world);
+        }
+    }
+}
+
+/// Copy of a removed value, written back on rollback.
+struct SavedValue {
+    ptr: NonNull<u8>,
+    ti: Rc<TypeInfo>,
+    alloc: Rc<dyn WorldAlloc>,
+    /// The value was moved into the world, only its memory is left to free.
+    moved: bool,
+}
+
+impl SavedValue {
+    /// Clones the value at `src` in memory of the world allocator, returns `None`
+    /// if the value has no clone hook and isn't plain old data.
+    ///
+    /// # Safety
+    /// `src` must point to a value of the type of `ti`.
+    unsafe fn clone_from(
+        ti: Rc<TypeInfo>,
+        alloc: Rc<dyn WorldAlloc>,
+        src: NonNull<u8>,
+    ) -> Option<Self> {
+        if ti.hooks.clone.is_none() && !ti.hooks.pod {
+            return None;
+        }
+
+        let layout = Self::layout(&ti);
+
+        let ptr = match layout.size() {
+            0 => (ti.dangling)(),
+            // SAFETY: The layout has a non-zero size.
+            _ => match NonNull::new(unsafe { alloc.alloc(layout) }) {
+                Some(ptr) => ptr,
+                None => std::alloc::handle_alloc_error(layout),
+            },
+        };
+
+        // SAFETY: Values without a clone hook are plain old data, so their bytes can be copied.
+        match &ti.hooks.clone {
+            Some(clone) => clone(src, ptr),
+            None => unsafe { std::ptr::copy_nonoverlapping(src.as_ptr(), ptr.as_ptr(), ti.size) },
+        }
+
+        Some(Self {
+            ptr,
+            ti,
+            alloc,
+            moved: false,
+        })
+    }
+
+    fn layout(ti: &TypeInfo) -> Layout {
+        Layout::from_size_align(ti.size, ti.align).unwrap()
+    }
+
+    /// Writes the value back as the component of `id`.
+    fn restore(mut self, world: &mut World, id: Id, comp: Id) {
+        move_for_writes(world, id, &[comp]).expect(UNDO_FAILED);
+
+        // SAFETY:
+        // - The value was cloned from the same component and is not used again.
+        // - The component was removed, so id has no value for it.
+        unsafe { write_component_raw(world, id, comp, self.ptr) };
+        self.moved = true;
+    }
+}
+
+impl Drop for SavedValue {
+    fn drop(&mut self) {
+        if !self.moved
+            && let Some(drop_fn) = self.ti.drop_fn
+        {
+            // SAFETY: The value is still owned by the copy.
+            unsafe { drop_fn(self.ptr.as_ptr()) };
+        }
+
+        let layout = Self::layout(&self.ti);
+
+        if layout.size() != 0 {
+            // SAFETY: The value was allocated with this layout.
+            unsafe { self.alloc.dealloc(self.ptr.as_ptr(), layout) };
+        }
+    }
+}
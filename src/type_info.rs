@@ -1,25 +1,42 @@
 use crate::{id::Id, type_traits::DataComponent, utils::NoOpHash};
-use const_assert::const_assert;
 use std::{
     alloc::{Layout, LayoutError},
     any::TypeId,
+    cell::RefCell,
     collections::{HashMap, hash_map::Entry},
+    fmt,
     marker::PhantomData,
-    mem::needs_drop,
+    mem::{MaybeUninit, needs_drop},
     ptr::{self, NonNull},
 };
 
 pub type TypeName = String;
 type DefaultHook = Box<dyn Fn(NonNull<u8>)>;
 type CloneHook = Box<dyn Fn(NonNull<u8>, NonNull<u8>)>;
-type SetHook = Box<dyn FnMut(Id, NonNull<u8>)>;
-type RemoveHook = Box<dyn FnMut(Id, NonNull<u8>)>;
+// Mutable hooks are behind a RefCell, since type info is shared.
+type SetHook = RefCell<Box<dyn FnMut(Id, NonNull<u8>)>>;
+type RemoveHook = RefCell<Box<dyn FnMut(Id, NonNull<u8>)>>;
+type FmtHook = Box<dyn Fn(NonNull<u8>, &mut fmt::Formatter) -> fmt::Result>;
+type SerializeHook = Box<dyn Fn(NonNull<u8>, &mut Vec<u8>)>;
+type DeserializeHook = Box<dyn Fn(&[u8], NonNull<u8>)>;
+/// Converts the bytes of an older version of the type, see [TypeHooksBuilder::with_migration].
+type MigrateHook = Box<dyn Fn(&[u8], NonNull<u8>)>;
+type EqHook = Box<dyn Fn(NonNull<u8>, NonNull<u8>) -> bool>;
+type HashHook = Box<dyn Fn(NonNull<u8>) -> u64>;
 
 pub struct TypeHooksBuilder<T: DataComponent> {
     default: Option<DefaultHook>,
     clone: Option<CloneHook>,
     on_set: Option<SetHook>,
     on_remove: Option<RemoveHook>,
+    fmt: Option<FmtHook>,
+    serialize: Option<SerializeHook>,
+    deserialize: Option<DeserializeHook>,
+    pod: bool,
+    version: u32,
+    migrations: Vec<(u32, MigrateHook)>,
+    eq: Option<EqHook>,
+    hash: Option<HashHook>,
     phantom: PhantomData<fn(&mut T)>,
 }
 
@@ -30,10 +47,32 @@ impl<T: DataComponent> TypeHooksBuilder<T> {
             clone: None,
             on_set: None,
             on_remove: None,
+            fmt: None,
+            serialize: None,
+            deserialize: None,
+            pod: false,
+            version: 0,
+            migrations: vec![],
+            eq: None,
+            hash: None,
             phantom: PhantomData,
         }
     }
 
+    /// Creates hooks with default, clone, eq and fmt implemented by the traits of `T`.
+    ///
+    /// Used by `#[component(reflect)]`.
+    pub fn for_reflectable() -> Self
+    where
+        T: Default + Clone + PartialEq + fmt::Debug,
+    {
+        Self::new()
+            .with_default(T::default)
+            .with_clone(T::clone)
+            .with_eq(T::eq)
+            .with_fmt(<T as fmt::Debug>::fmt)
+    }
+
     pub fn with_default(mut self, f: fn() -> T) -> Self {
         self.default = Some(Box::new(move |ptr| unsafe {
             ptr.as_ptr().cast::<T>().write(f());
@@ -54,34 +93,134 @@ impl<T: DataComponent> TypeHooksBuilder<T> {
     }
 
     pub fn on_set(mut self, mut f: impl FnMut(Id, &mut T) + 'static) -> Self {
-        self.on_set = Some(Box::new(move |entity, ptr| {
+        self.on_set = Some(RefCell::new(Box::new(move |entity, ptr| {
             f(entity, unsafe { ptr.cast::<T>().as_mut() });
-        }));
+        })));
         self
     }
 
     pub fn on_remove(mut self, mut f: impl FnMut(Id, &mut T) + 'static) -> Self {
-        self.on_remove = Some(Box::new(move |entity, ptr| {
+        self.on_remove = Some(RefCell::new(Box::new(move |entity, ptr| {
             f(entity, unsafe { ptr.cast::<T>().as_mut() })
+        })));
+        self
+    }
+
+    /// Formats values in [World::debug_entity](crate::world::World::debug_entity).
+    pub fn with_fmt(mut self, f: fn(&T, &mut fmt::Formatter) -> fmt::Result) -> Self {
+        self.fmt = Some(Box::new(move |ptr, fmt| {
+            f(unsafe { ptr.cast::<T>().as_ref() }, fmt)
+        }));
+        self
+    }
+
+    /// Converts values to and from bytes in
+    /// [World::serialize_entity](crate::world::World::serialize_entity).
+    ///
+    /// Values without these hooks are only serialized if the type is [plain old data](Self::with_pod).
+    pub fn with_serialize(mut self, ser: fn(&T, &mut Vec<u8>), de: fn(&[u8]) -> T) -> Self {
+        self.serialize = Some(Box::new(move |ptr, buf| {
+            ser(unsafe { ptr.cast::<T>().as_ref() }, buf)
+        }));
+        self.deserialize = Some(Box::new(move |bytes, ptr| unsafe {
+            ptr.cast::<T>().write(de(bytes));
+        }));
+        self
+    }
+
+    /// Marks `T` as plain old data, its values are serialized as their raw bytes
+    /// when it has no serialize hooks.
+    ///
+    /// # Safety
+    /// `T` must have no padding, and every bit pattern of its size must be a valid `T`.
+    pub unsafe fn with_pod(mut self) -> Self {
+        self.pod = true;
+        self
+    }
+
+    /// Sets the version of the layout written by
+    /// [World::serialize_entity](crate::world::World::serialize_entity), defaults to 0.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Converts values written with the layout of `version` to the current version
+    /// in [World::deserialize_entity](crate::world::World::deserialize_entity).
+    pub fn with_migration(mut self, version: u32, f: fn(&[u8]) -> T) -> Self {
+        let hook: MigrateHook = Box::new(move |bytes, ptr| unsafe {
+            ptr.cast::<T>().write(f(bytes));
+        });
+
+        match self
+            .migrations
+            .iter_mut()
+            .find(|(from, _)| *from == version)
+        {
+            Some((_, old)) => *old = hook,
+            None => self.migrations.push((version, hook)),
+        }
+
+        self
+    }
+
+    /// Compares values in [World::find_with_value](crate::world::World::find_with_value).
+    pub fn with_eq(mut self, f: fn(&T, &T) -> bool) -> Self {
+        self.eq = Some(Box::new(move |a, b| unsafe {
+            f(a.cast::<T>().as_ref(), b.cast::<T>().as_ref())
         }));
         self
     }
 
+    /// Hashes values to skip comparisons in
+    /// [World::find_with_value](crate::world::World::find_with_value).
+    ///
+    /// Values equal by the eq hook must have the same hash.
+    pub fn with_hash(mut self, f: fn(&T) -> u64) -> Self {
+        self.hash = Some(Box::new(move |ptr| f(unsafe { ptr.cast::<T>().as_ref() })));
+        self
+    }
+
     pub fn build(self) -> TypeHooks {
         TypeHooks {
             default: self.default,
             clone: self.clone,
             on_set: self.on_set,
             on_remove: self.on_remove,
+            fmt: self.fmt,
+            serialize: self.serialize,
+            deserialize: self.deserialize,
+            pod: self.pod,
+            version: self.version,
+            migrations: self.migrations,
+            eq: self.eq,
+            hash: self.hash,
         }
     }
 }
 
+impl<T: DataComponent> Default for TypeHooksBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct TypeHooks {
     pub(crate) default: Option<DefaultHook>,
     pub(crate) clone: Option<CloneHook>,
     pub(crate) on_set: Option<SetHook>,
     pub(crate) on_remove: Option<RemoveHook>,
+    pub(crate) fmt: Option<FmtHook>,
+    pub(crate) serialize: Option<SerializeHook>,
+    pub(crate) deserialize: Option<DeserializeHook>,
+    /// Whether values can be read and written as raw bytes, see [TypeHooksBuilder::with_pod].
+    pub(crate) pod: bool,
+    /// Version of the serialized layout.
+    pub(crate) version: u32,
+    /// Conversions from older serialized layouts, keyed by their version.
+    pub(crate) migrations: Vec<(u32, MigrateHook)>,
+    pub(crate) eq: Option<EqHook>,
+    pub(crate) hash: Option<HashHook>,
 }
 
 pub struct TypeInfo {
@@ -130,8 +269,36 @@ impl TypeInfo {
     pub fn name(&self) -> &'static str {
         (self.type_name)()
     }
+
+    /// Calls the on_set hook, if any, with the value of `id`.
+    ///
+    /// The hook is skipped if it's already running.
+    ///
+    /// # Safety
+    /// `ptr` must point to a value of this type.
+    pub(crate) unsafe fn call_on_set(&self, id: Id, ptr: NonNull<u8>) {
+        if let Some(Ok(mut hook)) = self.hooks.on_set.as_ref().map(RefCell::try_borrow_mut) {
+            hook(id, ptr);
+        }
+    }
+
+    /// Creates a new value with the default hook.
+    ///
+    /// Returns `None` if the type has no default hook.
+    ///
+    /// # Safety
+    /// `T` must be the type described by this [TypeInfo].
+    pub(crate) unsafe fn new_default<T>(&self) -> Option<T> {
+        let default = self.hooks.default.as_ref()?;
+        let mut val = MaybeUninit::<T>::uninit();
+        default(NonNull::from(&mut val).cast());
+
+        // SAFETY: The default hook initializes the value.
+        Some(unsafe { val.assume_init() })
+    }
 }
 
+#[derive(Clone)]
 pub struct TypeMap<V> {
     types: HashMap<TypeId, V, NoOpHash>,
 }
@@ -165,7 +332,60 @@ impl<V> TypeMap<V> {
     }
 
     #[inline(always)]
-    pub fn entry<T: 'static>(&mut self) -> Entry<TypeId, V> {
+    pub fn entry<T: 'static>(&mut self) -> Entry<'_, TypeId, V> {
         self.types.entry(TypeId::of::<T>())
     }
+
+    #[inline(always)]
+    pub(crate) fn get_by_id(&self, type_id: &TypeId) -> Option<&V> {
+        self.types.get(type_id)
+    }
+
+    #[inline(always)]
+    pub(crate) fn insert_by_id(&mut self, type_id: TypeId, val: V) {
+        self.types.insert(type_id, val);
+    }
+
+    #[inline(always)]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&TypeId, &V)> {
+        self.types.iter()
+    }
+}
+
+impl<V> Default for TypeMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{component::ComponentBuilder, world::World, world_utils::component_type_info};
+    use xecs_macros::Component;
+
+    #[derive(Component, Default, Clone, PartialEq, Debug)]
+    #[component(reflect)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    struct Mana(#[allow(dead_code)] u32);
+
+    #[test]
+    fn reflected_components_have_all_trait_hooks() {
+        let mut world = World::new();
+        let health = world.register::<Health>(ComponentBuilder::new());
+        let mana = world.register::<Mana>(ComponentBuilder::new());
+
+        let hooks = &component_type_info(&world, health).unwrap().hooks;
+        assert!(hooks.default.is_some());
+        assert!(hooks.clone.is_some());
+        assert!(hooks.eq.is_some());
+        assert!(hooks.fmt.is_some());
+
+        let hooks = &component_type_info(&world, mana).unwrap().hooks;
+        assert!(hooks.default.is_none());
+        assert!(hooks.clone.is_none());
+        assert!(hooks.eq.is_none());
+        assert!(hooks.fmt.is_none());
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
     id::{Id, pair},
     registration::ComponentId,
     storage::StorageType,
+    type_info::TypeHooksBuilder,
     world::World,
 };
 use private::Sealed;
@@ -57,11 +58,23 @@ impl<T: ComponentId, U: ComponentId> PairType for PairTypeSelect<Tag, T, U> {
     const IS_FIRST: bool = false;
 }
 
+/// Implemented with `#[derive(Component)]`.
+///
+/// # Safety
+/// `DataType` must be [Tag] only for types without fields.
 pub unsafe trait Component: Sized + 'static {
     type DataType: ComponentDataType;
     type DescType: ComponentDescriptor;
     const IS_GENERIC: bool;
     const STORAGE: StorageType = StorageType::Sparse;
+
+    /// Hooks the component starts with in [ComponentBuilder::new](crate::component::ComponentBuilder::new).
+    fn default_hooks() -> TypeHooksBuilder<Self>
+    where
+        Self: DataComponent,
+    {
+        TypeHooksBuilder::new()
+    }
 }
 
 pub trait TagComponent: SealedTag {}
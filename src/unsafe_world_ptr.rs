@@ -30,15 +30,6 @@ impl<'w> From<&'w mut World> for UnsafeWorldPtr<'w> {
 }
 
 impl<'w> UnsafeWorldPtr<'w> {
-    /// TODO: documentation
-    #[inline]
-    pub(crate) unsafe fn world_mut(self) -> &'w mut World {
-        // TODO: self.assert_allows_mutable_access();
-        // SAFETY:
-        // - caller ensures the created `&mut World` is the only borrow of world
-        unsafe { &mut *self.ptr }
-    }
-
     /// Gets a reference to the [`&World`](World) this [`UnsafeWorldPtr`] belongs to.
     /// This can be used for arbitrary shared/readonly access.
     ///
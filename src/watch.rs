@@ -0,0 +1,165 @@
+//! Watches of the ids matching a query, see [World::watch].
+
+use crate::{
+    id::Id,
+    query::{QueryPlan, SelectStmt, WithStmt},
+    world::World,
+};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, task::Waker};
+
+/// Change in the ids matched by a watch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MembershipEvent {
+    /// The id started matching the query.
+    Entered(Id),
+    /// The id stopped matching the query, or was despawned.
+    Left(Id),
+}
+
+/// Events shared by a watch and its handle.
+#[derive(Default)]
+struct WatchState {
+    events: VecDeque<MembershipEvent>,
+    /// Task to wake when events are pushed, see [WatchHandle::into_stream].
+    waker: Option<Waker>,
+    /// Set when the world drops the watch.
+    closed: bool,
+}
+
+impl WatchState {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Watch registered in the world.
+pub(crate) struct Watch {
+    plan: QueryPlan,
+    /// Ids matched at the last update, sorted.
+    members: Vec<Id>,
+    state: Rc<RefCell<WatchState>>,
+}
+
+impl Watch {
+    /// Pushes the ids that entered or left the query since the last update.
+    ///
+    /// Returns `false` if the handle was dropped, so the watch can be removed.
+    pub(crate) fn update(&mut self, world: &World) -> bool {
+        if Rc::strong_count(&self.state) == 1 {
+            return false;
+        }
+
+        let mut matched = self.plan.matching_ids(world);
+        matched.sort_unstable();
+
+        let mut state = self.state.borrow_mut();
+        let pushed = state.events.len();
+
+        let left = self
+            .members
+            .iter()
+            .filter(|id| matched.binary_search(id).is_err());
+        state
+            .events
+            .extend(left.map(|&id| MembershipEvent::Left(id)));
+
+        let entered = matched
+            .iter()
+            .filter(|id| self.members.binary_search(id).is_err());
+        state
+            .events
+            .extend(entered.map(|&id| MembershipEvent::Entered(id)));
+
+        if state.events.len() > pushed {
+            state.wake();
+        }
+
+        self.members = matched;
+        true
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.closed = true;
+        state.wake();
+    }
+}
+
+/// Handle to a watch created by [World::watch].
+///
+/// Dropping it removes the watch from the world.
+pub struct WatchHandle {
+    state: Rc<RefCell<WatchState>>,
+}
+
+impl WatchHandle {
+    /// Takes the events pushed since the last call, in the order they happened.
+    pub fn drain_events(&self) -> Vec<MembershipEvent> {
+        self.state.borrow_mut().events.drain(..).collect()
+    }
+
+    /// Returns `true` once the world was dropped, no event is pushed after that.
+    pub fn is_closed(&self) -> bool {
+        self.state.borrow().closed
+    }
+
+    /// Turns the handle into a stream of its events.
+    ///
+    /// The stream is pending while there is no event, and is woken when
+    /// [World::dispatch_events] pushes some. It ends once the world was dropped.
+    #[cfg(feature = "async")]
+    pub fn into_stream(self) -> impl futures_core::Stream<Item = MembershipEvent> {
+        WatchStream(self)
+    }
+}
+
+#[cfg(feature = "async")]
+struct WatchStream(WatchHandle);
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for WatchStream {
+    type Item = MembershipEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::task::Poll;
+
+        let mut state = self.0.state.borrow_mut();
+
+        if let Some(event) = state.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        if state.closed {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+pub(crate) fn watch(world: &mut World, with: WithStmt) -> WatchHandle {
+    let state = Rc::new(RefCell::new(WatchState::default()));
+
+    world.observers.watches.push(Watch {
+        plan: QueryPlan::new(SelectStmt::default(), with),
+        members: vec![],
+        state: Rc::clone(&state),
+    });
+
+    WatchHandle { state }
+}
+
+/// Updates all watches, and removes the ones whose handle was dropped.
+pub(crate) fn update_watches(world: &mut World) {
+    let mut watches = std::mem::take(&mut world.observers.watches);
+    watches.retain_mut(|watch| watch.update(world));
+    world.observers.watches = watches;
+}
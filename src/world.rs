@@ -1,49 +1,248 @@
 use crate::{
-    component::{ComponentDescriptor, ComponentInfo, private::Passkey},
-    error::{EcsResult, GetResult, UnregisteredTypeErr},
-    flags::{IdFlags, TableFlags},
-    get_params::Params,
-    graph::GraphNode,
+    allocator::{DefaultAlloc, WorldAlloc},
+    arena::FrameArena,
+    comp_mut::CompMut,
+    component::{
+        BuilderCommon, ComponentBuilder, ComponentDescriptor, ComponentInfo, TagBuilder,
+        ensure_component, private::Passkey,
+    },
+    debug_entity::DebugEntity,
+    entity_ref::EntityRef,
+    error::{
+        EcsError, EcsResult, GetError, GetResult, InvalidId, InvariantViolation,
+        UnregisteredTypeErr,
+    },
+    flags::{ComponentFlags, IdFlags, Policy, TableFlags},
+    get_params::{Params, QueryData},
+    graph::{GraphNode, new_table},
+    guid::Guid,
     id::{
         Id, IdMap, IntoId, Signature,
         manager::{IdLocation, IdManager, IdRecord},
+        pair,
+    },
+    observer::Observers,
+    prefab::PrefabTreeBuilder,
+    query::{QueryPlan, SelectStmt, SparseQueryIter, WithStmt, WorldQuery},
+    recording::{Event, EventSink, record},
+    registration::{ComponentId, ComponentList},
+    schema::{Schema, SchemaMismatch},
+    stats::{ComponentStats, ComponentStorageStats, MemoryUsage, StorageSuggestion},
+    storage::{
+        Storage, StorageType,
+        relation::stored_relation,
+        table::{self, Table},
     },
-    registration::ComponentId,
-    storage::table::{self, Table},
     table_index::{TableId, TableIndex},
+    transaction::TxWorld,
     type_info::TypeMap,
     type_traits::{DataComponent, TagComponent, TypedId},
-    world_utils::{add_tag, has_component, set_component, set_component_checked},
+    unsafe_world_ptr::UnsafeWorldPtr,
+    watch::{WatchHandle, update_watches, watch},
+    world_ref::WorldRef,
+    world_split::{ComponentView, IdView, LockRank, StorageCell, TablesView, split},
+    world_utils::{
+        add_auto_components, add_tag, add_tag_to_all, apply_auto_components, batch_remove,
+        check_invariants, checked_pair, collect_subtree, component_overlap, component_type_info,
+        dangling_relationships, deep_clone, defragment, deserialize_entity, despawn_cascade,
+        export_column, find_transitive, find_with_value, first_target, for_each_column,
+        get_component, get_component_ptr, get_or_insert_component, guid_of, has_component, id_name,
+        instantiate_prefab, mark_changed, merge_world, migrate_storage, move_for_writes, move_to,
+        new_raw_id, pairs_of, prefab_parent_count, remove_component, reparent_children,
+        replace_component, replay_events, serialize_entity, set_component, set_guid, set_id_flag,
+        set_union, try_set_component,
+    },
+    write_list::ComponentWriteList,
+};
+use std::{
+    cell::RefCell, collections::HashMap, fmt::Write, mem::MaybeUninit, ptr::NonNull, rc::Rc,
 };
 
 pub struct World {
-    pub(crate) id_manager: IdManager,
+    pub(crate) id_manager: StorageCell<IdManager>,
     pub(crate) type_arr: Vec<Option<Id>>,
     pub(crate) type_map: TypeMap<Id>,
-    pub(crate) components: IdMap<ComponentInfo>,
-    pub(crate) table_index: TableIndex,
+    pub(crate) components: StorageCell<IdMap<ComponentInfo>>,
+    pub(crate) table_index: StorageCell<TableIndex>,
     pub(crate) root_table: TableId,
+    pub(crate) observers: Observers,
+    /// Id that holds event components while they are delivered.
+    pub(crate) event_entity: Id,
+    pub(crate) frame_arena: FrameArena,
+    /// Incremented when a component is named, invalidates cached table names.
+    pub(crate) names_version: u32,
+    /// Sparse tag added to ids marked for deletion.
+    pub(crate) pending_delete: Id,
+    /// Tag of prefab ids, see [World::prefab_tree].
+    pub(crate) prefab: Id,
+    /// Relationship between prefabs and their parent.
+    pub(crate) child_of: Id,
+    /// Component holding the [Guid] of ids.
+    pub(crate) guid: Id,
+    /// Ids by guid, see [World::set_guid].
+    pub(crate) guids: HashMap<u128, Id>,
+    /// Generation of each pair target index when it was last made a target.
+    ///
+    /// Pairs only store indices, this tells a recycled target from the one the pair was added for.
+    pub(crate) target_generations: HashMap<u32, u32>,
+    /// Variants of each union, see [World::register_union].
+    pub(crate) unions: IdMap<Box<[Id]>>,
+    /// Union of each variant, see [World::register_union].
+    pub(crate) variant_unions: IdMap<Id>,
+    /// Ids marked for deletion, in marking order.
+    pub(crate) pending_deletions: Vec<Id>,
+    /// Subtrees marked by [World::despawn_recursive_deferred], in marking order.
+    pub(crate) deferred_deletions: Vec<DeferredDeletion>,
+    /// Tick recorded for component writes, see [World::change_tick].
+    pub(crate) change_tick: u64,
+    /// Allocator for column and arena memory.
+    pub(crate) allocator: Rc<dyn WorldAlloc>,
+    /// Sink of structural changes, see [World::enable_recording].
+    pub(crate) recorder: Option<Box<dyn EventSink>>,
+    /// Components added to new ids by the active scopes, outer scopes first.
+    /// See [World::with_auto_component].
+    pub(crate) auto_components: Vec<(Id, AutoComponent)>,
+    /// Whether queries check the tables they match, see [World::disable_query_validation].
+    pub(crate) query_validation: bool,
+    /// Whether moves between tables are counted, see [World::enable_move_stats].
+    pub(crate) move_stats: bool,
+}
+
+/// Subtree marked by [World::despawn_recursive_deferred].
+#[derive(Clone)]
+pub(crate) struct DeferredDeletion {
+    /// Marked ids, popped children first.
+    pub(crate) ids: Vec<Id>,
+    /// Maximum number of ids despawned per flush.
+    pub(crate) budget: usize,
+}
+
+/// Writes a component value to a new id.
+type AutoWrite = Box<dyn Fn(&mut World, Id)>;
+
+/// Component added to every id created in a scope.
+pub(crate) enum AutoComponent {
+    Tag,
+    /// Writes a clone of the scope value to the new id.
+    Data(AutoWrite),
+}
+
+/// Kind of event watched by a batched observer, see [World::observe_batched].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// The value of a component was set or written through a [CompMut].
+    OnSet,
+}
+
+/// Builder for a [World] with custom settings.
+pub struct WorldBuilder {
+    allocator: Rc<dyn WorldAlloc>,
+}
+
+impl WorldBuilder {
+    pub fn new() -> Self {
+        Self {
+            allocator: Rc::new(DefaultAlloc),
+        }
+    }
+
+    /// Sets the allocator used for column and arena memory.
+    pub fn with_allocator(mut self, allocator: impl WorldAlloc + 'static) -> Self {
+        self.allocator = Rc::new(allocator);
+        self
+    }
+
+    pub fn build(self) -> World {
+        World::with_allocator(self.allocator)
+    }
+}
+
+impl Default for WorldBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
+        WorldBuilder::new().build()
+    }
+
+    /// Returns a builder to create a world with custom settings.
+    #[inline]
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+
+    fn with_allocator(allocator: Rc<dyn WorldAlloc>) -> Self {
+        let mut world = Self::empty(allocator);
+
+        world.event_entity = world.new_id();
+        world.pending_delete = world.new_component(
+            TagBuilder::new()
+                .name("PendingDelete")
+                .storage(StorageType::Sparse),
+        );
+        world.prefab = world.new_component(TagBuilder::new().name("Prefab"));
+        world.child_of = world.new_component(
+            TagBuilder::new()
+                .name("ChildOf")
+                .on_delete_object(Policy::Delete),
+        );
+        // SAFETY: Guid is a u128, which has no padding and no invalid bit patterns.
+        let guid_desc = unsafe { ComponentBuilder::new().name("Guid").pod() };
+        world.guid = world.register::<Guid>(guid_desc);
+        world
+    }
+
+    /// Creates a world with only the root table, without builtin components.
+    pub(crate) fn empty(allocator: Rc<dyn WorldAlloc>) -> Self {
         let mut table_index = TableIndex::new();
         let root_table = table_index.add_with_id(|id| Table {
             id,
             _flags: TableFlags::empty(),
             signature: Signature::from(vec![]),
-            id_data: table::ComponentData::new(Box::from([])),
+            id_data: table::TableData::new(Box::from([])),
             column_map: IdMap::new(),
             node: GraphNode::new(),
+            debug_name: RefCell::new(None),
+            moves: HashMap::new(),
+            deletes: 0,
         });
 
         Self {
-            id_manager: IdManager::new(),
+            id_manager: StorageCell::new(IdManager::new(), LockRank::Ids),
             type_arr: Vec::new(),
             type_map: TypeMap::new(),
-            components: IdMap::new(),
-            table_index,
+            components: StorageCell::new(IdMap::new(), LockRank::Components),
+            table_index: StorageCell::new(table_index, LockRank::Tables),
             root_table,
+            observers: Observers::new(),
+            event_entity: Id::NULL,
+            frame_arena: FrameArena::new(Rc::clone(&allocator)),
+            names_version: 0,
+            pending_delete: Id::NULL,
+            prefab: Id::NULL,
+            child_of: Id::NULL,
+            guid: Id::NULL,
+            guids: HashMap::new(),
+            target_generations: HashMap::new(),
+            unions: IdMap::new(),
+            variant_unions: IdMap::new(),
+            pending_deletions: Vec::new(),
+            deferred_deletions: Vec::new(),
+            change_tick: 1,
+            allocator,
+            recorder: None,
+            auto_components: Vec::new(),
+            query_validation: true,
+            move_stats: false,
         }
     }
 
@@ -54,23 +253,82 @@ impl World {
         T::id(self)
     }
 
+    /// Panics if the type is not registered with this world.
+    ///
+    /// Useful to catch missing registrations early, since some functions
+    /// (like [World::set]) silently do nothing for unregistered types.
+    #[track_caller]
+    pub fn assert_registered<T: TypedId>(&self) {
+        if let Err(err) = T::id(self) {
+            panic!("component {} used before register()", err.type_name());
+        }
+    }
+
     /// Registers the type with the world if not registered and returns its id.
     ///
     /// This function eagerly evaluates `desc` (see [World::register_with]
     /// for lazily evaluated descriptor).
+    ///
+    /// # Panics
+    /// If the type is already registered with a different storage type
+    /// or the descriptor is invalid, see [World::try_register].
+    #[track_caller]
     pub fn register<T: ComponentId>(&mut self, desc: T::DescType) -> Id {
-        let id = T::get_or_register_type(self);
+        match self.try_register::<T>(desc) {
+            Ok(id) => id,
+            Err(err) => panic!("{err}"),
+        }
+    }
 
-        if !self.components.contains(id) {
-            desc.build(self, id, Passkey);
+    /// Registers all components of the tuple `L` with their default descriptors.
+    /// Returns their ids, in order.
+    ///
+    /// Capacity for the new components is reserved once up front.
+    ///
+    /// # Panics
+    /// If a type is already registered with a different storage type,
+    /// see [World::try_register].
+    #[track_caller]
+    pub fn register_bulk<L: ComponentList>(&mut self) -> Vec<Id> {
+        self.components.reserve(L::LEN);
+
+        let mut ids = Vec::with_capacity(L::LEN);
+
+        if let Err(err) = L::register_all(self, &mut ids) {
+            panic!("{err}");
         }
 
-        id
+        ids
+    }
+
+    /// Registers the type with the world if not registered and returns its id.
+    ///
+    /// Returns an error if the type is already registered with a different storage type,
+    /// since the storage of a component can't change after registration,
+    /// or if the descriptor is invalid, e.g. a symmetric component with data.
+    pub fn try_register<T: ComponentId>(&mut self, desc: T::DescType) -> EcsResult<Id> {
+        let id = T::get_or_register_type(self);
+
+        match self.components.get(id) {
+            Some(ci) if ci.storage.get_type() != desc.storage_type() => {
+                Err(EcsError::StorageConflict(id))
+            }
+            Some(_) => Ok(id),
+            None => {
+                desc.build(self, id, Passkey)?;
+                self.name_component::<T>(id);
+                Ok(id)
+            }
+        }
     }
 
     /// Registers the type with the world or returns its id if already registered.
     ///
     /// Lazily evaluates the descriptor and only calls it if the type is not registered.
+    ///
+    /// # Panics
+    /// If the descriptor is invalid, see [World::try_register].
+    #[track_caller]
     pub fn register_with<T>(&mut self, f: impl Fn() -> T::DescType) -> Id
     where
         T: ComponentId,
@@ -78,18 +336,31 @@ impl World {
         let id = T::get_or_register_type(self);
 
         if !self.components.contains(id) {
-            f().build(self, id, Passkey);
+            if let Err(err) = f().build(self, id, Passkey) {
+                panic!("{err}");
+            }
+            self.name_component::<T>(id);
         }
 
         id
     }
 
+    /// Names the component after the type if it wasn't named by its descriptor.
+    fn name_component<T: ComponentId>(&mut self, id: Id) {
+        if let Some(ci) = self.components.get_mut(id) {
+            ci.name
+                .get_or_insert_with(|| std::any::type_name::<T>().into());
+            self.names_version = self.names_version.wrapping_add(1);
+        }
+    }
+
     /// Creates a component from this `id` if one doesn't exist.
     ///
     /// Returns `false` if:
     /// - `id` is already a component/tag.
     /// - `id` is a pair.
     /// - `id` is not valid.
+    /// - the descriptor is invalid, see [World::try_register].
     #[inline(always)]
     pub fn to_component<T>(&mut self, id: Id, f: impl FnOnce() -> T) -> bool
     where
@@ -98,125 +369,1820 @@ impl World {
         if id.is_pair() || !self.is_alive(id) || self.components.contains(id) {
             false
         } else {
-            f().build(self, id, Passkey);
-            true
+            f().build(self, id, Passkey).is_ok()
         }
     }
 
     /// Creates a new component and returns its [Id].
     ///
     /// Useful for creating "newtype" components.
+    ///
+    /// # Panics
+    /// If the descriptor is invalid, see [World::try_register].
+    #[track_caller]
     pub fn new_component<T>(&mut self, desc: T) -> Id
     where
         T: ComponentDescriptor,
     {
         let id = self.new_id();
-        desc.build(self, id, Passkey);
+
+        if let Err(err) = desc.build(self, id, Passkey) {
+            self.despawn(id).ok();
+            panic!("{err}");
+        }
+
         id
     }
 
     /// Creates a new [Id].
+    ///
+    /// The id gets the components of the active [World::with_auto_component] scopes.
     pub fn new_id(&mut self) -> Id {
+        let id = new_raw_id(self);
+
+        if !self.auto_components.is_empty() {
+            apply_auto_components(self, id);
+        }
+
+        id
+    }
+
+    /// Creates a new [Id] with `flags` set, e.g. [IdFlags::IS_TRAVERSABLE].
+    ///
+    /// Same as [World::new_id] otherwise, see [World::entity_flags].
+    pub fn new_id_with_flags(&mut self, flags: IdFlags) -> Id {
+        let id = self.new_id();
+        set_id_flag(self, id, flags);
+        id
+    }
+
+    /// Returns the flags of `id`, `None` if it's not alive.
+    ///
+    /// [IdFlags::IS_TARGET] and [IdFlags::HAS_SPARSE] are set once the id is a pair target
+    /// or gets a sparse component, and are not cleared when it's removed.
+    pub fn entity_flags(&self, id: Id) -> Option<IdFlags> {
+        self.id_manager
+            .get_record(id)
+            .ok()
+            .map(|record| record.flags)
+    }
+
+    /// Creates an id at `index`, e.g. to keep the ids of data migrated from another world.
+    ///
+    /// Unused indices below `index` are created dead and recycled by [World::new_id].
+    /// Returns an error if the index is alive or was retired by [World::compact_ids]
+    /// or [World::despawn].
+    ///
+    /// # Safety
+    /// Ids with the index that are stored outside the world, e.g. in component values,
+    /// must not be confused with the new id.
+    pub unsafe fn new_id_at(&mut self, index: u32) -> EcsResult<Id> {
         let root = self.root_table;
-        self.id_manager.new_id(|id| IdRecord {
+        let id = self.id_manager.new_id_at(index, |id| IdRecord {
             location: IdLocation {
                 table: root,
                 row: unsafe { self.table_index[root].id_data.new_row(id) },
             },
             flags: IdFlags::default(),
-        })
+        })?;
+
+        record(self, |_| Event::Spawn { id });
+
+        if !self.auto_components.is_empty() {
+            apply_auto_components(self, id);
+        }
+
+        Ok(id)
     }
 
-    /// Add `comp` as tag to `id`. No side effect if `id` already has tag.
+    /// Finds or creates the table for a set of components and returns its id.
+    ///
+    /// Useful to create tables up front before spawning many ids with the same components.
+    /// Components that don't exist are created as tags, sparse components are
+    /// not stored in tables so they're left out of the signature.
+    pub fn ensure_archetype(&mut self, comps: &[Id]) -> TableId {
+        let mut ids = Vec::with_capacity(comps.len());
+
+        for &comp in comps {
+            ensure_component(self, comp);
+
+            // Pairs of a relation store have no component of their own.
+            if let Some(Storage::Tables(_)) = self.components.get(comp).map(|ci| &ci.storage) {
+                ids.push(comp);
+            }
+        }
+
+        let signature = Signature::from(ids);
+
+        match self.table_index.get_id(&signature) {
+            Some(table) => table,
+            None => new_table(self, signature),
+        }
+    }
+
+    /// Moves `id` to the table with the components of `target`, adding and
+    /// removing components with a single move.
+    ///
+    /// Added data components are initialized with their default hook, removed values
+    /// are dropped. Auto-added components and symmetric pairs are handled like
+    /// [World::set] and [World::remove_id]. The Prefab tag and ChildOf pairs of `id`
+    /// are kept. Components that are not stored in tables are ignored.
+    ///
+    /// Returns an error if `id` is not alive, an added component has no default hook
+    /// or `target` has two pairs of an exclusive relationship or two variants of a union.
+    #[inline]
+    pub fn move_to(&mut self, id: Id, target: &Signature) -> EcsResult<()> {
+        move_to(self, id, target)
+    }
+
+    /// Returns the sorted component ids stored in a table.
+    ///
+    /// Returns `None` if the table doesn't exist.
+    #[inline]
+    pub fn components_of_table(&self, table: TableId) -> Option<&[Id]> {
+        self.table_index
+            .get(table)
+            .map(|table| table.signature.ids())
+    }
+
+    /// Checks if a table stores the component.
     #[inline]
+    pub fn has_component_in_table(&self, table: TableId, comp: Id) -> bool {
+        self.table_index
+            .get(table)
+            .is_some_and(|table| table.signature.has_id(comp))
+    }
+
+    /// Add `comp` as tag to `id`. No side effect if `id` already has tag.
+    ///
+    /// If `comp` is a pair of a symmetric relationship `(R, B)`, `(R, id)` is also added to `B`.
     pub fn add_id(&mut self, id: Id, comp: impl IntoId) -> EcsResult<()> {
-        debug_assert!(comp.validate(self), "id or pair is not valid");
-        add_tag(self, id, comp.into_id())
+        let comp = comp.into_id();
+        add_tag(self, id, comp)
     }
 
     /// Add the type as tag to `id`. No side effect if `id` already has tag.
+    ///
+    /// Symmetric pairs are mirrored like in [add_id](World::add_id).
     #[inline]
     pub fn add<T: TypedId + TagComponent>(&mut self, id: Id) -> EcsResult<()> {
         add_tag(self, id, T::id(self)?)
     }
 
-    /// Checks if the `id` has the component.
-    pub fn has_id(&self, id: Id, comp: impl IntoId) -> bool {
-        debug_assert!(comp.validate(self), "id or pair is not valid");
-        has_component(self, id, comp.into_id())
-    }
-
-    /// Checks if `id` has the component.
-    pub fn has<T: TypedId>(&self, id: Id) -> bool {
-        T::id(self).is_ok_and(|comp| has_component(self, id, comp))
+    /// Adds the tag `T` to all ids matching `query`.
+    ///
+    /// Tables where every row matches are moved at once, instead of id by id.
+    pub fn add_tag_to_all<T: TypedId + TagComponent>(
+        &mut self,
+        query: &mut QueryPlan,
+    ) -> EcsResult<()> {
+        add_tag_to_all(self, T::id(self)?, query)
     }
 
-    #[inline(always)]
-    pub fn set_id<T>(&mut self, id: Id, comp: impl IntoId, val: T) -> Option<T>
+    /// Gets the target of the `(Rel, *)` pair of `id`, adding `(Rel, Tgt)` if there's none.
+    ///
+    /// Returns the existing target or the id of `Tgt` if the pair was added.
+    /// Mostly useful for exclusive relationships, where `id` has at most one target.
+    pub fn get_or_set_pair<Rel, Tgt>(&mut self, id: Id) -> EcsResult<Id>
     where
-        T: DataComponent,
+        Rel: TypedId + TagComponent,
+        Tgt: TypedId + TagComponent,
     {
-        debug_assert!(comp.validate(self), "id or pair is not valid");
-        set_component_checked(self, id, comp.into_id(), val)
+        let rel = Rel::id(self)?;
+
+        if let Some(tgt) = first_target(self, id, rel)? {
+            return Ok(tgt);
+        }
+
+        let tgt = Tgt::id(self)?;
+        self.add_id(id, (rel, tgt))?;
+        Ok(tgt)
+    }
+
+    /// Removes `comp` from `id`. No side effect if `id` doesn't have the component.
+    ///
+    /// If `comp` is a pair of a symmetric relationship `(R, B)`, `(R, id)` is also removed from `B`.
+    pub fn remove_id(&mut self, id: Id, comp: impl IntoId) -> EcsResult<()> {
+        let comp = comp.into_id();
+        remove_component(self, id, comp)
     }
 
+    /// Removes the type from `id`. No side effect if `id` doesn't have the component.
     #[inline]
-    pub fn set<T: TypedId>(&mut self, id: Id, val: T::Data) -> Option<T::Data>
+    pub fn remove<T: TypedId>(&mut self, id: Id) -> EcsResult<()> {
+        let comp = T::id(self)?;
+        self.remove_id(id, comp)
+    }
+
+    /// Removes the type from all `entities`, computing the destination once per table.
+    ///
+    /// Fails before removing anything if an id is not alive.
+    pub fn batch_remove<T: TypedId>(&mut self, entities: &[Id]) -> EcsResult<()> {
+        let comp = T::id(self)?;
+        batch_remove(self, entities, comp)
+    }
+
+    /// Removes `Old` from `entity` and adds `New`, with at most one table move.
+    ///
+    /// Returns the value of `Old`, or `None` if it's a tag or `entity` didn't have it.
+    /// `new_val` is ignored if `New` is a tag. Useful for components encoding states,
+    /// e.g. replacing `Idle` with `Running`.
+    pub fn replace_component<Old: TypedId, New: TypedId>(
+        &mut self,
+        entity: Id,
+        new_val: New::Data,
+    ) -> EcsResult<Option<Old::Data>> {
+        let old = Old::id(self)?;
+        let new = New::id(self)?;
+        let val = (!New::IS_TAG).then_some(new_val);
+
+        // SAFETY: The component ids are obtained from the types, so the data types match.
+        let old_val =
+            unsafe { replace_component::<Old::Data, New::Data>(self, entity, old, new, val)? };
+        Ok(old_val.filter(|_| !Old::IS_TAG))
+    }
+
+    /// Removes the component `T` from `id` if `pred` returns `true` for its current value.
+    ///
+    /// Returns whether the component was removed, `false` if `id` doesn't have it.
+    pub fn remove_if<T: TypedId>(
+        &mut self,
+        id: Id,
+        pred: impl FnOnce(&T::Data) -> bool,
+    ) -> EcsResult<bool>
     where
         T::Data: DataComponent,
     {
-        // SAFETY:
-        // The component id is obtained from the type, so the data type matches.
-        unsafe { set_component(self, id, T::id(self).ok()?, val) }
+        let comp = T::id(self)?;
+        self.id_manager.get_location(id)?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        let remove = get_component_ptr(self, id, comp)
+            .is_some_and(|ptr| pred(unsafe { ptr.deref::<T::Data>() }));
+
+        if remove {
+            remove_component(self, id, comp)?;
+        }
+
+        Ok(remove)
     }
 
-    #[inline(always)]
-    pub fn is_alive(&self, entity: Id) -> bool {
-        self.id_manager.is_alive(entity)
+    /// Despawns `id`, dropping all its components.
+    ///
+    /// Mirrored pairs of symmetric relationships are removed from the other side.
+    /// Ids with a pair targeting `id` are handled by the target policy of the relationship,
+    /// see [BuilderCommon::on_delete_object]. Children are despawned with their parent,
+    /// since [ChildOf](World::child_of) deletes the ids targeting a deleted id.
+    /// If `id` is a component, the ids using it are handled by its delete policy,
+    /// see [BuilderCommon::on_delete]. A component still stored in tables or sparse
+    /// storages has its index retired, it's never issued again.
+    ///
+    /// # Panics
+    /// If a relationship with pairs targeting `id` has the [Policy::Panic] target policy,
+    /// or `id` is a component in use with the [Policy::Panic] delete policy.
+    pub fn despawn(&mut self, id: Id) -> EcsResult<()> {
+        despawn_cascade(self, id).map(|_| ())
     }
-}
 
-const fn assert_immutable<T: Params>() {
-    assert!(
-        T::ALL_IMMUTABLE,
-        "immutable World ref requires all Params to be immutable"
-    )
-}
+    /// Despawns all ids matching the query, returns how many were despawned.
+    ///
+    /// The ids are collected before any is despawned, see [World::collect].
+    /// Ids already despawned by the deletion of an earlier one are not counted.
+    pub fn despawn_matching<A>(&mut self, select: SelectStmt<A>, with: WithStmt) -> usize {
+        self.collect(select, with)
+            .into_iter()
+            .filter(|&id| self.despawn(id).is_ok())
+            .count()
+    }
 
-pub trait WorldGet<'a> {
-    fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'a>>;
-}
+    /// Marks `id` for deletion, it's despawned by the next [flush_deletions](World::flush_deletions).
+    ///
+    /// A marked id is still alive, but has the [pending_delete_tag](World::pending_delete_tag)
+    /// and queries skip it. No side effect if `id` is already marked.
+    pub fn mark_for_delete(&mut self, id: Id) -> EcsResult<()> {
+        if self.mark_pending(id)? {
+            self.pending_deletions.push(id);
+        }
 
-pub trait WorldMap<'a, Ret> {
-    fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'a>) -> Ret) -> GetResult<Ret>;
-}
+        Ok(())
+    }
 
-impl<'a> WorldGet<'a> for &'a World {
+    /// Flags and tags `id` as pending deletion.
+    /// Returns `false` if it was already marked.
+    fn mark_pending(&mut self, id: Id) -> EcsResult<bool> {
+        let record = self.id_manager.get_record_mut(id)?;
+
+        if record.flags.contains(IdFlags::PENDING_DELETE) {
+            return Ok(false);
+        }
+
+        record.flags.insert(IdFlags::PENDING_DELETE);
+
+        if let Err(err) = add_tag(self, id, self.pending_delete) {
+            self.id_manager
+                .get_record_mut(id)?
+                .flags
+                .remove(IdFlags::PENDING_DELETE);
+            return Err(err);
+        }
+
+        Ok(true)
+    }
+
+    /// Clears the flag and tag set by [World::mark_pending].
+    fn unmark_pending(&mut self, id: Id) -> EcsResult<()> {
+        let record = self.id_manager.get_record_mut(id)?;
+        record.flags.remove(IdFlags::PENDING_DELETE);
+        remove_component(self, id, self.pending_delete)
+    }
+
+    /// Marks `root` and every id under it through [ChildOf](World::child_of) pairs for deletion,
+    /// despawning at most `budget_per_flush` of them per [World::flush_deletions].
+    ///
+    /// Children are despawned before their parents, so a despawned parent never
+    /// has a live child in between flushes. Each call keeps its own budget.
+    ///
+    /// Nothing is marked if an error is returned.
+    pub fn despawn_recursive_deferred(
+        &mut self,
+        root: Id,
+        budget_per_flush: usize,
+    ) -> EcsResult<()> {
+        if !self.is_alive(root) {
+            return Err(InvalidId(root).into());
+        }
+
+        let mut ids = vec![];
+
+        for id in collect_subtree(self, root, self.child_of) {
+            match self.mark_pending(id) {
+                Ok(true) => ids.push(id),
+                Ok(false) => {}
+                Err(err) => {
+                    for id in ids {
+                        self.unmark_pending(id)?;
+                    }
+
+                    return Err(err);
+                }
+            }
+        }
+
+        self.deferred_deletions.push(DeferredDeletion {
+            ids,
+            budget: budget_per_flush,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the number of ids waiting to be despawned by [World::flush_deletions].
     #[inline]
-    fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'a>> {
-        const { assert_immutable::<T>() };
-        T::create(self.into(), id)
+    pub fn pending_delete_count(&self) -> usize {
+        let deferred: usize = self.deferred_deletions.iter().map(|d| d.ids.len()).sum();
+        self.pending_deletions.len() + deferred
     }
-}
 
-impl<'a, Ret> WorldMap<'a, Ret> for &'a World {
+    /// Cancels a pending deletion of `id`, fully restoring it.
+    ///
+    /// No side effect if `id` is not marked.
+    pub fn cancel_delete(&mut self, id: Id) -> EcsResult<()> {
+        if !self.is_pending_delete(id) {
+            return Ok(());
+        }
+
+        self.pending_deletions.retain(|&pending| pending != id);
+
+        for deferred in &mut self.deferred_deletions {
+            deferred.ids.retain(|&pending| pending != id);
+        }
+
+        self.unmark_pending(id)
+    }
+
+    /// Checks if `id` is marked for deletion.
     #[inline]
-    fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'a>) -> Ret) -> GetResult<Ret> {
-        const { assert_immutable::<T>() };
-        T::create(self.into(), id).map(f)
+    pub fn is_pending_delete(&self, id: Id) -> bool {
+        self.id_manager
+            .get_record(id)
+            .is_ok_and(|record| record.flags.contains(IdFlags::PENDING_DELETE))
+    }
+
+    /// Returns the sparse tag held by ids marked for deletion.
+    #[inline]
+    pub fn pending_delete_tag(&self) -> Id {
+        self.pending_delete
+    }
+
+    /// Despawns all ids marked for deletion and returns how many were despawned,
+    /// including the ids deleted along with them, see [World::despawn].
+    ///
+    /// Ids are despawned grouped by table. Ids that were despawned since they were marked
+    /// are skipped. Ids marked by [World::despawn_recursive_deferred] are despawned
+    /// within their budget.
+    pub fn flush_deletions(&mut self) -> usize {
+        let mut pending = std::mem::take(&mut self.pending_deletions);
+
+        // Despawn from the back of each table, so swapped rows stay in the same table.
+        pending.sort_by_cached_key(|&id| {
+            let loc = self.id_manager.get_location(id).ok();
+            loc.map(|loc| (loc.table, std::cmp::Reverse(loc.row)))
+        });
+
+        let mut count = pending
+            .into_iter()
+            .filter_map(|id| despawn_cascade(self, id).ok())
+            .sum();
+
+        let mut deferred = std::mem::take(&mut self.deferred_deletions);
+
+        for deletion in &mut deferred {
+            let mut budget = deletion.budget;
+
+            while budget > 0 {
+                let Some(id) = deletion.ids.pop() else {
+                    break;
+                };
+
+                if let Ok(despawned) = despawn_cascade(self, id) {
+                    count += despawned;
+                    budget -= 1;
+                }
+            }
+        }
+
+        // Hooks run by the despawns may have marked more subtrees.
+        deferred.retain(|deletion| !deletion.ids.is_empty());
+        deferred.append(&mut self.deferred_deletions);
+        self.deferred_deletions = deferred;
+
+        count
+    }
+
+    /// Returns the tag of prefab ids.
+    #[inline]
+    pub fn prefab_tag(&self) -> Id {
+        self.prefab
+    }
+
+    /// Returns the relationship between prefabs and their parent.
+    #[inline]
+    pub fn child_of(&self) -> Id {
+        self.child_of
+    }
+
+    /// Moves `child` under `new_parent`, replacing its `(ChildOf, old_parent)` pair.
+    ///
+    /// `fixup` is called with `(child, old_parent, new_parent)` right before the pairs are
+    /// swapped, e.g. to adjust a local transform. It can only change values of `child`,
+    /// and `old_parent` is [Id::NULL] if `child` had no parent. The old pair is removed and
+    /// the new one added in a single table move, so no orphan state is ever observable.
+    pub fn reparent(
+        &mut self,
+        child: Id,
+        new_parent: Id,
+        fixup: Option<impl FnMut(&mut ReparentView<'_>, Id, Id, Id)>,
+    ) -> EcsResult<()> {
+        let new = checked_pair(self, self.child_of, new_parent)?;
+        let old_parent = first_target(self, child, self.child_of)?;
+        let old = old_parent.map(|old_parent| pair(self.child_of, old_parent));
+        ensure_component(self, new);
+
+        // Pairs with a data target take its type, and only tag pairs can be swapped.
+        for pair in old.into_iter().chain([new]) {
+            if component_type_info(self, pair).is_some() {
+                return Err(EcsError::IsNotTag(pair));
+            }
+        }
+
+        if let Some(mut fixup) = fixup {
+            let old_parent = old_parent.unwrap_or(Id::NULL);
+            let mut view = ReparentView { world: self, child };
+            fixup(&mut view, child, old_parent, new_parent);
+        }
+
+        match old {
+            Some(old) => {
+                // SAFETY: Both pairs are tags, so no value is read or written.
+                unsafe { replace_component::<(), ()>(self, child, old, new, None)? };
+                Ok(())
+            }
+            None => add_tag(self, child, new),
+        }
+    }
+
+    /// Moves all children of `from` under `to`, returns how many were moved.
+    ///
+    /// Tables of children are moved at once instead of id by id.
+    pub fn reparent_children(&mut self, from: Id, to: Id) -> EcsResult<usize> {
+        reparent_children(self, self.child_of, from, to)
+    }
+
+    /// Starts building a tree of prefabs.
+    ///
+    /// ```ignore
+    /// let prefab = world.prefab_tree().root().with::<Health>(100).child().with::<Weapon>(1).build();
+    /// ```
+    pub fn prefab_tree(&mut self) -> PrefabTreeBuilder<'_> {
+        PrefabTreeBuilder::new(self)
+    }
+
+    /// Instantiates `root_prefab` and its child prefabs, keeping the same hierarchy.
+    ///
+    /// Returns an error if a component value in the tree can't be cloned,
+    /// nothing is instantiated then.
+    pub fn instantiate_tree(&mut self, root_prefab: Id) -> EcsResult<Id> {
+        self.reserve_relationships(prefab_parent_count(self, root_prefab));
+        instantiate_prefab(self, root_prefab)
+    }
+
+    /// Reserves room for the pair records of at least `targets` more relationship targets,
+    /// e.g. the parents of a hierarchy about to be built.
+    ///
+    /// Each new target of a table relationship gets its own pair record, reserving
+    /// avoids growing the record map while the hierarchy is built.
+    #[inline]
+    pub fn reserve_relationships(&mut self, targets: usize) {
+        self.components.reserve_pairs(targets);
+    }
+
+    /// Returns the current change tick.
+    ///
+    /// Component writes record the current tick, use [World::advance_tick] to start a new one.
+    #[inline]
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick
+    }
+
+    /// Advances the change tick and returns the new tick.
+    #[inline]
+    pub fn advance_tick(&mut self) -> u64 {
+        self.change_tick += 1;
+        self.change_tick
+    }
+
+    /// Iterates the ids and values of the component `T`, across all tables or its sparse set.
+    ///
+    /// Yields nothing if `T` is not registered or is a tag.
+    pub fn values<T: TypedId>(&self) -> impl Iterator<Item = (Id, &T::Data)> + '_
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self).ok();
+        let storage = comp
+            .and_then(|comp| self.components.get(comp))
+            .map(|ci| &ci.storage);
+
+        let sparse = match storage {
+            Some(Storage::SparseData(set)) => Some(set),
+            _ => None,
+        };
+
+        let tables = match storage {
+            Some(Storage::Tables(tables)) => Some(tables),
+            _ => None,
+        };
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        let from_sparse = sparse
+            .into_iter()
+            .flat_map(|set| unsafe { set.iter::<T::Data>() });
+
+        let from_tables = tables.into_iter().flat_map(move |tables| {
+            tables.keys().flat_map(move |&table| {
+                let table = &self.table_index[table];
+                let col = table.column_map.get(comp.unwrap()).copied();
+
+                col.into_iter().flat_map(move |col| {
+                    let ids = table.id_data.ids().iter().enumerate();
+
+                    // SAFETY:
+                    // - rows are in bounds of the table.
+                    // - The component id is obtained from the type, so the data type matches.
+                    ids.map(move |(row, &id)| {
+                        (id, unsafe { table.id_data.get::<T::Data>(col, row) })
+                    })
+                })
+            })
+        });
+
+        from_sparse.chain(from_tables)
+    }
+
+    /// Calls `f` once with the contiguous values of `T` in each table, then once with its
+    /// sparse values, for loops that don't need ids.
+    ///
+    /// Tags and components that aren't registered are skipped, pairs of a sparse relationship
+    /// have no column of their own. Values are marked as written but not recorded,
+    /// see [World::enable_recording].
+    pub fn for_each_column<T: TypedId>(&mut self, f: impl FnMut(&mut [T::Data]))
+    where
+        T::Data: DataComponent,
+    {
+        if let Ok(comp) = T::id(self) {
+            for_each_column(self, comp, f);
+        }
+    }
+
+    /// Calls `visitor` with the contiguous values of `T` and their ids in each table
+    /// that has rows, then with its sparse values, e.g. to copy them to a GPU buffer.
+    ///
+    /// The table is `None` for sparse values. The slices are only valid during the call.
+    /// Tags, pairs of a sparse relationship and components that aren't registered are skipped.
+    pub fn export_column<T: TypedId>(
+        &self,
+        mut visitor: impl FnMut(Option<TableId>, &[T::Data], &[Id]),
+    ) where
+        T::Data: DataComponent,
+    {
+        let Ok(comp) = T::id(self) else {
+            return;
+        };
+
+        export_column(self, comp, |table, column, ids| {
+            // SAFETY: The component id is obtained from the type, so the data type matches.
+            visitor(table, unsafe { column.as_slice() }, ids)
+        });
+    }
+
+    /// Calls `visitor` with the bytes of the values of `comp`, their ids and the size
+    /// of a value, see [World::export_column].
+    ///
+    /// Padding bytes of the values may be uninitialized, so the bytes are only meant
+    /// to be copied.
+    pub fn export_column_bytes(
+        &self,
+        comp: Id,
+        mut visitor: impl FnMut(&[MaybeUninit<u8>], &[Id], usize),
+    ) {
+        export_column(self, comp, |_, column, ids| {
+            visitor(column.as_bytes(), ids, column.type_info().size)
+        });
+    }
+
+    /// Returns the ids whose component `T` was written after `tick`.
+    ///
+    /// Tables whose column wasn't written after `tick` are skipped without checking rows.
+    pub fn get_modified_since<T: TypedId>(&self, tick: u64) -> impl Iterator<Item = Id> + '_ {
+        let comp = T::id(self).ok();
+        let storage = comp
+            .and_then(|comp| self.components.get(comp))
+            .map(|ci| &ci.storage);
+
+        let sparse = match storage {
+            Some(Storage::SparseData(set)) => Some(set),
+            _ => None,
+        };
+
+        let tables = match storage {
+            Some(Storage::Tables(tables)) => Some(tables),
+            _ => None,
+        };
+
+        let from_sparse = sparse
+            .into_iter()
+            .flat_map(move |set| set.modified_since(tick));
+        let from_tables = tables.into_iter().flat_map(move |tables| {
+            tables
+                .keys()
+                .flat_map(move |&table| self.table_index[table].modified_since(comp.unwrap(), tick))
+        });
+
+        from_sparse.chain(from_tables)
+    }
+
+    /// Checks if the `id` has the component.
+    pub fn has_id(&self, id: Id, comp: impl IntoId) -> bool {
+        has_component(self, id, comp.into_id())
+    }
+
+    /// Returns the number of ids that have both `A` and `B`.
+    ///
+    /// Returns 0 if either is not registered.
+    pub fn component_overlap<A: TypedId, B: TypedId>(&self) -> usize {
+        match (A::id(self), B::id(self)) {
+            (Ok(a), Ok(b)) => component_overlap(self, a, b),
+            _ => 0,
+        }
+    }
+
+    /// Runs `f` once with a query over all ids matching the params `Q`.
+    ///
+    /// `Q` is usually inferred from the closure, e.g.
+    /// `world.run_once(|mut q: WorldQuery<(&Position, &mut Velocity)>| ...)`.
+    pub fn run_once<Q: Params, F: FnOnce(WorldQuery<'_, Q>)>(&mut self, f: F) {
+        f(WorldQuery::new(self))
+    }
+
+    /// Iterates the ids matching the fields of the struct view `Q`, see [QueryData].
+    ///
+    /// Returns an error if the fields access a component mutably more than once.
+    /// Ids whose fields can't be created are skipped, see [WorldQuery::try_iter].
+    pub fn query_data<Q: QueryData>(
+        &mut self,
+    ) -> GetResult<impl Iterator<Item = Q::Item<'_>> + '_> {
+        Q::Fields::check_access(self)?;
+
+        let items = WorldQuery::<Q::Fields>::new(self)
+            .into_try_items()
+            .filter_map(|(_, fields)| fields.ok().map(Q::from_fields));
+
+        Ok(items)
+    }
+
+    /// Calls `f` with the params `Q` of every row matched by `query`.
+    ///
+    /// Rows are processed table by table, the params are created from the row index
+    /// without looking up each id. `f` is `Fn` so rows can be processed in parallel
+    /// in the future.
+    ///
+    /// Returns the error of the first row for which `Q` can't be created, e.g. if it
+    /// accesses a component mutably more than once. The rows after it are not processed.
+    pub fn batch_query_execute<Q, F>(&mut self, mut query: QueryPlan, f: F) -> GetResult<()>
+    where
+        Q: Params,
+        F: Fn(Id, Q::ParamsType<'_>),
+    {
+        // Rows of tables without per-row terms are all matched and aren't collected.
+        let mut tables = vec![];
+
+        query.for_each_table(self, |view| {
+            let rows = view
+                .has_row_terms()
+                .then(|| view.rows().map(|(row, _)| row).collect::<Vec<_>>());
+            tables.push((view.table_id(), rows));
+        });
+
+        let world: UnsafeWorldPtr<'_> = self.into();
+
+        for (table, rows) in tables {
+            // SAFETY: Only the ids of the table are read.
+            let ids = unsafe { world.world() }.table_index[table].id_data.ids();
+
+            let mut run = |row: usize| {
+                let id = ids[row];
+
+                // SAFETY:
+                // - We hold the exclusive borrow of the world.
+                // - The row was matched in the table, so it's the location of the id.
+                // - Each row is visited once, so params of different rows don't alias.
+                let params = unsafe { Q::create_at(world, id, IdLocation { table, row }) }?;
+                f(id, params);
+                GetResult::Ok(())
+            };
+
+            match rows {
+                Some(rows) => rows.into_iter().try_for_each(&mut run)?,
+                None => (0..ids.len()).try_for_each(&mut run)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a query and returns a snapshot of the matching ids.
+    ///
+    /// The ids can be held across mutations, but may go stale.
+    pub fn collect<A>(&self, select: SelectStmt<A>, with: WithStmt) -> Vec<Id> {
+        QueryPlan::new(select, with).matching_ids(self)
+    }
+
+    /// Iterates the ids and values of the sparse component `T`, without touching the tables.
+    ///
+    /// Yields nothing if `T` is not registered or is not stored in a sparse set.
+    pub fn query_sparse_only<T: TypedId>(&self) -> SparseQueryIter<'_, T>
+    where
+        T::Data: DataComponent,
+    {
+        let set = T::id(self)
+            .ok()
+            .and_then(|comp| self.components.get(comp))
+            .and_then(|ci| match &ci.storage {
+                Storage::SparseData(set) => Some(set),
+                _ => None,
+            });
+
+        SparseQueryIter::new(self, set)
+    }
+
+    /// Checks if `id` has the component.
+    pub fn has<T: TypedId>(&self, id: Id) -> bool {
+        T::id(self).is_ok_and(|comp| has_component(self, id, comp))
+    }
+
+    #[inline(always)]
+    pub fn set_id<T>(&mut self, id: Id, comp: impl IntoId, val: T) -> Option<T>
+    where
+        T: DataComponent,
+    {
+        if !comp.validate(self) {
+            return None;
+        }
+
+        try_set_component(self, id, comp.into_id(), val)
+            .ok()
+            .flatten()
+    }
+
+    #[inline]
+    pub fn set<T: TypedId>(&mut self, id: Id, val: T::Data) -> Option<T::Data>
+    where
+        T::Data: DataComponent,
+    {
+        // SAFETY:
+        // The component id is obtained from the type, so the data type matches.
+        unsafe { set_component(self, id, T::id(self).ok()?, val) }
+            .ok()
+            .flatten()
+    }
+
+    /// Gets a guard to the component `T` of `id` that calls the on_set hook when
+    /// dropped after a mutable access.
+    ///
+    /// Returns `None` if `id` doesn't have the component.
+    pub fn component_mut<T: TypedId>(&mut self, id: Id) -> Option<CompMut<'_, T>>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self).ok()?;
+        // The guard holds the exclusive borrow of the world, so the value can't move.
+        let ptr = get_component_ptr(self, id, comp)?.as_ptr();
+        let ti = Rc::clone(component_type_info(self, comp)?);
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        Some(unsafe { CompMut::new(self, id, comp, ptr, ti) })
+    }
+
+    /// Sets the singleton `T`, stored on the id of the component itself.
+    ///
+    /// Queries read it with [WithStmt::singleton]. Returns the old value if it was set.
+    pub fn set_singleton<T>(&mut self, val: T) -> EcsResult<Option<T>>
+    where
+        T: ComponentId + DataComponent,
+    {
+        let comp = <T as ComponentId>::id(self)?;
+        try_set_component(self, comp, comp, val)
+    }
+
+    /// Gets the singleton `T`, see [World::set_singleton].
+    pub fn singleton<T>(&self) -> Option<&T>
+    where
+        T: ComponentId + DataComponent,
+    {
+        let comp = <T as ComponentId>::id(self).ok()?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        unsafe { get_component(self, comp, comp) }.ok()
+    }
+
+    /// Gets a guard to the singleton `T`, see [World::component_mut].
+    pub fn singleton_mut<T>(&mut self) -> Option<CompMut<'_, T>>
+    where
+        T: ComponentId + DataComponent,
+    {
+        let comp = <T as ComponentId>::id(self).ok()?;
+        self.component_mut::<T>(comp)
+    }
+
+    /// Creates a union of the components `variants`, of which ids hold at most one at a time.
+    ///
+    /// Setting a variant, with [World::set_union] or any other write, removes the other
+    /// variants and adds the returned union tag, so queries can match all variants at once.
+    /// The tag is removed with the last variant.
+    ///
+    /// Returns an error if:
+    /// - `variants` is empty or lists a component more than once.
+    /// - A variant is not a component with data.
+    /// - A variant is already a variant of another union.
+    pub fn register_union(&mut self, variants: &[Id]) -> EcsResult<Id> {
+        if variants.is_empty() {
+            return Err(EcsError::EmptyUnion);
+        }
+
+        for (i, &variant) in variants.iter().enumerate() {
+            if !self.components.contains(variant) {
+                return Err(EcsError::IdNotComponent(variant));
+            }
+
+            if component_type_info(self, variant).is_none() {
+                return Err(EcsError::IsTag(variant));
+            }
+
+            if variants[..i].contains(&variant) {
+                return Err(EcsError::DuplicateUnionVariant(variant));
+            }
+
+            if let Some(&union) = self.variant_unions.get(variant) {
+                return Err(EcsError::VariantOfOtherUnion(variant, union));
+            }
+        }
+
+        let union = self.new_component(TagBuilder::new().add_flags(ComponentFlags::UNION));
+        self.unions.insert(union, variants.into());
+
+        for &variant in variants {
+            self.variant_unions.insert(variant, union);
+        }
+
+        Ok(union)
+    }
+
+    /// Sets `T` as the active variant of `union` for `id`, dropping the value of
+    /// the previously active variant.
+    ///
+    /// Same as [World::set], after checking that `T` is a variant of `union`.
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - `union` is not a union or `T` is not one of its variants.
+    pub fn set_union<T: TypedId>(&mut self, id: Id, union: Id, val: T::Data) -> EcsResult<()>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self)?;
+        set_union(self, id, union, comp, val)
+    }
+
+    /// Gets the value of `T` for `id`, only if it's the active variant of `union`.
+    pub fn get_union<T: TypedId>(&self, id: Id, union: Id) -> Option<&T::Data>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self).ok()?;
+
+        if !self.unions.get(union)?.contains(&comp) {
+            return None;
+        }
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        unsafe { get_component(self, id, comp) }.ok()
+    }
+
+    /// Sets all components of `list` for `id`, moving it to its final table at most once.
+    ///
+    /// The whole list is validated before anything is written, so on error `id` is unchanged.
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - a component is not registered, is a tag or has a different data type.
+    /// - a component appears more than once in the list.
+    pub fn set_many(&mut self, id: Id, list: impl ComponentWriteList) -> EcsResult<()> {
+        fn resolve<L: ComponentWriteList>(_: &L, world: &World) -> EcsResult<Vec<Id>> {
+            let mut comps = vec![];
+            L::resolve(world, &mut comps)?;
+            Ok(comps)
+        }
+
+        self.id_manager.get_location(id)?;
+        let comps = resolve(&list, self)?;
+        move_for_writes(self, id, &comps)?;
+
+        // SAFETY:
+        // - comps were resolved from the list.
+        // - id was moved to a table with all table components.
+        unsafe { list.write(self, id, &comps) };
+
+        // Auto-added tags may move the id again, so they're added once all values are written.
+        for comp in comps {
+            add_auto_components(self, id, comp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the component value of `id`, inserting the value returned by `init` if it's missing.
+    ///
+    /// `init` is only called if the component is missing. The guard derefs to `&mut T::Data`.
+    /// It's returned instead of a plain reference so the value is recorded as changed and
+    /// the on_set hook runs once the caller is done writing it, see [CompMut].
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - the component is a tag.
+    #[inline]
+    pub fn get_or_insert_with<T: TypedId>(
+        &mut self,
+        id: Id,
+        init: impl FnOnce() -> T::Data,
+    ) -> EcsResult<CompMut<'_, T>>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self)?;
+
+        // SAFETY:
+        // The component id is obtained from the type, so the data type matches.
+        let (ptr, inserted) = unsafe { get_or_insert_component::<T::Data>(self, id, comp, init) }?;
+        let ti = Rc::clone(component_type_info(self, comp).unwrap());
+
+        // SAFETY: The pointer points to the value of the component, whose type is `T::Data`.
+        Ok(unsafe { CompMut::<T>::new(self, id, comp, ptr, ti) }.written(inserted))
+    }
+
+    /// Gets the component value of `id`, setting it to `val` if it's missing.
+    ///
+    /// An existing value is returned as is, `val` is dropped in that case.
+    /// See [World::get_or_insert_with] to only construct the value when it's missing.
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - the component is a tag.
+    #[inline]
+    pub fn get_or_set<T: TypedId>(&mut self, id: Id, val: T::Data) -> EcsResult<&T::Data>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self)?;
+
+        // SAFETY:
+        // The component id is obtained from the type, so the data type matches.
+        let (ptr, inserted) = unsafe { get_or_insert_component(self, id, comp, || val) }?;
+
+        if inserted {
+            mark_changed(self, id, comp);
+        }
+
+        // SAFETY: The pointer is valid for the world borrow and holds a `T::Data`.
+        Ok(unsafe { ptr.cast::<T::Data>().as_ref() })
+    }
+
+    /// Gets the component value of `id`, inserting the registered default if it's missing.
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - the component is a tag.
+    /// - the component has no default hook.
+    pub fn get_or_default<T: TypedId>(&mut self, id: Id) -> EcsResult<CompMut<'_, T>>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self)?;
+
+        let type_info = match self.components.get(comp) {
+            Some(ci) => ci.type_info.clone().ok_or(EcsError::IsTag(comp))?,
+            None => return Err(EcsError::IdNotComponent(comp)),
+        };
+
+        if type_info.hooks.default.is_none() {
+            return Err(EcsError::NoDefaultHook(comp));
+        }
+
+        // SAFETY:
+        // - The component id is obtained from the type, so the data type matches.
+        // - We just checked that the default hook exists.
+        let (ptr, inserted) = unsafe {
+            get_or_insert_component::<T::Data>(self, id, comp, || {
+                type_info.new_default::<T::Data>().unwrap()
+            })
+        }?;
+
+        // SAFETY: The pointer points to the value of the component, whose type is `T::Data`.
+        Ok(unsafe { CompMut::<T>::new(self, id, comp, ptr, type_info) }.written(inserted))
+    }
+
+    /// Adds the data component `T` to `id`, initialized with its default hook.
+    ///
+    /// No side effect if `id` already has the component.
+    ///
+    /// Returns an error if:
+    /// - `id` is not alive.
+    /// - the component is a tag.
+    /// - the component has no default hook.
+    #[inline]
+    pub fn add_default<T: TypedId>(&mut self, id: Id) -> EcsResult<()>
+    where
+        T::Data: DataComponent,
+    {
+        self.get_or_default::<T>(id).map(|_| ())
+    }
+
+    /// Gets the component `C` of `entity`, or inherits it through the transitive relationship `Rel`.
+    ///
+    /// If `entity` doesn't have `C`, its `(Rel, target)` pairs are followed for at most
+    /// `max_depth` hops and the value of the closest target with `C` is returned.
+    /// Only `entity` itself is checked if `Rel` is not [transitive](crate::flags::ComponentFlags::TRANSITIVE).
+    pub fn get_transitive<Rel: TypedId, C: TypedId>(
+        &self,
+        entity: Id,
+        max_depth: usize,
+    ) -> Option<&C::Data>
+    where
+        C::Data: DataComponent,
+    {
+        let rel = Rel::id(self).ok()?;
+        let comp = C::id(self).ok()?;
+        let ptr = find_transitive(self, entity, rel, comp, max_depth)?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        Some(unsafe { ptr.deref::<C::Data>() })
+    }
+
+    /// Sets the value of the typed pair `(R, T)` for `id`.
+    ///
+    /// The data type is `R` if it holds data, otherwise `T`.
+    /// This is the checked equivalent of `world.set::<(R, T)>(id, val)`.
+    ///
+    /// Returns the old value if `id` already had the pair.
+    pub fn set_pair<R, T>(
+        &mut self,
+        id: Id,
+        val: <(R, T) as TypedId>::Data,
+    ) -> EcsResult<Option<<(R, T) as TypedId>::Data>>
+    where
+        (R, T): TypedId,
+        <(R, T) as TypedId>::Data: DataComponent,
+    {
+        let comp = <(R, T)>::id(self)?;
+        try_set_component(self, id, comp, val)
+    }
+
+    /// Sets the value of the pair `(R, tgt)` for `id`, where `R` holds the data.
+    ///
+    /// Returns an error if:
+    /// - `id` or `tgt` is not alive.
+    /// - `tgt` can't be used as a pair target.
+    /// - the pair was already created as a tag.
+    pub fn set_pair_target<R>(&mut self, id: Id, tgt: Id, val: R) -> EcsResult<Option<R>>
+    where
+        R: ComponentId + DataComponent,
+    {
+        let comp = checked_pair(self, <R as ComponentId>::id(self)?, tgt)?;
+        try_set_component(self, id, comp, val)
+    }
+
+    /// Gets the value of the typed pair `(R, T)` for `id`.
+    pub fn get_pair<R, T>(&self, id: Id) -> GetResult<&<(R, T) as TypedId>::Data>
+    where
+        (R, T): TypedId,
+        <(R, T) as TypedId>::Data: DataComponent,
+    {
+        let comp = <(R, T)>::id(self)?;
+
+        // SAFETY:
+        // The component id is obtained from the type, so the data type matches.
+        unsafe { get_component(self, id, comp) }
+    }
+
+    /// Gets the value of the pair `(R, tgt)` for `id`, where `R` holds the data.
+    pub fn get_pair_target<R>(&self, id: Id, tgt: Id) -> GetResult<&R>
+    where
+        R: ComponentId + DataComponent,
+    {
+        let comp = match checked_pair(self, <R as ComponentId>::id(self)?, tgt) {
+            Ok(comp) => comp,
+            Err(_) => return Err(GetError::InvalidId(InvalidId(tgt))),
+        };
+
+        // SAFETY:
+        // The relationship holds the data, so the data type of the pair is `R`.
+        unsafe { get_component(self, id, comp) }
+    }
+
+    /// Checks if `id` has the pair `(R, tgt)`.
+    pub fn has_pair_target<R: ComponentId>(&self, id: Id, tgt: Id) -> bool {
+        <R as ComponentId>::id(self)
+            .ok()
+            .and_then(|rel| checked_pair(self, rel, tgt).ok())
+            .is_some_and(|comp| has_component(self, id, comp))
+    }
+
+    /// Returns the `(rel, *)` pairs of `id`.
+    ///
+    /// Pairs of a sparse relationship are read from its single relation store.
+    pub fn pairs_of(&self, id: Id, rel: Id) -> EcsResult<Vec<Id>> {
+        pairs_of(self, id, rel)
+    }
+
+    /// Returns a read-only handle to the world.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn as_ref(&self) -> WorldRef<'_> {
+        WorldRef::new(self)
+    }
+
+    #[inline(always)]
+    pub fn is_alive(&self, entity: Id) -> bool {
+        self.id_manager.is_alive(entity)
+    }
+
+    /// Checks if the index of `id` was ever used, even if `id` is a stale generation.
+    #[inline]
+    pub fn exists(&self, id: Id) -> bool {
+        self.id_manager.exists(id)
+    }
+
+    /// Releases id storage above the highest alive id, see [IdManager::compact].
+    #[inline]
+    pub fn compact_ids(&mut self) {
+        self.id_manager.compact();
+    }
+
+    /// Frees the memory of unused table rows, e.g. after despawning many ids.
+    pub fn compact_tables(&mut self) {
+        for table in self.table_index.all_tables_mut() {
+            table.id_data.compact();
+        }
+    }
+
+    /// Returns the number of rows allocated but unused by a table.
+    ///
+    /// Returns `None` if the table doesn't exist.
+    pub fn table_excess_capacity(&self, table: TableId) -> Option<usize> {
+        self.table_index
+            .get(table)
+            .map(|table| table.id_data.excess_capacity())
+    }
+
+    /// Returns the arena for temporary allocations that live until [World::end_frame].
+    #[inline]
+    pub fn frame_arena(&self) -> &FrameArena {
+        &self.frame_arena
+    }
+
+    /// Ends the current frame, releasing all allocations from the frame arena.
+    pub fn end_frame(&mut self) {
+        self.frame_arena.reset();
+    }
+
+    /// Exports the registration schema of all components, without any entity data.
+    pub fn export_schema(&self) -> Schema {
+        Schema::of(self)
+    }
+
+    /// Lists how the registered components differ from a saved schema.
+    ///
+    /// An empty list means data saved with that schema can be loaded safely.
+    pub fn check_schema_compat(&self, saved: &Schema) -> Vec<SchemaMismatch> {
+        saved.mismatches(self)
+    }
+
+    /// Moves all ids of `other` into this world under new ids.
+    ///
+    /// Returns the mapping from ids of `other` to ids of this world, to fix up ids stored
+    /// in component values. Pairs are remapped automatically, components are matched
+    /// by registered type, then by name.
+    ///
+    /// Returns an error without merging if an id of `other` has a guid already used here.
+    pub fn merge_into(&mut self, other: World) -> EcsResult<HashMap<Id, Id>> {
+        merge_world(self, other)
+    }
+
+    /// Moves all ids of `other` into this world like [World::merge_into],
+    /// recording the mapping in `id_remap`.
+    ///
+    /// Values are moved rather than cloned since `other` is consumed, so components
+    /// don't need a clone hook.
+    pub fn merge(&mut self, other: World, id_remap: &mut HashMap<Id, Id>) -> EcsResult<()> {
+        id_remap.extend(merge_world(self, other)?);
+        Ok(())
+    }
+
+    /// Creates a copy of the world with the same ids and generations, tables and values,
+    /// e.g. to roll a simulation back.
+    ///
+    /// Values are cloned with their clone hook, or copied as raw bytes if their type is
+    /// [plain old data](ComponentBuilder::pod).
+    /// Observers, the recorder and [World::with_auto_component] scopes are not copied.
+    ///
+    /// Returns an error if a component has values and neither a clone hook nor pod.
+    pub fn deep_clone(&self) -> EcsResult<World> {
+        deep_clone(self)
+    }
+
+    /// Reorders the rows of tables fragmented by removals to follow id order,
+    /// moving at most `budget_rows` rows so the work can be spread across frames.
+    ///
+    /// Only the order of rows changes. Returns the number of rows moved.
+    pub fn defragment(&mut self, budget_rows: usize) -> usize {
+        defragment(self, budget_rows)
+    }
+
+    /// Appends the components of `id` to `buf` and returns the number of bytes written.
+    ///
+    /// Only named components are written. Values are written by their serialize hook,
+    /// or copied as raw bytes if their type is [plain old data](ComponentBuilder::pod).
+    /// Other values and pairs are not written. Returns an error if a length doesn't fit
+    /// the format, nothing is written then.
+    pub fn serialize_entity(&self, id: Id, buf: &mut Vec<u8>) -> EcsResult<usize> {
+        serialize_entity(self, id, buf)
+    }
+
+    /// Creates an id with the components written by [World::serialize_entity].
+    ///
+    /// Components are looked up by name. Values written with an older layout version
+    /// are converted by the migration registered with [ComponentBuilder::migrate_from].
+    /// Data written before layout versions were added is read as version 0.
+    /// Returns an error if a component is unknown, has no migration from the written
+    /// version, or the data is malformed, in which case no id is created.
+    pub fn deserialize_entity(&mut self, buf: &[u8]) -> EcsResult<Id> {
+        deserialize_entity(self, buf)
+    }
+
+    /// Splits the world into views of its ids, component records and tables.
+    ///
+    /// The views lock their storage on each access, so they can be shared with scoped
+    /// threads. See [world_split](crate::world_split) for the lock order.
+    pub fn split(&mut self) -> (IdView<'_>, ComponentView<'_>, TablesView<'_>) {
+        split(self)
+    }
+
+    /// Returns a read-only handle to `id`, or `None` if it's not alive.
+    #[inline]
+    pub fn entity(&self, id: Id) -> Option<EntityRef<'_>> {
+        let loc = self.id_manager.get_location(id).ok()?;
+        Some(EntityRef::new(self, id, loc))
+    }
+
+    /// Returns a view of `id` that formats its components with `{:?}`.
+    #[inline]
+    pub fn debug_entity(&self, id: Id) -> DebugEntity<'_> {
+        DebugEntity::new(self, id)
+    }
+
+    /// Returns the first id whose value of `T` equals `value`, compared with the eq hook.
+    ///
+    /// Scans all values of the component, so it's only meant for small sets.
+    /// Returns `None` if `T` has no eq hook,
+    /// see [ComponentBuilder::eq](crate::component::ComponentBuilder::eq).
+    pub fn find_with_value<T: TypedId>(&self, value: &T::Data) -> Option<Id>
+    where
+        T::Data: DataComponent,
+    {
+        let comp = T::id(self).ok()?;
+
+        // SAFETY: The component id is obtained from the type, so the data type matches.
+        unsafe { find_with_value(self, comp, NonNull::from(value).cast()) }
+    }
+
+    /// Returns the processing priority of a component,
+    /// see [ComponentBuilder::priority](crate::component::ComponentBuilder::priority).
+    ///
+    /// Returns `None` if `comp` is not a component.
+    pub fn component_priority(&self, comp: Id) -> Option<i32> {
+        // Pairs stored together have no component of their own.
+        let comp = stored_relation(self, comp).unwrap_or(comp);
+        self.components.get(comp).map(|ci| ci.priority)
+    }
+
+    /// Changes the processing priority of a component after registration.
+    pub fn set_component_priority(&mut self, comp: Id, priority: i32) -> EcsResult<()> {
+        match self.components.get_mut(comp) {
+            Some(ci) => {
+                ci.priority = priority;
+                Ok(())
+            }
+            None => Err(EcsError::IdNotComponent(comp)),
+        }
+    }
+
+    /// Returns storage usage and add/remove counts of a component.
+    ///
+    /// Returns `None` if `comp` is not a component.
+    pub fn component_stats(&self, comp: Id) -> Option<ComponentStats> {
+        ComponentStats::of(self, comp)
+    }
+
+    /// Moves every value of `T` to a storage of type `to`, e.g. after profiling shows
+    /// a sparse component is better stored in tables.
+    ///
+    /// Tables are created as needed, values are moved without being cloned.
+    /// Returns an error if `T` is a relationship.
+    pub fn migrate_storage<T: TypedId>(&mut self, to: StorageType) -> EcsResult<()> {
+        let comp = T::id(self)?;
+        migrate_storage(self, comp, to)
+    }
+
+    /// Returns the number of ids with a component and the bytes used to store it.
+    ///
+    /// Returns `None` if `comp` is not a component.
+    pub fn component_storage_stats(&self, comp: Id) -> Option<ComponentStorageStats> {
+        ComponentStorageStats::of(self, comp)
+    }
+
+    /// Estimates the bytes allocated by the world, by kind of storage.
+    ///
+    /// Memory owned by component values (e.g. the buffer of a `Vec`) is not counted.
+    pub fn total_memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::of(self)
+    }
+
+    /// Suggests a storage type for a component from its [World::component_stats].
+    ///
+    /// Returns `None` if `comp` is not a component.
+    pub fn suggest_storage(&self, comp: Id) -> Option<StorageSuggestion> {
+        self.component_stats(comp)
+            .map(|stats| StorageSuggestion::of(&stats))
+    }
+
+    /// Returns a readable listing of all tables with their row count and column sizes.
+    ///
+    /// Tables are sorted by memory usage, largest first.
+    pub fn dump_tables(&self) -> String {
+        let mut tables: Vec<_> = self
+            .table_index
+            .all_tables()
+            .iter()
+            .map(|table| {
+                let columns = table.id_data.columns();
+                let bytes = columns.iter().map(|col| col.byte_size()).sum::<usize>();
+                (bytes, table)
+            })
+            .collect();
+
+        tables.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let mut out = String::new();
+
+        for (bytes, table) in tables {
+            let _ = writeln!(
+                out,
+                "{} {}: {} rows, {} bytes",
+                table.id,
+                table.debug_name(self),
+                table.id_data.row_count(),
+                bytes
+            );
+
+            for col in table.id_data.columns() {
+                let _ = writeln!(
+                    out,
+                    "  {}: {} bytes",
+                    id_name(self, *col.id()),
+                    col.byte_size()
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Checks the internal consistency of the world and returns every violation found.
+    ///
+    /// This walks every id, table and sparse storage, so it's expensive.
+    pub fn check_invariants(&self) -> Vec<InvariantViolation> {
+        check_invariants(self)
+    }
+
+    /// Returns `(entity, pair, target)` for every pair whose target is not alive.
+    ///
+    /// Useful to find dangling references after loading a world.
+    pub fn validate_relationships(&self) -> Vec<(Id, Id, Id)> {
+        dangling_relationships(self)
+    }
+
+    /// Panics if the world is not internally consistent.
+    ///
+    /// Only checks in debug builds, see [World::check_invariants].
+    #[track_caller]
+    pub fn assert_invariants(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let violations = self.check_invariants();
+
+            if !violations.is_empty() {
+                let report = violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                panic!("world invariants violated:\n{report}");
+            }
+        }
+    }
+
+    /// Records every structural change of the world to `sink`, replacing the previous sink.
+    ///
+    /// Values written through references from [World::get] are not recorded,
+    /// use [World::component_mut] to record them.
+    pub fn enable_recording(&mut self, sink: impl EventSink + 'static) {
+        self.recorder = Some(Box::new(sink));
+    }
+
+    /// Stops recording and returns the sink, if any.
+    pub fn disable_recording(&mut self) -> Option<Box<dyn EventSink>> {
+        self.recorder.take()
+    }
+
+    /// Stops queries from checking the data of each table they match.
+    ///
+    /// The checks only run in debug builds and are on by default. Disabling them
+    /// speeds up hot loops in trusted code without changing the matched rows.
+    pub fn disable_query_validation(&mut self) {
+        self.query_validation = false;
+    }
+
+    /// Turns the checks disabled by [World::disable_query_validation] back on.
+    pub fn enable_query_validation(&mut self) {
+        self.query_validation = true;
+    }
+
+    /// Counts the ids moved between tables, so [World::component_stats] reports adds
+    /// and removes of table components.
+    ///
+    /// Off by default, since each move then updates a per-table map. Despawns and
+    /// changes of sparse components are always counted.
+    pub fn enable_move_stats(&mut self) {
+        self.move_stats = true;
+    }
+
+    /// Stops counting the moves enabled by [World::enable_move_stats], keeping the counts.
+    pub fn disable_move_stats(&mut self) {
+        self.move_stats = false;
+    }
+
+    /// Runs `f` as a transaction: if it returns an error or panics, every change it
+    /// made through the [TxWorld] is undone in reverse order.
+    ///
+    /// Nested transactions are not supported.
+    pub fn transaction(&mut self, f: impl FnOnce(&mut TxWorld) -> EcsResult<()>) -> EcsResult<()> {
+        let mut tx = TxWorld::new(self);
+
+        // On error, dropping the transaction undoes its changes.
+        f(&mut tx)?;
+        tx.commit();
+        Ok(())
+    }
+
+    /// Applies recorded events to the world.
+    ///
+    /// Components are resolved by id, so they must be registered in the same order as in
+    /// the recorded world. Ids spawned by the events are mapped to new ids.
+    pub fn replay(&mut self, events: impl IntoIterator<Item = Event>) -> EcsResult<()> {
+        replay_events(self, events)
+    }
+
+    /// Sets the [Guid] of `id` and indexes it, replacing its previous guid.
+    ///
+    /// Returns an error if another id already has the guid.
+    pub fn set_guid(&mut self, id: Id, guid: u128) -> EcsResult<()> {
+        set_guid(self, id, guid)
+    }
+
+    /// Returns the id with the guid, if any.
+    pub fn by_guid(&self, guid: u128) -> Option<Id> {
+        let id = *self.guids.get(&guid)?;
+        (guid_of(self, id) == Some(guid)).then_some(id)
+    }
+
+    /// Returns the guid of `id`, if it has one.
+    #[inline]
+    pub fn guid_of(&self, id: Id) -> Option<u128> {
+        guid_of(self, id)
+    }
+
+    /// Creates a new id with the guid.
+    ///
+    /// Returns an error without creating the id if another id already has the guid.
+    pub fn spawn_with_guid(&mut self, guid: u128) -> EcsResult<Id> {
+        if self.by_guid(guid).is_some() {
+            return Err(EcsError::GuidCollision(guid));
+        }
+
+        let id = self.new_id();
+        set_guid(self, id, guid)?;
+        Ok(id)
+    }
+
+    /// Calls `f` and sets a clone of `val` as the component `T` of every id it creates.
+    ///
+    /// Scopes can be nested, ids get the components of all enclosing scopes.
+    pub fn with_auto_component<T: TypedId, R>(
+        &mut self,
+        val: T::Data,
+        f: impl FnOnce(&mut World) -> R,
+    ) -> EcsResult<R>
+    where
+        T::Data: DataComponent + Clone,
+    {
+        let comp = T::id(self)?;
+
+        let write = move |world: &mut World, id| {
+            // SAFETY: The component id is obtained from the type, so the data type matches.
+            drop(unsafe { set_component(world, id, comp, val.clone()) });
+        };
+
+        Ok(self.auto_component_scope(comp, AutoComponent::Data(Box::new(write)), f))
+    }
+
+    /// Calls `f` and adds the tag `T` to every id it creates.
+    ///
+    /// Scopes can be nested, ids get the tags of all enclosing scopes.
+    pub fn with_auto_tag<T: TypedId + TagComponent, R>(
+        &mut self,
+        f: impl FnOnce(&mut World) -> R,
+    ) -> EcsResult<R> {
+        let comp = T::id(self)?;
+        Ok(self.auto_component_scope(comp, AutoComponent::Tag, f))
+    }
+
+    fn auto_component_scope<R>(
+        &mut self,
+        comp: Id,
+        auto: AutoComponent,
+        f: impl FnOnce(&mut World) -> R,
+    ) -> R {
+        /// Pops the scope when dropped, even if `f` panics.
+        struct ScopeGuard<'w> {
+            world: &'w mut World,
+            depth: usize,
+        }
+
+        impl Drop for ScopeGuard<'_> {
+            fn drop(&mut self) {
+                self.world.auto_components.truncate(self.depth - 1);
+            }
+        }
+
+        self.auto_components.push((comp, auto));
+        let depth = self.auto_components.len();
+        let guard = ScopeGuard { world: self, depth };
+
+        f(guard.world)
+    }
+
+    /// Registers a listener for events of type `E`.
+    ///
+    /// Listeners receive the id holding the event and the event data,
+    /// and are called in registration order.
+    pub fn on_event<E: TypedId>(
+        &mut self,
+        f: impl Fn(&World, Id, &E::Data) + 'static,
+    ) -> EcsResult<()>
+    where
+        E::Data: DataComponent,
+    {
+        let event = E::id(self)?;
+
+        self.observers.register(
+            event,
+            Box::new(move |world, id, ptr| {
+                // SAFETY: Listeners for this event are only called with `E::Data`.
+                f(world, id, unsafe { ptr.cast::<E::Data>().as_ref() })
+            }),
+        );
+
+        Ok(())
+    }
+
+    /// Registers a callback called with the id and signature of every table created
+    /// from now on, e.g. to keep caches of tables in sync.
+    ///
+    /// Tables are never deleted, so a table id stays valid once it was passed to `on_create`.
+    pub fn observe_tables(&mut self, on_create: impl FnMut(TableId, &Signature) + 'static) {
+        self.observers.tables.push(Box::new(on_create));
+    }
+
+    /// Registers a batched observer for `kind` events on the components `comps`.
+    ///
+    /// Instead of being called on every write, `f` is called by [World::dispatch_events]
+    /// once for each id and component written since the last dispatch.
+    pub fn observe_batched(
+        &mut self,
+        kind: EventKind,
+        comps: &[Id],
+        f: impl Fn(&World, Id, Id) + 'static,
+    ) {
+        match kind {
+            EventKind::OnSet => self.observers.batched.push((comps.to_vec(), Box::new(f))),
+        }
+    }
+
+    /// Calls the batched observers once for each id and component written since the
+    /// last dispatch, ordered by id index then by [World::component_priority], higher first.
+    /// Then updates the watches, see [World::watch].
+    ///
+    /// Components removed in the meantime, and ids despawned, are skipped.
+    pub fn dispatch_events(&mut self) {
+        let mut touched = self.observers.take_touched();
+
+        touched.sort_by_cached_key(|&(id, comp)| {
+            let priority = self.component_priority(comp).unwrap_or(0);
+            (id.index(), std::cmp::Reverse(priority), comp)
+        });
+
+        for (id, comp) in touched {
+            if !has_component(self, id, comp) {
+                continue;
+            }
+
+            for (comps, listener) in &self.observers.batched {
+                if comps.contains(&comp) {
+                    listener(self, id, comp);
+                }
+            }
+        }
+
+        update_watches(self);
+    }
+
+    /// Watches the ids matching `with`, see [WatchHandle].
+    ///
+    /// [World::dispatch_events] pushes the ids that entered or left the query since the
+    /// previous dispatch, ids already matching are reported as entered by the first one.
+    pub fn watch(&mut self, with: WithStmt) -> WatchHandle {
+        watch(self, with)
+    }
+
+    /// Emits an event to all listeners registered with [World::on_event].
+    ///
+    /// The event is set as a component on the world's event entity
+    /// while listeners run, and is removed once all of them have been called.
+    /// Listeners only get shared access to the world, so events emitted
+    /// one after another are delivered sequentially in emission order.
+    pub fn emit<E: TypedId>(&mut self, event: E::Data) -> EcsResult<()>
+    where
+        E::Data: DataComponent,
+    {
+        let comp = E::id(self)?;
+        let entity = self.event_entity;
+
+        // SAFETY:
+        // The component id is obtained from the type, so the data type matches.
+        unsafe { set_component(self, entity, comp, event) }?;
+
+        if let Some(ptr) = get_component_ptr(self, entity, comp) {
+            for listener in self.observers.listeners(comp) {
+                listener(self, entity, ptr.as_ptr());
+            }
+        }
+
+        remove_component(self, entity, comp)
+    }
+}
+
+/// Access to a [World] in the fixup of [World::reparent].
+///
+/// Values of the reparented id can be changed, but nothing can be added or removed.
+pub struct ReparentView<'w> {
+    world: &'w mut World,
+    child: Id,
+}
+
+impl ReparentView<'_> {
+    /// Gives read access to the world, e.g. to get the transforms of both parents.
+    #[inline]
+    pub fn world(&self) -> &World {
+        self.world
+    }
+
+    /// Gets a guard to the component `T` of the reparented id, see [World::component_mut].
+    #[inline]
+    pub fn get_mut<T: TypedId>(&mut self) -> Option<CompMut<'_, T>>
+    where
+        T::Data: DataComponent,
+    {
+        self.world.component_mut::<T>(self.child)
+    }
+}
+
+const fn assert_immutable<T: Params>() {
+    assert!(
+        T::ALL_IMMUTABLE,
+        "immutable World ref requires all Params to be immutable"
+    )
+}
+
+pub trait WorldGet<'a> {
+    fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'a>>;
+}
+
+pub trait WorldMap<'a, Ret> {
+    fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'a>) -> Ret) -> GetResult<Ret>;
+}
+
+impl<'a> WorldGet<'a> for &'a World {
+    #[inline]
+    fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'a>> {
+        const { assert_immutable::<T>() };
+
+        // SAFETY: All params are immutable.
+        unsafe { T::create(self.into(), id) }
+    }
+}
+
+impl<'a, Ret> WorldMap<'a, Ret> for &'a World {
+    #[inline]
+    fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'a>) -> Ret) -> GetResult<Ret> {
+        const { assert_immutable::<T>() };
+
+        // SAFETY: All params are immutable.
+        unsafe { T::create(self.into(), id).map(f) }
     }
 }
 
 impl<'a> WorldGet<'a> for &'a mut World {
     #[inline]
     fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'a>> {
-        T::create(self, id)
+        // SAFETY: We have exclusive access to the world.
+        unsafe { T::create(self.into(), id) }
     }
 }
 
 impl<'a, Ret> WorldMap<'a, Ret> for &'a mut World {
     #[inline]
     fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'a>) -> Ret) -> GetResult<Ret> {
-        T::create(self, id).map(f)
+        // SAFETY: We have exclusive access to the world.
+        unsafe { T::create(self.into(), id).map(f) }
     }
 }
@@ -0,0 +1,145 @@
+use crate::{
+    error::{GetResult, UnregisteredTypeErr},
+    get_params::Params,
+    id::{Id, IntoId},
+    query::{Context, QueryPlan, ReadOnly, SelectStmt, SparseQueryIter, WithStmt},
+    schema::Schema,
+    type_traits::{DataComponent, TypedId},
+    world::{World, WorldGet, WorldMap},
+    world_utils::id_name,
+};
+
+/// Read-only handle to a [World].
+///
+/// Only exposes methods that can't mutate the world, so functions taking a `WorldRef`
+/// can only read. Queries built from it only accept read selects.
+#[derive(Clone, Copy)]
+pub struct WorldRef<'w> {
+    world: &'w World,
+}
+
+impl<'w> WorldRef<'w> {
+    #[inline]
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self { world }
+    }
+
+    #[inline]
+    pub fn id<T: TypedId>(self) -> Result<Id, UnregisteredTypeErr> {
+        self.world.id::<T>()
+    }
+
+    #[inline]
+    pub fn is_alive(self, id: Id) -> bool {
+        self.world.is_alive(id)
+    }
+
+    /// See [World::exists].
+    #[inline]
+    pub fn exists(self, id: Id) -> bool {
+        self.world.exists(id)
+    }
+
+    /// Checks if `id` has the component.
+    #[inline]
+    pub fn has<T: TypedId>(self, id: Id) -> bool {
+        self.world.has::<T>(id)
+    }
+
+    /// Checks if `id` has the component.
+    #[inline]
+    pub fn has_id(self, id: Id, comp: impl IntoId) -> bool {
+        self.world.has_id(id, comp)
+    }
+
+    /// Gets a reference to the component `T` of `id`.
+    #[inline]
+    pub fn get_ref<T>(self, id: Id) -> GetResult<&'w T::Data>
+    where
+        T: TypedId + DataComponent,
+        T::Data: DataComponent,
+    {
+        self.get::<&T>(id)
+    }
+
+    /// Returns the name of the id, or the id itself if it has none.
+    /// Pairs are formatted as `(Rel, Tgt)`.
+    #[inline]
+    pub fn name(self, id: Id) -> String {
+        id_name(self.world, id)
+    }
+
+    /// Builds a query plan over the matching tables, iterated with [WorldRef::context].
+    pub fn query(self, select: SelectStmt<ReadOnly>, with: WithStmt) -> QueryPlan {
+        let mut plan = QueryPlan::new(select, with);
+        plan.init_tables(self.world);
+        plan
+    }
+
+    #[inline]
+    pub fn context(self) -> Context<'w> {
+        Context::new(self.world)
+    }
+
+    /// See [World::values].
+    #[inline]
+    pub fn values<T: TypedId>(self) -> impl Iterator<Item = (Id, &'w T::Data)>
+    where
+        T::Data: DataComponent,
+    {
+        self.world.values::<T>()
+    }
+
+    /// See [World::query_sparse_only].
+    #[inline]
+    pub fn query_sparse_only<T: TypedId>(self) -> SparseQueryIter<'w, T>
+    where
+        T::Data: DataComponent,
+    {
+        self.world.query_sparse_only::<T>()
+    }
+
+    /// See [World::get_transitive].
+    #[inline]
+    pub fn get_transitive<Rel: TypedId, C: TypedId>(
+        self,
+        id: Id,
+        max_depth: usize,
+    ) -> Option<&'w C::Data>
+    where
+        C::Data: DataComponent,
+    {
+        self.world.get_transitive::<Rel, C>(id, max_depth)
+    }
+
+    #[inline]
+    pub fn change_tick(self) -> u64 {
+        self.world.change_tick()
+    }
+
+    /// See [World::dump_tables].
+    #[inline]
+    pub fn dump_tables(self) -> String {
+        self.world.dump_tables()
+    }
+
+    /// See [World::export_schema].
+    #[inline]
+    pub fn export_schema(self) -> Schema {
+        self.world.export_schema()
+    }
+}
+
+impl<'w> WorldGet<'w> for WorldRef<'w> {
+    #[inline]
+    fn get<T: Params>(self, id: Id) -> GetResult<T::ParamsType<'w>> {
+        self.world.get::<T>(id)
+    }
+}
+
+impl<'w, Ret> WorldMap<'w, Ret> for WorldRef<'w> {
+    #[inline]
+    fn map<T: Params>(self, id: Id, f: impl FnOnce(T::ParamsType<'w>) -> Ret) -> GetResult<Ret> {
+        self.world.map::<T>(id, f)
+    }
+}
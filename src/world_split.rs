@@ -0,0 +1,340 @@
+//! Storages of a [World] behind their own cells, and disjoint views of them.
+//!
+//! The id manager, the component records and the tables each sit in a [StorageCell].
+//! World methods reach them without locking, since writes need `&mut World`. The views
+//! returned by [World::split] lock them instead, so they can be shared with threads.
+//!
+//! # Lock order
+//! Ids, then components, then tables. A view blocks while another thread holds a
+//! conflicting lock, so two threads locking in opposite orders can deadlock. Debug builds
+//! panic when a thread locks a storage while holding a lock on a later one.
+
+use crate::{
+    atomic_refcell::{AtomicRef, AtomicRefCell, AtomicRefMut},
+    component::ComponentInfo,
+    flags::ComponentFlags,
+    id::{Id, IdMap, manager::IdManager},
+    storage::StorageType,
+    table_index::{TableId, TableIndex},
+    world::World,
+};
+use std::ops::{Deref, DerefMut};
+
+/// Position of a storage in the lock order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LockRank {
+    Ids,
+    Components,
+    Tables,
+}
+
+/// A storage of the world, locked by the views of [World::split].
+pub(crate) struct StorageCell<T> {
+    cell: AtomicRefCell<T>,
+    rank: LockRank,
+}
+
+impl<T> StorageCell<T> {
+    pub(crate) fn new(value: T, rank: LockRank) -> Self {
+        Self {
+            cell: AtomicRefCell::new(value),
+            rank,
+        }
+    }
+
+    /// Locks the storage for reads, waiting for writers on other threads.
+    fn read(&self) -> AtomicRef<'_, T> {
+        order::acquire(self.rank, false);
+
+        loop {
+            match self.cell.try_borrow() {
+                Ok(guard) => return guard,
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+    }
+
+    /// Locks the storage for writes, waiting for readers and writers on other threads.
+    fn write(&self) -> AtomicRefMut<'_, T> {
+        order::acquire(self.rank, true);
+
+        loop {
+            match self.cell.try_borrow_mut() {
+                Ok(guard) => return guard,
+                Err(_) => std::thread::yield_now(),
+            }
+        }
+    }
+}
+
+impl<T> Deref for StorageCell<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // SAFETY: Locks are only taken by the views of World::split, which borrow the
+        // world mutably. No view is alive while the world is borrowed to reach this.
+        unsafe { &*self.cell.as_ptr() }
+    }
+}
+
+impl<T> DerefMut for StorageCell<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.cell.get_mut()
+    }
+}
+
+/// Debug checker of the lock order, tracking the locks held by the current thread.
+#[cfg(debug_assertions)]
+mod order {
+    use super::LockRank;
+    use std::cell::Cell;
+
+    const RANKS: [LockRank; 3] = [LockRank::Ids, LockRank::Components, LockRank::Tables];
+
+    thread_local! {
+        /// Readers and writers held on each storage, by rank.
+        static HELD: [Cell<(u32, bool)>; 3] = const { [const { Cell::new((0, false)) }; 3] };
+    }
+
+    pub(super) fn acquire(rank: LockRank, write: bool) {
+        HELD.with(|held| {
+            for (later, cell) in RANKS.iter().zip(held).skip(rank as usize + 1) {
+                if cell.get().0 > 0 {
+                    panic!("lock order violation: {rank:?} locked while holding {later:?}");
+                }
+            }
+
+            let (count, writing) = held[rank as usize].get();
+
+            if count > 0 && (write || writing) {
+                panic!("{rank:?} locked again by the thread that holds it");
+            }
+
+            held[rank as usize].set((count + 1, write));
+        });
+    }
+
+    pub(super) fn release(rank: LockRank) {
+        HELD.with(|held| {
+            let (count, writing) = held[rank as usize].get();
+            held[rank as usize].set((count - 1, writing && count > 1));
+        });
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod order {
+    use super::LockRank;
+
+    #[inline]
+    pub(super) fn acquire(_: LockRank, _: bool) {}
+
+    #[inline]
+    pub(super) fn release(_: LockRank) {}
+}
+
+/// Lock on a storage, released when dropped.
+struct Locked<G> {
+    guard: G,
+    rank: LockRank,
+}
+
+impl<G> Drop for Locked<G> {
+    fn drop(&mut self) {
+        order::release(self.rank);
+    }
+}
+
+/// View of the id manager of a [World], see [World::split].
+pub struct IdView<'w> {
+    ids: &'w StorageCell<IdManager>,
+}
+
+impl IdView<'_> {
+    /// Locks the ids for reads.
+    pub fn read(&self) -> IdsRead<'_> {
+        IdsRead(Locked {
+            guard: self.ids.read(),
+            rank: LockRank::Ids,
+        })
+    }
+}
+
+/// Read lock on the ids, see [IdView::read].
+pub struct IdsRead<'a>(Locked<AtomicRef<'a, IdManager>>);
+
+impl IdsRead<'_> {
+    /// See [World::is_alive].
+    #[inline]
+    pub fn is_alive(&self, id: Id) -> bool {
+        self.0.guard.is_alive(id)
+    }
+
+    /// Returns the current version of `id`, `None` if it's not alive.
+    #[inline]
+    pub fn get_current(&self, id: Id) -> Option<Id> {
+        self.0.guard.get_current(id)
+    }
+
+    /// Returns the number of alive ids.
+    #[inline]
+    pub fn alive_count(&self) -> usize {
+        self.0.guard.alive_count()
+    }
+}
+
+/// View of the component records of a [World], see [World::split].
+pub struct ComponentView<'w> {
+    components: &'w StorageCell<IdMap<ComponentInfo>>,
+}
+
+// SAFETY: The records hold Rc type infos and hooks, which aren't thread safe. The locks
+// only give access to names, flags, priorities and storage types, and never clone or
+// drop an Rc or call a hook.
+unsafe impl Send for ComponentView<'_> {}
+unsafe impl Sync for ComponentView<'_> {}
+
+impl ComponentView<'_> {
+    /// Locks the component records for reads.
+    pub fn read(&self) -> ComponentsRead<'_> {
+        ComponentsRead(Locked {
+            guard: self.components.read(),
+            rank: LockRank::Components,
+        })
+    }
+
+    /// Locks the component records for writes.
+    pub fn write(&self) -> ComponentsWrite<'_> {
+        ComponentsWrite(Locked {
+            guard: self.components.write(),
+            rank: LockRank::Components,
+        })
+    }
+}
+
+/// Read lock on the component records, see [ComponentView::read].
+pub struct ComponentsRead<'a>(Locked<AtomicRef<'a, IdMap<ComponentInfo>>>);
+
+impl ComponentsRead<'_> {
+    /// Returns the number of components.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.guard.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the name of the component, `None` if it's not a component.
+    pub fn name(&self, comp: Id) -> Option<&str> {
+        self.0.guard.get(comp).and_then(|ci| ci.name.as_deref())
+    }
+
+    /// Returns the flags of the component, `None` if it's not a component.
+    pub fn flags(&self, comp: Id) -> Option<ComponentFlags> {
+        self.0.guard.get(comp).map(|ci| ci.flags)
+    }
+
+    /// Returns where the component is stored, `None` if it's not a component.
+    pub fn storage_type(&self, comp: Id) -> Option<StorageType> {
+        self.0.guard.get(comp).map(|ci| ci.storage.get_type())
+    }
+
+    /// See [World::component_priority].
+    pub fn priority(&self, comp: Id) -> Option<i32> {
+        self.0.guard.get(comp).map(|ci| ci.priority)
+    }
+}
+
+/// Write lock on the component records, see [ComponentView::write].
+pub struct ComponentsWrite<'a>(Locked<AtomicRefMut<'a, IdMap<ComponentInfo>>>);
+
+impl ComponentsWrite<'_> {
+    /// Sets the priority of the component, returns `false` if it's not a component.
+    ///
+    /// See [World::component_priority].
+    pub fn set_priority(&mut self, comp: Id, priority: i32) -> bool {
+        match self.0.guard.get_mut(comp) {
+            Some(ci) => {
+                ci.priority = priority;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// View of the tables of a [World], see [World::split].
+///
+/// Not to be confused with [query::TableView](crate::query::TableView), a table matched by a query.
+pub struct TablesView<'w> {
+    tables: &'w StorageCell<TableIndex>,
+}
+
+// SAFETY: Tables hold Rc type infos and allocators, and cache their name in a RefCell.
+// The locks only give access to signatures and row counts, and never clone or drop an
+// Rc, allocate, or touch the cached name.
+unsafe impl Send for TablesView<'_> {}
+unsafe impl Sync for TablesView<'_> {}
+
+impl TablesView<'_> {
+    /// Locks the tables for reads.
+    pub fn read(&self) -> TablesRead<'_> {
+        TablesRead(Locked {
+            guard: self.tables.read(),
+            rank: LockRank::Tables,
+        })
+    }
+}
+
+/// Read lock on the tables, see [TablesView::read].
+pub struct TablesRead<'a>(Locked<AtomicRef<'a, TableIndex>>);
+
+impl TablesRead<'_> {
+    /// Returns the number of tables.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.guard.all_tables().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the ids of all tables.
+    pub fn table_ids(&self) -> impl Iterator<Item = TableId> + '_ {
+        self.0.guard.all_tables().iter().map(|table| table.id)
+    }
+
+    /// Returns the components of the table, `None` if it doesn't exist.
+    pub fn signature(&self, table: TableId) -> Option<&[Id]> {
+        self.0.guard.get(table).map(|table| table.signature.ids())
+    }
+
+    /// Returns the number of ids in the table, `None` if it doesn't exist.
+    pub fn row_count(&self, table: TableId) -> Option<usize> {
+        self.0
+            .guard
+            .get(table)
+            .map(|table| table.id_data.row_count())
+    }
+}
+
+pub(crate) fn split(world: &mut World) -> (IdView<'_>, ComponentView<'_>, TablesView<'_>) {
+    (
+        IdView {
+            ids: &world.id_manager,
+        },
+        ComponentView {
+            components: &world.components,
+        },
+        TablesView {
+            tables: &world.table_index,
+        },
+    )
+}
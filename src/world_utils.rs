@@ -1,43 +1,229 @@
 use crate::{
-    component::ensure_component,
-    error::{EcsError, EcsResult},
-    graph::table_traverse_add,
-    id::Id,
-    storage::{Storage, table::move_id},
+    component::{ComponentInfo, ComponentLocation, ensure_component, new_storage},
+    error::{EcsError, EcsResult, GetError, GetResult, InvalidPair, InvariantViolation},
+    flags::{ComponentFlags, IdFlags},
+    graph::{auto_added_tags, new_table, table_traverse_add, table_traverse_remove},
+    guid::Guid,
+    id::{
+        Id, Signature,
+        manager::{IdLocation, IdRecord},
+        pair,
+    },
+    query::QueryPlan,
+    recording::{Event, SetData, record, set_event},
+    storage::{
+        Storage, StorageType,
+        column::{ColumnVec, Ptr, PtrMut},
+        relation::{relation_store, relation_store_mut, stored_relation},
+        table::{delete_id, move_all, move_id, move_id_forget},
+    },
+    table_index::TableId,
+    type_info::TypeInfo,
     type_traits::DataComponent,
-    world::World,
+    world::{AutoComponent, World},
 };
 use const_assert::const_assert;
+use std::{
+    alloc::Layout,
+    collections::{HashMap, HashSet, hash_map::Entry},
+    ptr::NonNull,
+    rc::Rc,
+};
 
 /// Add the id as tag to the entity
 ///
-/// # Safety
-/// Caller ensures that id does not have associated data.
+/// If `tag` is a pair of a symmetric relationship `(R, B)`, `(R, id)` is also added to `B`.
 pub(crate) fn add_tag(world: &mut World, id: Id, tag: Id) -> EcsResult<()> {
+    insert_tag(world, id, tag)?;
+    add_auto_components(world, id, tag)?;
+
+    // Mirror with insert_tag directly so the mirrored add doesn't mirror again.
+    if let Some((tgt, mirror)) = symmetric_pair(world, id, tag) {
+        insert_tag(world, tgt, mirror)?;
+    }
+
+    Ok(())
+}
+
+/// Adds `tag` to all ids matching `query`.
+///
+/// Tables where every row matches are moved to the destination table at once.
+pub(crate) fn add_tag_to_all(world: &mut World, tag: Id, query: &mut QueryPlan) -> EcsResult<()> {
+    ensure_component(world, tag);
+
+    let in_tables = matches!(
+        world.components.get(tag).unwrap().storage,
+        Storage::Tables(_)
+    );
+
+    let mut full_tables = vec![];
+    let mut ids = vec![];
+
+    query.for_each_table(world, |view| {
+        let start = ids.len();
+        ids.extend(view.rows().map(|(_, id)| id));
+
+        if in_tables && ids.len() - start == view.ids().len() {
+            ids.truncate(start);
+            full_tables.push(view.table_id());
+        }
+    });
+
+    for table in full_tables {
+        // No destination table means the ids already have the tag.
+        let Some(dst) = table_traverse_add(world, table, tag) else {
+            continue;
+        };
+
+        let moved = world.table_index[table].id_data.ids().to_vec();
+
+        // SAFETY:
+        // - We ensured that dst is not the same as src.
+        // - Tags have no columns, so dst has the same columns as src.
+        unsafe { move_all(world, table, dst) };
+
+        for id in moved {
+            add_auto_components(world, id, tag)?;
+        }
+    }
+
+    for id in ids {
+        add_tag(world, id, tag)?;
+    }
+
+    Ok(())
+}
+
+/// Creates an id in the root table, without the components of auto-component scopes.
+///
+/// Internal callers write the values of the new id themselves, which requires
+/// that it has none yet.
+pub(crate) fn new_raw_id(world: &mut World) -> Id {
+    let root = world.root_table;
+    let id = world.id_manager.new_id(|id| IdRecord {
+        location: IdLocation {
+            table: root,
+            // SAFETY: The new row is owned by the new id.
+            row: unsafe { world.table_index[root].id_data.new_row(id) },
+        },
+        flags: IdFlags::default(),
+    });
+
+    record(world, |_| Event::Spawn { id });
+    id
+}
+
+/// Adds the components of the active auto-component scopes to a new id, outer scopes first.
+pub(crate) fn apply_auto_components(world: &mut World, id: Id) {
+    // Taken out of the world so the writes can borrow it mutably.
+    let autos = std::mem::take(&mut world.auto_components);
+
+    for (comp, auto) in &autos {
+        match auto {
+            AutoComponent::Tag => {
+                add_tag(world, id, *comp).ok();
+            }
+            AutoComponent::Data(write) => write(world, id),
+        }
+    }
+
+    world.auto_components = autos;
+}
+
+/// Adds the components auto-added with `comp` that `id` doesn't have yet.
+///
+/// Tags stored in tables are usually added by the table move of `comp` already,
+/// this covers sparse tags and data components, which are initialized with their default hook.
+/// If `comp` is a union variant, it also becomes the active one, see [activate_variant].
+pub(crate) fn add_auto_components(world: &mut World, id: Id, comp: Id) -> EcsResult<()> {
+    activate_variant(world, id, comp)?;
+
+    let with = match world.components.get(comp) {
+        Some(ci) if ci.flags.contains(ComponentFlags::WITH) => ci.with.clone(),
+        _ => return Ok(()),
+    };
+
+    for auto in with {
+        ensure_component(world, auto);
+
+        if has_component(world, id, auto) {
+            continue;
+        }
+
+        let Some(ti) = component_type_info(world, auto).map(Rc::clone) else {
+            add_tag(world, id, auto)?;
+            continue;
+        };
+
+        let default = ti
+            .hooks
+            .default
+            .as_ref()
+            .ok_or(EcsError::NoDefaultHook(auto))?;
+        move_for_writes(world, id, &[auto])?;
+
+        // SAFETY:
+        // - The default hook writes a value of the component type.
+        // - id doesn't have the component, so it has no value for it.
+        unsafe { write_component_with(world, id, auto, default) };
+
+        add_auto_components(world, id, auto)?;
+    }
+
+    Ok(())
+}
+
+/// Add the id as tag to the entity, without mirroring symmetric pairs.
+fn insert_tag(world: &mut World, id: Id, tag: Id) -> EcsResult<()> {
     let id_loc = world.id_manager.get_location(id)?;
+    check_component(world, tag)?;
 
     // Create ComponentRecord for tag if it doesn't exist.
     // Unlike components, tags can be registered on the fly,
     // allowing us to add regular ids or pairs as tags without first registering them.
     ensure_component(world, tag);
 
+    if let Some(rel) = stored_relation(world, tag) {
+        let store = relation_store_mut(world, rel);
+
+        if !store.is_tag() {
+            return Err(EcsError::IsNotTag(tag));
+        }
+
+        if store.insert_tag(id, tag.pair_tgt()) {
+            record(world, |_| Event::AddId { id, comp: tag });
+            set_id_flag(world, id, IdFlags::HAS_SPARSE);
+        }
+
+        return Ok(());
+    }
+
+    if component_type_info(world, tag).is_some() {
+        return Err(EcsError::IsNotTag(tag));
+    }
+
     let ci = world.components.get_mut(tag).unwrap();
 
     // SAFETY: we just checked that the id is a tag.
     match &mut ci.storage {
         Storage::SparseTag(set) => {
-            set.insert(id);
+            if !set.contains(id) {
+                set.insert(id);
+                record(world, |_| Event::AddId { id, comp: tag });
+                set_id_flag(world, id, IdFlags::HAS_SPARSE);
+            }
             Ok(())
         }
         Storage::SparseData(_) => Err(EcsError::IsNotTag(tag)),
+        Storage::SparseRelation(_) => Err(EcsError::IsRelationship(tag)),
         Storage::Tables(tables) => {
-            if !tables.contains_key(&id_loc.table) {
-                if let Some(dst_table) = table_traverse_add(world, id_loc.table, tag) {
-                    // SAFETY
-                    // - We ensured that dst_table is not the same as src.
-                    // - id is valid, which means that src_row must be valid.
-                    unsafe { move_id(world, id, id_loc.table, id_loc.row, dst_table) };
-                }
+            if !tables.contains_key(&id_loc.table)
+                && let Some(dst_table) = table_traverse_add(world, id_loc.table, tag)
+            {
+                // SAFETY
+                // - We ensured that dst_table is not the same as src.
+                // - id is valid, which means that src_row must be valid.
+                unsafe { move_id(world, id, id_loc.table, id_loc.row, dst_table) };
             }
             // Does nothing if there's no destination table.
             // This means that the id already contains the tag.
@@ -46,7 +232,9 @@ pub(crate) fn add_tag(world: &mut World, id: Id, tag: Id) -> EcsResult<()> {
     }
 }
 
-/// Sets the value of a component for an id.
+/// Sets the value of a component for an id, adding its auto-added components if it was missing.
+///
+/// Returns the old value if the id already had the component.
 ///
 /// # Safety
 /// - Caller must ensure that `val` is the same type and layout of the component.
@@ -55,27 +243,30 @@ pub(crate) unsafe fn set_component<T: DataComponent>(
     id: Id,
     comp: Id,
     val: T,
-) -> Option<T> {
-    let id_loc = world.id_manager.get_location(id).unwrap();
+) -> EcsResult<Option<T>> {
+    let id_loc = world.id_manager.get_location(id)?;
 
     ensure_component(world, comp);
 
-    let ci = world.components.get_mut(comp)?;
+    if let Some(rel) = stored_relation(world, comp) {
+        // SAFETY: Caller ensures that the type matches the component.
+        return unsafe { set_relation_pair(world, id, comp, rel, val) };
+    }
+
+    let ci = world.components.get_mut(comp).unwrap();
 
     // SAFETY:
     // - Valid entity must have valid table and row.
     // - Caller ensures that the type matches the component.
-    match &mut ci.storage {
-        Storage::SparseTag(_) => None,
+    let old = match &mut ci.storage {
+        Storage::SparseTag(_) => return Err(EcsError::IsTag(comp)),
         Storage::SparseData(set) => unsafe { set.insert(id, val) },
+        Storage::SparseRelation(_) => return Err(EcsError::IsRelationship(comp)),
         Storage::Tables(_) => unsafe {
             let table = &mut world.table_index[id_loc.table];
 
             match table.column_map.get(comp) {
-                Some(&col) => {
-                    let ptr = table.id_data.get_mut::<T>(col, id_loc.row);
-                    Some(std::mem::replace(ptr, val))
-                }
+                Some(&col) => Some(table.id_data.column_mut(col).replace(id_loc.row, val)),
                 None => {
                     let dst_table_id = table_traverse_add(world, id_loc.table, comp).unwrap();
 
@@ -90,67 +281,315 @@ pub(crate) unsafe fn set_component<T: DataComponent>(
                 }
             }
         },
+    };
+
+    mark_changed(world, id, comp);
+    add_auto_components(world, id, comp)?;
+    Ok(old)
+}
+
+/// Sets `flag` on the record of `id`, no side effect if it's not alive.
+///
+/// Flags are hints and are not cleared when the component is removed.
+pub(crate) fn set_id_flag(world: &mut World, id: Id, flag: IdFlags) {
+    if let Ok(record) = world.id_manager.get_record_mut(id) {
+        record.flags.insert(flag);
     }
 }
 
-/// Sets the value of a component for an entity.
-pub(crate) fn set_component_checked<T: DataComponent>(
+/// Sets the value of a pair stored in the [RelationStore](crate::storage::relation::RelationStore) of `rel`.
+///
+/// # Safety
+/// - Caller must ensure that `val` is the same type and layout of the relationship data.
+unsafe fn set_relation_pair<T: DataComponent>(
     world: &mut World,
     id: Id,
     comp: Id,
+    rel: Id,
     val: T,
-) -> Option<T> {
-    const_assert!(|T| size_of::<T>() != 0);
+) -> EcsResult<Option<T>> {
+    let store = relation_store_mut(world, rel);
 
-    let id_loc = world.id_manager.get_location(id).unwrap();
+    if store.is_tag() {
+        return Err(EcsError::IsTag(comp));
+    }
 
-    ensure_component(world, comp);
+    // SAFETY: Caller ensures that the type matches the relationship.
+    let old = unsafe { store.insert(id, comp.pair_tgt(), val) };
+
+    mark_changed(world, id, comp);
+    Ok(old)
+}
+
+/// Returns the type info of a component, or `None` if it's a tag or doesn't exist.
+///
+/// Pairs stored in a relation store use the type info of their relationship.
+pub(crate) fn component_type_info(world: &World, comp: Id) -> Option<&Rc<TypeInfo>> {
+    let info = stored_relation(world, comp).unwrap_or(comp);
+    world.components.get(info)?.type_info.as_ref()
+}
+
+/// Moves `id` to the table that holds all table components in `comps`, with a single move.
+///
+/// Columns of the added components are left uninitialized for [write_component].
+pub(crate) fn move_for_writes(world: &mut World, id: Id, comps: &[Id]) -> EcsResult<()> {
+    let id_loc = world.id_manager.get_location(id)?;
+    let mut dst = id_loc.table;
+
+    for &comp in comps {
+        let in_tables = world
+            .components
+            .get(comp)
+            .is_some_and(|ci| matches!(ci.storage, Storage::Tables(_)));
+
+        if in_tables {
+            // No destination table means the component is already present.
+            if let Some(next) = table_traverse_add(world, dst, comp) {
+                dst = next;
+            }
+        }
+    }
+
+    if dst != id_loc.table {
+        // SAFETY:
+        // - We ensured that dst is not the same as src.
+        // - id is valid, which means that src_row must be valid.
+        unsafe { move_id(world, id, id_loc.table, id_loc.row, dst) };
+    }
+
+    Ok(())
+}
+
+/// Moves `id` to the table with the components of `target`, with a single move.
+///
+/// Values of removed components are dropped, added data components are initialized
+/// with their default hook. Added components bring their auto-added components, and
+/// pairs of symmetric relationships are mirrored like [add_tag] and [remove_component].
+/// The Prefab tag and ChildOf pairs of `id` are kept.
+///
+/// Nothing is moved if an added component has no default hook, or if `target` has
+/// two pairs of an exclusive relationship or two variants of a union.
+pub(crate) fn move_to(world: &mut World, id: Id, target: &Signature) -> EcsResult<()> {
+    let id_loc = world.id_manager.get_location(id)?;
+    let src_ids = world.table_index[id_loc.table].signature.ids().to_vec();
+
+    // Removing these detaches prefabs and hierarchies, which takes an explicit remove.
+    let (prefab, child_of) = (world.prefab, world.child_of);
+    let mut comps: Vec<Id> = src_ids
+        .iter()
+        .copied()
+        .filter(|&comp| {
+            comp == prefab || (comp.is_pair() && comp.pair_rel().index() == child_of.index())
+        })
+        .collect();
+
+    for &comp in target.ids() {
+        check_component(world, comp)?;
+        ensure_component(world, comp);
+        comps.push(comp);
+
+        if !src_ids.contains(&comp) {
+            comps.extend(auto_added_tags(world, comp));
+        }
+    }
+
+    check_exclusive(world, &comps)?;
+
+    let dst = world.ensure_archetype(&comps);
+
+    if dst == id_loc.table {
+        return Ok(());
+    }
 
-    let ci = world.components.get_mut(comp)?;
+    let dst_ids = world.table_index[dst].signature.ids();
+    let added: Vec<Id> = dst_ids
+        .iter()
+        .copied()
+        .filter(|comp| !src_ids.contains(comp))
+        .collect();
+    let removed: Vec<Id> = src_ids
+        .iter()
+        .copied()
+        .filter(|comp| !dst_ids.contains(comp))
+        .collect();
 
-    // Check that type matches.
-    if let Some(ti) = &ci.type_info {
-        if !ti.is::<T>() {
-            return None;
+    for &comp in &added {
+        if let Some(ti) = component_type_info(world, comp)
+            && ti.hooks.default.is_none()
+        {
+            return Err(EcsError::NoDefaultHook(comp));
         }
     }
 
     // SAFETY:
-    // - Valid entity must have valid table and row.
-    // - Caller ensures that the type matches the component.
+    // - We ensured that dst is not the same as src.
+    // - id is valid, which means that src_row must be valid.
+    unsafe { move_id(world, id, id_loc.table, id_loc.row, dst) };
+
+    for &comp in &added {
+        if let Some(ti) = component_type_info(world, comp).map(Rc::clone) {
+            let default = ti.hooks.default.as_ref().unwrap();
+
+            // SAFETY:
+            // - The default hook writes a value of the component type.
+            // - The column of the component was just added, so id has no value for it.
+            unsafe { write_component_with(world, id, comp, default) };
+        }
+    }
+
+    for &comp in &added {
+        add_auto_components(world, id, comp)?;
+
+        if let Some((tgt, mirror)) = symmetric_pair(world, id, comp) {
+            insert_tag(world, tgt, mirror)?;
+        }
+    }
+
+    for comp in removed {
+        deactivate_variant(world, id, comp)?;
+
+        if let Some((tgt, mirror)) = symmetric_pair(world, id, comp) {
+            take_component(world, tgt, mirror)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `comps` has at most one pair of each exclusive relationship
+/// and at most one variant of each union.
+fn check_exclusive(world: &World, comps: &[Id]) -> EcsResult<()> {
+    let mut pairs = HashMap::new();
+    let mut variants = HashMap::new();
+
+    for &comp in comps {
+        if let Some(&union) = world.variant_unions.get(comp)
+            && *variants.entry(union).or_insert(comp) != comp
+        {
+            return Err(EcsError::ExclusiveUnion(union));
+        }
+
+        if !comp.is_pair() {
+            continue;
+        }
+
+        let Some(rel) = world.id_manager.get_current(comp.pair_rel()) else {
+            continue;
+        };
+
+        let exclusive = world
+            .components
+            .get(rel)
+            .is_some_and(|ci| ci.flags.contains(ComponentFlags::EXCLUSIVE));
+
+        if exclusive && *pairs.entry(rel).or_insert(comp) != comp {
+            return Err(EcsError::ExclusiveRelationship(rel));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a component value after [move_for_writes], dropping the old value if any.
+///
+/// # Safety
+/// - `val` must be the same type as the component data.
+/// - If the component is stored in tables, `id` must be in a table with its column.
+pub(crate) unsafe fn write_component<T: DataComponent>(
+    world: &mut World,
+    id: Id,
+    comp: Id,
+    val: T,
+) {
+    let id_loc = world.id_manager.get_location(id).unwrap();
+
+    if let Some(rel) = stored_relation(world, comp) {
+        // SAFETY: Caller ensures that the type matches the component.
+        drop(unsafe { set_relation_pair(world, id, comp, rel, val) });
+        return;
+    }
+
+    let ci = world.components.get_mut(comp).unwrap();
+
     match &mut ci.storage {
-        Storage::SparseTag(_) => None,
-        Storage::SparseData(set) => unsafe { set.insert(id, val) },
+        Storage::SparseTag(_) => unreachable!("tags are rejected before writing"),
+        // SAFETY: Caller ensures that the type matches the component.
+        Storage::SparseData(set) => drop(unsafe { set.insert(id, val) }),
+        Storage::SparseRelation(_) => unreachable!("relationships have no value of their own"),
         Storage::Tables(_) => unsafe {
             let table = &mut world.table_index[id_loc.table];
+            let col = *table.column_map.get(comp).unwrap();
 
-            match table.column_map.get(comp) {
-                Some(&col) => {
-                    let ptr = table.id_data.get_ptr_mut(col, id_loc.row);
-                    Some(ptr.cast::<T>().replace(val))
-                }
-                None => {
-                    let dst_table_id = table_traverse_add(world, id_loc.table, comp).unwrap();
+            // The column of a newly added component is one row short.
+            if table.id_data.column(col).len() == id_loc.row {
+                table.id_data.push(col, val);
+            } else {
+                *table.id_data.get_mut::<T>(col, id_loc.row) = val;
+            }
+        },
+    }
 
-                    move_id(world, id, id_loc.table, id_loc.row, dst_table_id);
+    mark_changed(world, id, comp);
+}
 
-                    let table = &mut world.table_index[dst_table_id];
-                    let col = *table.column_map.get(comp).unwrap();
+/// Records that the value of `comp` for `id` was written at the current change tick.
+pub(crate) fn mark_changed(world: &mut World, id: Id, comp: Id) {
+    let tick = world.change_tick;
 
-                    table.id_data.push(col, val);
-                    table.validate_data();
-                    None
-                }
+    let Ok(id_loc) = world.id_manager.get_location(id) else {
+        return;
+    };
+
+    if let Some(rel) = stored_relation(world, comp) {
+        let store = relation_store_mut(world, rel);
+
+        if store.is_tag() {
+            return;
+        }
+
+        store.set_tick(id, comp.pair_tgt(), tick);
+        set_id_flag(world, id, IdFlags::HAS_SPARSE);
+        world.observers.touch(id, comp);
+        record(world, |world| set_event(world, id, comp));
+        return;
+    }
+
+    let Some(ci) = world.components.get_mut(comp) else {
+        return;
+    };
+
+    match &mut ci.storage {
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => return,
+        Storage::SparseData(set) => {
+            set.set_tick(id, tick);
+            set_id_flag(world, id, IdFlags::HAS_SPARSE);
+        }
+        Storage::Tables(_) => {
+            let table = &mut world.table_index[id_loc.table];
+
+            match table.column_map.get(comp) {
+                Some(&col) => table.id_data.set_tick(col, id_loc.row, tick),
+                None => return,
             }
-        },
+        }
     }
+
+    world.observers.touch(id, comp);
+    record(world, |world| set_event(world, id, comp));
 }
 
 pub(crate) fn has_component(world: &World, id: Id, comp: Id) -> bool {
-    let id_loc = match world.id_manager.get_location(id) {
-        Ok(location) => location,
-        Err(_) => return false,
-    };
+    match world.id_manager.get_location(id) {
+        Ok(id_loc) => has_component_at(world, id, id_loc, comp),
+        Err(_) => false,
+    }
+}
+
+/// Checks if an id has the component, given the location of the id.
+pub(crate) fn has_component_at(world: &World, id: Id, id_loc: IdLocation, comp: Id) -> bool {
+    if let Some(rel) = stored_relation(world, comp) {
+        return relation_store(world, rel).contains(id, comp.pair_tgt());
+    }
 
     let cr = match world.components.get(comp) {
         Some(cr) => cr,
@@ -161,6 +600,2439 @@ pub(crate) fn has_component(world: &World, id: Id, comp: Id) -> bool {
     match &cr.storage {
         Storage::SparseTag(set) => set.contains(id),
         Storage::SparseData(set) => set.contains(id),
+        Storage::SparseRelation(_) => false,
         Storage::Tables(tables) => tables.contains_key(&id_loc.table),
     }
 }
+
+/// Removes the component from the id, dropping its value.
+///
+/// No side effect if the id doesn't have the component.
+/// If `comp` is a pair of a symmetric relationship `(R, B)`, `(R, id)` is also removed from `B`.
+pub(crate) fn remove_component(world: &mut World, id: Id, comp: Id) -> EcsResult<()> {
+    take_component(world, id, comp)?;
+
+    if let Some((tgt, mirror)) = symmetric_pair(world, id, comp) {
+        take_component(world, tgt, mirror)?;
+    }
+
+    Ok(())
+}
+
+/// Removes the component from all `ids`, dropping their values.
+///
+/// Ids are grouped by table, so the destination is computed once per table.
+/// Tables whose ids are all listed are moved at once.
+pub(crate) fn batch_remove(world: &mut World, ids: &[Id], comp: Id) -> EcsResult<()> {
+    let mut groups = HashMap::<TableId, Vec<Id>>::new();
+    let mut seen = HashSet::new();
+
+    for &id in ids {
+        let id_loc = world.id_manager.get_location(id)?;
+
+        if seen.insert(id) {
+            groups.entry(id_loc.table).or_default().push(id);
+        }
+    }
+
+    // Pairs stored in a relation store use the record of their relationship.
+    let info = stored_relation(world, comp).unwrap_or(comp);
+
+    let Some(ci) = world.components.get(info) else {
+        return Ok(());
+    };
+
+    let flags = ci.flags;
+
+    match &ci.storage {
+        Storage::SparseTag(_) | Storage::SparseData(_) | Storage::SparseRelation(_) => {
+            for group in groups.values() {
+                for &id in group {
+                    take_component(world, id, comp)?;
+                }
+            }
+        }
+        Storage::Tables(tables) => {
+            groups.retain(|table, _| tables.contains_key(table));
+
+            for (table, group) in &groups {
+                let dst = table_traverse_remove(world, *table, comp).unwrap();
+
+                if group.len() == world.table_index[*table].id_data.row_count() {
+                    // SAFETY:
+                    // - We ensured that dst is not the same as src.
+                    // - dst has the columns of src, without the removed component.
+                    unsafe { move_all(world, *table, dst) };
+                    continue;
+                }
+
+                for &id in group {
+                    // Rows shift as ids are moved out, so locate each id again.
+                    let id_loc = world.id_manager.get_location(id)?;
+
+                    // SAFETY:
+                    // - We ensured that dst is not the same as src.
+                    // - id is valid, which means that src_row must be valid.
+                    unsafe { move_id(world, id, id_loc.table, id_loc.row, dst) };
+                }
+            }
+        }
+    }
+
+    if flags.contains(ComponentFlags::SYMMETRIC) {
+        for &id in &seen {
+            if let Some((tgt, mirror)) = symmetric_pair(world, id, comp) {
+                take_component(world, tgt, mirror)?;
+            }
+        }
+    }
+
+    if world.variant_unions.contains(comp) {
+        for id in seen {
+            deactivate_variant(world, id, comp)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes a component from an id, without mirroring symmetric pairs.
+///
+/// The union tag goes with the last variant of its union.
+fn take_component(world: &mut World, id: Id, comp: Id) -> EcsResult<()> {
+    let id_loc = world.id_manager.get_location(id)?;
+
+    if let Some(rel) = stored_relation(world, comp) {
+        if relation_store_mut(world, rel).remove(id, comp.pair_tgt()) {
+            record(world, |_| Event::Remove { id, comp });
+        }
+
+        return Ok(());
+    }
+
+    // Records are found by index, so an older generation of `comp` finds the current one.
+    let Some(ci) = world.components.get_mut(comp).filter(|ci| ci.id == comp) else {
+        return Ok(());
+    };
+
+    match &mut ci.storage {
+        Storage::SparseTag(set) if set.contains(id) => {
+            set.remove(id);
+            record(world, |_| Event::Remove { id, comp });
+        }
+        Storage::SparseData(set) if set.contains(id) => {
+            set.remove(id);
+            record(world, |_| Event::Remove { id, comp });
+        }
+        Storage::SparseTag(_) | Storage::SparseData(_) | Storage::SparseRelation(_) => {}
+        Storage::Tables(tables) => {
+            if tables.contains_key(&id_loc.table) {
+                let dst_table = table_traverse_remove(world, id_loc.table, comp).unwrap();
+
+                // SAFETY:
+                // - We ensured that dst_table is not the same as src.
+                // - id is valid, which means that src_row must be valid.
+                unsafe { move_id(world, id, id_loc.table, id_loc.row, dst_table) };
+            }
+        }
+    }
+
+    deactivate_variant(world, id, comp)
+}
+
+/// Returns a pointer to the component data of an id, valid while the world is borrowed.
+///
+/// Returns `None` if the id is not alive, doesn't have the component or the component is a tag.
+pub(crate) fn get_component_ptr(world: &World, id: Id, comp: Id) -> Option<Ptr<'_>> {
+    let id_loc = world.id_manager.get_location(id).ok()?;
+
+    if let Some(rel) = stored_relation(world, comp) {
+        let ptr = relation_store(world, rel).get_ptr(id, comp.pair_tgt())?;
+
+        // SAFETY: The store is borrowed with the world, so the value can't move.
+        return Some(unsafe { Ptr::new(ptr) });
+    }
+
+    let ci = world.components.get(comp)?;
+
+    match &ci.storage {
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => None,
+        // SAFETY: The set is borrowed with the world, so the value can't move.
+        Storage::SparseData(set) => set.get_ptr(id).map(|ptr| unsafe { Ptr::new(ptr) }),
+        Storage::Tables(_) => {
+            let table = &world.table_index[id_loc.table];
+
+            // SAFETY: Valid id must have valid table and row.
+            table
+                .column_map
+                .get(comp)
+                .map(|&col| unsafe { table.id_data.column_ref(col).ptr_unchecked(id_loc.row) })
+        }
+    }
+}
+
+/// Returns a pointer valid for writes to the component data of an id, see [get_component_ptr].
+pub(crate) fn get_component_ptr_mut(world: &mut World, id: Id, comp: Id) -> Option<PtrMut<'_>> {
+    let ptr = get_component_ptr(world, id, comp)?;
+
+    // SAFETY: The world is borrowed exclusively for the lifetime of the pointer.
+    Some(unsafe { ptr.assert_unique() })
+}
+
+/// Gets the value of a component for an id, inserting it with `init` if it's missing.
+///
+/// The id is located once. If inserting the component moves the id to a new table,
+/// the returned pointer points into the destination table.
+/// Returns whether the value was inserted, the caller marks it changed once it's written.
+///
+/// # Safety
+/// - Caller must ensure that `T` is the same type and layout of the component.
+pub(crate) unsafe fn get_or_insert_component<T: DataComponent>(
+    world: &mut World,
+    id: Id,
+    comp: Id,
+    init: impl FnOnce() -> T,
+) -> EcsResult<(NonNull<u8>, bool)> {
+    let id_loc = world.id_manager.get_location(id)?;
+
+    if let Some(rel) = stored_relation(world, comp) {
+        let store = relation_store_mut(world, rel);
+        let tgt = comp.pair_tgt();
+
+        if store.is_tag() {
+            return Err(EcsError::IsTag(comp));
+        }
+
+        let inserted = !store.contains(id, tgt);
+
+        if inserted {
+            // SAFETY: Caller ensures that the type matches the relationship.
+            unsafe { store.insert(id, tgt, init()) };
+        }
+
+        return Ok((store.get_ptr(id, tgt).unwrap(), inserted));
+    }
+
+    let ci = match world.components.get_mut(comp) {
+        Some(ci) => ci,
+        None => return Err(EcsError::IdNotComponent(comp)),
+    };
+
+    if ci.type_info.is_none() {
+        return Err(EcsError::IsTag(comp));
+    }
+
+    let (mut ptr, inserted) = match &mut ci.storage {
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => return Err(EcsError::IsTag(comp)),
+        Storage::SparseData(set) => {
+            let inserted = !set.contains(id);
+
+            if inserted {
+                // SAFETY: Caller ensures that the type matches the component.
+                unsafe { set.insert(id, init()) };
+            }
+
+            (set.get_ptr_mut(id).unwrap(), inserted)
+        }
+        Storage::Tables(tables) => unsafe {
+            if tables.contains_key(&id_loc.table) {
+                let table = &mut world.table_index[id_loc.table];
+                let col = *table.column_map.get(comp).unwrap();
+                (
+                    table.id_data.column_mut(col).ptr_mut(id_loc.row).as_ptr(),
+                    false,
+                )
+            } else {
+                let dst_table_id = table_traverse_add(world, id_loc.table, comp).unwrap();
+
+                move_id(world, id, id_loc.table, id_loc.row, dst_table_id);
+
+                let table = &mut world.table_index[dst_table_id];
+                let col = *table.column_map.get(comp).unwrap();
+
+                table.id_data.push(col, init());
+                table.validate_data();
+
+                // The moved id is always appended to the destination table.
+                let row = table.id_data.row_count() - 1;
+                (table.id_data.column_mut(col).ptr_mut(row).as_ptr(), true)
+            }
+        },
+    };
+
+    if inserted {
+        add_auto_components(world, id, comp)?;
+
+        // Auto components can move the id to another table.
+        ptr = get_component_ptr(world, id, comp).unwrap().as_ptr();
+    }
+
+    Ok((ptr, inserted))
+}
+
+/// Checks the consistency of the id manager, tables and sparse storages.
+pub(crate) fn check_invariants(world: &World) -> Vec<InvariantViolation> {
+    let mut violations = vec![];
+
+    // Every alive id must point to its own row.
+    for (id, record) in world.id_manager.iter_alive() {
+        let loc = record.location;
+        let is_valid = world
+            .table_index
+            .get(loc.table)
+            .is_some_and(|table| table.id_data.ids().get(loc.row) == Some(&id));
+
+        if !is_valid {
+            violations.push(InvariantViolation::InvalidLocation(id, loc.row));
+        }
+    }
+
+    for table in world.table_index.all_tables() {
+        let rows = table.id_data.row_count();
+
+        // Every row must be recorded at its location.
+        for (row, &id) in table.id_data.ids().iter().enumerate() {
+            match world.id_manager.get_location(id) {
+                Ok(loc) if loc.table == table.id && loc.row == row => {}
+                _ => violations.push(InvariantViolation::UnrecordedRow(id, row)),
+            }
+        }
+
+        // Every column must have a value for each row.
+        for col in table.id_data.columns() {
+            if col.len() != rows {
+                violations.push(InvariantViolation::ColumnLength(
+                    *col.id(),
+                    col.len(),
+                    rows,
+                    table.debug_name(world),
+                ));
+            }
+        }
+
+        // Every pair target must be alive.
+        for &comp in table.signature.ids() {
+            if comp.is_pair() && world.id_manager.get_current(comp.pair_tgt()).is_none() {
+                for &id in table.id_data.ids() {
+                    violations.push(InvariantViolation::DeadPairTarget(id, comp));
+                }
+            }
+        }
+    }
+
+    for (comp, ci) in world.components.iter() {
+        let ids: Box<dyn Iterator<Item = Id>> = match &ci.storage {
+            Storage::SparseTag(set) => Box::new(set.iter()),
+            Storage::SparseData(set) => Box::new(set.ids().iter().copied()),
+            Storage::SparseRelation(store) => {
+                for id in store.ids() {
+                    if !world.id_manager.is_alive(id) {
+                        violations.push(InvariantViolation::DeadSparseEntry(comp, id));
+                    }
+
+                    for tgt in store.targets(id) {
+                        if world.id_manager.get_current(tgt).is_none() {
+                            violations
+                                .push(InvariantViolation::DeadPairTarget(id, pair(comp, tgt)));
+                        }
+                    }
+                }
+
+                continue;
+            }
+            Storage::Tables(_) => continue,
+        };
+
+        let dead_target = comp.is_pair() && world.id_manager.get_current(comp.pair_tgt()).is_none();
+
+        for id in ids {
+            if !world.id_manager.is_alive(id) {
+                violations.push(InvariantViolation::DeadSparseEntry(comp, id));
+            }
+
+            if dead_target {
+                violations.push(InvariantViolation::DeadPairTarget(id, comp));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Returns `(id, pair, target)` for every pair whose target is not alive.
+///
+/// Pairs only store the index of the target, so the returned target has no generation.
+pub(crate) fn dangling_relationships(world: &World) -> Vec<(Id, Id, Id)> {
+    let is_dangling = |comp: Id| comp.is_pair() && is_dead_target(world, comp.pair_tgt());
+
+    let mut dangling = vec![];
+
+    for table in world.table_index.all_tables() {
+        for &comp in table.signature.ids() {
+            if is_dangling(comp) {
+                let ids = table.id_data.ids().iter();
+                dangling.extend(ids.map(|&id| (id, comp, comp.pair_tgt())));
+            }
+        }
+    }
+
+    for (comp, ci) in world.components.iter() {
+        let ids = match &ci.storage {
+            Storage::SparseTag(set) => set.ids(),
+            Storage::SparseData(set) => set.ids(),
+            Storage::SparseRelation(store) => {
+                for id in store.ids() {
+                    let dead = store.targets(id).filter(|&tgt| is_dead_target(world, tgt));
+
+                    dangling.extend(dead.map(|tgt| (id, pair(comp, tgt), tgt)));
+                }
+
+                continue;
+            }
+            Storage::Tables(_) => continue,
+        };
+
+        if is_dangling(comp) {
+            dangling.extend(ids.iter().map(|&id| (id, comp, comp.pair_tgt())));
+        }
+    }
+
+    dangling
+}
+
+/// Checks if the target index of a pair is dead, or was recycled since the pair was added.
+fn is_dead_target(world: &World, tgt: Id) -> bool {
+    match world.id_manager.get_current(tgt) {
+        Some(current) => world
+            .target_generations
+            .get(&tgt.index())
+            .is_some_and(|&generation| generation != current.generation()),
+        None => true,
+    }
+}
+
+/// Returns the first id whose value of `comp` equals `value`, compared with the eq hook.
+///
+/// Returns `None` if the component has no eq hook.
+///
+/// # Safety
+/// `value` must point to a value of the component data type.
+pub(crate) unsafe fn find_with_value(world: &World, comp: Id, value: NonNull<u8>) -> Option<Id> {
+    let ci = world
+        .components
+        .get(stored_relation(world, comp).unwrap_or(comp))?;
+    let hooks = &ci.type_info.as_ref()?.hooks;
+    let eq = hooks.eq.as_ref()?;
+    let hash = hooks.hash.as_ref().map(|hash| (hash, hash(value)));
+
+    let matches = |ptr: NonNull<u8>| {
+        hash.is_none_or(|(hash, value_hash)| hash(ptr) == value_hash) && eq(ptr, value)
+    };
+
+    match &ci.storage {
+        Storage::SparseTag(_) => None,
+        Storage::SparseData(set) => set
+            .ids()
+            .iter()
+            .copied()
+            .find(|&id| set.get_ptr(id).is_some_and(matches)),
+        // The relationship itself has no value, only its pairs do.
+        Storage::SparseRelation(_) if !comp.is_pair() => None,
+        Storage::SparseRelation(store) => store
+            .ids()
+            .find(|&id| store.get_ptr(id, comp.pair_tgt()).is_some_and(matches)),
+        Storage::Tables(tables) => tables.keys().find_map(|&table| {
+            let table = &world.table_index[table];
+            let column = table.id_data.column_ref(*table.column_map.get(comp)?);
+
+            let row =
+                (0..table.id_data.row_count()).find(|&row| matches(column.ptr(row).as_ptr()))?;
+            Some(table.id_data.ids()[row])
+        }),
+    }
+}
+
+/// Returns the components of an id, table components first, then sparse components.
+pub(crate) fn components_of(world: &World, id: Id) -> EcsResult<Vec<Id>> {
+    let id_loc = world.id_manager.get_location(id)?;
+    let mut comps = world.table_index[id_loc.table].signature.to_vec();
+
+    for (comp, ci) in world.components.iter() {
+        let has_comp = match &ci.storage {
+            Storage::SparseTag(set) => set.contains(id),
+            Storage::SparseData(set) => set.contains(id),
+            Storage::SparseRelation(store) => {
+                comps.extend(store.targets(id).map(|tgt| pair(comp, tgt)));
+                false
+            }
+            Storage::Tables(_) => false,
+        };
+
+        if has_comp {
+            comps.push(comp);
+        }
+    }
+
+    Ok(comps)
+}
+
+/// Calls `f` with the values of `comp` in each table that has rows, then with its sparse values.
+///
+/// Does nothing if `T` is not the data type of the component. Every passed value is
+/// marked as written at the current change tick.
+pub(crate) fn for_each_column<T: 'static>(
+    world: &mut World,
+    comp: Id,
+    mut f: impl FnMut(&mut [T]),
+) {
+    let tick = world.change_tick;
+
+    let Some(ci) = world.components.get_mut(comp) else {
+        return;
+    };
+
+    if !ci.type_info.as_ref().is_some_and(|ti| ti.is::<T>()) {
+        return;
+    }
+
+    match &mut ci.storage {
+        Storage::SparseData(set) => {
+            let mut column = set.column_mut();
+            column.set_all_ticks(tick);
+
+            // SAFETY: We just checked that T is the data type of the component.
+            f(unsafe { column.into_slice::<T>() });
+        }
+        Storage::Tables(tables) => {
+            for &table in tables.keys() {
+                let table = &mut world.table_index[table];
+
+                let Some(&col) = table.column_map.get(comp) else {
+                    continue;
+                };
+
+                if table.id_data.row_count() == 0 {
+                    continue;
+                }
+
+                let mut column = table.id_data.column_mut(col);
+                column.set_all_ticks(tick);
+
+                // SAFETY: We just checked that T is the data type of the component.
+                f(unsafe { column.into_slice::<T>() });
+            }
+        }
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => {}
+    }
+}
+
+/// Calls `f` with the values of `comp` and their ids in each table that has rows,
+/// then with its sparse values, the table is `None` for sparse values.
+///
+/// Does nothing for tags and pairs of a sparse relationship.
+pub(crate) fn export_column(
+    world: &World,
+    comp: Id,
+    mut f: impl FnMut(Option<TableId>, &ColumnVec<Id>, &[Id]),
+) {
+    let Some(ci) = world.components.get(comp) else {
+        return;
+    };
+
+    match &ci.storage {
+        Storage::SparseData(set) => {
+            if !set.ids().is_empty() {
+                f(None, set.column(), set.ids());
+            }
+        }
+        Storage::Tables(tables) => {
+            for &table in tables.keys() {
+                let table = &world.table_index[table];
+
+                let Some(&col) = table.column_map.get(comp) else {
+                    continue;
+                };
+
+                if table.id_data.row_count() == 0 {
+                    continue;
+                }
+
+                f(
+                    Some(table.id),
+                    table.id_data.column(col),
+                    table.id_data.ids(),
+                );
+            }
+        }
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => {}
+    }
+}
+
+/// Sets `comp` as the active variant of `union` for an id, see [activate_variant].
+pub(crate) fn set_union<T: DataComponent>(
+    world: &mut World,
+    id: Id,
+    union: Id,
+    comp: Id,
+    val: T,
+) -> EcsResult<()> {
+    let Some(variants) = world.unions.get(union) else {
+        return Err(EcsError::NotUnion(union));
+    };
+
+    if !variants.contains(&comp) {
+        return Err(EcsError::NotUnionVariant(union, comp));
+    }
+
+    // Variants are checked on registration, so they all have data.
+    if !component_type_info(world, comp).unwrap().is::<T>() {
+        return Err(EcsError::TypeMismatch(comp));
+    }
+
+    // SAFETY: We just checked that the type matches the component.
+    unsafe { set_component(world, id, comp, val) }?;
+    Ok(())
+}
+
+/// Makes `comp` the active variant of its union for `id`, removing the other variants
+/// and adding the union tag. No side effect if `comp` is not a union variant.
+fn activate_variant(world: &mut World, id: Id, comp: Id) -> EcsResult<()> {
+    let Some(&union) = world.variant_unions.get(comp) else {
+        return Ok(());
+    };
+
+    let inactive: Vec<Id> = world
+        .unions
+        .get(union)
+        .unwrap()
+        .iter()
+        .copied()
+        .filter(|&variant| variant != comp && has_component(world, id, variant))
+        .collect();
+
+    for variant in inactive {
+        take_component(world, id, variant)?;
+    }
+
+    add_tag(world, id, union)
+}
+
+/// Removes the union tag of `comp` from `id` once it has none of the union variants left.
+fn deactivate_variant(world: &mut World, id: Id, comp: Id) -> EcsResult<()> {
+    let Some(&union) = world.variant_unions.get(comp) else {
+        return Ok(());
+    };
+
+    let active = world
+        .unions
+        .get(union)
+        .unwrap()
+        .iter()
+        .any(|&variant| has_component(world, id, variant));
+
+    if !active {
+        take_component(world, id, union)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the guid of an id, if it has one.
+pub(crate) fn guid_of(world: &World, id: Id) -> Option<u128> {
+    let ptr = get_component_ptr(world, id, world.guid)?;
+
+    // SAFETY: The guid component holds a Guid.
+    Some(unsafe { ptr.deref::<Guid>() }.0)
+}
+
+/// Sets the guid of an id and indexes it, replacing its previous guid.
+pub(crate) fn set_guid(world: &mut World, id: Id, guid: u128) -> EcsResult<()> {
+    world.id_manager.get_location(id)?;
+
+    // Entries of ids whose guid changed since are stale, they don't collide.
+    if let Some(&other) = world.guids.get(&guid)
+        && guid_of(world, other) == Some(guid)
+    {
+        return match other == id {
+            true => Ok(()),
+            false => Err(EcsError::GuidCollision(guid)),
+        };
+    }
+
+    // SAFETY: The guid component holds a Guid.
+    if let Some(old) = unsafe { set_component(world, id, world.guid, Guid(guid)) }? {
+        world.guids.remove(&old.0);
+    }
+
+    world.guids.insert(guid, id);
+    Ok(())
+}
+
+/// Returns the `(rel, *)` pairs of an id.
+///
+/// Sparse relationships read the targets of the id from their relation store.
+pub(crate) fn pairs_of(world: &World, id: Id, rel: Id) -> EcsResult<Vec<Id>> {
+    world.id_manager.get_location(id)?;
+
+    if let Some(Storage::SparseRelation(store)) = world.components.get(rel).map(|ci| &ci.storage) {
+        return Ok(store.targets(id).map(|tgt| pair(rel, tgt)).collect());
+    }
+
+    let pairs = components_of(world, id)?
+        .into_iter()
+        .filter(|comp| comp.is_pair() && comp.pair_rel().index() == rel.index());
+
+    Ok(pairs.collect())
+}
+
+/// Returns the target and the mirrored pair if `comp` is a pair of a symmetric relationship.
+///
+/// Returns `None` for self pairs, since they mirror onto themselves.
+pub(crate) fn symmetric_pair(world: &World, id: Id, comp: Id) -> Option<(Id, Id)> {
+    if !comp.is_pair() {
+        return None;
+    }
+
+    let rel = world.id_manager.get_current(comp.pair_rel())?;
+    let tgt = world.id_manager.get_current(comp.pair_tgt())?;
+    let ci = world.components.get(rel)?;
+
+    if ci.flags.contains(ComponentFlags::SYMMETRIC) && tgt != id {
+        Some((tgt, pair(rel, id)))
+    } else {
+        None
+    }
+}
+
+/// Returns a readable name for an id.
+///
+/// Components use their registered name, pairs are formatted as `(Rel, Tgt)`
+/// and other ids fall back to their [Display](std::fmt::Display) format.
+pub(crate) fn id_name(world: &World, id: Id) -> String {
+    if id.is_pair() {
+        let rel = world.id_manager.get_current(id.pair_rel());
+        let tgt = world.id_manager.get_current(id.pair_tgt());
+
+        return format!(
+            "({}, {})",
+            rel.map_or_else(|| id.pair_rel().to_string(), |rel| id_name(world, rel)),
+            tgt.map_or_else(|| id.pair_tgt().to_string(), |tgt| id_name(world, tgt)),
+        );
+    }
+
+    match world.components.get(id) {
+        Some(ci) => ci.display_name(),
+        None => id.to_string(),
+    }
+}
+
+/// Removes the id from its table and all sparse storages, then kills the id.
+pub(crate) fn despawn_id(world: &mut World, id: Id) -> EcsResult<()> {
+    let id_loc = world.id_manager.get_location(id)?;
+
+    record(world, |_| Event::Despawn { id });
+
+    if let Some(guid) = guid_of(world, id) {
+        world.guids.remove(&guid);
+    }
+
+    for ci in world.components.values_mut() {
+        match &mut ci.storage {
+            Storage::SparseTag(set) => set.remove(id),
+            Storage::SparseData(set) => set.remove(id),
+            Storage::SparseRelation(store) => store.remove_all(id),
+            Storage::Tables(_) => {}
+        }
+    }
+
+    // SAFETY: Valid id must have valid table and row.
+    unsafe { delete_id(world, id_loc.table, id_loc.row) };
+
+    // Tables are never deleted, so the index of a component in use stays in their
+    // signatures or storage and can't be recycled for another id.
+    let in_use = world
+        .components
+        .get(id)
+        .is_some_and(|ci| match &ci.storage {
+            Storage::Tables(tables) => !tables.is_empty(),
+            Storage::SparseTag(set) => !set.ids().is_empty(),
+            Storage::SparseData(set) => !set.ids().is_empty(),
+            Storage::SparseRelation(store) => store.ids().next().is_some(),
+        });
+
+    match in_use {
+        true => world.id_manager.retire_id(id),
+        false => {
+            // A recycled index must not find the record of the old id.
+            world.components.remove(id);
+            world.id_manager.remove_id(id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Despawns `id` along with the ids deleted by the target policies of relationships
+/// with pairs targeting it, see [Policy](crate::flags::Policy). Returns how many ids were despawned.
+///
+/// Ids are despawned after everything they target, e.g. children before their parent.
+/// Mirrored pairs of symmetric relationships are removed from the other side.
+///
+/// Ids using a deleted component are handled by its delete policy the same way.
+///
+/// # Panics
+/// If a relationship with pairs targeting a deleted id has the [Policy::Panic](crate::flags::Policy::Panic)
+/// target policy, or a deleted component in use has it as delete policy. Nothing is despawned then.
+pub(crate) fn despawn_cascade(world: &mut World, id: Id) -> EcsResult<usize> {
+    world.id_manager.get_location(id)?;
+
+    let mut ids = vec![id];
+    let mut visited = HashSet::from([id]);
+    let mut removes = vec![];
+    let mut next = 0;
+
+    while let Some(&tgt) = ids.get(next) {
+        next += 1;
+
+        for (comp, flags) in pairs_targeting(world, tgt) {
+            let holders = ids_with_component(world, comp);
+
+            if flags.contains(ComponentFlags::ON_DELETE_TARGET_PANIC) && !holders.is_empty() {
+                panic!(
+                    "deleting {} which is the target of {} on {} ids",
+                    id_name(world, tgt),
+                    id_name(world, comp),
+                    holders.len()
+                );
+            }
+
+            if flags.contains(ComponentFlags::ON_DELETE_TARGET_DELETE) {
+                ids.extend(holders.into_iter().filter(|&holder| visited.insert(holder)));
+            } else {
+                removes.extend(holders.into_iter().map(|holder| (holder, comp)));
+            }
+        }
+
+        // The delete policy of a deleted component applies to the ids using it.
+        let flags = world
+            .components
+            .get(tgt)
+            .map_or(ComponentFlags::empty(), |ci| ci.flags);
+
+        if flags.intersects(ComponentFlags::ON_DELETE_MASK) {
+            let uses = component_uses(world, tgt);
+
+            if flags.contains(ComponentFlags::ON_DELETE_PANIC) && !uses.is_empty() {
+                panic!(
+                    "deleting {} which is used by {} ids",
+                    id_name(world, tgt),
+                    uses.len()
+                );
+            }
+
+            if flags.contains(ComponentFlags::ON_DELETE_DELETE) {
+                let holders = uses.into_iter().map(|(holder, _)| holder);
+                ids.extend(holders.filter(|&holder| visited.insert(holder)));
+            } else {
+                removes.extend(uses);
+            }
+        }
+    }
+
+    for (holder, comp) in removes {
+        if !visited.contains(&holder) {
+            remove_component(world, holder, comp)?;
+        }
+    }
+
+    for &id in ids.iter().rev() {
+        for comp in components_of(world, id)? {
+            if let Some((tgt, mirror)) = symmetric_pair(world, id, comp)
+                && !visited.contains(&tgt)
+            {
+                remove_component(world, tgt, mirror)?;
+            }
+        }
+
+        despawn_id(world, id)?;
+    }
+
+    Ok(ids.len())
+}
+
+/// Returns the ids using `comp` with the component they have, which is `comp` itself
+/// or one of its pairs if it's a relationship.
+fn component_uses(world: &World, comp: Id) -> Vec<(Id, Id)> {
+    let mut uses: Vec<(Id, Id)> = ids_with_component(world, comp)
+        .into_iter()
+        .map(|holder| (holder, comp))
+        .collect();
+
+    if let Some(Storage::SparseRelation(store)) = world.components.get(comp).map(|ci| &ci.storage) {
+        for holder in store.ids() {
+            uses.extend(store.targets(holder).map(|tgt| (holder, pair(comp, tgt))));
+        }
+    }
+
+    let pairs = world
+        .components
+        .iter()
+        .filter(|&(pair, _)| pair.is_pair() && pair.pair_rel().index() == comp.index());
+
+    for (pair, _) in pairs {
+        uses.extend(
+            ids_with_component(world, pair)
+                .into_iter()
+                .map(|holder| (holder, pair)),
+        );
+    }
+
+    uses.sort_unstable();
+    uses.dedup();
+    uses
+}
+
+/// Returns the pairs targeting `tgt` whose relationship has a target policy, with its flags.
+fn pairs_targeting(world: &World, tgt: Id) -> Vec<(Id, ComponentFlags)> {
+    let is_target = world
+        .id_manager
+        .get_record(tgt)
+        .is_ok_and(|record| record.flags.contains(IdFlags::IS_TARGET));
+
+    if !is_target {
+        return vec![];
+    }
+
+    world
+        .components
+        .iter()
+        .filter(|(_, ci)| ci.flags.intersects(ComponentFlags::ON_DELETE_TARGET_MASK))
+        .filter_map(|(comp, ci)| match ci.storage {
+            Storage::SparseRelation(_) if comp.is_id() => Some((pair(comp, tgt), ci.flags)),
+            _ if comp.is_pair() && comp.pair_tgt().index() == tgt.index() => Some((comp, ci.flags)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks that `comp` is alive, or that both members are alive if it's a pair.
+pub(crate) fn check_component(world: &World, comp: Id) -> EcsResult<()> {
+    if !comp.is_pair() {
+        world.id_manager.get_location(comp)?;
+        return Ok(());
+    }
+
+    if world.id_manager.get_current(comp.pair_rel()).is_none() {
+        return Err(InvalidPair::Relationship(comp.pair_rel()).into());
+    }
+
+    if world.id_manager.get_current(comp.pair_tgt()).is_none() {
+        return Err(InvalidPair::Target(comp.pair_tgt()).into());
+    }
+
+    Ok(())
+}
+
+/// Creates the pair of a relationship and a target, checking that both are valid.
+pub(crate) fn checked_pair(world: &World, rel: Id, tgt: Id) -> Result<Id, InvalidPair> {
+    if !world.is_alive(rel) || rel.index() as u64 > Id::MAX_TGT_ID {
+        return Err(InvalidPair::Relationship(rel));
+    }
+
+    if !world.is_alive(tgt) || tgt.index() as u64 > Id::MAX_TGT_ID {
+        return Err(InvalidPair::Target(tgt));
+    }
+
+    Ok(pair(rel, tgt))
+}
+
+/// Sets the value of a component for an id, checking the id and the component type.
+///
+/// Returns the old value if the id already had the component.
+pub(crate) fn try_set_component<T: DataComponent>(
+    world: &mut World,
+    id: Id,
+    comp: Id,
+    val: T,
+) -> EcsResult<Option<T>> {
+    const_assert!(|T| size_of::<T>() != 0);
+
+    world.id_manager.get_location(id)?;
+
+    ensure_component(world, comp);
+
+    match component_type_info(world, comp) {
+        None => return Err(EcsError::IsTag(comp)),
+        Some(ti) if !ti.is::<T>() => return Err(EcsError::TypeMismatch(comp)),
+        Some(_) => {}
+    }
+
+    // SAFETY: We just checked that the type matches the component.
+    unsafe { set_component(world, id, comp, val) }
+}
+
+/// Gets a reference to the component value of an id.
+///
+/// # Safety
+/// - Caller must ensure that `T` is the same type and layout of the component.
+pub(crate) unsafe fn get_component<T: DataComponent>(
+    world: &World,
+    id: Id,
+    comp: Id,
+) -> GetResult<&T> {
+    world.id_manager.get_location(id)?;
+
+    match world
+        .components
+        .get(stored_relation(world, comp).unwrap_or(comp))
+    {
+        Some(ci) if ci.type_info.is_none() => return Err(GetError::IdNotComponent(comp)),
+        Some(_) => {}
+        // Pairs are only built once used, so the id can't have it.
+        None if comp.is_pair() => return Err(missing_component(world, comp)),
+        None => return Err(GetError::IdNotComponent(comp)),
+    }
+
+    match get_component_ptr(world, id, comp) {
+        // SAFETY: Caller ensures that the type matches the component.
+        Some(ptr) => Ok(unsafe { ptr.deref::<T>() }),
+        None => Err(missing_component(world, comp)),
+    }
+}
+
+/// Creates a [GetError::MissingComponent] with the storage checked for `comp`.
+///
+/// Pairs that were never built report the default storage.
+pub(crate) fn missing_component(world: &World, comp: Id) -> GetError {
+    let storage = world
+        .components
+        .get(stored_relation(world, comp).unwrap_or(comp))
+        .map_or(StorageType::default(), |ci| ci.storage.get_type());
+
+    GetError::MissingComponent(comp, storage)
+}
+
+/// Returns the target of the first `(rel, *)` pair of an id.
+pub(crate) fn first_target(world: &World, id: Id, rel: Id) -> EcsResult<Option<Id>> {
+    let target = components_of(world, id)?
+        .into_iter()
+        .find(|comp| comp.is_pair() && comp.pair_rel().index() == rel.index())
+        .and_then(|comp| world.id_manager.get_current(comp.pair_tgt()));
+
+    Ok(target)
+}
+
+/// Finds the first id reachable from `id` through `rel` that has `comp`, checking `id` first.
+///
+/// Targets are followed breadth-first for at most `max_depth` hops, only if `rel` is transitive.
+/// Ids that were already visited are skipped, so cycles terminate.
+pub(crate) fn find_transitive(
+    world: &World,
+    id: Id,
+    rel: Id,
+    comp: Id,
+    max_depth: usize,
+) -> Option<Ptr<'_>> {
+    let transitive = world
+        .components
+        .get(rel)
+        .is_some_and(|ci| ci.flags.contains(ComponentFlags::TRANSITIVE));
+
+    let mut visited = vec![id];
+    let mut current = vec![id];
+
+    for depth in 0..=max_depth {
+        let mut next = vec![];
+
+        for &id in &current {
+            if let Some(ptr) = get_component_ptr(world, id, comp) {
+                return Some(ptr);
+            }
+
+            if !transitive || depth == max_depth {
+                continue;
+            }
+
+            for tgt in components_of(world, id).ok()? {
+                if !tgt.is_pair() || tgt.pair_rel().index() != rel.index() {
+                    continue;
+                }
+
+                match world.id_manager.get_current(tgt.pair_tgt()) {
+                    Some(tgt) if !visited.contains(&tgt) => {
+                        visited.push(tgt);
+                        next.push(tgt);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+
+        current = next;
+    }
+
+    None
+}
+
+/// Writes a component value by copying its bytes, after [move_for_writes].
+///
+/// # Safety
+/// - `src` must point to a value of the component data type, which must not be used again.
+/// - `id` must not have a value for the component yet.
+pub(crate) unsafe fn write_component_raw(world: &mut World, id: Id, comp: Id, src: NonNull<u8>) {
+    let id_loc = world.id_manager.get_location(id).unwrap();
+
+    if let Some(rel) = stored_relation(world, comp) {
+        // SAFETY: Caller ensures that src matches the relationship and id doesn't have the pair.
+        unsafe { relation_store_mut(world, rel).insert_raw(id, comp.pair_tgt(), src) };
+        mark_changed(world, id, comp);
+        return;
+    }
+
+    let ci = world.components.get_mut(comp).unwrap();
+
+    match &mut ci.storage {
+        Storage::SparseTag(_) | Storage::SparseRelation(_) => unreachable!("tags have no value"),
+        // SAFETY: Caller ensures that src matches the component and id is not in the set.
+        Storage::SparseData(set) => unsafe { set.insert_raw(id, src) },
+        Storage::Tables(_) => {
+            let table = &mut world.table_index[id_loc.table];
+            let col = *table.column_map.get(comp).unwrap();
+
+            // SAFETY: Caller ensures that src matches the component.
+            unsafe { table.id_data.push_raw(col, src) };
+        }
+    }
+
+    mark_changed(world, id, comp);
+}
+
+/// Writes a component value initialized by `init` in a temporary, after [move_for_writes].
+///
+/// # Safety
+/// - `init` must write a valid value of the component data type to the pointer.
+/// - `id` must not have a value for the component yet.
+unsafe fn write_component_with(
+    world: &mut World,
+    id: Id,
+    comp: Id,
+    init: impl FnOnce(NonNull<u8>),
+) {
+    let ti = component_type_info(world, comp).unwrap();
+    let layout = Layout::from_size_align(ti.size, ti.align).unwrap();
+    let alloc = Rc::clone(&world.allocator);
+
+    let tmp = match layout.size() {
+        0 => (ti.dangling)(),
+        // SAFETY: The layout has a non-zero size.
+        _ => match NonNull::new(unsafe { alloc.alloc(layout) }) {
+            Some(tmp) => tmp,
+            None => std::alloc::handle_alloc_error(layout),
+        },
+    };
+
+    init(tmp);
+
+    // SAFETY:
+    // - Caller ensures init wrote a value of the component type.
+    // - The temporary is deallocated without dropping the value, so it's only used once.
+    // - The temporary was allocated by the same allocator with the same layout.
+    unsafe {
+        write_component_raw(world, id, comp, tmp);
+
+        if layout.size() != 0 {
+            alloc.dealloc(tmp.as_ptr(), layout);
+        }
+    }
+}
+
+/// Moves every id of `src` into `dst` under a new id and returns the mapping.
+///
+/// Components are matched by registered type, then by name if their data types match.
+/// Components missing from `dst` are created with the same type info.
+/// Pairs are remapped to the new relationship and target ids.
+///
+/// Returns an error without merging if an id of `src` has a guid already used in `dst`.
+pub(crate) fn merge_world(dst: &mut World, mut src: World) -> EcsResult<HashMap<Id, Id>> {
+    for (&guid, &id) in src.guids.iter() {
+        if guid_of(&src, id) == Some(guid) && dst.by_guid(guid).is_some() {
+            return Err(EcsError::GuidCollision(guid));
+        }
+    }
+
+    let mut map = HashMap::new();
+    map.insert(src.event_entity, dst.event_entity);
+    map.insert(src.pending_delete, dst.pending_delete);
+
+    for (index, comp) in src.type_arr.iter().enumerate() {
+        if let (Some(comp), Some(Some(dst_comp))) = (comp, dst.type_arr.get(index)) {
+            map.insert(*comp, *dst_comp);
+        }
+    }
+
+    for (type_id, &comp) in src.type_map.iter() {
+        if let Some(&dst_comp) = dst.type_map.get_by_id(type_id) {
+            map.insert(comp, dst_comp);
+        }
+    }
+
+    let same_data = |a: &ComponentInfo, b: &ComponentInfo| match (&a.type_info, &b.type_info) {
+        (Some(a), Some(b)) => (a.type_id)() == (b.type_id)(),
+        (None, None) => true,
+        _ => false,
+    };
+
+    for (comp, ci) in src.components.iter() {
+        if comp.is_pair() || ci.name.is_none() || map.contains_key(&comp) {
+            continue;
+        }
+
+        let found = dst
+            .components
+            .iter()
+            .find(|(id, dst_ci)| !id.is_pair() && dst_ci.name == ci.name && same_data(ci, dst_ci));
+
+        if let Some((dst_comp, _)) = found {
+            map.insert(comp, dst_comp);
+        }
+    }
+
+    // Every other id gets a new id in dst.
+    let mut moved = vec![];
+    let mut kept = vec![];
+
+    for (id, _) in src.id_manager.iter_alive() {
+        match map.entry(id) {
+            Entry::Occupied(_) => kept.push(id),
+            Entry::Vacant(entry) => {
+                entry.insert(new_raw_id(dst));
+                moved.push(id);
+            }
+        }
+    }
+
+    // Create the components dst doesn't have.
+    for &comp in &moved {
+        let Some(ci) = src.components.get(comp) else {
+            continue;
+        };
+
+        let dst_comp = map[&comp];
+        let storage = new_storage(
+            dst,
+            dst_comp,
+            ci.storage.get_type(),
+            ci.flags,
+            ci.type_info.as_ref(),
+        );
+
+        dst.components.insert(
+            dst_comp,
+            ComponentInfo {
+                id: dst_comp,
+                name: ci.name.clone(),
+                flags: ci.flags,
+                type_info: ci.type_info.as_ref().map(Rc::clone),
+                storage,
+                with: ci
+                    .with
+                    .iter()
+                    .filter_map(|id| map.get(id).copied())
+                    .collect(),
+                priority: ci.priority,
+            },
+        );
+    }
+
+    for (index, comp) in src.type_arr.iter().enumerate() {
+        if let Some(comp) = comp {
+            if index >= dst.type_arr.len() {
+                dst.type_arr.resize(index + 1, None);
+            }
+
+            dst.type_arr[index].get_or_insert(map[comp]);
+        }
+    }
+
+    for (type_id, comp) in src.type_map.iter() {
+        if dst.type_map.get_by_id(type_id).is_none() {
+            dst.type_map.insert_by_id(*type_id, map[comp]);
+        }
+    }
+
+    // Returns `None` for pairs whose relationship or target is dead in src.
+    let map_id = |src: &World, comp: Id| -> Option<Id> {
+        if comp.is_pair() {
+            let rel = src.id_manager.get_current(comp.pair_rel())?;
+            let tgt = src.id_manager.get_current(comp.pair_tgt())?;
+            Some(pair(map[&rel], map[&tgt]))
+        } else {
+            Some(map[&comp])
+        }
+    };
+
+    // Move the components of each id.
+    for &id in &moved {
+        let dst_id = map[&id];
+        let mut comps = vec![];
+        let mut dst_comps = vec![];
+
+        for comp in components_of(&src, id).unwrap() {
+            match map_id(&src, comp) {
+                Some(dst_comp) => {
+                    comps.push(comp);
+                    dst_comps.push(dst_comp);
+                }
+                // Dangling pairs are not merged, their values are dropped in src.
+                None => take_component(&mut src, id, comp).unwrap(),
+            }
+        }
+
+        for &comp in &dst_comps {
+            ensure_component(dst, comp);
+        }
+
+        move_for_writes(dst, dst_id, &dst_comps).unwrap();
+
+        for (&comp, &dst_comp) in comps.iter().zip(&dst_comps) {
+            match get_component_ptr(&src, id, comp) {
+                // SAFETY:
+                // - Components are only matched if their data types match.
+                // - The value is forgotten in src below, so it's never used again.
+                Some(ptr) => unsafe { write_component_raw(dst, dst_id, dst_comp, ptr.as_ptr()) },
+                None if stored_relation(dst, dst_comp).is_some() => {
+                    insert_tag(dst, dst_id, dst_comp).unwrap();
+                }
+                None => {
+                    if let Storage::SparseTag(set) =
+                        &mut dst.components.get_mut(dst_comp).unwrap().storage
+                    {
+                        set.insert(dst_id);
+                        record(dst, |_| Event::AddId {
+                            id: dst_id,
+                            comp: dst_comp,
+                        });
+                        set_id_flag(dst, dst_id, IdFlags::HAS_SPARSE);
+                    }
+                }
+            }
+        }
+    }
+
+    // Guids of moved ids were checked to be unused in dst above.
+    for &id in &moved {
+        if let Some(guid) = guid_of(dst, map[&id]) {
+            dst.guids.insert(guid, map[&id]);
+        }
+    }
+
+    // Ids that weren't moved drop their values normally.
+    for id in kept {
+        let _ = despawn_id(&mut src, id);
+    }
+
+    // The moved values are now owned by dst.
+    for table in src.table_index.all_tables_mut() {
+        table.id_data.forget_values();
+    }
+
+    for ci in src.components.values_mut() {
+        match &mut ci.storage {
+            Storage::SparseData(set) => set.forget_all(),
+            Storage::SparseRelation(store) => store.forget_all(),
+            _ => {}
+        }
+    }
+
+    Ok(map)
+}
+
+/// Creates a copy of a world with the same ids, tables and values.
+///
+/// Observers, the recorder and auto component scopes hold closures, so they're not copied.
+pub(crate) fn deep_clone(src: &World) -> EcsResult<World> {
+    let mut dst = World::empty(Rc::clone(&src.allocator));
+
+    for (comp, ci) in src.components.iter() {
+        let storage = match &ci.storage {
+            Storage::Tables(_) => Storage::Tables(HashMap::new()),
+            Storage::SparseTag(set) => Storage::SparseTag(set.clone()),
+            Storage::SparseData(set) => {
+                Storage::SparseData(set.try_clone().ok_or(EcsError::NoCloneHook(comp))?)
+            }
+            Storage::SparseRelation(store) => {
+                Storage::SparseRelation(store.try_clone().ok_or(EcsError::NoCloneHook(comp))?)
+            }
+        };
+
+        dst.components.insert(
+            comp,
+            ComponentInfo {
+                id: ci.id,
+                name: ci.name.clone(),
+                flags: ci.flags,
+                type_info: ci.type_info.as_ref().map(Rc::clone),
+                storage,
+                with: ci.with.clone(),
+                priority: ci.priority,
+            },
+        );
+    }
+
+    // Tables are created in the same order, so they get the same ids
+    // and the locations of the ids can be copied as is.
+    for table in src.table_index.all_tables() {
+        let dst_table = match table.id == src.root_table {
+            true => dst.root_table,
+            false => new_table(&mut dst, table.signature.clone()),
+        };
+
+        debug_assert_eq!(dst_table, table.id, "cloned table has a different id");
+
+        let id_data = table
+            .id_data
+            .try_clone()
+            .map_err(|col| EcsError::NoCloneHook(*table.id_data.column(col).id()))?;
+
+        dst.table_index[dst_table].id_data = id_data;
+        dst.table_index[dst_table].moves = table.moves.clone();
+        dst.table_index[dst_table].deletes = table.deletes;
+    }
+
+    *dst.id_manager = src.id_manager.clone();
+    dst.type_arr = src.type_arr.clone();
+    dst.type_map = src.type_map.clone();
+    dst.event_entity = src.event_entity;
+    dst.names_version = src.names_version;
+    dst.pending_delete = src.pending_delete;
+    dst.prefab = src.prefab;
+    dst.child_of = src.child_of;
+    dst.guid = src.guid;
+    dst.guids = src.guids.clone();
+    dst.target_generations = src.target_generations.clone();
+    dst.unions = src.unions.clone();
+    dst.variant_unions = src.variant_unions.clone();
+    dst.pending_deletions = src.pending_deletions.clone();
+    dst.deferred_deletions = src.deferred_deletions.clone();
+    dst.change_tick = src.change_tick;
+    dst.query_validation = src.query_validation;
+    dst.move_stats = src.move_stats;
+
+    Ok(dst)
+}
+
+/// Tables with fewer swap removes since their last defragmentation are left as is.
+const DEFRAG_THRESHOLD: usize = 16;
+
+/// Sorts the rows of fragmented tables by id, moving at most `budget` rows.
+/// Returns the number of rows moved.
+pub(crate) fn defragment(world: &mut World, budget: usize) -> usize {
+    let mut moved = 0;
+
+    for table in world.table_index.all_tables_mut() {
+        if moved == budget {
+            break;
+        }
+
+        if table.id_data.swap_removes() < DEFRAG_THRESHOLD {
+            continue;
+        }
+
+        let mut sorted = table.id_data.ids().to_vec();
+        sorted.sort_unstable();
+
+        let mut done = true;
+
+        for (row, &id) in sorted.iter().enumerate() {
+            if table.id_data.ids()[row] == id {
+                continue;
+            }
+
+            if moved == budget {
+                done = false;
+                break;
+            }
+
+            let from = world.id_manager.get_location(id).unwrap().row;
+            let displaced = table.id_data.ids()[row];
+
+            // SAFETY: Both rows are locations of ids in this table.
+            unsafe { table.id_data.swap_rows(row, from) };
+
+            world.id_manager.set_location(
+                id,
+                IdLocation {
+                    table: table.id,
+                    row,
+                },
+            );
+            world.id_manager.set_location(
+                displaced,
+                IdLocation {
+                    table: table.id,
+                    row: from,
+                },
+            );
+
+            moved += 1;
+        }
+
+        if done {
+            table.id_data.reset_swap_removes();
+        }
+    }
+
+    moved
+}
+
+/// Counts the ids that have both `a` and `b`.
+///
+/// Table components intersect their tables, sparse components check the ids of the
+/// smaller sparse set.
+pub(crate) fn component_overlap(world: &World, a: Id, b: Id) -> usize {
+    // Pairs of a relation store check the ids that have a pair of the relationship.
+    for (comp, other) in [(a, b), (b, a)] {
+        if let Some(rel) = stored_relation(world, comp) {
+            let store = relation_store(world, rel);
+
+            return store
+                .ids()
+                .filter(|&id| store.contains(id, comp.pair_tgt()))
+                .filter(|&id| has_component(world, id, other))
+                .count();
+        }
+    }
+
+    let (Some(ci_a), Some(ci_b)) = (world.components.get(a), world.components.get(b)) else {
+        return 0;
+    };
+
+    fn sparse_ids(storage: &Storage) -> Option<&[Id]> {
+        match storage {
+            Storage::SparseTag(set) => Some(set.ids()),
+            Storage::SparseData(set) => Some(set.ids()),
+            // The relationship itself is never added, only its pairs.
+            Storage::SparseRelation(_) => Some(&[]),
+            Storage::Tables(_) => None,
+        }
+    }
+
+    match (&ci_a.storage, &ci_b.storage) {
+        (Storage::Tables(tables_a), Storage::Tables(tables_b)) => {
+            let (small, large) = match tables_a.len() <= tables_b.len() {
+                true => (tables_a, tables_b),
+                false => (tables_b, tables_a),
+            };
+
+            small
+                .keys()
+                .filter(|&table| large.contains_key(table))
+                .map(|&table| world.table_index[table].id_data.row_count())
+                .sum()
+        }
+        (storage_a, storage_b) => {
+            let ids_a = sparse_ids(storage_a);
+            let ids_b = sparse_ids(storage_b);
+
+            let (ids, other) = match (ids_a, ids_b) {
+                (Some(ids_a), Some(ids_b)) if ids_b.len() < ids_a.len() => (ids_b, a),
+                (Some(ids_a), _) => (ids_a, b),
+                (None, Some(ids_b)) => (ids_b, a),
+                (None, None) => unreachable!(),
+            };
+
+            ids.iter()
+                .filter(|&&id| has_component(world, id, other))
+                .count()
+        }
+    }
+}
+
+/// Returns the ids that have `comp`.
+pub(crate) fn ids_with_component(world: &World, comp: Id) -> Vec<Id> {
+    if let Some(rel) = stored_relation(world, comp) {
+        let store = relation_store(world, rel);
+        let ids = store
+            .ids()
+            .filter(|&id| store.contains(id, comp.pair_tgt()));
+        return ids.collect();
+    }
+
+    let Some(ci) = world.components.get(comp) else {
+        return vec![];
+    };
+
+    match &ci.storage {
+        Storage::SparseTag(set) => set.ids().to_vec(),
+        Storage::SparseData(set) => set.ids().to_vec(),
+        Storage::SparseRelation(_) => vec![],
+        Storage::Tables(tables) => tables
+            .keys()
+            .flat_map(|&table| world.table_index[table].id_data.ids())
+            .copied()
+            .collect(),
+    }
+}
+
+/// Replaces `(rel, from)` with `(rel, to)` on every id that has it, returns how many were moved.
+///
+/// `rel` must be a tag. Tables holding the pair are moved at once, since all their rows have it.
+pub(crate) fn reparent_children(world: &mut World, rel: Id, from: Id, to: Id) -> EcsResult<usize> {
+    let old = checked_pair(world, rel, from)?;
+    let new = checked_pair(world, rel, to)?;
+
+    if old == new {
+        return Ok(0);
+    }
+
+    ensure_component(world, new);
+
+    // Pairs with a data target take its type, and only tag pairs can be moved as is.
+    for pair in [old, new] {
+        if component_type_info(world, pair).is_some() {
+            return Err(EcsError::IsNotTag(pair));
+        }
+    }
+
+    let tables: Option<Vec<_>> = match world.components.get(old).map(|ci| &ci.storage) {
+        Some(Storage::Tables(tables)) => Some(tables.keys().copied().collect()),
+        _ => None,
+    };
+
+    let Some(tables) = tables else {
+        let children = ids_with_component(world, old);
+
+        for &child in &children {
+            // SAFETY: The pairs are tags, so no value is read or written.
+            unsafe { replace_component::<(), ()>(world, child, old, new, None)? };
+        }
+
+        return Ok(children.len());
+    };
+
+    let mut moved = 0;
+
+    for table in tables {
+        let without = table_traverse_remove(world, table, old).unwrap();
+        // No destination means the ids already have the new pair.
+        let dst = table_traverse_add(world, without, new).unwrap_or(without);
+        moved += world.table_index[table].id_data.row_count();
+
+        // SAFETY:
+        // - dst doesn't have the old pair, so it's not the same table.
+        // - Only the tag pairs differ, so dst has the columns of src.
+        unsafe { move_all(world, table, dst) };
+    }
+
+    Ok(moved)
+}
+
+/// Collects `root` and all ids that reach it through `(rel, parent)` pairs, parents first.
+pub(crate) fn collect_subtree(world: &World, root: Id, rel: Id) -> Vec<Id> {
+    let mut ids = vec![root];
+    let mut visited = HashSet::from([root]);
+    let mut next = 0;
+
+    while let Some(&parent) = ids.get(next) {
+        next += 1;
+
+        for child in ids_with_component(world, pair(rel, parent)) {
+            if visited.insert(child) {
+                ids.push(child);
+            }
+        }
+    }
+
+    ids
+}
+
+/// Creates an instance of `prefab` and all its child prefabs, returns the instance of `prefab`.
+///
+/// Values are cloned with their clone hook, values without drop glue are copied.
+/// The `Prefab` tag is not copied, and children are attached to the new instances.
+/// The whole tree is checked first, nothing is created if a value can't be cloned.
+pub(crate) fn instantiate_prefab(world: &mut World, prefab: Id) -> EcsResult<Id> {
+    check_prefab_tree(world, prefab)?;
+    instantiate_node(world, prefab, None)
+}
+
+/// Returns the components of `prefab` that are copied to its instances.
+fn instance_components(world: &World, prefab: Id) -> EcsResult<Vec<Id>> {
+    let child_of = world.child_of;
+    let is_child_of = |comp: Id| comp.is_pair() && comp.pair_rel().index() == child_of.index();
+
+    // Guids are unique, so instances don't copy the guid of the prefab.
+    Ok(components_of(world, prefab)?
+        .into_iter()
+        .filter(|&comp| comp != world.prefab && comp != world.guid && !is_child_of(comp))
+        .collect())
+}
+
+/// Checks that every value in the tree of `prefab` can be copied.
+fn check_prefab_tree(world: &World, prefab: Id) -> EcsResult<()> {
+    for comp in instance_components(world, prefab)? {
+        if let Some(ti) = component_type_info(world, comp)
+            && ti.hooks.clone.is_none()
+            && ti.drop_fn.is_some()
+        {
+            return Err(EcsError::NoCloneHook(comp));
+        }
+    }
+
+    for child in prefab_children(world, prefab) {
+        check_prefab_tree(world, child)?;
+    }
+
+    Ok(())
+}
+
+/// Instantiates a prefab checked by [check_prefab_tree], see [instantiate_prefab].
+fn instantiate_node(world: &mut World, prefab: Id, parent: Option<Id>) -> EcsResult<Id> {
+    let comps = instance_components(world, prefab)?;
+    let id = new_raw_id(world);
+    move_for_writes(world, id, &comps)?;
+
+    for &comp in &comps {
+        let info = stored_relation(world, comp).unwrap_or(comp);
+        let ci = world.components.get(info).unwrap();
+
+        let Some(ptr) = get_component_ptr(world, prefab, comp) else {
+            if let Storage::SparseTag(_) | Storage::SparseRelation(_) = ci.storage {
+                add_tag(world, id, comp)?;
+            }
+            continue;
+        };
+
+        let ti = Rc::clone(ci.type_info.as_ref().unwrap());
+        let src = ptr.as_ptr();
+
+        // SAFETY:
+        // - Values without a clone hook have no drop glue, so their bytes can be copied.
+        // - init clones into a temporary before anything is written, so `src` is still valid.
+        unsafe {
+            write_component_with(world, id, comp, |dst| match &ti.hooks.clone {
+                Some(clone) => clone(src, dst),
+                None => std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_ptr(), ti.size),
+            })
+        };
+    }
+
+    if let Some(parent) = parent {
+        add_tag(world, id, pair(world.child_of, parent))?;
+    }
+
+    // Scopes apply once the values are written, since writing requires an empty id.
+    apply_auto_components(world, id);
+
+    for child in prefab_children(world, prefab) {
+        instantiate_node(world, child, Some(id))?;
+    }
+
+    Ok(id)
+}
+
+/// Returns the child prefabs of `prefab`.
+fn prefab_children(world: &World, prefab: Id) -> Vec<Id> {
+    ids_with_component(world, pair(world.child_of, prefab))
+        .into_iter()
+        .filter(|&child| has_component(world, child, world.prefab))
+        .collect()
+}
+
+/// Returns the number of prefabs in the tree of `prefab` that have child prefabs.
+pub(crate) fn prefab_parent_count(world: &World, prefab: Id) -> usize {
+    let children = prefab_children(world, prefab);
+
+    if children.is_empty() {
+        return 0;
+    }
+
+    1 + children
+        .into_iter()
+        .map(|child| prefab_parent_count(world, child))
+        .sum::<usize>()
+}
+
+/// Checks if the value of a component can be serialized, tags have no value.
+fn is_serializable(ci: &ComponentInfo) -> bool {
+    ci.type_info
+        .as_ref()
+        .is_none_or(|ti| ti.hooks.pod || ti.hooks.serialize.is_some())
+}
+
+/// Appends the named components of `id` to `buf` and returns the number of bytes written.
+///
+/// Format: component count (u32), then for each component its name length (u16),
+/// name bytes, layout version (u32), data size (u32) and data bytes.
+/// All integers are little endian. Nothing is appended if an error is returned.
+pub(crate) fn serialize_entity(world: &World, id: Id, buf: &mut Vec<u8>) -> EcsResult<usize> {
+    let start = buf.len();
+    let comps: Vec<_> = components_of(world, id)?
+        .into_iter()
+        .filter(|&comp| !comp.is_pair() && comp != world.pending_delete)
+        .filter_map(|comp| world.components.get(comp))
+        .filter(|ci| ci.name.is_some() && is_serializable(ci))
+        .collect();
+
+    if let Err(err) = write_entity(world, id, &comps, buf) {
+        buf.truncate(start);
+        return Err(err);
+    }
+
+    Ok(buf.len() - start)
+}
+
+/// Prefix of the entities written by [serialize_entity], followed by [ENTITY_FORMAT].
+///
+/// Entities written before the prefix start with their component count, which never
+/// reaches these bytes read as a count, so both layouts can be told apart.
+const ENTITY_MAGIC: [u8; 4] = *b"XECS";
+
+/// Format of the entities written by [serialize_entity], with a layout version per component.
+/// Entities without [ENTITY_MAGIC] have no versions, their components are read as version 0.
+const ENTITY_FORMAT: u16 = 1;
+
+/// Writes the components of [serialize_entity].
+fn write_entity(
+    world: &World,
+    id: Id,
+    comps: &[&ComponentInfo],
+    buf: &mut Vec<u8>,
+) -> EcsResult<()> {
+    let count =
+        u32::try_from(comps.len()).map_err(|_| EcsError::DataTooLarge("component count"))?;
+    buf.extend_from_slice(&ENTITY_MAGIC);
+    buf.extend_from_slice(&ENTITY_FORMAT.to_le_bytes());
+    buf.extend_from_slice(&count.to_le_bytes());
+
+    let mut data = vec![];
+
+    for ci in comps {
+        let name = ci.name.as_ref().unwrap().as_bytes();
+        let len =
+            u16::try_from(name.len()).map_err(|_| EcsError::DataTooLarge("component name"))?;
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(name);
+
+        let version = ci.type_info.as_ref().map_or(0, |ti| ti.hooks.version);
+        buf.extend_from_slice(&version.to_le_bytes());
+
+        data.clear();
+
+        if let (Some(ti), Some(ptr)) = (&ci.type_info, get_component_ptr(world, id, ci.id)) {
+            match &ti.hooks.serialize {
+                Some(ser) => ser(ptr.as_ptr(), &mut data),
+                // SAFETY:
+                // - The pointer is valid for a value of the component size.
+                // - Values without a serialize hook are plain old data, they have no padding.
+                None => data.extend_from_slice(unsafe {
+                    std::slice::from_raw_parts(ptr.as_ptr().as_ptr(), ti.size)
+                }),
+            }
+        }
+
+        let size =
+            u32::try_from(data.len()).map_err(|_| EcsError::DataTooLarge("component data"))?;
+        buf.extend_from_slice(&size.to_le_bytes());
+        buf.extend_from_slice(&data);
+    }
+
+    Ok(())
+}
+
+/// Creates an id with the components written by [serialize_entity].
+///
+/// Components are looked up by name, the data is validated before the id is created.
+/// Entities written before [ENTITY_MAGIC] was added are read as version 0.
+pub(crate) fn deserialize_entity(world: &mut World, mut buf: &[u8]) -> EcsResult<Id> {
+    fn take<'a>(buf: &mut &'a [u8], n: usize) -> EcsResult<&'a [u8]> {
+        if buf.len() < n {
+            return Err(EcsError::MalformedData("unexpected end of data"));
+        }
+
+        let (bytes, rest) = buf.split_at(n);
+        *buf = rest;
+        Ok(bytes)
+    }
+
+    let versioned = buf.starts_with(&ENTITY_MAGIC);
+
+    if versioned {
+        take(&mut buf, ENTITY_MAGIC.len())?;
+        let format = u16::from_le_bytes(take(&mut buf, 2)?.try_into().unwrap());
+
+        if format != ENTITY_FORMAT {
+            return Err(EcsError::MalformedData("unknown format version"));
+        }
+    }
+
+    let count = u32::from_le_bytes(take(&mut buf, 4)?.try_into().unwrap());
+    let mut entries = vec![];
+
+    for _ in 0..count {
+        let len = u16::from_le_bytes(take(&mut buf, 2)?.try_into().unwrap());
+        let name = std::str::from_utf8(take(&mut buf, len as usize)?)
+            .map_err(|_| EcsError::MalformedData("component name is not utf-8"))?;
+
+        let version = match versioned {
+            true => u32::from_le_bytes(take(&mut buf, 4)?.try_into().unwrap()),
+            false => 0,
+        };
+        let size = u32::from_le_bytes(take(&mut buf, 4)?.try_into().unwrap());
+        let data = take(&mut buf, size as usize)?;
+
+        let found = world
+            .components
+            .iter()
+            .find(|(comp, ci)| !comp.is_pair() && ci.name.as_deref() == Some(name));
+
+        let Some((comp, ci)) = found else {
+            return Err(EcsError::UnknownComponent(name.to_string()));
+        };
+
+        // Values of other versions are converted by a migration hook, checked below.
+        let valid = match &ci.type_info {
+            None => data.is_empty(),
+            Some(ti) if ti.hooks.version != version => {
+                if !ti.hooks.migrations.iter().any(|&(from, _)| from == version) {
+                    return Err(EcsError::MissingMigration(comp, version, ti.hooks.version));
+                }
+                true
+            }
+            Some(ti) if ti.hooks.deserialize.is_some() => true,
+            Some(ti) => ti.hooks.pod && data.len() == ti.size,
+        };
+
+        if !valid {
+            return Err(EcsError::MalformedData(
+                "component data doesn't match its type",
+            ));
+        }
+
+        if entries.iter().any(|&(other, _, _)| other == comp) {
+            return Err(EcsError::DuplicateComponent(comp));
+        }
+
+        entries.push((comp, version, data));
+    }
+
+    // The guid is checked before the id is created, its size was validated above.
+    let guid = entries
+        .iter()
+        .find(|&&(comp, _, _)| comp == world.guid)
+        .map(|&(_, _, data)| u128::from_ne_bytes(data.try_into().unwrap()));
+
+    if let Some(guid) = guid
+        && world.by_guid(guid).is_some()
+    {
+        return Err(EcsError::GuidCollision(guid));
+    }
+
+    let id = new_raw_id(world);
+
+    if let Err(err) = read_entity(world, id, entries) {
+        despawn_id(world, id)?;
+        return Err(err);
+    }
+
+    // Scopes apply once the values are read, since reading requires an empty id.
+    apply_auto_components(world, id);
+
+    if let Some(guid) = guid {
+        world.guids.insert(guid, id);
+    }
+
+    Ok(id)
+}
+
+/// Writes the validated components of [deserialize_entity] to the new `id`.
+fn read_entity(world: &mut World, id: Id, entries: Vec<(Id, u32, &[u8])>) -> EcsResult<()> {
+    let comps: Vec<_> = entries.iter().map(|&(comp, _, _)| comp).collect();
+    move_for_writes(world, id, &comps)?;
+
+    for (comp, version, data) in entries {
+        let ci = world.components.get(comp).unwrap();
+
+        let Some(ti) = ci.type_info.as_ref().map(Rc::clone) else {
+            if let Storage::SparseTag(_) = ci.storage {
+                add_tag(world, id, comp)?;
+            }
+            continue;
+        };
+
+        let migration = ti
+            .hooks
+            .migrations
+            .iter()
+            .find(|&&(from, _)| from == version && version != ti.hooks.version)
+            .map(|(_, migrate)| migrate);
+
+        // SAFETY:
+        // - Values without a deserialize hook are plain old data and were checked to have
+        //   the size of the type, so their bytes can be copied.
+        // - Values of another version were checked to have a migration hook.
+        // - The component was just added to id, so it has no value yet.
+        unsafe {
+            write_component_with(world, id, comp, |dst| {
+                match (migration, &ti.hooks.deserialize) {
+                    (Some(migrate), _) => migrate(data, dst),
+                    (None, Some(de)) => de(data, dst),
+                    (None, None) => {
+                        std::ptr::copy_nonoverlapping(data.as_ptr(), dst.as_ptr(), ti.size)
+                    }
+                }
+            })
+        };
+    }
+
+    Ok(())
+}
+
+/// Applies events recorded by [World::enable_recording](crate::world::World::enable_recording).
+///
+/// Ids spawned by the events are mapped to new ids, other ids are used as is.
+/// Symmetric pairs and auto tags are not added again, they were recorded as their own events.
+pub(crate) fn replay_events(
+    world: &mut World,
+    events: impl IntoIterator<Item = Event>,
+) -> EcsResult<()> {
+    // Pairs only store indices, so spawned ids are mapped by index.
+    let mut spawned = HashMap::<u32, Id>::new();
+
+    let map_id = |spawned: &HashMap<u32, Id>, id: Id| match spawned.get(&id.index()) {
+        Some(&new) => new,
+        None => id,
+    };
+
+    let map_comp = |spawned: &HashMap<u32, Id>, comp: Id| match comp.is_pair() {
+        true => pair(
+            map_id(spawned, comp.pair_rel()),
+            map_id(spawned, comp.pair_tgt()),
+        ),
+        false => map_id(spawned, comp),
+    };
+
+    for event in events {
+        match event {
+            Event::Spawn { id } => {
+                // Components of the replayed id are set by the recorded events.
+                let new = new_raw_id(world);
+                spawned.insert(id.index(), new);
+            }
+            Event::Despawn { id } => despawn_id(world, map_id(&spawned, id))?,
+            Event::AddId { id, comp } => {
+                let (id, comp) = (map_id(&spawned, id), map_comp(&spawned, comp));
+                ensure_component(world, comp);
+
+                // Values of data components are added by the following Set.
+                if component_type_info(world, comp).is_none() {
+                    insert_tag(world, id, comp)?;
+                }
+            }
+            Event::Remove { id, comp } => {
+                take_component(world, map_id(&spawned, id), map_comp(&spawned, comp))?
+            }
+            Event::Set { id, comp, data } => {
+                let (id, comp) = (map_id(&spawned, id), map_comp(&spawned, comp));
+                replay_set(world, id, comp, data)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the value of a recorded [Event::Set], replacing the current value if any.
+fn replay_set(world: &mut World, id: Id, comp: Id, data: SetData) -> EcsResult<()> {
+    world.id_manager.get_location(id)?;
+    ensure_component(world, comp);
+
+    // Guids are written through the index so a replay can't duplicate them.
+    if comp == world.guid {
+        return match &data {
+            SetData::Bytes(bytes) => match <[u8; 16]>::try_from(&bytes[..]) {
+                Ok(bytes) => set_guid(world, id, u128::from_ne_bytes(bytes)),
+                Err(_) => Err(EcsError::MalformedData("recorded guid has the wrong size")),
+            },
+            SetData::Marker => Err(EcsError::MalformedData("recorded guid has no value")),
+        };
+    }
+
+    let Some(ti) = component_type_info(world, comp).cloned() else {
+        return Err(EcsError::IsTag(comp));
+    };
+
+    let valid = match &data {
+        SetData::Bytes(bytes) => {
+            ti.hooks.deserialize.is_some() || (ti.hooks.pod && bytes.len() == ti.size)
+        }
+        SetData::Marker => ti.hooks.default.is_some(),
+    };
+
+    if !valid {
+        return Err(EcsError::MalformedData(
+            "recorded value can't be written to its component",
+        ));
+    }
+
+    // SAFETY: The value was checked to be writable above.
+    let init = |dst: NonNull<u8>| match (&data, &ti.hooks.deserialize, &ti.hooks.default) {
+        (SetData::Bytes(bytes), Some(de), _) => de(bytes, dst),
+        (SetData::Bytes(bytes), None, _) => unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), dst.as_ptr(), ti.size)
+        },
+        (SetData::Marker, _, Some(default)) => default(dst),
+        (SetData::Marker, _, None) => unreachable!("checked above"),
+    };
+
+    match get_component_ptr_mut(world, id, comp) {
+        Some(ptr) => {
+            // SAFETY: The pointer holds a value of the component, which is replaced.
+            if let Some(drop_fn) = ti.drop_fn {
+                unsafe { drop_fn(ptr.as_ptr().as_ptr()) };
+            }
+
+            init(ptr.as_ptr());
+            mark_changed(world, id, comp);
+        }
+        None => {
+            move_for_writes(world, id, &[comp])?;
+
+            // SAFETY: init writes a value of the component, and id has no value yet.
+            unsafe { write_component_with(world, id, comp, init) };
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes `old` from `id` and adds `new`, with at most one table move.
+/// Returns the value of `old`, `None` if it's a tag or `id` didn't have it.
+///
+/// `val` is `None` if `new` is a tag.
+///
+/// # Safety
+/// - `O` must be the data type of `old` if it has data.
+/// - `N` must be the data type of `new` if it has data.
+pub(crate) unsafe fn replace_component<O: 'static, N: 'static>(
+    world: &mut World,
+    id: Id,
+    old: Id,
+    new: Id,
+    val: Option<N>,
+) -> EcsResult<Option<O>> {
+    let id_loc = world.id_manager.get_location(id)?;
+    ensure_component(world, new);
+
+    let is_tag = component_type_info(world, new).is_none();
+
+    match (is_tag, &val) {
+        (true, Some(_)) => return Err(EcsError::IsTag(new)),
+        (false, None) => return Err(EcsError::IsNotTag(new)),
+        _ => {}
+    }
+
+    // Replacing a value with itself is done in place.
+    if old == new
+        && let Some(ptr) = get_component_ptr_mut(world, id, old)
+    {
+        // SAFETY:
+        // - Caller ensures that O and N match the component.
+        // - The old value is read out before it's overwritten.
+        let old_val = unsafe {
+            let ptr = ptr.as_ptr();
+            let old_val = ptr.cast::<O>().read();
+            ptr.cast::<N>().write(val.unwrap());
+            old_val
+        };
+
+        mark_changed(world, id, new);
+        return Ok(Some(old_val));
+    }
+
+    let had_new = has_component(world, id, new);
+    let old_rel = stored_relation(world, old);
+    let mut dst = id_loc.table;
+    let mut forget = None;
+
+    let old_val = match world.components.get_mut(old).map(|ci| &mut ci.storage) {
+        None => match old_rel {
+            Some(rel) => {
+                let store = relation_store_mut(world, rel);
+                let tgt = old.pair_tgt();
+                let had_old = store.contains(id, tgt);
+
+                let val = match store.is_tag() {
+                    true => {
+                        store.remove(id, tgt);
+                        None
+                    }
+                    // SAFETY: Caller ensures that O matches the relationship.
+                    false => unsafe { store.take::<O>(id, tgt) },
+                };
+
+                if had_old {
+                    record(world, |_| Event::Remove { id, comp: old });
+                }
+                val
+            }
+            None => None,
+        },
+        Some(Storage::SparseRelation(_)) => None,
+        Some(Storage::SparseTag(set)) => {
+            if set.contains(id) {
+                set.remove(id);
+                record(world, |_| Event::Remove { id, comp: old });
+            }
+            None
+        }
+        Some(Storage::SparseData(set)) => {
+            // SAFETY: Caller ensures that O matches the component.
+            let val = unsafe { set.take::<O>(id) };
+
+            if val.is_some() {
+                record(world, |_| Event::Remove { id, comp: old });
+            }
+            val
+        }
+        Some(Storage::Tables(tables)) => {
+            if tables.contains_key(&id_loc.table) {
+                dst = table_traverse_remove(world, dst, old).unwrap();
+            }
+
+            let table = &world.table_index[id_loc.table];
+
+            // SAFETY:
+            // - Valid id must have valid table and row.
+            // - Caller ensures that O matches the component.
+            // - The value is not dropped by the table move below.
+            table.column_map.get(old).map(|&col| unsafe {
+                forget = Some(old);
+                table.id_data.column_ref(col).read::<O>(id_loc.row)
+            })
+        }
+    };
+
+    let in_tables = world
+        .components
+        .get(new)
+        .is_some_and(|ci| matches!(ci.storage, Storage::Tables(_)));
+
+    if in_tables && let Some(next) = table_traverse_add(world, dst, new) {
+        dst = next;
+    }
+
+    if dst != id_loc.table {
+        let forget: &[Id] = forget.as_slice();
+
+        // SAFETY:
+        // - We ensured that dst is not the same as src.
+        // - id is valid, which means that src_row must be valid.
+        // - The value of old was read out above.
+        unsafe { move_id_forget(world, id, id_loc.table, id_loc.row, dst, forget) };
+    }
+
+    match (val, had_new) {
+        (Some(val), true) => {
+            let ptr = get_component_ptr_mut(world, id, new).unwrap();
+
+            // SAFETY: Caller ensures that N matches the component.
+            drop(std::mem::replace(unsafe { ptr.deref_mut::<N>() }, val));
+            mark_changed(world, id, new);
+        }
+        // SAFETY: Caller ensures that N matches the component, and id was moved to its table.
+        (Some(val), false) => unsafe {
+            write_component_with(world, id, new, |dst| dst.cast::<N>().write(val))
+        },
+        (None, _) if stored_relation(world, new).is_some() => insert_tag(world, id, new)?,
+        (None, _) => {
+            if let Storage::SparseTag(set) = &mut world.components.get_mut(new).unwrap().storage
+                && !set.contains(id)
+            {
+                set.insert(id);
+                record(world, |_| Event::AddId { id, comp: new });
+                set_id_flag(world, id, IdFlags::HAS_SPARSE);
+            }
+        }
+    }
+
+    add_auto_components(world, id, new)?;
+    deactivate_variant(world, id, old)?;
+    Ok(old_val)
+}
+
+/// Moves all values of `comp` to a new storage of type `to`, see [World::migrate_storage].
+///
+/// Relationships can't be migrated, a sparse relationship stores all its pairs together.
+pub(crate) fn migrate_storage(world: &mut World, comp: Id, to: StorageType) -> EcsResult<()> {
+    let Some(ci) = world.components.get(comp) else {
+        return Err(EcsError::IdNotComponent(comp));
+    };
+
+    if ci.flags.contains(ComponentFlags::RELATIONSHIP) {
+        return Err(EcsError::IsRelationship(comp));
+    }
+
+    if ci.storage.get_type() == to {
+        return Ok(());
+    }
+
+    match to {
+        StorageType::Sparse => tables_to_sparse(world, comp),
+        StorageType::Tables => sparse_to_tables(world, comp),
+    }
+}
+
+/// Moves the values of `comp` out of its tables into a new sparse storage.
+fn tables_to_sparse(world: &mut World, comp: Id) -> EcsResult<()> {
+    let ci = world.components.get(comp).unwrap();
+    let mut storage = new_storage(
+        world,
+        comp,
+        StorageType::Sparse,
+        ci.flags,
+        ci.type_info.as_ref(),
+    );
+
+    let tables: Vec<_> = match &ci.storage {
+        Storage::Tables(tables) => tables.keys().copied().collect(),
+        _ => unreachable!("component is not stored in tables"),
+    };
+
+    let mut moved = vec![];
+
+    for table in tables {
+        let dst = table_traverse_remove(world, table, comp).unwrap();
+
+        for id in world.table_index[table].id_data.ids().to_vec() {
+            // Rows shift as ids are moved out, so locate each id again.
+            let id_loc = world.id_manager.get_location(id)?;
+
+            match &mut storage {
+                Storage::SparseTag(set) => set.insert(id),
+                Storage::SparseData(set) => {
+                    let ptr = get_component_ptr(world, id, comp).unwrap();
+
+                    // SAFETY:
+                    // - The set was created with the type info of the component.
+                    // - The value is forgotten by the table when the id is moved below.
+                    unsafe { set.insert_raw(id, ptr.as_ptr()) };
+                }
+                _ => unreachable!("relationships are not migrated"),
+            }
+
+            // SAFETY:
+            // - dst doesn't have the component, so it's not the same as src.
+            // - id is valid, which means that src_row must be valid.
+            // - The value was moved to the set above.
+            unsafe { move_id_forget(world, id, id_loc.table, id_loc.row, dst, &[comp]) };
+            moved.push(id);
+        }
+    }
+
+    world.components.get_mut(comp).unwrap().storage = storage;
+
+    for id in moved {
+        set_id_flag(world, id, IdFlags::HAS_SPARSE);
+        mark_changed(world, id, comp);
+    }
+
+    Ok(())
+}
+
+/// Moves the values of `comp` out of its sparse storage into tables.
+fn sparse_to_tables(world: &mut World, comp: Id) -> EcsResult<()> {
+    // Tables left by a previous migration are still linked in the graph, so they are reused.
+    let records = world
+        .table_index
+        .all_tables()
+        .iter()
+        .filter(|table| table.signature.has_id(comp))
+        .map(|table| {
+            let col_idx = table.column_map.get(comp).copied();
+            (table.id, ComponentLocation { col_idx })
+        })
+        .collect();
+
+    let ci = world.components.get_mut(comp).unwrap();
+    let old = std::mem::replace(&mut ci.storage, Storage::Tables(records));
+
+    match old {
+        Storage::SparseTag(set) => {
+            for id in set.iter().collect::<Vec<_>>() {
+                move_for_writes(world, id, &[comp])?;
+            }
+        }
+        Storage::SparseData(mut set) => {
+            // Values are forgotten as they move, so an error drops only the ones left.
+            for id in set.ids().to_vec() {
+                move_for_writes(world, id, &[comp])?;
+                let ptr = set.get_ptr(id).unwrap();
+
+                // SAFETY:
+                // - The set holds values of the component type.
+                // - The id was moved to a table with an empty slot in the column.
+                // - The set forgets the value right after, so it's not used again.
+                unsafe { write_component_raw(world, id, comp, ptr) };
+                set.forget(id);
+            }
+        }
+        _ => unreachable!("component is not stored in a sparse set"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::ComponentBuilder;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use xecs_macros::Component;
+
+    #[derive(Component)]
+    struct Health(#[allow(dead_code)] u32);
+
+    #[test]
+    fn stale_rows_are_reported() {
+        let mut world = World::new();
+        world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+
+        let [a, b] = [(); 2].map(|_| world.new_id());
+        world.set::<Health>(a, Health(1));
+        world.set::<Health>(b, Health(2));
+        assert!(check_invariants(&world).is_empty());
+
+        // Point a at the row of b, as if a swap remove forgot to update it.
+        let loc = world.id_manager.get_location(a).unwrap();
+        world.id_manager.set_location(
+            a,
+            IdLocation {
+                row: loc.row + 1,
+                ..loc
+            },
+        );
+
+        let violations = check_invariants(&world);
+        assert_eq!(
+            violations,
+            [
+                InvariantViolation::InvalidLocation(a, loc.row + 1),
+                InvariantViolation::UnrecordedRow(a, loc.row),
+            ]
+        );
+
+        if cfg!(debug_assertions) {
+            let panic = catch_unwind(AssertUnwindSafe(|| world.assert_invariants())).unwrap_err();
+            let message = panic.downcast_ref::<String>().unwrap();
+
+            for violation in &violations {
+                assert!(message.contains(&violation.to_string()));
+            }
+        }
+    }
+}
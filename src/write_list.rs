@@ -0,0 +1,82 @@
+use crate::{
+    error::{EcsError, EcsResult},
+    id::Id,
+    registration::ComponentId,
+    type_traits::DataComponent,
+    world::World,
+    world_utils::write_component,
+};
+use private::Sealed;
+use xecs_macros::all_tuples;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A list of component values written to an id at once by [World::set_many].
+///
+/// Implemented for tuples of data components, e.g. `(Position, Velocity)`.
+pub trait ComponentWriteList: Sealed {
+    /// Resolves and validates the component ids of the list, in order.
+    #[doc(hidden)]
+    fn resolve(world: &World, comps: &mut Vec<Id>) -> EcsResult<()>;
+
+    /// Writes the values of the list to `id`.
+    ///
+    /// # Safety
+    /// - `comps` must be the ids returned by [resolve](ComponentWriteList::resolve).
+    /// - `id` must already be in a table with columns for all table components in `comps`.
+    #[doc(hidden)]
+    unsafe fn write(self, world: &mut World, id: Id, comps: &[Id]);
+}
+
+/// Checks that `T` is registered with data of type `T` and is not already in `comps`.
+fn resolve_one<T: ComponentId + DataComponent>(
+    world: &World,
+    comps: &mut Vec<Id>,
+) -> EcsResult<()> {
+    let comp = <T as ComponentId>::id(world)?;
+
+    let ci = match world.components.get(comp) {
+        Some(ci) => ci,
+        None => return Err(EcsError::IdNotComponent(comp)),
+    };
+
+    match &ci.type_info {
+        None => return Err(EcsError::IsTag(comp)),
+        Some(ti) if !ti.is::<T>() => return Err(EcsError::TypeMismatch(comp)),
+        Some(_) => {}
+    }
+
+    if comps.contains(&comp) {
+        return Err(EcsError::DuplicateComponent(comp));
+    }
+
+    comps.push(comp);
+    Ok(())
+}
+
+macro_rules! impl_tuple_write_list {
+    ($($t:ident),*) => {
+        impl<$($t: ComponentId + DataComponent),*> Sealed for ($($t,)*) {}
+        impl<$($t: ComponentId + DataComponent),*> ComponentWriteList for ($($t,)*) {
+            fn resolve(world: &World, comps: &mut Vec<Id>) -> EcsResult<()> {
+                $(resolve_one::<$t>(world, comps)?;)*
+                Ok(())
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn write(self, world: &mut World, id: Id, comps: &[Id]) {
+                let ($($t,)*) = self;
+                let mut comps = comps.iter();
+
+                // SAFETY:
+                // - comps were resolved from the same types, in the same order.
+                // - Caller ensures that id has columns for all table components.
+                $(unsafe { write_component(world, id, *comps.next().unwrap(), $t) };)*
+            }
+        }
+    }
+}
+
+all_tuples!(impl_tuple_write_list, 1, 13);
@@ -0,0 +1,83 @@
+use std::{alloc::Layout, cell::Cell, rc::Rc};
+use xecs::{
+    allocator::{DefaultAlloc, WorldAlloc},
+    arena::ArenaVec,
+    component::ComponentBuilder,
+    storage::StorageType,
+    world::{World, WorldBuilder},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Velocity(#[allow(dead_code)] [f64; 2]);
+
+/// Bytes allocated and not yet freed through the allocator.
+#[derive(Default)]
+struct Usage {
+    live: Cell<isize>,
+    blocks: Cell<isize>,
+}
+
+struct CountingAlloc(Rc<Usage>);
+
+unsafe impl WorldAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0.live.set(self.0.live.get() + layout.size() as isize);
+        self.0.blocks.set(self.0.blocks.get() + 1);
+        unsafe { DefaultAlloc.alloc(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let grown = new_size as isize - layout.size() as isize;
+        self.0.live.set(self.0.live.get() + grown);
+        unsafe { DefaultAlloc.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.live.set(self.0.live.get() - layout.size() as isize);
+        self.0.blocks.set(self.0.blocks.get() - 1);
+        unsafe { DefaultAlloc.dealloc(ptr, layout) }
+    }
+}
+
+fn counted_world() -> (World, Rc<Usage>) {
+    let usage = Rc::new(Usage::default());
+    let world = WorldBuilder::new()
+        .with_allocator(CountingAlloc(Rc::clone(&usage)))
+        .build();
+    (world, usage)
+}
+
+#[test]
+fn column_memory_goes_through_the_world_allocator() {
+    let (mut world, usage) = counted_world();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+
+    for i in 0..100 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 2 == 0 {
+            world.set::<Velocity>(id, Velocity([i as f64; 2]));
+        }
+    }
+
+    let columns = world.component_stats(pos).unwrap().capacity_bytes
+        + world.component_stats(vel).unwrap().capacity_bytes;
+    assert!(columns > 0);
+    assert!(usage.live.get() >= columns as isize);
+
+    let before = usage.live.get();
+    {
+        let mut values = ArenaVec::new_in(world.frame_arena());
+        values.extend(0..1000u64);
+    }
+    assert!(usage.live.get() >= before + 8000);
+
+    drop(world);
+    assert_eq!((usage.live.get(), usage.blocks.get()), (0, 0));
+}
@@ -0,0 +1,59 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Health(f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Regen(f32);
+
+fn register(world: &mut World, storage: StorageType) {
+    let regen = world.register::<Regen>(
+        ComponentBuilder::new()
+            .storage(storage)
+            .default(|| Regen(0.5)),
+    );
+
+    world.register::<Health>(ComponentBuilder::new().storage(storage).auto_add(regen));
+}
+
+#[test]
+fn data_component_is_auto_added() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        register(&mut world, storage);
+
+        let id = world.new_id();
+        world.set::<Health>(id, Health(10.0));
+
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(10.0)));
+        assert_eq!(world.get::<&Regen>(id).ok(), Some(&Regen(0.5)));
+    }
+}
+
+#[test]
+fn auto_add_keeps_existing_value() {
+    let mut world = World::new();
+    register(&mut world, StorageType::Tables);
+
+    let id = world.new_id();
+    world.set::<Regen>(id, Regen(2.0));
+    world.set::<Health>(id, Health(10.0));
+
+    assert_eq!(world.get::<&Regen>(id).ok(), Some(&Regen(2.0)));
+}
+
+#[test]
+fn auto_add_without_default_is_rejected() {
+    let mut world = World::new();
+    let regen = world.register::<Regen>(ComponentBuilder::new());
+
+    let result = world.try_register::<Health>(ComponentBuilder::new().auto_add(regen));
+
+    assert!(matches!(result, Err(EcsError::NoDefaultHook(id)) if id == regen));
+}
@@ -0,0 +1,82 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct Team(u32);
+
+#[derive(Component)]
+struct Spawned;
+
+fn new_world() -> World {
+    let mut world = World::new();
+    world.register::<Team>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .clone(Team::clone),
+    );
+    world.register::<Spawned>(TagBuilder::new());
+    world
+}
+
+#[test]
+fn nested_scopes_add_all_components() {
+    let mut world = new_world();
+    let spawned = world.id::<Spawned>().unwrap();
+
+    let (inner, outer) = world
+        .with_auto_tag::<Spawned, _>(|world| {
+            let outer = world.new_id();
+            let inner = world
+                .with_auto_component::<Team, _>(Team(1), |world| world.new_id())
+                .unwrap();
+            (inner, outer)
+        })
+        .unwrap();
+
+    assert!(world.has_id(inner, spawned));
+    assert_eq!(world.get::<&Team>(inner).ok(), Some(&Team(1)));
+    assert!(world.has_id(outer, spawned));
+    assert!(world.get::<&Team>(outer).is_err());
+
+    let after = world.new_id();
+    assert!(!world.has_id(after, spawned));
+}
+
+#[test]
+fn scope_is_popped_when_the_closure_panics() {
+    let mut world = new_world();
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        world
+            .with_auto_component::<Team, _>(Team(1), |_| panic!("scope body"))
+            .unwrap()
+    }));
+    assert!(result.is_err());
+
+    let id = world.new_id();
+    assert!(world.get::<&Team>(id).is_err());
+}
+
+#[test]
+fn scoped_instances_keep_storage_consistent() {
+    let mut world = new_world();
+    let prefab = world
+        .prefab_tree()
+        .root()
+        .with::<Team>(Team(7))
+        .build()
+        .unwrap();
+
+    let instance = world
+        .with_auto_component::<Team, _>(Team(2), |world| world.instantiate_tree(prefab))
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(world.get::<&Team>(instance).ok(), Some(&Team(2)));
+    assert!(world.check_invariants().is_empty());
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use xecs::{
+    component::ComponentBuilder,
+    id::Id,
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Brain(#[allow(dead_code)] u32);
+
+/// Creates 10 tables of 100 ids each, all with `Brain`.
+fn new_world() -> (World, Id) {
+    let mut world = World::new();
+    let brain = world.register::<Brain>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    for _ in 0..10 {
+        let group = world.new_id();
+
+        for _ in 0..100 {
+            // Adding the group first leaves no empty table with only `Brain`.
+            let id = world.new_id();
+            world.add_id(id, group).unwrap();
+            world.set::<Brain>(id, Brain(0));
+        }
+    }
+
+    (world, brain)
+}
+
+#[test]
+fn budgeted_batches_visit_every_row_once() {
+    let (mut world, brain) = new_world();
+    let mut plan = QueryPlan::new(SelectStmt::default().read(brain), WithStmt::new());
+    let mut seen = HashMap::<Id, usize>::new();
+
+    let mut batch = plan.iter(&mut world).by_budget(250);
+    batch
+        .by_ref()
+        .for_each(|(id, _)| *seen.entry(id).or_default() += 1);
+    let mut cursor = batch.into_cursor();
+    let mut batches = 1;
+
+    while let Some(next) = cursor {
+        let mut batch = plan.resume(next, &mut world).by_budget(250);
+        batch
+            .by_ref()
+            .for_each(|(id, _)| *seen.entry(id).or_default() += 1);
+        cursor = batch.into_cursor();
+        batches += 1;
+    }
+
+    assert_eq!(batches, 4);
+    assert_eq!(seen.len(), 1000);
+    assert!(seen.values().all(|&count| count == 1));
+}
+
+#[test]
+fn changed_table_restarts_on_resume() {
+    let (mut world, brain) = new_world();
+    let mut plan = QueryPlan::new(SelectStmt::default().read(brain), WithStmt::new());
+
+    let mut batch = plan.iter(&mut world).by_budget(50);
+    let first: Vec<Id> = batch.by_ref().map(|(id, _)| id).collect();
+    let cursor = batch.into_cursor().unwrap();
+
+    // Removing a row reorders the table the cursor points into.
+    world.despawn(first[0]).unwrap();
+
+    let resumed: Vec<Id> = plan
+        .resume(cursor, &mut world)
+        .by_budget(50)
+        .map(|(id, _)| id)
+        .collect();
+
+    assert!(first[1..].iter().all(|id| resumed.contains(id)));
+    assert!(!resumed.contains(&first[0]));
+}
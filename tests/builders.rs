@@ -0,0 +1,71 @@
+use xecs::{
+    component::{BuilderCommon, ComponentBuilder, TagBuilder},
+    flags::{ComponentFlags, Policy},
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Weight(#[allow(dead_code)] f32);
+
+#[test]
+fn builder_chains_set_the_flag_bits() {
+    let mut tag = TagBuilder::new().exclusive().transitive();
+    assert_eq!(
+        *tag.flags_mut(),
+        ComponentFlags::EXCLUSIVE | ComponentFlags::TRANSITIVE
+    );
+
+    let mut comp = ComponentBuilder::<Weight>::new()
+        .on_delete(Policy::Remove)
+        .on_delete_object(Policy::Delete);
+    assert_eq!(
+        *comp.flags_mut(),
+        ComponentFlags::ON_DELETE_REMOVE | ComponentFlags::ON_DELETE_TARGET_DELETE
+    );
+}
+
+#[test]
+fn later_policies_replace_earlier_ones() {
+    let mut tag = TagBuilder::new()
+        .on_delete(Policy::Delete)
+        .on_delete(Policy::Panic)
+        .on_delete_object(Policy::Panic)
+        .on_delete_object(Policy::Remove);
+
+    assert_eq!(
+        *tag.flags_mut(),
+        ComponentFlags::ON_DELETE_PANIC | ComponentFlags::ON_DELETE_TARGET_REMOVE
+    );
+}
+
+#[test]
+#[should_panic(expected = "is used by 1 ids")]
+fn panic_policy_refuses_deleting_a_used_component() {
+    let mut world = World::new();
+    let tag = world.new_component(TagBuilder::new().on_delete(Policy::Panic));
+    let id = world.new_id();
+    world.add_id(id, tag).unwrap();
+
+    let _ = world.despawn(tag);
+}
+
+#[test]
+fn delete_and_remove_policies_apply_to_users() {
+    let mut world = World::new();
+    let doomed = world.new_component(TagBuilder::new().on_delete(Policy::Delete));
+    let dropped = world.new_component(TagBuilder::new().on_delete(Policy::Remove));
+    let [a, b, c] = [(); 3].map(|_| world.new_id());
+
+    world.add_id(a, doomed).unwrap();
+    world.add_id(b, dropped).unwrap();
+    world.add_id(b, (dropped, c)).unwrap();
+
+    world.despawn(doomed).unwrap();
+    assert!(!world.is_alive(a));
+
+    world.despawn(dropped).unwrap();
+    assert!(world.is_alive(b));
+    assert!(world.entity(b).unwrap().signature().ids().is_empty());
+    assert!(world.check_invariants().is_empty());
+}
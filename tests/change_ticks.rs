@@ -0,0 +1,38 @@
+use xecs::{component::ComponentBuilder, storage::StorageType, world::World};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Score(#[allow(dead_code)] u32);
+
+#[test]
+fn modified_since_returns_the_written_half() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        world.register::<Score>(ComponentBuilder::new().storage(storage));
+
+        let ids: Vec<_> = (0..100)
+            .map(|i| {
+                let id = world.new_id();
+                world.set::<Score>(id, Score(i));
+                id
+            })
+            .collect();
+
+        let before = world.change_tick();
+        world.advance_tick();
+
+        for &id in ids.iter().step_by(2) {
+            world.set::<Score>(id, Score(0));
+        }
+
+        world.advance_tick();
+
+        let mut modified: Vec<_> = world.get_modified_since::<Score>(before).collect();
+        modified.sort();
+        let written: Vec<_> = ids.iter().copied().step_by(2).collect();
+        assert_eq!(modified, written, "{storage:?}");
+
+        let now = world.change_tick();
+        assert_eq!(world.get_modified_since::<Score>(now).count(), 0);
+    }
+}
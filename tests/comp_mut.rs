@@ -0,0 +1,39 @@
+use std::{cell::Cell, rc::Rc};
+use xecs::{component::ComponentBuilder, world::World};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Health(u32);
+
+#[test]
+fn dropping_a_written_guard_calls_on_set_once() {
+    let mut world = World::new();
+    let calls = Rc::new(Cell::new(0));
+    let seen = Rc::new(Cell::new(0));
+
+    world.register::<Health>(ComponentBuilder::new().on_set({
+        let (calls, seen) = (Rc::clone(&calls), Rc::clone(&seen));
+        move |_, health: &mut Health| {
+            calls.set(calls.get() + 1);
+            seen.set(health.0);
+        }
+    }));
+
+    let id = world.new_id();
+    world.set::<Health>(id, Health(10));
+    let after_set = calls.get();
+
+    {
+        let mut health = world.component_mut::<Health>(id).unwrap();
+        health.0 += 5;
+        health.0 *= 2;
+        assert_eq!(calls.get(), after_set);
+    }
+
+    assert_eq!(calls.get(), after_set + 1);
+    assert_eq!(seen.get(), 30);
+
+    // Reading through the guard isn't a write.
+    assert_eq!(world.component_mut::<Health>(id).unwrap().0, 30);
+    assert_eq!(calls.get(), after_set + 1);
+}
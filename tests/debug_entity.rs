@@ -0,0 +1,29 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Health(u32);
+
+#[derive(Component)]
+struct Frozen;
+
+#[test]
+fn debug_entity_formats_values_with_the_fmt_hook() {
+    let mut world = World::new();
+    world.register::<Health>(
+        ComponentBuilder::new().fmt(|health: &Health, f| write!(f, "{} hp", health.0)),
+    );
+    world.register::<Frozen>(TagBuilder::new());
+
+    let id = world.new_id();
+    world.set::<Health>(id, Health(42));
+    world.add::<Frozen>(id).unwrap();
+
+    let debug = format!("{:?}", world.debug_entity(id));
+    assert!(debug.contains("Health: 42 hp"), "{debug}");
+    assert!(debug.contains("Frozen"), "{debug}");
+    assert!(!debug.contains("Frozen: "), "{debug}");
+}
@@ -0,0 +1,56 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct Name(String);
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+struct Score(u32);
+
+/// Has no drop glue, but must not be duplicated.
+#[derive(Component, Debug)]
+struct Unique(#[allow(dead_code)] &'static mut u32);
+
+#[test]
+fn values_are_cloned_or_copied() {
+    let mut world = World::new();
+    world.register::<Name>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .clone(Name::clone),
+    );
+    // SAFETY: `Score` is a single `u32`.
+    world.register::<Score>(unsafe { ComponentBuilder::new().pod() });
+
+    let id = world.new_id();
+    world.set::<Name>(id, Name("a".into()));
+    world.set::<Score>(id, Score(3));
+
+    let clone = world.deep_clone().unwrap();
+    world.set::<Name>(id, Name("b".into()));
+
+    assert_eq!(clone.get::<&Name>(id).ok(), Some(&Name("a".into())));
+    assert_eq!(clone.get::<&Score>(id).ok(), Some(&Score(3)));
+    assert!(clone.check_invariants().is_empty());
+}
+
+#[test]
+fn values_without_drop_glue_need_a_clone_hook_or_pod() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        let unique = world.register::<Unique>(ComponentBuilder::new().storage(storage));
+
+        let id = world.new_id();
+        world.set::<Unique>(id, Unique(Box::leak(Box::new(0))));
+
+        assert!(matches!(
+            world.deep_clone(),
+            Err(EcsError::NoCloneHook(comp)) if comp == unique
+        ));
+    }
+}
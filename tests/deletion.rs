@@ -0,0 +1,199 @@
+use xecs::{
+    component::{BuilderCommon, ComponentBuilder, TagBuilder},
+    flags::Policy,
+    id::Id,
+    query::{SelectStmt, WithStmt},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Unit;
+
+#[derive(Component)]
+struct Dead;
+
+#[derive(Component)]
+struct Health(u32);
+
+fn spawn_units(world: &mut World, count: usize) -> Vec<Id> {
+    (0..count)
+        .map(|_| {
+            let id = world.new_id();
+            world.add::<Unit>(id).unwrap();
+            id
+        })
+        .collect()
+}
+
+fn units(world: &World, with: WithStmt) -> Vec<Id> {
+    let unit = world.id::<Unit>().unwrap();
+    let mut ids = world.collect(SelectStmt::default(), with.with(unit));
+    ids.sort();
+    ids
+}
+
+fn new_world() -> World {
+    let mut world = World::new();
+    world.register::<Unit>(TagBuilder::new().storage(StorageType::Tables));
+    world
+}
+
+#[test]
+fn marked_ids_are_hidden_until_cancelled() {
+    let mut world = new_world();
+    let ids = spawn_units(&mut world, 3);
+
+    world.mark_for_delete(ids[1]).unwrap();
+    world.mark_for_delete(ids[1]).unwrap();
+
+    assert!(world.is_alive(ids[1]));
+    assert!(world.is_pending_delete(ids[1]));
+    assert_eq!(world.pending_delete_count(), 1);
+    assert_eq!(units(&world, WithStmt::new()), [ids[0], ids[2]]);
+
+    // Asking for the tag finds the marked ids.
+    let pending = world.pending_delete_tag();
+    assert_eq!(units(&world, WithStmt::new().with(pending)), [ids[1]]);
+
+    world.cancel_delete(ids[1]).unwrap();
+
+    assert!(!world.is_pending_delete(ids[1]));
+    assert_eq!(world.pending_delete_count(), 0);
+    assert_eq!(units(&world, WithStmt::new()), ids);
+    assert!(!world.has_id(ids[1], pending));
+}
+
+#[test]
+fn flush_cascades_to_children() {
+    let mut world = new_world();
+    let child_of = world.child_of();
+    let [parent, a, b, grandchild, other] = spawn_units(&mut world, 5).try_into().unwrap();
+
+    world.add_id(a, (child_of, parent)).unwrap();
+    world.add_id(b, (child_of, parent)).unwrap();
+    world.add_id(grandchild, (child_of, a)).unwrap();
+
+    world.mark_for_delete(b).unwrap();
+    world.mark_for_delete(parent).unwrap();
+
+    assert_eq!(world.flush_deletions(), 4);
+    assert_eq!(world.pending_delete_count(), 0);
+
+    for id in [parent, a, b, grandchild] {
+        assert!(!world.is_alive(id));
+    }
+
+    assert_eq!(units(&world, WithStmt::new()), [other]);
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn target_policies_apply_on_despawn() {
+    let mut world = new_world();
+    let keep = world.new_component(TagBuilder::new());
+    let remove = world.new_component(TagBuilder::new().on_delete_object(Policy::Remove));
+    let [target, holder] = spawn_units(&mut world, 2).try_into().unwrap();
+
+    world.add_id(holder, (keep, target)).unwrap();
+    world.add_id(holder, (remove, target)).unwrap();
+    world.despawn(target).unwrap();
+
+    assert!(world.is_alive(holder));
+    assert_eq!(world.validate_relationships().len(), 1);
+    assert_eq!(world.validate_relationships()[0].0, holder);
+}
+
+#[test]
+#[should_panic(expected = "which is the target of")]
+fn panic_policy_panics_on_despawn() {
+    let mut world = new_world();
+    let rel = world.new_component(TagBuilder::new().on_delete_object(Policy::Panic));
+    let [target, holder] = spawn_units(&mut world, 2).try_into().unwrap();
+
+    world.add_id(holder, (rel, target)).unwrap();
+    let _ = world.despawn(target);
+}
+
+/// Spawns `root` with `children` ids under it.
+fn spawn_tree(world: &mut World, children: usize) -> (Id, Vec<Id>) {
+    let child_of = world.child_of();
+    let root = world.new_id();
+
+    let children = (0..children)
+        .map(|_| {
+            let child = world.new_id();
+            world.add_id(child, (child_of, root)).unwrap();
+            child
+        })
+        .collect();
+
+    (root, children)
+}
+
+#[test]
+fn deferred_subtrees_keep_their_own_budget() {
+    let mut world = new_world();
+    let (slow, slow_children) = spawn_tree(&mut world, 3);
+    let (fast, _) = spawn_tree(&mut world, 2);
+
+    world.despawn_recursive_deferred(slow, 1).unwrap();
+    world.despawn_recursive_deferred(fast, 3).unwrap();
+    assert_eq!(world.pending_delete_count(), 7);
+
+    assert_eq!(world.flush_deletions(), 4);
+    assert!(!world.is_alive(fast));
+
+    // Children go first, the root is despawned by the last flush.
+    for _ in 0..2 {
+        assert_eq!(world.flush_deletions(), 1);
+        assert!(world.is_alive(slow));
+    }
+
+    assert!(slow_children.iter().all(|&id| !world.is_alive(id)));
+    assert_eq!(world.flush_deletions(), 1);
+    assert!(!world.is_alive(slow));
+    assert_eq!(world.pending_delete_count(), 0);
+}
+
+#[test]
+fn dead_ids_are_despawned_together() {
+    let mut world = new_world();
+    let dead = world.register::<Dead>(TagBuilder::new().storage(StorageType::Tables));
+    world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let ids = spawn_units(&mut world, 10);
+
+    for (i, &id) in ids.iter().enumerate() {
+        world.set::<Health>(id, Health(i as u32));
+
+        if i % 3 == 0 {
+            world.add_id(id, dead).unwrap();
+        }
+    }
+
+    let despawned = world.despawn_matching(SelectStmt::default(), WithStmt::new().with(dead));
+    assert_eq!(despawned, 4);
+    assert_eq!(
+        world.despawn_matching(SelectStmt::default(), WithStmt::new().with(dead)),
+        0
+    );
+
+    for (i, &id) in ids.iter().enumerate() {
+        assert_eq!(world.is_alive(id), i % 3 != 0);
+    }
+
+    // Survivors swapped into the freed rows still find their own values.
+    let survivors: Vec<_> = ids
+        .iter()
+        .copied()
+        .filter(|&id| world.is_alive(id))
+        .collect();
+    assert_eq!(units(&world, WithStmt::new()), survivors);
+
+    for (i, &id) in ids.iter().enumerate().filter(|&(i, _)| i % 3 != 0) {
+        assert_eq!(world.get::<&Health>(id).unwrap().0, i as u32);
+    }
+    assert!(world.check_invariants().is_empty());
+}
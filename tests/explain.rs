@@ -0,0 +1,56 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Marker;
+
+#[test]
+fn impossible_without_leaves_no_tables() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let marker = world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..4 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 2 == 0 {
+            world.add::<Marker>(id).unwrap();
+        }
+    }
+
+    let plan = QueryPlan::new(
+        SelectStmt::default().read(pos),
+        WithStmt::new().with(marker).without(pos),
+    );
+    let explain = plan.explain(&world);
+    let stages: Vec<_> = explain.lines().collect();
+
+    assert!(stages[0].starts_with("candidates: 1 tables"), "{explain}");
+    assert!(
+        stages
+            .iter()
+            .any(|stage| stage.starts_with("with ") && stage.ends_with(": 1 tables")),
+        "{explain}"
+    );
+    assert!(
+        stages
+            .iter()
+            .any(|stage| stage.starts_with("without ") && stage.ends_with(": 0 tables")),
+        "{explain}"
+    );
+    assert!(
+        stages
+            .iter()
+            .any(|stage| stage.starts_with("select ") && stage.ends_with(": 0 tables")),
+        "{explain}"
+    );
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    id::Id,
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Transform(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Velocity(u32);
+
+#[derive(Component)]
+struct Moving;
+
+/// Spawns ids, then moves and despawns some so table rows are reordered.
+fn new_world() -> (World, Vec<Id>) {
+    let mut world = World::new();
+    // SAFETY: `Transform` is a single `u32`.
+    let transform = unsafe { ComponentBuilder::new().storage(StorageType::Tables).pod() };
+    world.register::<Transform>(transform);
+    world.register::<Velocity>(ComponentBuilder::new());
+    world.register::<Moving>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..50)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Transform>(id, Transform(i));
+            world.set::<Velocity>(id, Velocity(i * 10));
+            id
+        })
+        .collect();
+
+    for &id in ids.iter().step_by(3) {
+        world.add::<Moving>(id).unwrap();
+    }
+
+    for &id in ids.iter().step_by(6) {
+        world.remove::<Moving>(id).unwrap();
+    }
+
+    for &id in ids.iter().skip(1).step_by(7) {
+        world.despawn(id).unwrap();
+    }
+
+    let alive = ids.into_iter().filter(|&id| world.is_alive(id)).collect();
+    (world, alive)
+}
+
+#[test]
+fn exported_values_match_their_ids() {
+    let (world, alive) = new_world();
+    let mut transforms = HashMap::new();
+    let mut velocities = HashMap::new();
+
+    world.export_column::<Transform>(|table, values, ids| {
+        assert!(table.is_some() && !ids.is_empty());
+        assert_eq!(values.len(), ids.len());
+        transforms.extend(ids.iter().copied().zip(values.iter().map(|v| v.0)));
+    });
+
+    world.export_column::<Velocity>(|table, values, ids| {
+        assert!(table.is_none());
+        velocities.extend(ids.iter().copied().zip(values.iter().map(|v| v.0)));
+    });
+
+    assert_eq!(transforms.len(), alive.len());
+    assert_eq!(velocities.len(), alive.len());
+
+    for id in alive {
+        assert_eq!(
+            world.get::<&Transform>(id).ok().map(|t| t.0),
+            Some(transforms[&id])
+        );
+        assert_eq!(
+            world.get::<&Velocity>(id).ok().map(|v| v.0),
+            Some(velocities[&id])
+        );
+    }
+}
+
+#[test]
+fn exported_bytes_match_their_ids() {
+    let (world, alive) = new_world();
+    let transform = world.id::<Transform>().unwrap();
+    let mut transforms = HashMap::new();
+
+    world.export_column_bytes(transform, |bytes, ids, stride| {
+        assert_eq!(stride, size_of::<u32>());
+        assert_eq!(bytes.len(), ids.len() * stride);
+
+        for (&id, value) in ids.iter().zip(bytes.chunks_exact(stride)) {
+            // SAFETY: A `u32` has no padding, so all its bytes are initialized.
+            let value = value.iter().map(|byte| unsafe { byte.assume_init() });
+            let value = u32::from_ne_bytes(value.collect::<Vec<_>>().try_into().unwrap());
+            transforms.insert(id, value);
+        }
+    });
+
+    assert_eq!(transforms.len(), alive.len());
+
+    for id in alive {
+        assert_eq!(
+            world.get::<&Transform>(id).ok().map(|t| t.0),
+            Some(transforms[&id])
+        );
+    }
+}
+
+#[derive(Component)]
+struct Unused(#[allow(dead_code)] u32);
+
+#[test]
+fn columns_are_transformed_in_place() {
+    let (mut world, alive) = new_world();
+    let mut tables = 0;
+    world.export_column::<Transform>(|_, _, _| tables += 1);
+    assert!(tables > 1);
+
+    let mut calls = 0;
+    world.for_each_column::<Transform>(|values| {
+        calls += 1;
+        values.iter_mut().for_each(|t| t.0 += 1000);
+    });
+    assert_eq!(calls, tables);
+
+    let mut calls = 0;
+    world.for_each_column::<Velocity>(|values| {
+        calls += 1;
+        assert_eq!(values.len(), alive.len());
+        values.iter_mut().for_each(|v| v.0 += 1);
+    });
+    assert_eq!(calls, 1);
+
+    // Components that aren't registered have no column.
+    world.for_each_column::<Unused>(|_| panic!("unregistered component visited"));
+
+    for &id in &alive {
+        let transform = world.get::<&Transform>(id).unwrap().0;
+        assert!(transform >= 1000);
+        assert_eq!(
+            world.get::<&Velocity>(id).unwrap().0,
+            (transform - 1000) * 10 + 1
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use xecs::{component::ComponentBuilder, storage::StorageType, world::World};
+use xecs_macros::Component;
+
+#[derive(Component, PartialEq)]
+struct Tag(String);
+
+#[derive(Component)]
+struct Name(#[allow(dead_code)] String);
+
+fn find_tags(storage: StorageType) {
+    let mut world = World::new();
+    world.register::<Tag>(
+        ComponentBuilder::new()
+            .storage(storage)
+            .eq(|a: &Tag, b: &Tag| a == b),
+    );
+
+    let ids: Vec<_> = ["player", "enemy", "chest"]
+        .into_iter()
+        .map(|tag| {
+            let id = world.new_id();
+            world.set::<Tag>(id, Tag(tag.into()));
+            id
+        })
+        .collect();
+
+    assert_eq!(
+        world.find_with_value::<Tag>(&Tag("enemy".into())),
+        Some(ids[1])
+    );
+    assert_eq!(
+        world.find_with_value::<Tag>(&Tag("chest".into())),
+        Some(ids[2])
+    );
+    assert_eq!(world.find_with_value::<Tag>(&Tag("door".into())), None);
+}
+
+#[test]
+fn values_are_found_in_tables() {
+    find_tags(StorageType::Tables);
+}
+
+#[test]
+fn values_are_found_in_sparse_sets() {
+    find_tags(StorageType::Sparse);
+}
+
+#[test]
+fn values_without_an_eq_hook_are_not_found() {
+    let mut world = World::new();
+    world.register::<Name>(ComponentBuilder::new());
+
+    let id = world.new_id();
+    world.set::<Name>(id, Name("player".into()));
+    assert_eq!(world.find_with_value::<Name>(&Name("player".into())), None);
+}
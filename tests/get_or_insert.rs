@@ -0,0 +1,155 @@
+use std::{cell::Cell, rc::Rc};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    error::EcsError,
+    id::{Id, IntoId},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Health(u32);
+
+#[derive(Component)]
+struct Likes;
+
+/// Registers Health with `storage` and returns the number of on_set calls.
+fn world_with_health(storage: StorageType) -> (World, Rc<Cell<u32>>) {
+    let mut world = World::new();
+    let sets = Rc::new(Cell::new(0));
+    let on_set = sets.clone();
+
+    world.register::<Health>(
+        ComponentBuilder::new()
+            .storage(storage)
+            .on_set(move |_, _| on_set.set(on_set.get() + 1)),
+    );
+
+    (world, sets)
+}
+
+fn insert_then_mutate(storage: StorageType) {
+    let (mut world, sets) = world_with_health(storage);
+    let id = world.new_id();
+
+    world
+        .get_or_insert_with::<Health>(id, || Health(10))
+        .unwrap()
+        .0 -= 1;
+    assert_eq!(sets.get(), 1);
+    assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(9)));
+
+    // The existing value is returned, and reading it is not a write.
+    let health = world
+        .get_or_insert_with::<Health>(id, || unreachable!())
+        .unwrap();
+    assert_eq!(*health, Health(9));
+    drop(health);
+    assert_eq!(sets.get(), 1);
+}
+
+#[test]
+fn table_values_are_inserted_then_mutated() {
+    insert_then_mutate(StorageType::Tables);
+}
+
+#[test]
+fn sparse_values_are_inserted_then_mutated() {
+    insert_then_mutate(StorageType::Sparse);
+}
+
+#[test]
+fn table_insert_keeps_other_values() {
+    let (mut world, _) = world_with_health(StorageType::Tables);
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Health>(a, Health(1));
+
+    // b moves into the table of a, the returned value must be the one of b.
+    world
+        .get_or_insert_with::<Health>(b, || Health(2))
+        .unwrap()
+        .0 += 10;
+
+    assert_eq!(world.get::<&Health>(a).ok(), Some(&Health(1)));
+    assert_eq!(world.get::<&Health>(b).ok(), Some(&Health(12)));
+}
+
+#[test]
+fn dead_ids_are_rejected() {
+    let (mut world, sets) = world_with_health(StorageType::Tables);
+    let id = world.new_id();
+    world.despawn(id).unwrap();
+
+    assert!(matches!(
+        world.get_or_insert_with::<Health>(id, || unreachable!()),
+        Err(EcsError::InvalidId(_))
+    ));
+    assert_eq!(sets.get(), 0);
+}
+
+#[test]
+fn tag_relationships_are_rejected() {
+    let (mut world, _) = world_with_health(StorageType::Tables);
+    let likes = world.register::<Likes>(
+        TagBuilder::new()
+            .storage(StorageType::Sparse)
+            .relationship(),
+    );
+    let health = world.id::<Health>().unwrap();
+    let pair: Id = (likes, health).into_id();
+    let id = world.new_id();
+
+    let res = world.get_or_insert_with::<(Likes, Health)>(id, || unreachable!());
+    assert!(matches!(res, Err(EcsError::IsTag(comp)) if comp == pair));
+}
+
+#[test]
+fn get_or_set_keeps_the_first_value() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let (mut world, _) = world_with_health(storage);
+        let id = world.new_id();
+
+        assert_eq!(
+            world.get_or_set::<Health>(id, Health(100)).unwrap(),
+            &Health(100)
+        );
+        assert_eq!(
+            world.get_or_set::<Health>(id, Health(50)).unwrap(),
+            &Health(100)
+        );
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(100)));
+    }
+}
+
+#[test]
+fn added_values_are_initialized_with_the_default_hook() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        world.register::<Health>(
+            ComponentBuilder::new()
+                .storage(storage)
+                .default(|| Health(100)),
+        );
+        let id = world.new_id();
+
+        world.add_default::<Health>(id).unwrap();
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(100)));
+
+        // An existing value is kept.
+        world.set::<Health>(id, Health(5));
+        world.add_default::<Health>(id).unwrap();
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(5)));
+    }
+}
+
+#[test]
+fn adding_a_default_without_a_hook_fails() {
+    let (mut world, _) = world_with_health(StorageType::Tables);
+    let id = world.new_id();
+
+    let health = world.id::<Health>().unwrap();
+    let res = world.add_default::<Health>(id);
+    assert!(matches!(res, Err(EcsError::NoDefaultHook(comp)) if comp == health));
+    assert!(!world.has::<Health>(id));
+}
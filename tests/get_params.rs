@@ -0,0 +1,116 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::GetError,
+    get_params::{Entity, Or, OrOutput2},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+#[derive(Component)]
+struct Velocity(f32);
+
+#[test]
+fn or_returns_the_first_present_member() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+
+    let [both, moving, none] = [(); 3].map(|_| world.new_id());
+    world.set::<Position>(both, Position(1.0));
+    world.set::<Velocity>(both, Velocity(2.0));
+    world.set::<Velocity>(moving, Velocity(3.0));
+
+    match world.get::<Or<(Position, Velocity)>>(both).unwrap() {
+        OrOutput2::A(pos) => assert_eq!(pos.0, 1.0),
+        OrOutput2::B(_) => panic!("position comes first"),
+    }
+
+    match world.get::<Or<(Velocity, Position)>>(both).unwrap() {
+        OrOutput2::A(vel) => assert_eq!(vel.0, 2.0),
+        OrOutput2::B(_) => panic!("velocity comes first"),
+    }
+
+    match world.get::<Or<(Position, Velocity)>>(moving).unwrap() {
+        OrOutput2::A(_) => panic!("id has no position"),
+        OrOutput2::B(vel) => assert_eq!(vel.0, 3.0),
+    }
+
+    let err = world.get::<Or<(Position, Velocity)>>(none).err().unwrap();
+    assert!(matches!(err, GetError::MissingAnyComponent(comps) if comps == [pos, vel]));
+}
+
+#[test]
+fn missing_components_report_the_storage_checked() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let id = world.new_id();
+
+    let err = world.get::<&Velocity>(id).err().unwrap();
+    assert!(matches!(err, GetError::MissingComponent(comp, StorageType::Sparse) if comp == vel));
+    assert!(err.to_string().contains("Sparse"), "{err}");
+
+    let err = world.get::<&Position>(id).err().unwrap();
+    assert!(matches!(err, GetError::MissingComponent(comp, StorageType::Tables) if comp == pos));
+}
+
+#[test]
+fn entity_params_yield_the_queried_id() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Position>(a, Position(1.0));
+    world.set::<Position>(b, Position(2.0));
+    world.set::<Velocity>(b, Velocity(3.0));
+
+    assert_eq!(world.get::<Entity>(a).unwrap(), a);
+
+    let (id, pos) = world.get::<(Entity, &Position)>(b).unwrap();
+    assert_eq!((id, pos.0), (b, 2.0));
+
+    let (pos, id, vel) = world
+        .get::<(&Position, Entity, Option<&Velocity>)>(a)
+        .unwrap();
+    assert_eq!((pos.0, id, vel.map(|v| v.0)), (1.0, a, None));
+
+    // The id alone doesn't make a missing component optional.
+    assert!(world.get::<(Entity, &Velocity)>(a).is_err());
+}
+
+#[test]
+fn shared_gets_can_be_held_together() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Position>(a, Position(1.0));
+    world.set::<Position>(b, Position(2.0));
+    world.set::<Velocity>(b, Velocity(3.0));
+
+    let world = &world;
+    let pos_a = world.get::<&Position>(a).unwrap();
+    let vel_b = world.get::<&Velocity>(b).unwrap();
+    let pos_b = world.get::<&Position>(b).unwrap();
+    assert_eq!((pos_a.0, vel_b.0, pos_b.0), (1.0, 3.0, 2.0));
+}
+
+#[test]
+fn aliased_mutable_gets_are_rejected() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let id = world.new_id();
+    world.set::<Position>(id, Position(1.0));
+
+    let err = (&mut world)
+        .get::<(&mut Position, &Position)>(id)
+        .err()
+        .unwrap();
+    assert!(matches!(err, GetError::AliasedComponent(comp) if comp == pos));
+}
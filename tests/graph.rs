@@ -0,0 +1,73 @@
+use xecs::{
+    component::TagBuilder,
+    id::{Id, IntoId},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Walking;
+
+#[derive(Component)]
+struct Running;
+
+#[test]
+fn alternating_toggles_reach_the_right_tables() {
+    let mut world = World::new();
+    let walking = world.register::<Walking>(TagBuilder::new().storage(StorageType::Tables));
+    let running = world.register::<Running>(TagBuilder::new().storage(StorageType::Tables));
+
+    let id = world.new_id();
+
+    for _ in 0..3 {
+        world.add::<Walking>(id).unwrap();
+        assert_eq!(world.entity(id).unwrap().signature().ids(), [walking]);
+        world.remove::<Walking>(id).unwrap();
+
+        world.add::<Running>(id).unwrap();
+        assert_eq!(world.entity(id).unwrap().signature().ids(), [running]);
+        world.remove::<Running>(id).unwrap();
+
+        assert!(world.entity(id).unwrap().signature().ids().is_empty());
+    }
+}
+
+#[test]
+fn pairs_sharing_a_target_get_distinct_tables() {
+    let mut world = World::new();
+    let walking = world.register::<Walking>(TagBuilder::new().storage(StorageType::Tables));
+    let likes = world.new_component(TagBuilder::new().storage(StorageType::Tables));
+    let hates = world.new_component(TagBuilder::new().storage(StorageType::Tables));
+    let target = world.new_id();
+
+    // Both ids leave the same source table, so the second add takes an edge of the first.
+    let [a, b] = [(); 2].map(|_| {
+        let id = world.new_id();
+        world.add::<Walking>(id).unwrap();
+        id
+    });
+
+    world.add_id(a, (likes, target)).unwrap();
+    world.add_id(b, (hates, target)).unwrap();
+
+    let sorted = |mut ids: Vec<Id>| {
+        ids.sort();
+        ids
+    };
+    let likes_pair = (likes, target).into_id();
+    let hates_pair = (hates, target).into_id();
+
+    assert_eq!(
+        world.entity(a).unwrap().signature().ids(),
+        sorted(vec![walking, likes_pair])
+    );
+    assert_eq!(
+        world.entity(b).unwrap().signature().ids(),
+        sorted(vec![walking, hates_pair])
+    );
+    assert_ne!(
+        world.ensure_archetype(&[walking, likes_pair]),
+        world.ensure_archetype(&[walking, hates_pair])
+    );
+}
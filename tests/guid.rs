@@ -0,0 +1,74 @@
+use xecs::{error::EcsError, world::World};
+
+#[test]
+fn guids_are_unique_within_a_world() {
+    let mut world = World::new();
+    let a = world.spawn_with_guid(1).unwrap();
+    let b = world.new_id();
+
+    assert!(matches!(
+        world.set_guid(b, 1),
+        Err(EcsError::GuidCollision(1))
+    ));
+    assert!(matches!(
+        world.spawn_with_guid(1),
+        Err(EcsError::GuidCollision(1))
+    ));
+    assert_eq!(world.by_guid(1), Some(a));
+    assert_eq!(world.guid_of(b), None);
+
+    // Replacing a guid releases the old one.
+    world.set_guid(a, 2).unwrap();
+    world.set_guid(b, 1).unwrap();
+    assert_eq!(world.by_guid(1), Some(b));
+    assert_eq!(world.by_guid(2), Some(a));
+}
+
+#[test]
+fn despawned_ids_release_their_guid() {
+    let mut world = World::new();
+    let a = world.spawn_with_guid(7).unwrap();
+
+    world.despawn(a).unwrap();
+    assert_eq!(world.by_guid(7), None);
+
+    let b = world.spawn_with_guid(7).unwrap();
+    assert_eq!(world.by_guid(7), Some(b));
+}
+
+#[test]
+fn loaded_guids_are_indexed_and_checked() {
+    let mut world = World::new();
+    let a = world.spawn_with_guid(3).unwrap();
+
+    let mut buf = vec![];
+    world.serialize_entity(a, &mut buf).unwrap();
+
+    assert!(matches!(
+        world.deserialize_entity(&buf),
+        Err(EcsError::GuidCollision(3))
+    ));
+
+    world.despawn(a).unwrap();
+    let b = world.deserialize_entity(&buf).unwrap();
+    assert_eq!(world.by_guid(3), Some(b));
+    assert_eq!(world.guid_of(b), Some(3));
+}
+
+#[test]
+fn merging_a_used_guid_fails() {
+    let mut dst = World::new();
+    dst.spawn_with_guid(5).unwrap();
+
+    let mut src = World::new();
+    src.spawn_with_guid(5).unwrap();
+    assert!(matches!(
+        dst.merge_into(src),
+        Err(EcsError::GuidCollision(5))
+    ));
+
+    let mut src = World::new();
+    let moved = src.spawn_with_guid(6).unwrap();
+    let map = dst.merge_into(src).unwrap();
+    assert_eq!(dst.by_guid(6), Some(map[&moved]));
+}
@@ -0,0 +1,142 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    error::EcsError,
+    flags::IdFlags,
+    id::Id,
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Score(u32);
+
+#[test]
+fn compacted_indices_are_never_issued_again() {
+    let mut world = World::new();
+    let ids: Vec<_> = (0..10).map(|_| world.new_id()).collect();
+
+    for &id in &ids[5..] {
+        world.despawn(id).unwrap();
+    }
+
+    world.compact_ids();
+
+    for &id in &ids[5..] {
+        assert!(!world.exists(id));
+        let index = id.index();
+        assert!(matches!(
+            unsafe { world.new_id_at(index) },
+            Err(EcsError::IndexRetired(i)) if i == index
+        ));
+    }
+
+    for _ in 0..10 {
+        let id = world.new_id();
+        assert!(ids[5..].iter().all(|retired| retired.index() != id.index()));
+    }
+}
+
+#[test]
+fn ids_below_a_created_index_are_dead_and_recycled() {
+    let mut world = World::new();
+    // Every index below the first free one is alive.
+    let first = world.new_id().index() + 1;
+
+    // SAFETY: No id with the index is stored anywhere.
+    let id = unsafe { world.new_id_at(5000) }.unwrap();
+    assert_eq!(id.index(), 5000);
+    assert!(world.is_alive(id));
+
+    assert!((first..5000).all(|index| !world.is_alive(Id::from_parts(index, 0))));
+
+    let mut recycled: Vec<_> = (first..5000).map(|_| world.new_id().index()).collect();
+    recycled.sort_unstable();
+    assert!(recycled.iter().copied().eq(first..5000));
+
+    assert_eq!(world.new_id().index(), 5001);
+}
+
+#[test]
+fn despawned_components_in_use_are_retired() {
+    let mut world = World::new();
+    let tag = world.new_component(TagBuilder::new());
+    let unused = world.new_id();
+    let holder = world.new_id();
+    world.add_id(holder, tag).unwrap();
+
+    world.despawn(tag).unwrap();
+    world.despawn(unused).unwrap();
+
+    let recycled = world.new_id();
+    assert_eq!(recycled.index(), unused.index());
+    assert!(!world.exists(tag));
+    assert!(matches!(
+        unsafe { world.new_id_at(tag.index()) },
+        Err(EcsError::IndexRetired(i)) if i == tag.index()
+    ));
+}
+
+#[test]
+fn ids_are_created_with_flags() {
+    let mut world = World::new();
+    let id = world.new_id_with_flags(IdFlags::IS_TRAVERSABLE);
+
+    assert_eq!(world.entity_flags(id), Some(IdFlags::IS_TRAVERSABLE));
+    let plain = world.new_id();
+    assert_eq!(world.entity_flags(plain), Some(IdFlags::default()));
+}
+
+#[test]
+fn pair_targets_are_flagged() {
+    let mut world = World::new();
+    let likes = world.new_component(TagBuilder::new());
+    let [a, b] = [(); 2].map(|_| world.new_id());
+
+    assert!(!world.entity_flags(b).unwrap().contains(IdFlags::IS_TARGET));
+    world.add_id(a, (likes, b)).unwrap();
+
+    assert!(world.entity_flags(b).unwrap().contains(IdFlags::IS_TARGET));
+    assert!(!world.entity_flags(a).unwrap().contains(IdFlags::IS_TARGET));
+}
+
+#[test]
+fn ids_with_sparse_components_are_flagged() {
+    let mut world = World::new();
+    world.register::<Score>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let [sparse, other] = [(); 2].map(|_| world.new_id());
+
+    world.set::<Score>(sparse, Score(1));
+
+    assert!(
+        world
+            .entity_flags(sparse)
+            .unwrap()
+            .contains(IdFlags::HAS_SPARSE)
+    );
+    assert!(
+        !world
+            .entity_flags(other)
+            .unwrap()
+            .contains(IdFlags::HAS_SPARSE)
+    );
+    assert_eq!(world.entity_flags(Id::NULL), None);
+}
+
+#[test]
+fn exists_tells_stale_ids_from_unused_indices() {
+    let mut world = World::new();
+    let stale = world.new_id();
+    world.despawn(stale).unwrap();
+    let alive = world.new_id();
+    assert_eq!(alive.index(), stale.index());
+
+    // An index the world never issued, taken from a busier world.
+    let mut other = World::new();
+    let unused = (0..100).map(|_| other.new_id()).last().unwrap();
+
+    assert!(world.exists(alive) && world.is_alive(alive));
+    assert!(world.exists(stale) && !world.is_alive(stale));
+    assert!(!world.exists(unused) && !world.is_alive(unused));
+}
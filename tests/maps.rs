@@ -0,0 +1,66 @@
+use std::rc::Rc;
+use xecs::{
+    data_structures::{SparseEntry, SparseSet},
+    id::{IdMap, IntoId, KeyMap},
+    world::World,
+};
+
+#[test]
+fn sparse_entries_insert_once() {
+    let mut set = SparseSet::<usize, u32>::new();
+
+    assert!(matches!(set.entry(3), SparseEntry::Vacant(_)));
+    assert_eq!(*set.entry(3).or_insert_with(|| 1), 1);
+    assert_eq!(*set.entry(3).or_insert_with(|| 2), 1);
+
+    *set.entry(3).or_insert_with(|| unreachable!()) += 1;
+    assert_eq!(set.get(&3), Some(&2));
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn get_or_insert_with_returns_the_existing_value() {
+    let mut world = World::new();
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    let pair = (a, b).into_id();
+
+    let mut ids = IdMap::new();
+    let mut calls = 0;
+
+    for id in [a, pair, a, pair] {
+        ids.get_or_insert_with(id, || {
+            calls += 1;
+            calls
+        });
+    }
+
+    assert_eq!(calls, 2);
+    assert_eq!(ids.get(a), Some(&1));
+    assert_eq!(ids.get(pair), Some(&2));
+
+    let mut keys = KeyMap::default();
+    *keys.get_or_insert_with(&a, || 10) += 1;
+    assert_eq!(*keys.get_or_insert_with(&a, || 0), 11);
+    assert_eq!(keys.iter_ids().count(), 1);
+}
+
+#[test]
+fn cleared_sparse_sets_drop_their_values_and_accept_new_ones() {
+    let value = Rc::new(());
+    let mut set = SparseSet::<usize, Rc<()>>::new();
+
+    for key in [1, 4, 9] {
+        set.insert(key, Rc::clone(&value));
+    }
+
+    set.clear();
+    assert_eq!(Rc::strong_count(&value), 1);
+    assert_eq!(set.len(), 0);
+    assert!([1, 4, 9].iter().all(|key| !set.contains_key(key)));
+
+    set.insert(4, Rc::clone(&value));
+    assert_eq!(set.len(), 1);
+    assert!(set.contains_key(&4));
+    assert!(!set.contains_key(&9));
+    assert_eq!(set.iter().map(|(&key, _)| key).collect::<Vec<_>>(), [4]);
+}
@@ -0,0 +1,43 @@
+use xecs::{component::TagBuilder, world::World};
+
+#[test]
+fn merge_remaps_child_of_pairs() {
+    let mut dst = World::new();
+    let existing = [(); 4].map(|_| dst.new_id());
+
+    let mut src = World::new();
+    let parent = src.new_id();
+    let child = src.new_id();
+    let child_of = src.child_of();
+    src.add_id(child, (child_of, parent)).unwrap();
+
+    let map = dst.merge_into(src).unwrap();
+    let (parent, child) = (map[&parent], map[&child]);
+    let child_of = dst.child_of();
+
+    assert!(!existing.contains(&parent) && !existing.contains(&child));
+    assert!(dst.has_id(child, (child_of, parent)));
+
+    // The remapped pair cascades like one added in dst.
+    dst.despawn(parent).unwrap();
+    assert!(!dst.is_alive(child));
+    assert!(existing.iter().all(|&id| dst.is_alive(id)));
+}
+
+#[test]
+fn merge_skips_dangling_pairs() {
+    let mut src = World::new();
+    let likes = src.new_component(TagBuilder::new());
+    let holder = src.new_id();
+    let target = src.new_id();
+
+    src.add_id(holder, (likes, target)).unwrap();
+    src.despawn(target).unwrap();
+
+    let mut dst = World::new();
+    let map = dst.merge_into(src).unwrap();
+
+    assert!(dst.is_alive(map[&holder]));
+    assert!(dst.validate_relationships().is_empty());
+    assert!(dst.check_invariants().is_empty());
+}
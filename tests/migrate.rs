@@ -0,0 +1,89 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    id::Id,
+    query::{SelectStmt, WithStmt},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct Health(String);
+
+#[derive(Component)]
+struct Enemy;
+
+fn populated(storage: StorageType) -> (World, Vec<Id>) {
+    let mut world = World::new();
+    world.register::<Health>(
+        ComponentBuilder::new()
+            .storage(storage)
+            .clone(Health::clone),
+    );
+    world.register::<Enemy>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..10)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i.to_string()));
+
+            if i % 2 == 0 {
+                world.add::<Enemy>(id).unwrap();
+            }
+            id
+        })
+        .collect();
+
+    (world, ids)
+}
+
+fn assert_values(world: &World, ids: &[Id]) {
+    for (i, &id) in ids.iter().enumerate() {
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(i.to_string())));
+    }
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn sparse_values_survive_migrating_to_tables() {
+    let (mut world, ids) = populated(StorageType::Sparse);
+    let health = world.id::<Health>().unwrap();
+    let enemy = world.id::<Enemy>().unwrap();
+
+    world
+        .migrate_storage::<Health>(StorageType::Tables)
+        .unwrap();
+
+    assert_eq!(
+        world.component_stats(health).unwrap().storage,
+        StorageType::Tables
+    );
+    assert_values(&world, &ids);
+
+    let mut enemies = world.collect(
+        SelectStmt::default().read(health),
+        WithStmt::new().with(enemy),
+    );
+    enemies.sort();
+    assert_eq!(enemies, ids.iter().step_by(2).copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn migrating_back_and_forth_keeps_values_and_cloning() {
+    let (mut world, ids) = populated(StorageType::Tables);
+
+    world
+        .migrate_storage::<Health>(StorageType::Sparse)
+        .unwrap();
+    assert_values(&world, &ids);
+
+    // The emptied tables still hold the component in their signature.
+    let clone = world.deep_clone().unwrap();
+    assert_values(&clone, &ids);
+
+    world
+        .migrate_storage::<Health>(StorageType::Tables)
+        .unwrap();
+    assert_values(&world, &ids);
+    assert_values(&world.deep_clone().unwrap(), &ids);
+}
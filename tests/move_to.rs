@@ -0,0 +1,127 @@
+use std::{cell::RefCell, rc::Rc};
+use xecs::{
+    component::{BuilderCommon, ComponentBuilder, TagBuilder},
+    error::EcsError,
+    id::{Id, IntoId, Signature},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct A(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct B(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct C(u32);
+
+fn register(world: &mut World) -> [Id; 3] {
+    [
+        world.register::<A>(ComponentBuilder::new().storage(StorageType::Tables)),
+        world.register::<B>(
+            ComponentBuilder::new()
+                .storage(StorageType::Tables)
+                .default(|| B(2)),
+        ),
+        world.register::<C>(
+            ComponentBuilder::new()
+                .storage(StorageType::Tables)
+                .default(|| C(3)),
+        ),
+    ]
+}
+
+#[test]
+fn added_components_get_their_default() {
+    let mut world = World::new();
+    let [a, b, c] = register(&mut world);
+
+    let id = world.new_id();
+    world.set::<A>(id, A(1));
+    world.move_to(id, &Signature::from([a, b, c])).unwrap();
+
+    assert_eq!(world.get::<&A>(id).ok(), Some(&A(1)));
+    assert_eq!(world.get::<&B>(id).ok(), Some(&B(2)));
+    assert_eq!(world.get::<&C>(id).ok(), Some(&C(3)));
+}
+
+#[test]
+fn missing_default_moves_nothing() {
+    let mut world = World::new();
+    let [a, b, _] = register(&mut world);
+
+    let id = world.new_id();
+    world.set::<B>(id, B(5));
+
+    assert!(matches!(
+        world.move_to(id, &Signature::from([a])),
+        Err(EcsError::NoDefaultHook(comp)) if comp == a
+    ));
+    assert_eq!(world.get::<&B>(id).ok(), Some(&B(5)));
+    assert!(world.has_id(id, b));
+}
+
+#[test]
+fn defaults_are_replayed() {
+    let events = Rc::new(RefCell::new(vec![]));
+    let mut world = World::new();
+    let [_, b, c] = register(&mut world);
+
+    let sink = Rc::clone(&events);
+    world.enable_recording(move |event| sink.borrow_mut().push(event));
+    let id = world.new_id();
+    world.move_to(id, &Signature::from([b, c])).unwrap();
+    world.disable_recording();
+
+    let mut replayed = World::new();
+    register(&mut replayed);
+    replayed.replay(events.take()).unwrap();
+
+    let ids = replayed.collect(Default::default(), Default::default());
+    let values: Vec<_> = ids
+        .iter()
+        .filter_map(|&id| replayed.get::<&C>(id).ok())
+        .collect();
+    assert_eq!(values, [&C(3)]);
+}
+
+#[test]
+fn relationship_bookkeeping_is_applied() {
+    let mut world = World::new();
+    let friend = world.new_component(TagBuilder::new().symmetric());
+    let likes = world.new_component(TagBuilder::new().exclusive());
+    let marked = world.new_component(TagBuilder::new());
+    let tagged = world.new_component(TagBuilder::new().auto_add(marked));
+
+    let [x, y, z] = [(); 3].map(|_| world.new_id());
+
+    let target = Signature::from([(friend, y).into_id(), tagged]);
+    world.move_to(x, &target).unwrap();
+    assert!(world.has_id(y, (friend, x).into_id()));
+    assert!(world.has_id(x, marked));
+
+    world.move_to(x, &Signature::from([])).unwrap();
+    assert!(!world.has_id(y, (friend, x).into_id()));
+
+    let both = Signature::from([(likes, y).into_id(), (likes, z).into_id()]);
+    assert!(matches!(
+        world.move_to(x, &both),
+        Err(EcsError::ExclusiveRelationship(rel)) if rel == likes
+    ));
+}
+
+#[test]
+fn prefab_and_parent_are_kept() {
+    let mut world = World::new();
+    let [_, b, _] = register(&mut world);
+    let parent = world.new_id();
+    let id = world.new_id();
+
+    world.add_id(id, (world.child_of(), parent)).unwrap();
+    world.move_to(id, &Signature::from([b])).unwrap();
+
+    assert!(world.has_id(id, (world.child_of(), parent).into_id()));
+    assert!(world.has_id(id, b));
+}
@@ -0,0 +1,126 @@
+use std::{cell::RefCell, rc::Rc};
+use xecs::{
+    component::ComponentBuilder,
+    id::Id,
+    world::{EventKind, World},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Health(#[allow(dead_code)] u32);
+
+#[derive(Component)]
+struct Armor(#[allow(dead_code)] u32);
+
+#[derive(Component)]
+struct Damage(u32);
+
+fn observed_order(world: &mut World, comps: &[Id]) -> Rc<RefCell<Vec<Id>>> {
+    let order = Rc::new(RefCell::new(vec![]));
+    let seen = Rc::clone(&order);
+    world.observe_batched(EventKind::OnSet, comps, move |_, _, comp| {
+        seen.borrow_mut().push(comp)
+    });
+    order
+}
+
+#[test]
+fn higher_priority_components_are_observed_first() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().priority(1));
+    let armor = world.register::<Armor>(ComponentBuilder::new().priority(5));
+    let order = observed_order(&mut world, &[health, armor]);
+
+    let id = world.new_id();
+    world.set::<Health>(id, Health(10));
+    world.set::<Armor>(id, Armor(2));
+    world.dispatch_events();
+    assert_eq!(*order.borrow(), [armor, health]);
+
+    order.borrow_mut().clear();
+    world.set_component_priority(health, 10).unwrap();
+    world.set::<Armor>(id, Armor(3));
+    world.set::<Health>(id, Health(9));
+    world.dispatch_events();
+    assert_eq!(*order.borrow(), [health, armor]);
+}
+
+fn observed_writes(world: &mut World, comps: &[Id]) -> Rc<RefCell<Vec<(Id, Id)>>> {
+    let writes = Rc::new(RefCell::new(vec![]));
+    let seen = Rc::clone(&writes);
+    world.observe_batched(EventKind::OnSet, comps, move |_, id, comp| {
+        seen.borrow_mut().push((id, comp))
+    });
+    writes
+}
+
+#[test]
+fn repeated_sets_are_observed_once() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new());
+    let writes = observed_writes(&mut world, &[health]);
+
+    let id = world.new_id();
+    world.set::<Health>(id, Health(1));
+    world.set::<Health>(id, Health(2));
+    world.set::<Health>(id, Health(3));
+
+    assert!(writes.borrow().is_empty());
+    world.dispatch_events();
+    assert_eq!(*writes.borrow(), [(id, health)]);
+}
+
+#[test]
+fn distinct_ids_are_observed_in_index_order() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new());
+    let writes = observed_writes(&mut world, &[health]);
+
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Health>(b, Health(1));
+    world.set::<Health>(a, Health(2));
+    world.dispatch_events();
+
+    assert_eq!(*writes.borrow(), [(a, health), (b, health)]);
+}
+
+#[test]
+fn dispatch_resets_the_recorded_writes() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new());
+    let writes = observed_writes(&mut world, &[health]);
+
+    let id = world.new_id();
+    world.set::<Health>(id, Health(1));
+    world.dispatch_events();
+    world.dispatch_events();
+    assert_eq!(writes.borrow().len(), 1);
+
+    world.set::<Health>(id, Health(2));
+    world.dispatch_events();
+    assert_eq!(writes.borrow().len(), 2);
+}
+
+#[test]
+fn emitted_events_reach_every_listener_in_order() {
+    let mut world = World::new();
+    world.register::<Damage>(ComponentBuilder::new());
+
+    let received = Rc::new(RefCell::new(vec![]));
+
+    for listener in 0..2 {
+        let seen = Rc::clone(&received);
+        world
+            .on_event::<Damage>(move |_, _, damage| seen.borrow_mut().push((listener, damage.0)))
+            .unwrap();
+    }
+
+    for amount in [3, 1, 2] {
+        world.emit::<Damage>(Damage(amount)).unwrap();
+    }
+
+    assert_eq!(
+        *received.borrow(),
+        [(0, 3), (1, 3), (0, 1), (1, 1), (0, 2), (1, 2)]
+    );
+}
@@ -0,0 +1,113 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    id::Id,
+    query::{SelectStmt, WithStmt},
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+struct Level(u32);
+
+#[derive(Component)]
+struct Secret(#[allow(dead_code)] String);
+
+#[derive(Component)]
+struct Unregistered(#[allow(dead_code)] u32);
+
+fn new_world() -> World {
+    let mut world = World::new();
+    world.register::<Name>(ComponentBuilder::new().clone(Name::clone));
+    world.register::<Level>(ComponentBuilder::new());
+    world.register::<Secret>(ComponentBuilder::new());
+    world
+}
+
+/// Returns the ids that are not prefabs.
+fn instances(world: &World) -> Vec<Id> {
+    let level = world.id::<Level>().unwrap();
+    let with = WithStmt::new().with(level).without(world.prefab_tag());
+    world.collect(SelectStmt::default(), with)
+}
+
+fn children(world: &World, parent: Id) -> Vec<Id> {
+    let with = WithStmt::new().with_pair(world.child_of(), parent);
+    world.collect(SelectStmt::default(), with)
+}
+
+#[test]
+fn instantiates_three_levels() {
+    let mut world = new_world();
+
+    let prefab = world
+        .prefab_tree()
+        .root()
+        .with::<Name>(Name("root".into()))
+        .with::<Level>(Level(0))
+        .child()
+        .with::<Level>(Level(1))
+        .child()
+        .with::<Level>(Level(2))
+        .with::<Name>(Name("leaf".into()))
+        .build()
+        .unwrap();
+
+    let root = world.instantiate_tree(prefab).unwrap();
+    assert!(!world.has_id(root, world.prefab_tag()));
+    assert_eq!(world.get::<&Name>(root).ok(), Some(&Name("root".into())));
+
+    let [child] = children(&world, root).try_into().unwrap();
+    assert_eq!(world.get::<&Level>(child).ok(), Some(&Level(1)));
+
+    let [leaf] = children(&world, child).try_into().unwrap();
+    assert_eq!(world.get::<&Level>(leaf).ok(), Some(&Level(2)));
+    assert_eq!(world.get::<&Name>(leaf).ok(), Some(&Name("leaf".into())));
+}
+
+#[test]
+fn instantiation_is_atomic() {
+    let mut world = new_world();
+
+    let prefab = world
+        .prefab_tree()
+        .root()
+        .with::<Level>(Level(0))
+        .child()
+        .with::<Level>(Level(1))
+        .child()
+        .with::<Level>(Level(2))
+        .with::<Secret>(Secret("no clone hook".into()))
+        .build()
+        .unwrap();
+
+    let secret = world.id::<Secret>().unwrap();
+    let result = world.instantiate_tree(prefab);
+
+    assert!(matches!(result, Err(EcsError::NoCloneHook(id)) if id == secret));
+    assert!(instances(&world).is_empty());
+}
+
+#[test]
+fn builder_errors_are_returned() {
+    let mut world = new_world();
+
+    let result = world
+        .prefab_tree()
+        .root()
+        .with::<Level>(Level(0))
+        .child()
+        .with::<Unregistered>(Unregistered(1))
+        .with::<Level>(Level(1))
+        .build();
+
+    assert!(matches!(result, Err(EcsError::UnregisteredType(_))));
+
+    // The partial tree is despawned.
+    let level = world.id::<Level>().unwrap();
+    let with = WithStmt::new().with(level);
+    assert!(world.collect(SelectStmt::default(), with).is_empty());
+}
@@ -0,0 +1,83 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::GetError,
+    id::Id,
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::{Component, QueryData};
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(f32);
+
+#[derive(Component)]
+struct Velocity(f32);
+
+#[derive(Component)]
+struct Name(&'static str);
+
+#[derive(QueryData)]
+struct Mover<'a> {
+    #[entity]
+    id: Id,
+    pos: &'a mut Position,
+    vel: &'a Velocity,
+    name: Option<&'a Name>,
+}
+
+#[test]
+fn struct_views_are_iterated_per_id() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Name>(ComponentBuilder::new());
+
+    let [a, b, still] = [(); 3].map(|_| world.new_id());
+
+    for id in [a, b, still] {
+        world.set::<Position>(id, Position(1.0));
+    }
+
+    world.set::<Velocity>(a, Velocity(2.0));
+    world.set::<Velocity>(b, Velocity(3.0));
+    world.set::<Name>(a, Name("a"));
+
+    let mut named = vec![];
+
+    for mover in world.query_data::<Mover>().unwrap() {
+        mover.pos.0 += mover.vel.0;
+
+        if let Some(name) = mover.name {
+            named.push((mover.id, name.0));
+        }
+    }
+
+    assert_eq!(named, [(a, "a")]);
+    assert_eq!(world.get::<&Position>(a).ok(), Some(&Position(3.0)));
+    assert_eq!(world.get::<&Position>(b).ok(), Some(&Position(4.0)));
+    assert_eq!(world.get::<&Position>(still).ok(), Some(&Position(1.0)));
+}
+
+/// Same component as Position, under a name the derive can't tell apart.
+type OldPosition = Position;
+
+#[derive(QueryData)]
+#[allow(dead_code)]
+struct Aliased<'a> {
+    pos: &'a mut Position,
+    old: &'a OldPosition,
+}
+
+#[test]
+fn aliased_fields_are_reported() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let id = world.new_id();
+    world.set::<Position>(id, Position(1.0));
+
+    assert!(matches!(
+        world.query_data::<Aliased>().err(),
+        Some(GetError::AliasedComponent(_))
+    ));
+}
@@ -0,0 +1,154 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{Context, QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+#[derive(Component)]
+struct Velocity(f32);
+
+#[derive(Component)]
+struct Marker;
+
+/// Spawns 30 ids with a position over three tables, two thirds with a velocity.
+fn spawn(world: &mut World) {
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..30 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 3 != 0 {
+            world.set::<Velocity>(id, Velocity(1.0));
+        }
+        if i % 3 == 1 {
+            world.add::<Marker>(id).unwrap();
+        }
+    }
+}
+
+#[test]
+fn for_loops_visit_the_rows_of_nested_loops() {
+    let mut world = World::new();
+    spawn(&mut world);
+    let pos = world.id::<Position>().unwrap();
+    let vel = world.id::<Velocity>().unwrap();
+    let mut plan = QueryPlan::new(SelectStmt::default().read(pos).read(vel), WithStmt::new());
+
+    let mut nested = vec![];
+    plan.init_tables(&world);
+    let mut ctx = Context::new(&world);
+
+    while let Some(view) = plan.next_table(&mut ctx) {
+        nested.extend(view.rows().map(|(_, id)| id));
+    }
+
+    let iter = plan.iter(&mut world);
+    assert!(iter.size_hint().1.is_some_and(|max| max >= nested.len()));
+
+    let mut looped = vec![];
+
+    for (id, row) in iter {
+        assert_eq!(row.get::<Velocity>(1).map(|v| v.0), Some(1.0));
+        looped.push(id);
+    }
+
+    assert_eq!(looped, nested);
+    assert_eq!(looped.len(), 20);
+}
+
+#[test]
+fn writes_through_rows_persist() {
+    let mut world = World::new();
+    spawn(&mut world);
+    let pos = world.id::<Position>().unwrap();
+    let vel = world.id::<Velocity>().unwrap();
+
+    let mut plan = QueryPlan::new(SelectStmt::default().write(pos).read(vel), WithStmt::new());
+
+    for (_, mut row) in plan.iter(&mut world) {
+        // Read fields can't be borrowed mutably.
+        assert!(row.get_mut::<Velocity>(1).is_none());
+        let vel = row.get::<Velocity>(1).unwrap().0;
+        row.get_mut::<Position>(0).unwrap().0 += vel;
+    }
+
+    let mut plan = QueryPlan::new(SelectStmt::default(), WithStmt::new().with(vel));
+
+    for (_, (pos, vel)) in plan.iter_as::<(&mut Position, &Velocity)>(&mut world) {
+        pos.0 += vel.0 * 10.0;
+    }
+
+    let mut positions = world
+        .values::<Position>()
+        .map(|(_, pos)| pos.0)
+        .collect::<Vec<_>>();
+    positions.sort_by(f32::total_cmp);
+
+    let mut expected: Vec<_> = (0..30)
+        .map(|i| i as f32 + if i % 3 != 0 { 11.0 } else { 0.0 })
+        .collect();
+    expected.sort_by(f32::total_cmp);
+    assert_eq!(positions, expected);
+}
+
+#[test]
+fn collected_ids_survive_later_writes() {
+    let mut world = World::new();
+    spawn(&mut world);
+    let vel = world.id::<Velocity>().unwrap();
+    let marker = world.id::<Marker>().unwrap();
+
+    let with = || WithStmt::new().with(vel).without(marker);
+    let ids = world.collect(SelectStmt::default(), with());
+
+    let mut plan = QueryPlan::new(SelectStmt::default(), with());
+    let iterated: Vec<_> = plan.iter(&mut world).map(|(id, _)| id).collect();
+    assert_eq!(ids, iterated);
+    assert_eq!(ids.len(), 10);
+
+    // The snapshot can be used while the world moves and despawns its ids.
+    world.add::<Marker>(ids[0]).unwrap();
+    world.despawn(ids[1]).unwrap();
+
+    assert!(world.has::<Marker>(ids[0]));
+    assert!(!world.is_alive(ids[1]));
+    assert!(ids[2..].iter().all(|&id| world.is_alive(id)));
+    assert_eq!(world.collect(SelectStmt::default(), with()).len(), 8);
+}
+
+#[test]
+fn unvalidated_queries_match_the_same_rows() {
+    let mut world = World::new();
+    spawn(&mut world);
+    let pos = world.id::<Position>().unwrap();
+    let vel = world.id::<Velocity>().unwrap();
+    let marker = world.id::<Marker>().unwrap();
+
+    let run = |world: &mut World| {
+        let mut plan = QueryPlan::new(
+            SelectStmt::default().read(pos).read(vel),
+            WithStmt::new().without(marker),
+        );
+
+        plan.iter(world)
+            .map(|(id, row)| (id, row.get::<Position>(0).map(|p| p.0)))
+            .collect::<Vec<_>>()
+    };
+
+    let validated = run(&mut world);
+    world.disable_query_validation();
+    let fast = run(&mut world);
+    world.enable_query_validation();
+
+    assert_eq!(validated.len(), 10);
+    assert_eq!(fast, validated);
+    assert_eq!(run(&mut world), validated);
+}
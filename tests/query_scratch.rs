@@ -0,0 +1,79 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{Context, QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Marker;
+
+#[test]
+fn reused_plan_does_not_allocate() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let marker = world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..100 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 2 == 0 {
+            world.add::<Marker>(id).unwrap();
+        }
+    }
+
+    let mut plan = QueryPlan::new(
+        SelectStmt::default().read(pos),
+        WithStmt::new().with(marker),
+    );
+
+    let mut run_frame = |world: &mut World| {
+        let mut rows = 0;
+
+        for _ in 0..1000 {
+            plan.init_tables(world);
+            let mut ctx = Context::new(world);
+
+            while let Some(view) = plan.next_table(&mut ctx) {
+                rows += view.rows().count();
+            }
+        }
+
+        world.end_frame();
+        rows
+    };
+
+    // Warm-up grows the scratch memory of the plan.
+    assert_eq!(run_frame(&mut world), 50 * 1000);
+
+    let before = ALLOCS.load(Ordering::Relaxed);
+    assert_eq!(run_frame(&mut world), 50 * 1000);
+    assert_eq!(ALLOCS.load(Ordering::Relaxed), before);
+}
@@ -0,0 +1,89 @@
+use std::{cell::RefCell, rc::Rc};
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    recording::{Event, SetData},
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Score(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Flag(bool);
+
+fn register(world: &mut World) {
+    // SAFETY: `Score` is a single `u32`.
+    world.register::<Score>(unsafe { ComponentBuilder::new().pod() });
+    world.register::<Flag>(ComponentBuilder::new());
+}
+
+#[test]
+fn get_or_insert_records_the_written_value() {
+    let events = Rc::new(RefCell::new(vec![]));
+    let mut world = World::new();
+    register(&mut world);
+
+    let sink = Rc::clone(&events);
+    world.enable_recording(move |event| sink.borrow_mut().push(event));
+
+    let id = world.new_id();
+    world
+        .get_or_insert_with::<Score>(id, || Score(1))
+        .unwrap()
+        .0 = 7;
+    world.disable_recording();
+
+    let mut replayed = World::new();
+    register(&mut replayed);
+    replayed.replay(events.take()).unwrap();
+
+    let ids = replayed.collect(Default::default(), Default::default());
+    let values: Vec<_> = ids
+        .iter()
+        .filter_map(|&id| replayed.get::<&Score>(id).ok())
+        .collect();
+    assert_eq!(values, [&Score(7)]);
+}
+
+#[test]
+fn replay_rejects_raw_bytes_without_pod() {
+    let mut world = World::new();
+    register(&mut world);
+
+    let id = world.new_id();
+    let comp = world.id::<Flag>().unwrap();
+    let event = Event::Set {
+        id,
+        comp,
+        data: SetData::Bytes(vec![2]),
+    };
+
+    assert!(matches!(
+        world.replay([event]),
+        Err(EcsError::MalformedData(_))
+    ));
+}
+
+#[test]
+fn replayed_guids_are_indexed() {
+    let events = Rc::new(RefCell::new(vec![]));
+    let mut world = World::new();
+
+    let sink = Rc::clone(&events);
+    world.enable_recording(move |event| sink.borrow_mut().push(event));
+    world.spawn_with_guid(9).unwrap();
+    world.disable_recording();
+
+    let mut replayed = World::new();
+    let events = events.take();
+    replayed.replay(events.clone()).unwrap();
+    assert!(replayed.by_guid(9).is_some());
+
+    // Replaying again would give a second id the same guid.
+    assert!(matches!(
+        replayed.replay(events),
+        Err(EcsError::GuidCollision(9))
+    ));
+}
@@ -0,0 +1,142 @@
+use std::{cell::Cell, rc::Rc};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    error::{EcsError, GetError},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Unregistered(#[allow(dead_code)] u32);
+
+#[test]
+fn unregistered_errors_name_the_type() {
+    let mut world = World::new();
+    let id = world.new_id();
+
+    let err = match world.get::<&Unregistered>(id) {
+        Err(err @ GetError::UnregisteredType(_)) => err,
+        _ => panic!("expected an unregistered type error"),
+    };
+
+    let message = err.to_string();
+    assert!(
+        message.contains(std::any::type_name::<Unregistered>()),
+        "{message}"
+    );
+}
+
+#[test]
+#[should_panic(expected = "registration::Unregistered used before register()")]
+fn assert_registered_names_the_type() {
+    World::new().assert_registered::<Unregistered>();
+}
+
+#[test]
+fn assert_registered_accepts_registered_types() {
+    let mut world = World::new();
+    world.register::<Unregistered>(ComponentBuilder::new());
+    world.assert_registered::<Unregistered>();
+}
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Velocity(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Health(#[allow(dead_code)] u32);
+
+#[derive(Component)]
+struct Frozen;
+
+#[derive(Component)]
+struct Player;
+
+#[test]
+fn bulk_registered_components_get_distinct_ids() {
+    let mut world = World::new();
+    let ids = world.register_bulk::<(Position, Velocity, Health, Frozen, Player)>();
+
+    assert_eq!(
+        ids,
+        [
+            world.id::<Position>().unwrap(),
+            world.id::<Velocity>().unwrap(),
+            world.id::<Health>().unwrap(),
+            world.id::<Frozen>().unwrap(),
+            world.id::<Player>().unwrap(),
+        ]
+    );
+
+    let mut distinct = ids.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 5);
+
+    // Registering again returns the same ids.
+    assert_eq!(
+        world.register_bulk::<(Position, Velocity, Health, Frozen, Player)>(),
+        ids
+    );
+}
+
+#[derive(Component)]
+struct Sprite(#[allow(dead_code)] u32);
+
+#[derive(Component)]
+struct Texture(#[allow(dead_code)] u32);
+
+#[test]
+fn registering_a_component_can_register_its_dependencies() {
+    let mut world = World::new();
+    let seen = Rc::new(Cell::new(None));
+
+    let sprite = world.register::<Sprite>(ComponentBuilder::new().on_register({
+        let seen = Rc::clone(&seen);
+        move |world, id| {
+            seen.set(Some(id));
+            world.register::<Texture>(ComponentBuilder::new());
+        }
+    }));
+
+    assert_eq!(seen.get(), Some(sprite));
+    assert!(world.id::<Texture>().is_ok());
+    assert_ne!(world.id::<Texture>().unwrap(), sprite);
+
+    // The hook of a second registration is not called, the type already exists.
+    seen.set(None);
+    let again = world.register::<Sprite>(ComponentBuilder::new().on_register({
+        let seen = Rc::clone(&seen);
+        move |_, id| seen.set(Some(id))
+    }));
+    assert_eq!(again, sprite);
+    assert_eq!(seen.get(), None);
+}
+
+#[test]
+fn conflicting_storage_types_are_rejected() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let res = world.try_register::<Position>(ComponentBuilder::new().storage(StorageType::Sparse));
+    assert!(matches!(res, Err(EcsError::StorageConflict(comp)) if comp == pos));
+
+    // The same storage type registers again.
+    let res = world.try_register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    assert_eq!(res.unwrap(), pos);
+
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Sparse));
+    let res = world.try_register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+    assert!(matches!(res, Err(EcsError::StorageConflict(comp)) if comp == frozen));
+}
+
+#[test]
+#[should_panic(expected = "already registered with a different storage type")]
+fn register_panics_on_conflicting_storage_types() {
+    let mut world = World::new();
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+}
@@ -0,0 +1,304 @@
+use xecs::{
+    component::{BuilderCommon, ComponentBuilder, TagBuilder},
+    error::EcsError,
+    flags::ComponentFlags,
+    id::IntoId,
+    query::{SelectStmt, Term, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Distance(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Allies;
+
+#[derive(Component)]
+struct Likes;
+
+#[derive(Component)]
+struct Team;
+
+#[derive(Component)]
+struct IsA;
+
+#[derive(Component)]
+struct Red;
+
+#[derive(Component)]
+struct Blue;
+
+#[derive(Component, Debug, PartialEq)]
+struct Apples(u32);
+
+#[test]
+fn symmetric_component_with_data_is_rejected() {
+    let mut world = World::new();
+
+    let result = world
+        .try_register::<Distance>(ComponentBuilder::new().add_flags(ComponentFlags::SYMMETRIC));
+
+    assert!(matches!(result, Err(EcsError::SymmetricData(_))));
+}
+
+#[test]
+fn self_pair_terms_match_the_iterated_id() {
+    let mut world = World::new();
+    let owns = world.new_component(TagBuilder::new());
+    let [a, b, c] = [(); 3].map(|_| world.new_id());
+
+    world.add_id(a, (owns, a)).unwrap();
+    world.add_id(b, (owns, c)).unwrap();
+    world.add_id(c, (owns, c)).unwrap();
+
+    let mut owners = world.collect(
+        SelectStmt::default(),
+        WithStmt::new().with_pair(owns, Term::This),
+    );
+    owners.sort();
+    assert_eq!(owners, [a, c]);
+
+    let others = world.collect(
+        SelectStmt::default(),
+        WithStmt::new()
+            .with_pair(owns, c)
+            .without_pair(owns, Term::This),
+    );
+    assert_eq!(others, [b]);
+}
+
+#[test]
+fn pairs_to_recycled_targets_are_dangling() {
+    let mut world = World::new();
+    let likes = world.new_component(TagBuilder::new());
+    let [holder, tgt] = [(); 2].map(|_| world.new_id());
+
+    world.add_id(holder, (likes, tgt)).unwrap();
+    world.despawn(tgt).unwrap();
+
+    let recycled = world.new_id();
+    assert_eq!(recycled.index(), tgt.index());
+
+    let dangling = world.validate_relationships();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].0, holder);
+    assert_eq!(dangling[0].2.index(), tgt.index());
+}
+
+#[test]
+fn symmetric_pairs_are_mirrored() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        let allies = world.register::<Allies>(TagBuilder::new().storage(storage).symmetric());
+        let [a, b] = [(); 2].map(|_| world.new_id());
+
+        world.add_id(a, (allies, b)).unwrap();
+        assert!(world.has_id(a, (allies, b)));
+        assert!(world.has_id(b, (allies, a)));
+
+        world.remove_id(b, (allies, a)).unwrap();
+        assert!(!world.has_id(a, (allies, b)));
+        assert!(!world.has_id(b, (allies, a)));
+    }
+}
+
+#[derive(Component)]
+struct Owes(u32);
+
+/// Gives 10k ids a pair of `Owes` to one of 5k targets, returns the bytes used by
+/// sparse sets and component records, and the number of component records.
+fn owe_5k_targets(relationship: bool) -> (usize, usize) {
+    let mut world = World::new();
+    let builder = ComponentBuilder::new().storage(StorageType::Sparse);
+    world.register::<Owes>(match relationship {
+        true => builder.relationship(),
+        false => builder,
+    });
+
+    let targets: Vec<_> = (0..5000).map(|_| world.new_id()).collect();
+
+    for i in 0..10_000 {
+        let id = world.new_id();
+        world
+            .set_pair_target::<Owes>(id, targets[i % targets.len()], Owes(i as u32))
+            .unwrap();
+    }
+
+    let usage = world.total_memory_usage();
+    let records = world.split().1.read().len();
+    (
+        usage.sparse_set_bytes + usage.component_registry_bytes,
+        records,
+    )
+}
+
+#[test]
+fn sparse_relationships_share_one_store_for_all_pairs() {
+    let (per_pair_bytes, per_pair_records) = owe_5k_targets(false);
+    let (shared_bytes, shared_records) = owe_5k_targets(true);
+
+    assert!(per_pair_records >= 5000, "{per_pair_records}");
+    assert!(shared_records < 100, "{shared_records}");
+    assert!(
+        shared_bytes * 10 < per_pair_bytes,
+        "{shared_bytes} vs {per_pair_bytes}"
+    );
+}
+
+#[test]
+fn reserved_relationship_targets_dont_grow_the_records() {
+    let mut world = World::new();
+    world.register::<Owes>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let parents: Vec<_> = (0..100).map(|_| world.new_id()).collect();
+    let children: Vec<_> = (0..300).map(|_| world.new_id()).collect();
+
+    world.reserve_relationships(parents.len());
+    let reserved = world.total_memory_usage().component_registry_bytes;
+
+    for (i, &child) in children.iter().enumerate() {
+        let parent = parents[i % parents.len()];
+        world
+            .set_pair_target::<Owes>(child, parent, Owes(i as u32))
+            .unwrap();
+    }
+
+    assert_eq!(
+        world.total_memory_usage().component_registry_bytes,
+        reserved
+    );
+}
+
+#[test]
+fn typed_pairs_are_set_and_read() {
+    let mut world = World::new();
+    world.register::<Likes>(TagBuilder::new());
+    world.register::<Apples>(ComponentBuilder::new());
+    world.register::<Owes>(ComponentBuilder::new().relationship());
+
+    let [id, bank, shop] = [(); 3].map(|_| world.new_id());
+
+    // The tag relationship takes the data of its target type.
+    assert!(
+        world
+            .set_pair::<Likes, Apples>(id, Apples(3))
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(world.get_pair::<Likes, Apples>(id).ok(), Some(&Apples(3)));
+
+    assert!(
+        world
+            .set_pair_target::<Owes>(id, bank, Owes(10))
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        world
+            .set_pair_target::<Owes>(id, bank, Owes(20))
+            .unwrap()
+            .map(|o| o.0),
+        Some(10)
+    );
+    assert_eq!(
+        world.get_pair_target::<Owes>(id, bank).ok().map(|o| o.0),
+        Some(20)
+    );
+    assert!(world.has_pair_target::<Owes>(id, bank));
+    assert!(!world.has_pair_target::<Owes>(id, shop));
+
+    world.despawn(shop).unwrap();
+    let res = world.set_pair_target::<Owes>(id, shop, Owes(1));
+    assert!(matches!(res, Err(EcsError::InvalidPair(_))));
+    assert!(world.get_pair_target::<Owes>(id, shop).is_err());
+}
+
+#[test]
+fn values_of_sparse_tag_relationships_are_rejected() {
+    let mut world = World::new();
+    let likes = world.register::<Likes>(
+        TagBuilder::new()
+            .storage(StorageType::Sparse)
+            .relationship(),
+    );
+    let apples = world.register::<Apples>(ComponentBuilder::new());
+    let id = world.new_id();
+
+    let res = world.set_pair::<Likes, Apples>(id, Apples(3));
+    assert!(matches!(res, Err(EcsError::IsTag(comp)) if comp == (likes, apples).into_id()));
+    assert!(!world.has_id(id, (likes, apples)));
+}
+
+#[test]
+fn exclusive_pairs_are_only_set_once() {
+    let mut world = World::new();
+    let team = world.register::<Team>(TagBuilder::new().relationship().exclusive());
+    let red = world.register::<Red>(TagBuilder::new());
+    let blue = world.register::<Blue>(TagBuilder::new());
+
+    let [player, rival] = [(); 2].map(|_| world.new_id());
+
+    assert_eq!(world.get_or_set_pair::<Team, Red>(player).unwrap(), red);
+    assert_eq!(world.get_or_set_pair::<Team, Blue>(player).unwrap(), red);
+    assert_eq!(
+        world.pairs_of(player, team).unwrap(),
+        [(team, red).into_id()]
+    );
+
+    world.add_id(rival, (team, blue)).unwrap();
+    assert_eq!(world.get_or_set_pair::<Team, Red>(rival).unwrap(), blue);
+    assert_eq!(
+        world.pairs_of(rival, team).unwrap(),
+        [(team, blue).into_id()]
+    );
+}
+
+#[test]
+fn transitive_values_are_inherited_from_the_root() {
+    let mut world = World::new();
+    let is_a = world.register::<IsA>(TagBuilder::new().relationship().transitive());
+    world.register::<Apples>(ComponentBuilder::new());
+
+    let [root, a, b, c] = [(); 4].map(|_| world.new_id());
+    world.set::<Apples>(root, Apples(7));
+    world.add_id(a, (is_a, root)).unwrap();
+    world.add_id(b, (is_a, a)).unwrap();
+    world.add_id(c, (is_a, b)).unwrap();
+
+    for (id, depth) in [(root, 0), (a, 1), (b, 2), (c, 3)] {
+        assert_eq!(
+            world.get_transitive::<IsA, Apples>(id, depth),
+            Some(&Apples(7))
+        );
+    }
+    assert_eq!(world.get_transitive::<IsA, Apples>(c, 2), None);
+
+    // An own value hides the inherited one.
+    world.set::<Apples>(b, Apples(2));
+    assert_eq!(world.get_transitive::<IsA, Apples>(c, 3), Some(&Apples(2)));
+
+    // Cycles end the search.
+    let [x, y] = [(); 2].map(|_| world.new_id());
+    world.add_id(x, (is_a, y)).unwrap();
+    world.add_id(y, (is_a, x)).unwrap();
+    assert_eq!(world.get_transitive::<IsA, Apples>(x, 100), None);
+}
+
+#[test]
+fn values_are_not_inherited_through_other_relationships() {
+    let mut world = World::new();
+    let likes = world.register::<Likes>(TagBuilder::new().relationship());
+    world.register::<Apples>(ComponentBuilder::new());
+
+    let [root, child] = [(); 2].map(|_| world.new_id());
+    world.set::<Apples>(root, Apples(7));
+    world.add_id(child, (likes, root)).unwrap();
+
+    assert_eq!(world.get_transitive::<Likes, Apples>(child, 5), None);
+    assert_eq!(
+        world.get_transitive::<Likes, Apples>(root, 5),
+        Some(&Apples(7))
+    );
+}
@@ -0,0 +1,93 @@
+use std::{cell::RefCell, rc::Rc};
+use xecs::{
+    component::ComponentBuilder,
+    id::{Id, IntoId},
+    query::{Context, QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::{ReparentView, World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Local(i32);
+
+fn hierarchy() -> (World, Id, Id, Id) {
+    let mut world = World::new();
+    world.register::<Local>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let [a, b, child] = [(); 3].map(|_| world.new_id());
+    world.add_id(child, (world.child_of(), a)).unwrap();
+    world.set::<Local>(child, Local(1));
+    (world, a, b, child)
+}
+
+#[test]
+fn reparent_swaps_the_pair_in_one_move() {
+    let (mut world, a, b, child) = hierarchy();
+    let child_of = world.child_of();
+
+    let created = Rc::new(RefCell::new(vec![]));
+    let on_create = created.clone();
+    world.observe_tables(move |table, signature| {
+        on_create.borrow_mut().push((table, signature.clone()))
+    });
+
+    world
+        .reparent(child, b, None::<fn(&mut ReparentView<'_>, Id, Id, Id)>)
+        .unwrap();
+
+    // The graph creates the table without the old pair on the way, but the child
+    // goes straight to the table with the new pair.
+    for (table, signature) in created.borrow().iter() {
+        let mut plan = QueryPlan::new(SelectStmt::default(), WithStmt::new()).for_tables([*table]);
+        plan.init_tables(&world);
+        let mut ctx = Context::new(&world);
+        let mut rows = 0;
+
+        while let Some(view) = plan.next_table(&mut ctx) {
+            rows += view.rows().count();
+        }
+
+        let has_parent = signature.has_id((child_of, b).into_id());
+        assert_eq!(rows, usize::from(has_parent), "{:?}", signature.ids());
+    }
+
+    assert!(world.has_id(child, (child_of, b)));
+    assert!(!world.has_id(child, (child_of, a)));
+}
+
+#[test]
+fn fixup_runs_before_the_swap() {
+    let (mut world, a, b, child) = hierarchy();
+    let child_of = world.child_of();
+    let mut calls = vec![];
+
+    let fixup = |view: &mut ReparentView<'_>, id, old, new| {
+        assert!(view.world().has_id(id, (child_of, old)));
+        assert!(!view.world().has_id(id, (child_of, new)));
+        view.get_mut::<Local>().unwrap().0 += 10;
+        calls.push((id, old, new));
+    };
+    world.reparent(child, b, Some(fixup)).unwrap();
+
+    assert_eq!(calls, [(child, a, b)]);
+    assert_eq!(world.get::<&Local>(child).ok(), Some(&Local(11)));
+    assert!(world.has_id(child, (child_of, b)));
+}
+
+#[test]
+fn reparent_children_moves_whole_tables() {
+    let (mut world, a, b, child) = hierarchy();
+    let child_of = world.child_of();
+    let other = world.new_id();
+    world.add_id(other, (child_of, a)).unwrap();
+
+    assert_eq!(world.reparent_children(a, b).unwrap(), 2);
+
+    for id in [child, other] {
+        assert!(world.has_id(id, (child_of, b)));
+        assert!(!world.has_id(id, (child_of, a)));
+    }
+    assert_eq!(world.get::<&Local>(child).ok(), Some(&Local(1)));
+    assert!(world.check_invariants().is_empty());
+}
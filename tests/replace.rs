@@ -0,0 +1,73 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Health(u32);
+
+#[derive(Component)]
+struct Idle;
+
+#[derive(Component)]
+struct Running;
+
+#[test]
+fn replacing_a_state_tag_moves_the_id_once() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let idle = world.register::<Idle>(TagBuilder::new().storage(StorageType::Tables));
+    let running = world.register::<Running>(TagBuilder::new().storage(StorageType::Tables));
+    world.enable_move_stats();
+
+    let id = world.new_id();
+    world.add::<Idle>(id).unwrap();
+    world.set::<Health>(id, Health(7));
+
+    assert!(
+        world
+            .replace_component::<Idle, Running>(id, Running)
+            .unwrap()
+            .is_none()
+    );
+    assert!(!world.has::<Idle>(id));
+    assert!(world.has::<Running>(id));
+    assert_eq!(world.get::<&Health>(id).unwrap().0, 7);
+
+    // Neither table between the two states ever held a row.
+    for between in [vec![health], vec![health, idle, running]] {
+        let table = world.ensure_archetype(&between);
+        assert_eq!(world.table_excess_capacity(table), Some(0));
+    }
+
+    let stats = world.component_stats(idle).unwrap();
+    assert_eq!((stats.removes, stats.count), (1, 0));
+
+    let stats = world.component_stats(running).unwrap();
+    assert_eq!((stats.adds, stats.count), (1, 1));
+}
+
+#[test]
+fn components_are_removed_only_when_the_predicate_holds() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        world.register::<Health>(ComponentBuilder::new().storage(storage));
+
+        let [weak, strong, none] = [(); 3].map(|_| world.new_id());
+        world.set::<Health>(weak, Health(20));
+        world.set::<Health>(strong, Health(80));
+
+        let over_50 = |health: &Health| health.0 > 50;
+        assert!(!world.remove_if::<Health>(weak, over_50).unwrap());
+        assert!(world.remove_if::<Health>(strong, over_50).unwrap());
+        assert!(!world.remove_if::<Health>(none, |_| unreachable!()).unwrap());
+
+        assert_eq!(world.get::<&Health>(weak).unwrap().0, 20);
+        assert!(!world.has::<Health>(strong));
+
+        world.despawn(none).unwrap();
+        assert!(world.remove_if::<Health>(none, |_| true).is_err());
+    }
+}
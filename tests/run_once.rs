@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    error::GetError,
+    id::Id,
+    query::{QueryPlan, SelectStmt, WithStmt, WorldQuery},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Velocity(f32);
+
+#[derive(Component)]
+struct Marker;
+
+fn world_with_movers() -> (World, Vec<Id>) {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new());
+
+    let ids = (0..3)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Position>(id, Position(i as f32));
+            world.set::<Velocity>(id, Velocity(1.0));
+            id
+        })
+        .collect();
+
+    (world, ids)
+}
+
+#[test]
+fn run_once_mutates_components() {
+    let (mut world, ids) = world_with_movers();
+    let step = 2.0;
+
+    world.run_once(|mut q: WorldQuery<(&Position, &mut Velocity)>| {
+        for (_, (pos, vel)) in q.iter() {
+            vel.0 += pos.0 * step;
+        }
+    });
+
+    for (i, id) in ids.into_iter().enumerate() {
+        let vel = world.get::<&Velocity>(id).ok();
+        assert_eq!(vel, Some(&Velocity(1.0 + i as f32 * step)));
+    }
+}
+
+#[test]
+fn aliased_params_are_reported() {
+    let (mut world, ids) = world_with_movers();
+
+    world.run_once(|mut q: WorldQuery<(&mut Position, &mut Position)>| {
+        let results: Vec<_> = q.try_iter().map(|(id, res)| (id, res.err())).collect();
+
+        assert_eq!(results.len(), ids.len());
+        assert!(
+            results
+                .iter()
+                .all(|(_, err)| matches!(err, Some(GetError::AliasedComponent(_))))
+        );
+    });
+}
+
+#[test]
+#[should_panic(expected = "can't be created")]
+fn iter_panics_on_aliased_params() {
+    let (mut world, _) = world_with_movers();
+
+    world.run_once(|mut q: WorldQuery<(&mut Position, &mut Position)>| {
+        q.iter().for_each(drop);
+    });
+}
+
+/// Builds 10 000 ids with a Position, every third one also has a Marker.
+fn world_with_positions() -> (World, Id, Id) {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let marker = world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..10_000 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 3 == 0 {
+            world.add::<Marker>(id).unwrap();
+        }
+    }
+
+    (world, pos, marker)
+}
+
+#[test]
+fn batch_execute_matches_query_iter() {
+    let (mut batched, pos, marker) = world_with_positions();
+    let (mut iterated, _, _) = world_with_positions();
+    let plan = || {
+        QueryPlan::new(
+            SelectStmt::default().write(pos),
+            WithStmt::new().without(marker),
+        )
+    };
+
+    let visited = RefCell::new(vec![]);
+    batched
+        .batch_query_execute::<&mut Position, _>(plan(), |id, pos| {
+            pos.0 *= 2.0;
+            visited.borrow_mut().push(id);
+        })
+        .unwrap();
+
+    let mut expected = vec![];
+    let mut iter_plan = plan();
+
+    for (id, mut row) in iter_plan.iter(&mut iterated) {
+        row.get_mut::<Position>(0).unwrap().0 *= 2.0;
+        expected.push(id);
+    }
+
+    let visited = visited.into_inner();
+    assert_eq!(visited.len(), 6_666);
+    assert_eq!(visited, expected);
+
+    for id in expected {
+        let batched = batched.get::<&Position>(id).ok();
+        assert_eq!(batched, iterated.get::<&Position>(id).ok());
+    }
+}
+
+#[test]
+fn batch_execute_reports_aliased_params() {
+    let (mut world, pos, _) = world_with_positions();
+    let plan = QueryPlan::new(SelectStmt::default().read(pos), WithStmt::new());
+
+    let res = world.batch_query_execute::<(&mut Position, &mut Position), _>(plan, |_, _| {
+        panic!("aliased params were created")
+    });
+
+    assert!(matches!(res, Err(GetError::AliasedComponent(_))));
+}
+
+#[test]
+fn batch_execute_checks_sparse_terms_per_row() {
+    let (mut world, ids) = world_with_movers();
+    let pos = world.id::<Position>().unwrap();
+    let vel = world.id::<Velocity>().unwrap();
+    world.remove::<Velocity>(ids[1]).unwrap();
+
+    let plan = QueryPlan::new(SelectStmt::default().read(pos), WithStmt::new().with(vel));
+    let visited = RefCell::new(vec![]);
+
+    world
+        .batch_query_execute::<(&Position, &mut Velocity), _>(plan, |id, (_, vel)| {
+            vel.0 = 0.0;
+            visited.borrow_mut().push(id);
+        })
+        .unwrap();
+
+    assert_eq!(visited.into_inner(), [ids[0], ids[2]]);
+}
@@ -0,0 +1,79 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    flags::ComponentFlags,
+    schema::SchemaMismatch,
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Position(f32, f32);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Velocity(f32, f32);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct HealthV1(u16);
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct HealthV2(u32);
+
+#[derive(Component)]
+struct Frozen;
+
+#[test]
+fn schema_changes_are_listed_exactly() {
+    let mut saved = World::new();
+    saved.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    saved.register::<Velocity>(ComponentBuilder::new());
+    saved.register::<HealthV1>(ComponentBuilder::new().name("Health"));
+    saved.register::<Frozen>(TagBuilder::new());
+    let schema = saved.export_schema();
+
+    assert!(saved.check_schema_compat(&schema).is_empty());
+
+    // Position moves to sparse storage, Velocity becomes a relationship, Health grows
+    // and Frozen is not registered.
+    let mut current = World::new();
+    current.register::<Position>(ComponentBuilder::new().storage(StorageType::Sparse));
+    current.register::<Velocity>(ComponentBuilder::new().relationship());
+    current.register::<HealthV2>(ComponentBuilder::new().name("Health"));
+
+    let frozen = std::any::type_name::<Frozen>().to_string();
+    let position = std::any::type_name::<Position>().to_string();
+    let velocity = std::any::type_name::<Velocity>().to_string();
+
+    let mut expected = vec![
+        SchemaMismatch::MissingComponent(frozen),
+        SchemaMismatch::SizeChanged {
+            name: "Health".into(),
+            saved: Some(2),
+            current: Some(4),
+        },
+        SchemaMismatch::StorageChanged {
+            name: position,
+            saved: StorageType::Tables,
+            current: StorageType::Sparse,
+        },
+        SchemaMismatch::FlagsChanged {
+            name: velocity,
+            saved: ComponentFlags::empty(),
+            current: ComponentFlags::RELATIONSHIP,
+        },
+    ];
+
+    // Mismatches follow the saved components, which are sorted by name.
+    expected.sort_by_key(|mismatch| match mismatch {
+        SchemaMismatch::MissingComponent(name) => name.clone(),
+        SchemaMismatch::SizeChanged { name, .. }
+        | SchemaMismatch::StorageChanged { name, .. }
+        | SchemaMismatch::FlagsChanged { name, .. } => name.clone(),
+    });
+
+    assert_eq!(current.check_schema_compat(&schema), expected);
+}
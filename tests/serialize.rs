@@ -0,0 +1,128 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(f32, f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Visible(bool);
+
+fn new_world() -> World {
+    let mut world = World::new();
+
+    // SAFETY: Two f32 have no padding, and every bit pattern is a valid f32.
+    let position = unsafe { ComponentBuilder::new().name("Position").pod() };
+    world.register::<Position>(position);
+    world.register::<Visible>(ComponentBuilder::new().name("Visible"));
+    world
+}
+
+#[test]
+fn pod_values_round_trip() {
+    let mut world = new_world();
+    let id = world.new_id();
+    world.set::<Position>(id, Position(1.0, 2.0));
+    world.set::<Visible>(id, Visible(true));
+
+    let mut buf = vec![];
+    world.serialize_entity(id, &mut buf).unwrap();
+
+    let copy = world.deserialize_entity(&buf).unwrap();
+    assert_eq!(world.get::<&Position>(copy).ok(), Some(&Position(1.0, 2.0)));
+
+    // Visible is neither plain old data nor has serialize hooks.
+    assert!(!world.has::<Visible>(copy));
+}
+
+#[test]
+fn raw_bytes_are_rejected_without_pod() {
+    let mut world = new_world();
+
+    let mut buf = b"XECS".to_vec();
+    buf.extend_from_slice(&1u16.to_le_bytes());
+    buf.extend_from_slice(&1u32.to_le_bytes());
+    buf.extend_from_slice(&7u16.to_le_bytes());
+    buf.extend_from_slice(b"Visible");
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    buf.extend_from_slice(&1u32.to_le_bytes());
+    buf.push(2);
+
+    let result = world.deserialize_entity(&buf);
+    assert!(matches!(result, Err(EcsError::MalformedData(_))));
+}
+
+#[test]
+fn oversized_names_are_rejected() {
+    let mut world = World::new();
+    let long_name = "x".repeat(u16::MAX as usize + 1);
+    world.register::<Visible>(
+        ComponentBuilder::new()
+            .name(long_name)
+            .serialize(|v, buf| buf.push(v.0 as u8), |bytes| Visible(bytes[0] != 0)),
+    );
+
+    let id = world.new_id();
+    world.set::<Visible>(id, Visible(true));
+
+    let mut buf = vec![9];
+    let result = world.serialize_entity(id, &mut buf);
+
+    assert!(matches!(result, Err(EcsError::DataTooLarge(_))));
+    assert_eq!(buf, [9]);
+}
+
+#[test]
+fn data_without_versions_is_read_as_version_0() {
+    let mut world = World::new();
+    world.register::<Position>(
+        ComponentBuilder::new()
+            .name("Position")
+            .version(1)
+            .migrate_from(0, |bytes| {
+                let x = f32::from_le_bytes(bytes[..4].try_into().unwrap());
+                Position(x, 0.0)
+            })
+            .serialize(
+                |v, buf| buf.extend([v.0, v.1].map(f32::to_le_bytes).concat()),
+                |bytes| {
+                    let [x, y] =
+                        [0, 4].map(|i| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()));
+                    Position(x, y)
+                },
+            ),
+    );
+
+    // Written before the format prefix: count, then name, size and data of each component.
+    let mut buf = vec![];
+    buf.extend_from_slice(&1u32.to_le_bytes());
+    buf.extend_from_slice(&8u16.to_le_bytes());
+    buf.extend_from_slice(b"Position");
+    buf.extend_from_slice(&4u32.to_le_bytes());
+    buf.extend_from_slice(&3.0f32.to_le_bytes());
+
+    let old = world.deserialize_entity(&buf).unwrap();
+    assert_eq!(world.get::<&Position>(old).ok(), Some(&Position(3.0, 0.0)));
+
+    buf.clear();
+    world.serialize_entity(old, &mut buf).unwrap();
+    assert!(buf.starts_with(b"XECS"));
+
+    let copy = world.deserialize_entity(&buf).unwrap();
+    assert_eq!(world.get::<&Position>(copy).ok(), Some(&Position(3.0, 0.0)));
+}
+
+#[test]
+fn unknown_formats_are_rejected() {
+    let mut world = new_world();
+
+    let mut buf = b"XECS".to_vec();
+    buf.extend_from_slice(&2u16.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes());
+
+    let result = world.deserialize_entity(&buf);
+    assert!(matches!(result, Err(EcsError::MalformedData(_))));
+}
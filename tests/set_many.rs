@@ -0,0 +1,68 @@
+use xecs::{
+    component::ComponentBuilder,
+    error::EcsError,
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Velocity(f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Health(u32);
+
+fn new_world() -> World {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Health>(ComponentBuilder::new().storage(StorageType::Sparse));
+    world
+}
+
+#[test]
+fn values_are_written_with_a_single_move() {
+    let mut world = new_world();
+    let id = world.new_id();
+
+    world
+        .set_many(id, (Position(1.0), Velocity(2.0), Health(3)))
+        .unwrap();
+
+    assert_eq!(world.get::<&Position>(id).ok(), Some(&Position(1.0)));
+    assert_eq!(world.get::<&Velocity>(id).ok(), Some(&Velocity(2.0)));
+    assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(3)));
+
+    // The table with only the position never held a row.
+    let pos = world.id::<Position>().unwrap();
+    let table = world.ensure_archetype(&[pos]);
+    assert_eq!(world.table_excess_capacity(table), Some(0));
+
+    // Values the id already has are replaced in place.
+    world.set_many(id, (Velocity(5.0), Position(4.0))).unwrap();
+    assert_eq!(world.get::<&Position>(id).ok(), Some(&Position(4.0)));
+    assert_eq!(world.get::<&Velocity>(id).ok(), Some(&Velocity(5.0)));
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn invalid_lists_write_nothing() {
+    let mut world = new_world();
+    let id = world.new_id();
+    world.set::<Position>(id, Position(1.0));
+
+    let vel = world.id::<Velocity>().unwrap();
+    let res = world.set_many(id, (Position(2.0), Health(3), Velocity(4.0), Velocity(5.0)));
+    assert!(matches!(res, Err(EcsError::DuplicateComponent(comp)) if comp == vel));
+
+    assert_eq!(world.get::<&Position>(id).ok(), Some(&Position(1.0)));
+    assert!(!world.has::<Health>(id));
+    assert!(!world.has::<Velocity>(id));
+
+    let dead = world.new_id();
+    world.despawn(dead).unwrap();
+    assert!(world.set_many(dead, (Position(2.0),)).is_err());
+}
@@ -0,0 +1,81 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use xecs::{
+    id::{Id, Signature},
+    world::World,
+};
+
+struct CountingAlloc;
+
+thread_local! {
+    /// Allocations made by the current thread, so tests running in parallel don't mix.
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|allocs| allocs.set(allocs.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCS.with(Cell::get);
+    f();
+    ALLOCS.with(Cell::get) - before
+}
+
+fn ids(count: usize) -> Vec<Id> {
+    let mut world = World::new();
+    (0..count).map(|_| world.new_id()).collect()
+}
+
+#[test]
+fn extend_and_shrink_match_a_sorted_copy() {
+    let ids = ids(8);
+    let base = Signature::from(ids[..6].to_vec());
+
+    for &id in &ids {
+        let mut expected = base.to_vec();
+
+        match base.try_extend(id) {
+            Some(extended) => {
+                expected.push(id);
+                expected.sort();
+                assert_eq!(extended.ids(), expected);
+            }
+            None => assert!(base.has_id(id)),
+        }
+
+        let mut expected = base.to_vec();
+
+        match base.try_shrink(id) {
+            Some(shrunk) => {
+                expected.retain(|&other| other != id);
+                assert_eq!(shrunk.ids(), expected);
+            }
+            None => assert!(!base.has_id(id)),
+        }
+    }
+}
+
+#[test]
+fn extend_and_shrink_never_grow_their_buffer() {
+    let ids = ids(64);
+    let base = Signature::from(ids[1..63].to_vec());
+
+    // One buffer for the ids, and one for the shared signature. Growing the buffer
+    // while inserting at the front would add more.
+    assert_eq!(allocations(|| drop(base.try_extend(ids[0]))), 2);
+    assert_eq!(allocations(|| drop(base.try_extend(ids[63]))), 2);
+    assert_eq!(allocations(|| drop(base.try_shrink(ids[1]))), 2);
+}
@@ -0,0 +1,64 @@
+use xecs::{
+    component::ComponentBuilder,
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Time(f32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Speed(f32);
+
+fn new_world() -> World {
+    let mut world = World::new();
+    world.register::<Time>(ComponentBuilder::new());
+    world.register::<Speed>(ComponentBuilder::new().storage(StorageType::Tables));
+    world
+}
+
+#[test]
+fn singletons_are_read_directly() {
+    let mut world = new_world();
+    assert_eq!(world.singleton::<Time>(), None);
+
+    assert_eq!(world.set_singleton(Time(1.0)).unwrap(), None);
+    assert_eq!(world.singleton::<Time>(), Some(&Time(1.0)));
+
+    assert_eq!(world.set_singleton(Time(2.0)).unwrap(), Some(Time(1.0)));
+    assert_eq!(world.singleton::<Time>(), Some(&Time(2.0)));
+
+    world.singleton_mut::<Time>().unwrap().0 = 3.0;
+    assert_eq!(world.singleton::<Time>(), Some(&Time(3.0)));
+}
+
+#[test]
+fn queries_bind_the_singleton_once() {
+    let mut world = new_world();
+    let speed = world.id::<Speed>().unwrap();
+
+    for i in 1..=3 {
+        let id = world.new_id();
+        world.set::<Speed>(id, Speed(i as f32));
+    }
+
+    let mut plan = QueryPlan::new(
+        SelectStmt::default().read(speed),
+        WithStmt::new().singleton::<Time>(),
+    );
+
+    // Nothing matches until the singleton is set.
+    assert_eq!(plan.iter(&mut world).count(), 0);
+
+    world.set_singleton(Time(0.5)).unwrap();
+
+    let mut distances: Vec<f32> = plan
+        .iter(&mut world)
+        .map(|(_, row)| row.get::<Speed>(0).unwrap().0 * row.singleton::<Time>(0).unwrap().0)
+        .collect();
+
+    distances.sort_by(f32::total_cmp);
+    assert_eq!(distances, [0.5, 1.0, 1.5]);
+}
@@ -0,0 +1,117 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+/// Panics when dropped while armed.
+#[derive(Component, Debug, PartialEq)]
+struct Fuse {
+    value: u32,
+    armed: bool,
+}
+
+impl Fuse {
+    fn new(value: u32) -> Self {
+        Self {
+            value,
+            armed: false,
+        }
+    }
+}
+
+impl Drop for Fuse {
+    fn drop(&mut self) {
+        if self.armed && !std::thread::panicking() {
+            panic!("fuse {} blew", self.value);
+        }
+    }
+}
+
+#[test]
+fn panicking_drops_leave_sparse_sets_consistent() {
+    let mut world = World::new();
+    world.register::<Fuse>(ComponentBuilder::new());
+    let ids = [(); 4].map(|_| world.new_id());
+
+    for (value, &id) in ids.iter().enumerate() {
+        world.set::<Fuse>(id, Fuse::new(value as u32));
+    }
+
+    world.component_mut::<Fuse>(ids[1]).unwrap().armed = true;
+    let removed = catch_unwind(AssertUnwindSafe(|| world.remove::<Fuse>(ids[1])));
+    assert!(removed.is_err());
+
+    assert!(!world.has::<Fuse>(ids[1]));
+    assert!(world.get::<&Fuse>(ids[1]).is_err());
+
+    for &i in &[0, 2, 3] {
+        assert!(world.has::<Fuse>(ids[i]));
+        assert_eq!(world.get::<&Fuse>(ids[i]).unwrap().value, i as u32);
+    }
+
+    // Replacing an armed value keeps the new one.
+    world.component_mut::<Fuse>(ids[3]).unwrap().armed = true;
+    let replaced = catch_unwind(AssertUnwindSafe(|| {
+        drop(world.set::<Fuse>(ids[3], Fuse::new(30)))
+    }));
+    assert!(replaced.is_err());
+    assert_eq!(world.get::<&Fuse>(ids[3]).unwrap().value, 30);
+
+    world.set::<Fuse>(ids[1], Fuse::new(10));
+    assert_eq!(world.get::<&Fuse>(ids[1]).unwrap().value, 10);
+    assert_eq!(world.get::<&Fuse>(ids[2]).unwrap().value, 2);
+    assert!(world.check_invariants().is_empty());
+}
+
+#[derive(Component)]
+struct Velocity(u32);
+
+#[derive(Component)]
+struct Frozen;
+
+#[test]
+fn sparse_queries_only_visit_ids_with_the_component() {
+    let mut world = World::new();
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..1000).map(|_| world.new_id()).collect();
+    let moving: Vec<_> = ids.iter().copied().step_by(10).collect();
+
+    for (i, &id) in moving.iter().enumerate() {
+        world.set::<Velocity>(id, Velocity(i as u32));
+
+        if i % 2 == 0 {
+            world.add_id(id, frozen).unwrap();
+        }
+    }
+
+    // The iterator walks the 100 values, not the 1000 ids.
+    let query = world.query_sparse_only::<Velocity>();
+    assert_eq!(query.size_hint(), (100, Some(100)));
+
+    let mut found: Vec<_> = query.map(|(id, vel)| (id, vel.0)).collect();
+    found.sort();
+    let expected: Vec<_> = moving
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| (id, i as u32))
+        .collect();
+    assert_eq!(found, expected);
+
+    let frozen_count = world.query_sparse_only::<Velocity>().with(frozen).count();
+    let free_count = world
+        .query_sparse_only::<Velocity>()
+        .without(frozen)
+        .count();
+    assert_eq!((frozen_count, free_count), (50, 50));
+    assert!(
+        world
+            .query_sparse_only::<Velocity>()
+            .with(frozen)
+            .all(|(_, vel)| vel.0 % 2 == 0)
+    );
+}
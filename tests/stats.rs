@@ -0,0 +1,205 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+#[derive(Component)]
+struct Frozen;
+
+#[derive(Component)]
+#[allow(dead_code)]
+struct Velocity(f64);
+
+#[test]
+fn table_moves_are_counted_per_component() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+    world.enable_move_stats();
+
+    let ids: Vec<_> = (0..3).map(|_| world.new_id()).collect();
+
+    for &id in &ids {
+        world.set::<Position>(id, Position(1.0));
+        world.add::<Frozen>(id).unwrap();
+    }
+
+    world.remove::<Frozen>(ids[0]).unwrap();
+    world.despawn(ids[1]).unwrap();
+    assert_eq!(world.get::<&Position>(ids[0]).ok().map(|p| p.0), Some(1.0));
+
+    let stats = world.component_stats(pos).unwrap();
+    assert_eq!((stats.adds, stats.removes, stats.count), (3, 1, 2));
+
+    let stats = world.component_stats(frozen).unwrap();
+    assert_eq!((stats.adds, stats.removes, stats.count), (3, 2, 1));
+}
+
+#[test]
+fn table_moves_are_not_counted_by_default() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..3).map(|_| world.new_id()).collect();
+
+    for &id in &ids {
+        world.set::<Position>(id, Position(1.0));
+    }
+
+    world.despawn(ids[0]).unwrap();
+
+    // Despawns are counted without move stats.
+    let stats = world.component_stats(pos).unwrap();
+    assert_eq!((stats.adds, stats.removes, stats.count), (0, 1, 2));
+}
+
+#[test]
+fn stats_match_a_small_world() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+
+    // Three ids in [Position], one in [Position, Frozen], two with a sparse Velocity.
+    let ids: Vec<_> = (0..4).map(|_| world.new_id()).collect();
+
+    for &id in &ids {
+        world.set::<Position>(id, Position(0.0));
+    }
+
+    world.add::<Frozen>(ids[3]).unwrap();
+    world.set::<Velocity>(ids[0], Velocity(1.0));
+    world.set::<Velocity>(ids[1], Velocity(2.0));
+
+    let stats = world.component_stats(pos).unwrap();
+    assert_eq!(stats.storage, StorageType::Tables);
+    assert_eq!((stats.count, stats.tables), (4, 2));
+    assert_eq!(stats.bytes, 4 * size_of::<f32>());
+    assert!(stats.capacity_bytes >= stats.bytes);
+    assert_eq!(stats.avg_table_rows, 2.0);
+
+    let stats = world.component_stats(frozen).unwrap();
+    assert_eq!((stats.count, stats.tables, stats.bytes), (1, 1, 0));
+    assert_eq!(stats.avg_table_rows, 1.0);
+
+    let stats = world.component_stats(vel).unwrap();
+    assert_eq!(stats.storage, StorageType::Sparse);
+    assert_eq!((stats.count, stats.tables), (2, 0));
+    assert_eq!(stats.bytes, 2 * size_of::<f64>());
+    assert_eq!((stats.adds, stats.removes), (2, 0));
+}
+
+#[test]
+fn churning_components_are_suggested_sparse() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+    world.enable_move_stats();
+
+    let id = world.new_id();
+    world.set::<Position>(id, Position(0.0));
+
+    for _ in 0..5 {
+        world.add::<Frozen>(id).unwrap();
+        world.remove::<Frozen>(id).unwrap();
+    }
+
+    let stats = world.component_stats(frozen).unwrap();
+    assert_eq!((stats.adds, stats.removes), (5, 5));
+    assert_eq!(
+        world.suggest_storage(frozen).unwrap().storage,
+        StorageType::Sparse
+    );
+    assert_eq!(
+        world.suggest_storage(pos).unwrap().storage,
+        StorageType::Tables
+    );
+}
+
+#[test]
+fn overlaps_count_ids_with_both_components() {
+    let storages = [StorageType::Tables, StorageType::Sparse];
+
+    for (pos_storage, frozen_storage) in storages.into_iter().flat_map(|a| storages.map(|b| (a, b)))
+    {
+        let mut world = World::new();
+        world.register::<Position>(ComponentBuilder::new().storage(pos_storage));
+        world.register::<Frozen>(TagBuilder::new().storage(frozen_storage));
+
+        // 10 with a position, 6 frozen, 4 of them with both.
+        for i in 0..12 {
+            let id = world.new_id();
+
+            if i < 10 {
+                world.set::<Position>(id, Position(i as f32));
+            }
+            if i >= 6 {
+                world.add::<Frozen>(id).unwrap();
+            }
+        }
+
+        assert_eq!(world.component_overlap::<Position, Frozen>(), 4);
+        assert_eq!(world.component_overlap::<Frozen, Position>(), 4);
+        assert_eq!(world.component_overlap::<Position, Velocity>(), 0);
+    }
+}
+
+#[test]
+fn storage_stats_count_values_in_both_storages() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let vel = world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..100 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+        world.set::<Velocity>(id, Velocity(i as f64));
+
+        // Spreads the positions over two tables.
+        if i % 2 == 0 {
+            world.add_id(id, frozen).unwrap();
+        }
+    }
+
+    let stats = world.component_storage_stats(pos).unwrap();
+    assert_eq!(stats.entity_count, 100);
+    assert_eq!(stats.dense_bytes, 100 * size_of::<Position>());
+    assert_eq!(stats.sparse_bytes, 0);
+
+    let stats = world.component_storage_stats(vel).unwrap();
+    assert_eq!(stats.entity_count, 100);
+    assert_eq!(stats.dense_bytes, 100 * size_of::<Velocity>());
+    assert!(stats.sparse_bytes >= 100 * size_of::<usize>());
+
+    let stats = world.component_storage_stats(frozen).unwrap();
+    assert_eq!((stats.entity_count, stats.dense_bytes), (50, 0));
+
+    let id = world.new_id();
+    assert!(world.component_storage_stats(id).is_none());
+}
+
+#[test]
+fn memory_usage_grows_with_the_world() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Velocity>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let before = world.total_memory_usage();
+
+    for i in 0..1000 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+        world.set::<Velocity>(id, Velocity(i as f64));
+    }
+
+    let after = world.total_memory_usage();
+    assert!(after.entity_index_bytes > before.entity_index_bytes);
+    assert!(after.table_column_bytes >= before.table_column_bytes + 1000 * size_of::<Position>());
+    assert!(after.sparse_set_bytes >= before.sparse_set_bytes + 1000 * size_of::<Velocity>());
+    assert!(after.graph_bytes > 0);
+}
@@ -0,0 +1,383 @@
+use std::{cell::RefCell, rc::Rc};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::{World, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Health(u32);
+
+#[derive(Component)]
+struct Poisoned;
+
+#[derive(Component)]
+struct Frozen;
+
+#[test]
+fn compacting_frees_despawned_rows() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let table = world.ensure_archetype(&[health]);
+
+    let ids: Vec<_> = (0..1000)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i));
+            id
+        })
+        .collect();
+
+    for &id in &ids[200..] {
+        world.despawn(id).unwrap();
+    }
+
+    assert!(world.table_excess_capacity(table).unwrap() >= 800);
+    world.compact_tables();
+    assert_eq!(world.table_excess_capacity(table), Some(0));
+
+    for (i, &id) in ids[..200].iter().enumerate() {
+        assert_eq!(world.get::<&Health>(id).ok(), Some(&Health(i as u32)));
+    }
+}
+
+#[test]
+fn first_set_moves_from_the_root_table() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let id = world.new_id();
+    assert!(world.entity(id).unwrap().signature().ids().is_empty());
+
+    world.set::<Health>(id, Health(7));
+
+    let entity = world.entity(id).unwrap();
+    assert_eq!(entity.signature().ids(), [health]);
+    assert_eq!(entity.get::<Health>().ok(), Some(&Health(7)));
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn table_observers_see_each_new_table_once() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let poisoned = world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+
+    let created = Rc::new(RefCell::new(vec![]));
+    let on_create = created.clone();
+    world.observe_tables(move |table, signature| {
+        on_create
+            .borrow_mut()
+            .push((table, signature.ids().to_vec()))
+    });
+
+    let a = world.new_id();
+    let b = world.new_id();
+    world.set::<Health>(a, Health(1));
+    world.set::<Health>(b, Health(2));
+    world.add::<Poisoned>(a).unwrap();
+    world.add::<Poisoned>(b).unwrap();
+
+    let health_table = world.ensure_archetype(&[health]);
+    let both_table = world.ensure_archetype(&[health, poisoned]);
+
+    let mut both = vec![health, poisoned];
+    both.sort();
+
+    assert_eq!(
+        *created.borrow(),
+        [(health_table, vec![health]), (both_table, both)]
+    );
+}
+
+#[test]
+fn ensured_archetypes_are_created_once() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let poisoned = world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+
+    let created = Rc::new(RefCell::new(0));
+    let on_create = created.clone();
+    world.observe_tables(move |_, _| *on_create.borrow_mut() += 1);
+
+    let table = world.ensure_archetype(&[health, poisoned]);
+    assert_eq!(world.ensure_archetype(&[poisoned, health]), table);
+    assert_eq!(*created.borrow(), 1);
+    assert_eq!(world.components_of_table(table).unwrap().len(), 2);
+}
+
+#[test]
+fn plans_only_visit_the_given_tables() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    // Five tables of two ids each, told apart by a tag.
+    let mut tags = vec![];
+    let mut tables = vec![];
+    let mut ids = vec![];
+
+    for _ in 0..5 {
+        let tag = world.new_component(TagBuilder::new());
+        let pair: Vec<_> = (0..2)
+            .map(|i| {
+                let id = world.new_id();
+                world.set::<Health>(id, Health(i));
+                world.add_id(id, tag).unwrap();
+                id
+            })
+            .collect();
+
+        tables.push(world.ensure_archetype(&[health, tag]));
+        tags.push(tag);
+        ids.push(pair);
+    }
+
+    let plan = || QueryPlan::new(SelectStmt::default().read(health), WithStmt::new());
+    let visited = |world: &mut World, mut plan: QueryPlan| {
+        let mut visited: Vec<_> = plan.iter(world).map(|(id, _)| id).collect();
+        visited.sort();
+        visited
+    };
+
+    let mut expected = [ids[1].clone(), ids[3].clone()].concat();
+    expected.sort();
+    let selected = plan().for_tables([tables[1], tables[3]]);
+    assert_eq!(visited(&mut world, selected), expected);
+
+    let tag = tags[2];
+    let filtered = plan().add_table_filter(move |_, comps| comps.contains(&tag));
+    assert_eq!(visited(&mut world, filtered), ids[2]);
+}
+
+#[test]
+fn batch_removes_resolve_one_destination_per_table() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let poisoned = world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..100)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i));
+
+            if i % 2 == 0 {
+                world.add::<Poisoned>(id).unwrap();
+            }
+            id
+        })
+        .collect();
+
+    let created = Rc::new(RefCell::new(vec![]));
+    let on_create = created.clone();
+    world.observe_tables(move |_, signature| on_create.borrow_mut().push(signature.ids().to_vec()));
+
+    world.batch_remove::<Health>(&ids).unwrap();
+
+    // The table without health existed already, only the poisoned one is new.
+    assert_eq!(*created.borrow(), [vec![poisoned]]);
+
+    let mut signatures: Vec<_> = ids
+        .iter()
+        .map(|&id| world.entity(id).unwrap().signature().ids().to_vec())
+        .collect();
+    signatures.sort();
+    signatures.dedup();
+    assert_eq!(signatures, [vec![], vec![poisoned]]);
+
+    assert!(ids.iter().all(|&id| !world.has::<Health>(id)));
+    assert_eq!(world.component_stats(health).unwrap().count, 0);
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn table_components_are_listed_sorted() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let poisoned = world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+    let frozen = world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+
+    let id = world.new_id();
+    world.add::<Frozen>(id).unwrap();
+    world.set::<Health>(id, Health(3));
+    world.add::<Poisoned>(id).unwrap();
+
+    let table = world.ensure_archetype(&[frozen, health, poisoned]);
+    let mut expected = vec![health, poisoned, frozen];
+    expected.sort();
+    assert_eq!(world.components_of_table(table), Some(expected.as_slice()));
+    assert_eq!(world.entity(id).unwrap().signature().ids(), expected);
+
+    assert!(
+        [health, poisoned, frozen]
+            .iter()
+            .all(|&comp| world.has_component_in_table(table, comp))
+    );
+
+    let health_table = world.ensure_archetype(&[health]);
+    assert!(world.has_component_in_table(health_table, health));
+    assert!(!world.has_component_in_table(health_table, frozen));
+}
+
+#[test]
+fn dumped_tables_are_named_from_their_signature() {
+    let mut world = World::new();
+    world.register::<Health>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .name("Health"),
+    );
+    world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Health>(a, Health(1));
+    world.set::<Health>(b, Health(2));
+    world.add::<Poisoned>(b).unwrap();
+
+    let dump = world.dump_tables();
+    let health = dump.find("[Health]: 1 rows, 4 bytes\n  Health: 4 bytes\n");
+    // Unnamed components fall back to their type name.
+    let both = dump.find("[Health, tables::Poisoned]: 1 rows, 4 bytes\n  Health: 4 bytes\n");
+    let empty = dump.find(" []: ");
+    assert!(health.is_some() && both.is_some(), "{dump}");
+
+    // Tables without columns come last.
+    assert!(empty > health && empty > both, "{dump}");
+}
+
+#[test]
+fn values_are_summed_across_tables() {
+    for storage in [StorageType::Tables, StorageType::Sparse] {
+        let mut world = World::new();
+        world.register::<Health>(ComponentBuilder::new().storage(storage));
+        world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+        world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+
+        let mut expected = vec![];
+
+        for i in 0..30 {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i));
+
+            match i % 3 {
+                0 => world.add::<Poisoned>(id).unwrap(),
+                1 => world.add::<Frozen>(id).unwrap(),
+                _ => {}
+            }
+            expected.push((id, i));
+        }
+
+        // Ids without health are not visited.
+        let other = world.new_id();
+        world.add::<Poisoned>(other).unwrap();
+
+        let mut values: Vec<_> = world.values::<Health>().map(|(id, h)| (id, h.0)).collect();
+        values.sort();
+        assert_eq!(values, expected);
+        assert_eq!(world.values::<Health>().map(|(_, h)| h.0).sum::<u32>(), 435);
+    }
+}
+
+#[test]
+fn defragmenting_converges_to_id_order() {
+    let mut world = World::new();
+    world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..200)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i));
+            id
+        })
+        .collect();
+
+    // A few removals are below the threshold, the table is left alone.
+    world.despawn(ids[0]).unwrap();
+    assert_eq!(world.defragment(usize::MAX), 0);
+
+    for &id in ids.iter().skip(7).step_by(7) {
+        world.despawn(id).unwrap();
+    }
+
+    let rows = |world: &World| {
+        world
+            .values::<Health>()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+    let mut sorted = rows(&world);
+    sorted.sort();
+    assert_ne!(rows(&world), sorted);
+
+    let mut calls = 0;
+
+    loop {
+        let moved = world.defragment(10);
+        assert!(moved <= 10);
+        assert!(world.check_invariants().is_empty());
+        calls += 1;
+
+        if moved == 0 {
+            break;
+        }
+    }
+
+    assert!(calls > 2);
+    assert_eq!(rows(&world), sorted);
+
+    for (i, &id) in ids
+        .iter()
+        .enumerate()
+        .filter(|&(_, &id)| world.is_alive(id))
+    {
+        assert_eq!(world.get::<&Health>(id).unwrap().0, i as u32);
+    }
+}
+
+#[test]
+fn tags_are_added_to_all_matching_ids() {
+    let mut world = World::new();
+    let health = world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    let poisoned = world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+    world.register::<Frozen>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..600)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i));
+
+            // 100 ids in a second table don't match.
+            if i >= 500 {
+                world.add::<Poisoned>(id).unwrap();
+            }
+            id
+        })
+        .collect();
+
+    // A value filter only matches part of the table, those ids are moved one by one.
+    let mut plan = QueryPlan::new(
+        SelectStmt::default().filter::<Health>(|health| health.0 % 2 == 0),
+        WithStmt::new().without(poisoned),
+    );
+    world.add_tag_to_all::<Frozen>(&mut plan).unwrap();
+    assert_eq!(
+        world
+            .component_stats(world.id::<Frozen>().unwrap())
+            .unwrap()
+            .count,
+        250
+    );
+
+    let mut plan = QueryPlan::new(
+        SelectStmt::default(),
+        WithStmt::new().with(health).without(poisoned),
+    );
+    world.add_tag_to_all::<Frozen>(&mut plan).unwrap();
+
+    for (i, &id) in ids.iter().enumerate() {
+        assert_eq!(world.has::<Frozen>(id), i < 500);
+        assert_eq!(world.get::<&Health>(id).unwrap().0, i as u32);
+    }
+    assert!(world.check_invariants().is_empty());
+}
@@ -0,0 +1,154 @@
+use std::{
+    alloc::Layout,
+    cell::Cell,
+    panic::{AssertUnwindSafe, catch_unwind},
+    rc::Rc,
+};
+use xecs::{
+    allocator::{DefaultAlloc, WorldAlloc},
+    component::{ComponentBuilder, TagBuilder},
+    error::EcsError,
+    world::{World, WorldBuilder, WorldGet},
+};
+use xecs_macros::Component;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct Name(String);
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+struct Score(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Handle(u32);
+
+#[derive(Component)]
+struct Active;
+
+/// Counts the allocations that are still live.
+struct CountingAlloc(Rc<Cell<isize>>);
+
+unsafe impl WorldAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0.set(self.0.get() + 1);
+        unsafe { DefaultAlloc.alloc(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { DefaultAlloc.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.set(self.0.get() - 1);
+        unsafe { DefaultAlloc.dealloc(ptr, layout) }
+    }
+}
+
+fn register(world: &mut World) {
+    world.register::<Name>(ComponentBuilder::new().clone(Name::clone));
+    // SAFETY: `Score` is a single `u32`.
+    world.register::<Score>(unsafe { ComponentBuilder::new().pod() });
+    world.register::<Handle>(ComponentBuilder::new());
+    world.register::<Active>(TagBuilder::new());
+}
+
+#[test]
+fn failed_transactions_are_rolled_back() {
+    let mut world = World::new();
+    register(&mut world);
+
+    let id = world.new_id();
+    world.set::<Name>(id, Name("old".into()));
+    world.set::<Score>(id, Score(1));
+
+    let mut spawned = None;
+    let result = world.transaction(|tx| {
+        spawned = Some(tx.spawn());
+        tx.set::<Name>(id, Name("new".into()))?;
+        tx.remove::<Score>(id)?;
+        tx.add::<Active>(id)?;
+        Err(EcsError::UnknownComponent("abort".into()))
+    });
+
+    assert!(result.is_err());
+    assert!(!world.is_alive(spawned.unwrap()));
+    assert_eq!(world.get::<&Name>(id).ok(), Some(&Name("old".into())));
+    assert_eq!(world.get::<&Score>(id).ok(), Some(&Score(1)));
+    assert!(!world.has::<Active>(id));
+}
+
+#[test]
+fn panicking_transactions_are_rolled_back() {
+    let mut world = World::new();
+    let sets = Rc::new(Cell::new(0));
+    let on_set = Rc::clone(&sets);
+    world.register::<Name>(ComponentBuilder::new().clone(Name::clone));
+    world.register::<Score>(
+        ComponentBuilder::new()
+            .clone(|score: &Score| *score)
+            .on_set(move |_, _| on_set.set(on_set.get() + 1)),
+    );
+    world.register::<Active>(TagBuilder::new());
+
+    let id = world.new_id();
+    world.set::<Name>(id, Name("kept".into()));
+    world.set::<Score>(id, Score(1));
+    let sets_before = sets.get();
+
+    let mut spawned = None;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        world.transaction(|tx| {
+            spawned = Some(tx.spawn());
+            tx.set::<Score>(id, Score(2))?;
+            tx.add::<Active>(id)?;
+            tx.remove::<Name>(id)?;
+            panic!("transaction body");
+        })
+    }));
+
+    assert!(result.is_err());
+    assert!(!world.is_alive(spawned.unwrap()));
+    assert_eq!(world.get::<&Name>(id).ok(), Some(&Name("kept".into())));
+    assert_eq!(world.get::<&Score>(id).ok(), Some(&Score(1)));
+    assert!(!world.has::<Active>(id));
+
+    // The old score is written back without running the hook.
+    assert_eq!(sets.get(), sets_before);
+}
+
+#[test]
+fn values_that_cant_be_copied_are_not_removed() {
+    let mut world = World::new();
+    register(&mut world);
+
+    let id = world.new_id();
+    world.set::<Handle>(id, Handle(7));
+
+    let result = world.transaction(|tx| tx.remove::<Handle>(id));
+    assert!(matches!(result, Err(EcsError::NoCloneHook(_))));
+    assert_eq!(world.get::<&Handle>(id).ok(), Some(&Handle(7)));
+}
+
+#[test]
+fn saved_values_use_the_world_allocator() {
+    let live = Rc::new(Cell::new(0));
+    let mut world = WorldBuilder::new()
+        .with_allocator(CountingAlloc(Rc::clone(&live)))
+        .build();
+    register(&mut world);
+
+    let id = world.new_id();
+    world.set::<Name>(id, Name("saved".into()));
+
+    let result = world.transaction(|tx| {
+        let before = live.get();
+        tx.remove::<Name>(id)?;
+        assert!(live.get() > before);
+        Err(EcsError::UnknownComponent("abort".into()))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(world.get::<&Name>(id).ok(), Some(&Name("saved".into())));
+
+    drop(world);
+    assert_eq!(live.get(), 0);
+}
@@ -0,0 +1,17 @@
+use xecs::{component::ComponentBuilder, world::World};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+fn main() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new());
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.set::<Position>(a, Position(0.0));
+
+    // The guard points into the storage, which a write to another id can grow.
+    let mut value = world.component_mut::<Position>(a).unwrap();
+    world.set::<Position>(b, Position(1.0));
+    value.0 = 2.0;
+}
@@ -0,0 +1,13 @@
+use xecs_macros::{Component, QueryData};
+
+#[derive(Component)]
+struct Position(f32);
+
+// Both fields name the same component, so the mutable one would alias the other.
+#[derive(QueryData)]
+struct Mover<'a> {
+    pos: &'a mut Position,
+    old_pos: &'a Position,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use xecs_macros::{Component, QueryData};
+
+#[derive(Component)]
+struct Position(f32);
+
+// Fields can't outlive the borrow of the world the view is made from.
+#[derive(QueryData)]
+struct Mover<'a> {
+    pos: &'static mut Position,
+    name: Option<&'a str>,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use xecs::{component::ComponentBuilder, world::World};
+use xecs_macros::{Component, QueryData};
+
+#[derive(Component)]
+struct Position(f32);
+
+#[derive(QueryData)]
+struct Mover<'a> {
+    pos: &'a mut Position,
+}
+
+fn main() {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new());
+
+    // Views borrow the world, so they can't be kept once it's written to.
+    let mut movers: Vec<_> = world.query_data::<Mover>().unwrap().collect();
+    world.new_id();
+    movers[0].pos.0 = 1.0;
+}
@@ -0,0 +1,13 @@
+use xecs::{
+    query::{ReadOnly, SelectStmt, WithStmt},
+    world::World,
+};
+
+fn main() {
+    let mut world = World::new();
+    let id = world.new_id();
+
+    // Queries built from a WorldRef only take read-only statements, which can't write.
+    let select = SelectStmt::<ReadOnly>::new().write(id);
+    let _ = world.as_ref().query(select, WithStmt::new());
+}
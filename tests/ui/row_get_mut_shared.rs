@@ -0,0 +1,23 @@
+use xecs::{
+    component::ComponentBuilder,
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+fn main() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let id = world.new_id();
+    world.set::<Position>(id, Position(0.0));
+
+    let mut plan = QueryPlan::new(SelectStmt::default().write(pos), WithStmt::new());
+    let (_, row) = plan.iter(&mut world).next().unwrap();
+
+    // Writing a field needs the row itself to be borrowed mutably.
+    row.get_mut::<Position>(0).unwrap().0 = 1.0;
+}
@@ -0,0 +1,26 @@
+use xecs::{
+    component::ComponentBuilder,
+    query::{QueryPlan, SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(f32);
+
+fn main() {
+    let mut world = World::new();
+    let pos = world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    let id = world.new_id();
+    world.set::<Position>(id, Position(0.0));
+
+    let mut plan = QueryPlan::new(SelectStmt::default().read(pos), WithStmt::new());
+    let (_, row) = plan.iter(&mut world).next().unwrap();
+    let value = row.get::<Position>(0).unwrap();
+
+    // Adding a row can grow the column, so values can't be held across it.
+    let other = world.new_id();
+    world.set::<Position>(other, Position(1.0));
+    println!("{}", value.0);
+}
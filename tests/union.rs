@@ -0,0 +1,149 @@
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    error::EcsError,
+    id::{Id, Signature},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component, Debug, PartialEq)]
+struct Circle(u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Rect(u32, u32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Point(u32);
+
+fn register(world: &mut World) -> (Id, Id, Id) {
+    let circle = world.register::<Circle>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .default(|| Circle(1)),
+    );
+    let rect = world.register::<Rect>(ComponentBuilder::new().storage(StorageType::Sparse));
+    let shape = world.register_union(&[circle, rect]).unwrap();
+    (circle, rect, shape)
+}
+
+#[test]
+fn set_union_replaces_the_active_variant() {
+    let mut world = World::new();
+    let (_, _, shape) = register(&mut world);
+
+    let id = world.new_id();
+    world.set_union::<Circle>(id, shape, Circle(2)).unwrap();
+    world.set_union::<Rect>(id, shape, Rect(3, 4)).unwrap();
+
+    assert!(!world.has::<Circle>(id));
+    assert_eq!(world.get_union::<Circle>(id, shape), None);
+    assert_eq!(world.get_union::<Rect>(id, shape), Some(&Rect(3, 4)));
+    assert!(world.has_id(id, shape));
+}
+
+#[test]
+fn plain_set_replaces_the_active_variant() {
+    let mut world = World::new();
+    let (_, _, shape) = register(&mut world);
+
+    let id = world.new_id();
+    world.set_union::<Circle>(id, shape, Circle(2)).unwrap();
+    world.set::<Rect>(id, Rect(3, 4));
+
+    assert_eq!(world.get_union::<Circle>(id, shape), None);
+    assert_eq!(world.get_union::<Rect>(id, shape), Some(&Rect(3, 4)));
+
+    let other = world.new_id();
+    world.set::<Circle>(other, Circle(5));
+
+    assert!(world.has_id(other, shape));
+}
+
+#[test]
+fn removing_the_variant_removes_the_union_tag() {
+    let mut world = World::new();
+    let (_, _, shape) = register(&mut world);
+
+    let id = world.new_id();
+    world.set_union::<Circle>(id, shape, Circle(2)).unwrap();
+    world.set::<Rect>(id, Rect(3, 4));
+    world.remove::<Rect>(id).unwrap();
+
+    assert!(!world.has::<Circle>(id));
+    assert!(!world.has::<Rect>(id));
+    assert!(!world.has_id(id, shape));
+    assert!(world.check_invariants().is_empty());
+}
+
+#[test]
+fn move_to_updates_the_union_tag() {
+    let mut world = World::new();
+    let (circle, _, shape) = register(&mut world);
+
+    let id = world.new_id();
+    world.move_to(id, &Signature::from([circle])).unwrap();
+
+    assert_eq!(world.get_union::<Circle>(id, shape), Some(&Circle(1)));
+    assert!(world.has_id(id, shape));
+
+    world.move_to(id, &Signature::from(Vec::new())).unwrap();
+
+    assert!(!world.has::<Circle>(id));
+    assert!(!world.has_id(id, shape));
+}
+
+#[test]
+fn move_to_rejects_two_variants() {
+    let mut world = World::new();
+    let circle = world.register::<Circle>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .default(|| Circle(1)),
+    );
+    let rect = world.register::<Rect>(
+        ComponentBuilder::new()
+            .storage(StorageType::Tables)
+            .default(|| Rect(1, 1)),
+    );
+    let shape = world.register_union(&[circle, rect]).unwrap();
+
+    let id = world.new_id();
+
+    assert!(matches!(
+        world.move_to(id, &Signature::from([circle, rect])),
+        Err(EcsError::ExclusiveUnion(union)) if union == shape
+    ));
+    assert!(!world.has::<Circle>(id));
+}
+
+#[derive(Component)]
+struct Hidden;
+
+#[test]
+fn invalid_unions_are_rejected() {
+    let mut world = World::new();
+    let (circle, _, shape) = register(&mut world);
+    let hidden = world.register::<Hidden>(TagBuilder::new());
+    let point = world.register::<Point>(ComponentBuilder::new());
+
+    assert!(matches!(
+        world.register_union(&[]),
+        Err(EcsError::EmptyUnion)
+    ));
+    assert!(matches!(
+        world.register_union(&[point, hidden]),
+        Err(EcsError::IsTag(tag)) if tag == hidden
+    ));
+    assert!(matches!(
+        world.register_union(&[point, point]),
+        Err(EcsError::DuplicateUnionVariant(comp)) if comp == point
+    ));
+    assert!(matches!(
+        world.register_union(&[point, circle]),
+        Err(EcsError::VariantOfOtherUnion(comp, union)) if comp == circle && union == shape
+    ));
+
+    // Rejected unions don't claim their variants.
+    assert!(world.register_union(&[point]).is_ok());
+}
@@ -0,0 +1,53 @@
+use std::cell::Cell;
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Health(u32);
+
+#[derive(Component)]
+struct Poisoned;
+
+thread_local! {
+    static CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn is_dead(health: &Health) -> bool {
+    CALLS.set(CALLS.get() + 1);
+    health.0 == 0
+}
+
+#[test]
+fn filters_only_run_on_rows_with_the_component() {
+    let mut world = World::new();
+    world.register::<Health>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Poisoned>(TagBuilder::new().storage(StorageType::Tables));
+
+    let ids: Vec<_> = (0..6)
+        .map(|i| {
+            let id = world.new_id();
+            world.set::<Health>(id, Health(i % 3));
+            id
+        })
+        .collect();
+
+    // Ids in a table without health never reach the filter.
+    for _ in 0..4 {
+        let id = world.new_id();
+        world.add::<Poisoned>(id).unwrap();
+    }
+
+    let mut dead = world.collect(
+        SelectStmt::default().filter::<Health>(is_dead),
+        WithStmt::new(),
+    );
+    dead.sort();
+
+    assert_eq!(dead, [ids[0], ids[3]]);
+    assert_eq!(CALLS.get(), ids.len());
+}
@@ -0,0 +1,118 @@
+use xecs::{component::TagBuilder, query::WithStmt, watch::MembershipEvent, world::World};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Enemy;
+
+#[test]
+fn drained_events_follow_dispatches() {
+    let mut world = World::new();
+    let enemy = world.register::<Enemy>(TagBuilder::new());
+    let [a, b] = [(); 2].map(|_| world.new_id());
+    world.add::<Enemy>(a).unwrap();
+
+    let watch = world.watch(WithStmt::new().with(enemy));
+    assert_eq!(watch.drain_events(), []);
+
+    world.add::<Enemy>(b).unwrap();
+    world.dispatch_events();
+    assert_eq!(
+        watch.drain_events(),
+        [MembershipEvent::Entered(a), MembershipEvent::Entered(b)]
+    );
+
+    world.remove::<Enemy>(a).unwrap();
+    world.despawn(b).unwrap();
+    world.dispatch_events();
+    assert_eq!(
+        watch.drain_events(),
+        [MembershipEvent::Left(a), MembershipEvent::Left(b)]
+    );
+
+    world.dispatch_events();
+    assert_eq!(watch.drain_events(), []);
+
+    drop(world);
+    assert!(watch.is_closed());
+}
+
+#[cfg(feature = "async")]
+mod stream {
+    use super::Enemy;
+    use futures_core::Stream;
+    use std::{
+        pin::{Pin, pin},
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+    use xecs::{component::TagBuilder, query::WithStmt, watch::MembershipEvent, world::World};
+
+    /// Counts its wakeups, in place of an executor.
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Minimal block_on for the next item. Nothing else runs on this thread,
+    /// so a pending stream could never be woken, and panics instead of parking.
+    fn block_on_next<S: Stream>(
+        stream: Pin<&mut S>,
+        wakes: &Arc<CountingWaker>,
+    ) -> Option<S::Item> {
+        let waker = Waker::from(Arc::clone(wakes));
+
+        match stream.poll_next(&mut Context::from_waker(&waker)) {
+            Poll::Ready(item) => item,
+            Poll::Pending => panic!("stream is pending"),
+        }
+    }
+
+    #[test]
+    fn streams_wake_on_dispatch_and_end_with_the_world() {
+        let mut world = World::new();
+        let enemy = world.register::<Enemy>(TagBuilder::new());
+        let [a, b] = [(); 2].map(|_| world.new_id());
+
+        let mut stream = pin!(world.watch(WithStmt::new().with(enemy)).into_stream());
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&wakes));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+
+        world.add::<Enemy>(b).unwrap();
+        world.add::<Enemy>(a).unwrap();
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+
+        world.dispatch_events();
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            block_on_next(stream.as_mut(), &wakes),
+            Some(MembershipEvent::Entered(a))
+        );
+        assert_eq!(
+            block_on_next(stream.as_mut(), &wakes),
+            Some(MembershipEvent::Entered(b))
+        );
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+
+        world.remove::<Enemy>(a).unwrap();
+        world.dispatch_events();
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            block_on_next(stream.as_mut(), &wakes),
+            Some(MembershipEvent::Left(a))
+        );
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+
+        drop(world);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 3);
+        assert_eq!(block_on_next(stream.as_mut(), &wakes), None);
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+use xecs::{
+    component::{ComponentBuilder, TagBuilder},
+    query::{SelectStmt, WithStmt},
+    storage::StorageType,
+    world::World,
+};
+use xecs_macros::Component;
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] f32);
+
+#[derive(Component)]
+struct Marker;
+
+fn populated() -> World {
+    let mut world = World::new();
+    world.register::<Position>(ComponentBuilder::new().storage(StorageType::Tables));
+    world.register::<Marker>(TagBuilder::new().storage(StorageType::Tables));
+
+    for i in 0..100 {
+        let id = world.new_id();
+        world.set::<Position>(id, Position(i as f32));
+
+        if i % 2 == 0 {
+            world.add::<Marker>(id).unwrap();
+        }
+    }
+
+    world
+}
+
+#[test]
+fn views_are_read_from_scoped_threads() {
+    let mut world = populated();
+    let pos = world.id::<Position>().unwrap();
+    let ids: Vec<_> = world.collect(SelectStmt::default(), WithStmt::new().with(pos));
+    let (id_view, comp_view, table_view) = world.split();
+
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                // Locks are taken in order: ids, components, tables.
+                let alive = id_view.read();
+                let comps = comp_view.read();
+                let tables = table_view.read();
+
+                assert!(ids.iter().all(|&id| alive.is_alive(id)));
+                assert_eq!(comps.storage_type(pos), Some(StorageType::Tables));
+
+                let rows: usize = tables
+                    .table_ids()
+                    .filter(|&table| tables.signature(table).unwrap().contains(&pos))
+                    .map(|table| tables.row_count(table).unwrap())
+                    .sum();
+                assert_eq!(rows, 100);
+            });
+        }
+    });
+}
+
+#[test]
+fn writes_wait_for_readers() {
+    let mut world = populated();
+    let pos = world.id::<Position>().unwrap();
+    let (_, comp_view, _) = world.split();
+    let written = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let read = comp_view.read();
+
+        scope.spawn(|| {
+            comp_view.write().set_priority(pos, 5);
+            written.store(true, Ordering::Release);
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(!written.load(Ordering::Acquire));
+        assert_eq!(read.priority(pos), Some(0));
+    });
+
+    assert!(written.load(Ordering::Acquire));
+    assert_eq!(world.component_priority(pos), Some(5));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "lock order violation")]
+fn locking_out_of_order_panics() {
+    let mut world = populated();
+    let (_, comp_view, table_view) = world.split();
+
+    let _tables = table_view.read();
+    let _comps = comp_view.read();
+}
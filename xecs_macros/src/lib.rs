@@ -140,13 +140,44 @@ pub fn params(input: TokenStream) -> TokenStream {
     quote! { #params }.into()
 }
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn component(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
     impl_component(&item)
 }
 
+/// Options set with `#[component(...)]`.
+#[derive(Default)]
+struct ComponentAttrs {
+    /// Hooks are implemented from `Default`, `Clone`, `PartialEq` and `Debug`.
+    reflect: bool,
+}
+
+impl ComponentAttrs {
+    fn parse(ast: &DeriveInput) -> Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in ast.attrs.iter().filter(|a| a.path().is_ident("component")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("reflect") {
+                    attrs.reflect = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown component attribute"))
+                }
+            })?;
+        }
+
+        Ok(attrs)
+    }
+}
+
 fn impl_component(ast: &DeriveInput) -> TokenStream {
+    let attrs = match ComponentAttrs::parse(ast) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let name = &ast.ident;
     let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
     let has_generics = !ast.generics.params.is_empty();
@@ -175,6 +206,23 @@ fn impl_component(ast: &DeriveInput) -> TokenStream {
         }
     };
 
+    if is_tag && attrs.reflect {
+        return quote! { compile_error!("Tags can't be reflected, they have no data."); }.into();
+    }
+
+    let default_hooks = if attrs.reflect {
+        quote! {
+            fn default_hooks() -> xecs::type_info::TypeHooksBuilder<Self>
+            where
+                Self: xecs::type_traits::DataComponent,
+            {
+                xecs::type_info::TypeHooksBuilder::for_reflectable()
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let data_type = if is_tag {
         quote! {
             type DataType = xecs::type_traits::Tag;
@@ -193,6 +241,7 @@ fn impl_component(ast: &DeriveInput) -> TokenStream {
         {
             #data_type
             #is_generic
+            #default_hooks
         }
 
         unsafe impl #impl_generics xecs::registration::ComponentId for #name #ty_generics
@@ -205,3 +254,194 @@ fn impl_component(ast: &DeriveInput) -> TokenStream {
     }
     .into()
 }
+
+/// Derives `xecs::get_params::QueryData` for a struct with one lifetime.
+///
+/// Fields must be `&'a T`, `&'a mut T`, `Option<&'a T>` or `Option<&'a mut T>`,
+/// a field marked `#[entity]` holds the id.
+#[proc_macro_derive(QueryData, attributes(entity))]
+pub fn query_data(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+
+    match impl_query_data(&item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Largest tuple of params implemented by xecs.
+const MAX_QUERY_FIELDS: usize = 13;
+
+/// Param of a field of a `QueryData` struct.
+struct QueryField {
+    /// Component type, `None` for the `#[entity]` field.
+    comp: Option<Type>,
+    is_mut: bool,
+    is_opt: bool,
+}
+
+impl QueryField {
+    fn parse(field: &syn::Field, lifetime: &syn::Lifetime) -> Result<Self> {
+        if field.attrs.iter().any(|a| a.path().is_ident("entity")) {
+            return Ok(Self {
+                comp: None,
+                is_mut: false,
+                is_opt: false,
+            });
+        }
+
+        let (ty, is_opt) = match option_inner(&field.ty) {
+            Some(inner) => (inner, true),
+            None => (&field.ty, false),
+        };
+
+        let Type::Reference(reference) = ty else {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "expected `&T`, `&mut T`, `Option<&T>` or `Option<&mut T>`",
+            ));
+        };
+
+        match &reference.lifetime {
+            Some(lt) if lt == lifetime => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    reference,
+                    format!("field must borrow for the struct lifetime `{lifetime}`"),
+                ));
+            }
+        }
+
+        Ok(Self {
+            comp: Some((*reference.elem).clone()),
+            is_mut: reference.mutability.is_some(),
+            is_opt,
+        })
+    }
+
+    /// Returns the param type, borrowing for `'static` since params are lifetime-free.
+    fn param(&self) -> proc_macro2::TokenStream {
+        let Some(comp) = &self.comp else {
+            return quote! { xecs::get_params::Entity };
+        };
+
+        let mut_ref = if self.is_mut {
+            quote! { mut }
+        } else {
+            quote! {}
+        };
+
+        if self.is_opt {
+            quote! { core::option::Option<&'static #mut_ref #comp> }
+        } else {
+            quote! { &'static #mut_ref #comp }
+        }
+    }
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let last = path.path.segments.last()?;
+
+    if last.ident != "Option" {
+        return None;
+    }
+
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn impl_query_data(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+
+    let syn::Data::Struct(data) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            ast,
+            "QueryData is only derived for structs",
+        ));
+    };
+
+    let syn::Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(ast, "QueryData needs named fields"));
+    };
+
+    let lifetime = match ast.generics.lifetimes().collect::<Vec<_>>().as_slice() {
+        [param] if ast.generics.params.len() == 1 => param.lifetime.clone(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.generics,
+                "QueryData needs exactly one lifetime and no other generics",
+            ));
+        }
+    };
+
+    let parsed = fields
+        .named
+        .iter()
+        .map(|field| QueryField::parse(field, &lifetime))
+        .collect::<Result<Vec<_>>>()?;
+
+    if parsed.is_empty() || parsed.len() > MAX_QUERY_FIELDS {
+        return Err(syn::Error::new_spanned(
+            ast,
+            format!("QueryData needs between 1 and {MAX_QUERY_FIELDS} fields"),
+        ));
+    }
+
+    if parsed.iter().filter(|f| f.comp.is_none()).count() > 1 {
+        return Err(syn::Error::new_spanned(
+            ast,
+            "only one field can be `#[entity]`",
+        ));
+    }
+
+    // Components named the same way alias, other aliasing is checked when the params are created.
+    for (i, field) in parsed.iter().enumerate() {
+        let Some(comp) = &field.comp else { continue };
+        let key = comp.to_token_stream().to_string();
+
+        let aliased = parsed.iter().enumerate().any(|(j, other)| {
+            i != j
+                && (field.is_mut || other.is_mut)
+                && other
+                    .comp
+                    .as_ref()
+                    .is_some_and(|o| o.to_token_stream().to_string() == key)
+        });
+
+        if aliased {
+            return Err(syn::Error::new_spanned(
+                comp,
+                format!("`{key}` is accessed mutably more than once"),
+            ));
+        }
+    }
+
+    let idents: Vec<_> = fields
+        .named
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+    let params = parsed.iter().map(QueryField::param);
+
+    Ok(quote! {
+        impl<#lifetime> xecs::get_params::QueryData for #name<#lifetime> {
+            type Item<'__w> = #name<'__w>;
+            type Fields = (#(#params,)*);
+
+            fn from_fields(
+                fields: <Self::Fields as xecs::get_params::Params>::ParamsType<'_>,
+            ) -> Self::Item<'_> {
+                let (#(#idents,)*) = fields;
+                #name { #(#idents),* }
+            }
+        }
+    })
+}